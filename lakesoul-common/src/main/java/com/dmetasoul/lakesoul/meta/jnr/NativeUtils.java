@@ -39,6 +39,9 @@ public class NativeUtils {
         SelectOneDataCommitInfoByTableIdAndPartitionDescAndCommitId(DAO_TYPE_QUERY_ONE_OFFSET + 9, 3),
         SelectOneDataCommitInfoByTableId(DAO_TYPE_QUERY_ONE_OFFSET + 10, 3),
 
+        SelectTableTagByTableIdAndTagName(DAO_TYPE_QUERY_ONE_OFFSET + 11, 2),
+        SelectTableBranchByTableIdAndBranchName(DAO_TYPE_QUERY_ONE_OFFSET + 12, 2),
+
         // ==== Query List ====
 
         ListNamespaces(DAO_TYPE_QUERY_LIST_OFFSET),
@@ -57,6 +60,12 @@ public class NativeUtils {
         // Query DataCommitInfo List
         ListDataCommitInfoByTableIdAndPartitionDescAndCommitList(DAO_TYPE_QUERY_LIST_OFFSET + 10, 3),
 
+        // Query TableTag List
+        ListTableTagByTableId(DAO_TYPE_QUERY_LIST_OFFSET + 11, 1),
+
+        // Query TableBranch List
+        ListTableBranchByTableId(DAO_TYPE_QUERY_LIST_OFFSET + 12, 1),
+
         // ==== Insert One ====
         InsertNamespace(DAO_TYPE_INSERT_ONE_OFFSET),
         InsertTablePathId(DAO_TYPE_INSERT_ONE_OFFSET + 1),
@@ -64,10 +73,13 @@ public class NativeUtils {
         InsertTableInfo(DAO_TYPE_INSERT_ONE_OFFSET + 3),
         InsertPartitionInfo(DAO_TYPE_INSERT_ONE_OFFSET + 4),
         InsertDataCommitInfo(DAO_TYPE_INSERT_ONE_OFFSET + 5),
+        InsertTableTag(DAO_TYPE_INSERT_ONE_OFFSET + 6),
+        InsertTableBranch(DAO_TYPE_INSERT_ONE_OFFSET + 7),
 
         // ==== Transaction Insert List ====
         TransactionInsertPartitionInfo(DAO_TYPE_TRANSACTION_INSERT_LIST_OFFSET),
         TransactionInsertDataCommitInfo(DAO_TYPE_TRANSACTION_INSERT_LIST_OFFSET + 1),
+        TransactionInsertTable(DAO_TYPE_TRANSACTION_INSERT_LIST_OFFSET + 2),
 
         // ==== Query SCALAR ====
         GetLatestTimestampFromPartitionInfo(DAO_TYPE_QUERY_SCALAR_OFFSET, 2),
@@ -100,6 +112,14 @@ public class NativeUtils {
         DeleteDataCommitInfoByTableIdAndPartitionDescAndCommitIdList(DAO_TYPE_UPDATE_OFFSET + 13, 3),
         DeleteDataCommitInfoByTableIdAndPartitionDesc(DAO_TYPE_UPDATE_OFFSET + 14, 2),
         DeleteDataCommitInfoByTableId(DAO_TYPE_UPDATE_OFFSET + 15, 1),
+        // Update TableTag
+        DeleteTableTagByTableIdAndTagName(DAO_TYPE_UPDATE_OFFSET + 16, 2),
+        DeleteTableTagByTableId(DAO_TYPE_UPDATE_OFFSET + 17, 1),
+        // Update PartitionInfo
+        DeletePartitionInfoByTableIdAndPartitionDescAndVersion(DAO_TYPE_UPDATE_OFFSET + 18, 3),
+        // Update TableBranch
+        DeleteTableBranchByTableIdAndBranchName(DAO_TYPE_UPDATE_OFFSET + 19, 2),
+        DeleteTableBranchByTableId(DAO_TYPE_UPDATE_OFFSET + 20, 1),
         ;
 
         private final int code;
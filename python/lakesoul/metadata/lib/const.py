@@ -26,6 +26,9 @@ class DaoType:
 
     SelectOneDataCommitInfoByTableIdAndPartitionDescAndCommitId = DAO_TYPE_QUERY_ONE_OFFSET + 9,
 
+    SelectTableTagByTableIdAndTagName = DAO_TYPE_QUERY_ONE_OFFSET + 11,
+    SelectTableBranchByTableIdAndBranchName = DAO_TYPE_QUERY_ONE_OFFSET + 12,
+
     # // ==== Query List ====
 
     ListNamespaces = DAO_TYPE_QUERY_LIST_OFFSET,
@@ -44,6 +47,12 @@ class DaoType:
     # // Query DataCommitInfo List
     ListDataCommitInfoByTableIdAndPartitionDescAndCommitList = DAO_TYPE_QUERY_LIST_OFFSET + 10,
 
+    # // Query TableTag List
+    ListTableTagByTableId = DAO_TYPE_QUERY_LIST_OFFSET + 11,
+
+    # // Query TableBranch List
+    ListTableBranchByTableId = DAO_TYPE_QUERY_LIST_OFFSET + 12,
+
     # // ==== Insert One ====
     InsertNamespace = DAO_TYPE_INSERT_ONE_OFFSET,
     InsertTablePathId = DAO_TYPE_INSERT_ONE_OFFSET + 1,
@@ -51,10 +60,13 @@ class DaoType:
     InsertTableInfo = DAO_TYPE_INSERT_ONE_OFFSET + 3,
     InsertPartitionInfo = DAO_TYPE_INSERT_ONE_OFFSET + 4,
     InsertDataCommitInfo = DAO_TYPE_INSERT_ONE_OFFSET + 5,
+    InsertTableTag = DAO_TYPE_INSERT_ONE_OFFSET + 6,
+    InsertTableBranch = DAO_TYPE_INSERT_ONE_OFFSET + 7,
 
     # // ==== Transaction Insert List ====
     TransactionInsertPartitionInfo = DAO_TYPE_TRANSACTION_INSERT_LIST_OFFSET,
     TransactionInsertDataCommitInfo = DAO_TYPE_TRANSACTION_INSERT_LIST_OFFSET + 1,
+    TransactionInsertTable = DAO_TYPE_TRANSACTION_INSERT_LIST_OFFSET + 2,
 
     # // ==== Query SCALAR ====
     GetLatestTimestampFromPartitionInfo = DAO_TYPE_QUERY_SCALAR_OFFSET,
@@ -87,3 +99,11 @@ class DaoType:
     DeleteDataCommitInfoByTableIdAndPartitionDescAndCommitIdList = DAO_TYPE_UPDATE_OFFSET + 13,
     DeleteDataCommitInfoByTableIdAndPartitionDesc = DAO_TYPE_UPDATE_OFFSET + 14,
     DeleteDataCommitInfoByTableId = DAO_TYPE_UPDATE_OFFSET + 15,
+    # // Update TableTag
+    DeleteTableTagByTableIdAndTagName = DAO_TYPE_UPDATE_OFFSET + 16,
+    DeleteTableTagByTableId = DAO_TYPE_UPDATE_OFFSET + 17,
+    # // Update PartitionInfo
+    DeletePartitionInfoByTableIdAndPartitionDescAndVersion = DAO_TYPE_UPDATE_OFFSET + 18,
+    # // Update TableBranch
+    DeleteTableBranchByTableIdAndBranchName = DAO_TYPE_UPDATE_OFFSET + 19,
+    DeleteTableBranchByTableId = DAO_TYPE_UPDATE_OFFSET + 20,
@@ -0,0 +1,69 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+
+use lakesoul_metadata::{branch_table_id, MetaDataClientRef};
+use proto::proto::entity::{PartitionInfo, TableInfo};
+use tracing::debug;
+
+use crate::catalog::{added_files, delete_data_files};
+use crate::error::Result;
+
+/// Delete branch `branch_name` of the table of `table_info` from the metadata, and then the files written to the branch,
+/// i.e. the files added by its data commits which are not shared with the versions the branch is forked from.
+/// Returns whether the branch existed.
+pub(crate) async fn delete_branch(
+    client: MetaDataClientRef,
+    table_info: &TableInfo,
+    branch_name: &str,
+) -> Result<bool> {
+    let Some(table_branch) = client.get_branch(&table_info.table_id, branch_name).await? else {
+        return Ok(false);
+    };
+    let branch_id = branch_table_id(&table_info.table_id, branch_name);
+    let mut files = HashSet::new();
+    for partition_info in client.get_all_partition_info(&branch_id).await? {
+        let forked_version = table_branch
+            .partition_versions
+            .get(&partition_info.partition_desc)
+            .copied();
+        let (forked, written): (Vec<_>, Vec<_>) = client
+            .get_all_versions_of_partition(&branch_id, &partition_info.partition_desc)
+            .await?
+            .into_iter()
+            .partition(|version| Some(version.version) == forked_version);
+        let forked_files = added_files(&client, &merge_snapshots(&partition_info, &forked)).await?;
+        files.extend(
+            added_files(&client, &merge_snapshots(&partition_info, &written))
+                .await?
+                .into_iter()
+                .filter(|path| !forked_files.contains(path)),
+        );
+    }
+    debug!(
+        "delete branch {} of table {} with {} files",
+        branch_name,
+        table_info.table_name,
+        files.len()
+    );
+
+    client.delete_branch(&table_info.table_id, branch_name).await?;
+    delete_data_files(table_info, &files.into_iter().collect::<Vec<_>>()).await?;
+    Ok(true)
+}
+
+/// The partition of `partition_info` with the data commits of all the `versions`, each once.
+fn merge_snapshots(partition_info: &PartitionInfo, versions: &[PartitionInfo]) -> PartitionInfo {
+    let mut commit_ids = HashSet::new();
+    PartitionInfo {
+        snapshot: versions
+            .iter()
+            .flat_map(|version| version.snapshot.iter())
+            .filter(|commit_id| commit_ids.insert((commit_id.high, commit_id.low)))
+            .cloned()
+            .collect(),
+        ..partition_info.clone()
+    }
+}
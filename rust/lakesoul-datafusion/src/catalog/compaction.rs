@@ -0,0 +1,160 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use arrow::datatypes::{Schema, SchemaRef};
+use datafusion::sql::TableReference;
+use lakesoul_io::lakesoul_reader::LakeSoulReader;
+use lakesoul_io::lakesoul_writer::{AsyncBatchWriter, MultiPartAsyncWriter};
+use lakesoul_metadata::transfusion::DataFileInfo;
+use lakesoul_metadata::MetaDataClientRef;
+use proto::proto::entity::{CommitOp, DataFileOp, FileOp, PartitionInfo, TableInfo};
+use rand::distributions::DistString;
+use tracing::debug;
+
+use crate::catalog::{
+    create_data_commit_info, data_file_name, parse_table_info_partitions, partition_desc_matches,
+    partition_desc_to_sub_path,
+};
+use crate::error::{LakeSoulError, Result};
+use crate::lakesoul_table::helpers::create_io_config_builder_from_table_info;
+use crate::serialize::arrow_java::schema_from_metadata_str;
+
+/// Compact the partitions of `table_name` matching `condition`.
+///
+/// `condition` has the same form as a partition desc, e.g. `range=1` or `date=20240101,region=cn`,
+/// and an empty `condition` compacts every partition of the table.
+pub(crate) async fn compaction(client: MetaDataClientRef, table_name: &str, condition: &str) -> Result<()> {
+    compaction_with_merge_op(client, table_name, condition, HashMap::new()).await
+}
+
+/// Same as [compaction], but merges the rows sharing a primary key with `merge_operators`
+/// (column name -> merge operator name, e.g. `SumAll`, `JoinedLastByComma`).
+pub(crate) async fn compaction_with_merge_op(
+    client: MetaDataClientRef,
+    table_name: &str,
+    condition: &str,
+    merge_operators: HashMap<String, String>,
+) -> Result<()> {
+    let table_ref = TableReference::from(table_name);
+    let table_info = Arc::new(
+        client
+            .get_table_info_by_table_name(table_ref.table(), table_ref.schema().unwrap_or("default"))
+            .await?,
+    );
+    let partition_filter = parse_compaction_condition(condition)?;
+
+    for partition_info in client.get_all_partition_info(&table_info.table_id).await? {
+        if partition_info.snapshot.is_empty()
+            || !partition_desc_matches(&partition_info.partition_desc, &partition_filter)
+        {
+            continue;
+        }
+        compact_partition(client.clone(), table_info.clone(), partition_info, &merge_operators).await?;
+    }
+    Ok(())
+}
+
+async fn compact_partition(
+    client: MetaDataClientRef,
+    table_info: Arc<TableInfo>,
+    partition_info: PartitionInfo,
+    merge_operators: &HashMap<String, String>,
+) -> Result<()> {
+    let (range_partitions, primary_keys) = parse_table_info_partitions(table_info.partitions.clone())?;
+
+    // files of a table without primary keys are not bucketed, so all of them are merged together
+    let mut bucketed_files = BTreeMap::<isize, Vec<String>>::new();
+    for path in client.get_data_files_of_single_partition(&partition_info).await? {
+        let bucket_id = match primary_keys.is_empty() {
+            true => 0,
+            false => DataFileInfo::parse_bucket_id(&path).unwrap_or(-1),
+        };
+        bucketed_files.entry(bucket_id).or_default().push(path);
+    }
+    if bucketed_files.values().all(|files| files.len() <= 1) {
+        debug!("partition {} is already compacted", partition_info.partition_desc);
+        return Ok(());
+    }
+
+    // range columns are stored in the path instead of the data files
+    let table_schema = schema_from_metadata_str(&table_info.table_schema);
+    let file_schema = SchemaRef::new(Schema::new(
+        table_schema
+            .fields()
+            .iter()
+            .filter(|field| !range_partitions.contains(field.name()))
+            .cloned()
+            .collect::<Vec<_>>(),
+    ));
+
+    let write_id = rand::distributions::Alphanumeric.sample_string(&mut rand::thread_rng(), 16);
+    let mut file_ops = Vec::with_capacity(bucketed_files.len());
+    for (bucket_id, files) in bucketed_files {
+        let builder = merge_operators.iter().fold(
+            create_io_config_builder_from_table_info(table_info.clone())?
+                .with_range_partitions(vec![])
+                .with_schema(file_schema.clone()),
+            |builder, (column, merge_op)| builder.with_merge_op(column.clone(), merge_op.clone()),
+        );
+        let file_absolute_path = format!(
+            "{}{}{}",
+            table_info.table_path,
+            partition_desc_to_sub_path(&partition_info.partition_desc),
+            data_file_name(&write_id, bucket_id)
+        );
+
+        let mut reader = LakeSoulReader::new(builder.clone().with_files(files).build())?;
+        reader.start().await?;
+        let mut writer = Box::new(
+            MultiPartAsyncWriter::try_new(builder.with_files(vec![file_absolute_path.clone()]).build()).await?,
+        );
+        while let Some(batch) = reader.next_rb().await {
+            writer.write_record_batch(batch?).await?;
+        }
+        writer.flush_and_close().await?;
+
+        file_ops.push(DataFileOp {
+            file_op: FileOp::Add as i32,
+            path: file_absolute_path,
+            ..Default::default()
+        });
+    }
+
+    debug!(
+        "compact partition {} of table {} into {} files",
+        partition_info.partition_desc,
+        table_info.table_name,
+        file_ops.len()
+    );
+    client
+        .commit_data_commit_info_with_read_partition_info(
+            create_data_commit_info(
+                table_info.table_id.clone(),
+                partition_info.partition_desc.clone(),
+                file_ops,
+                CommitOp::CompactionCommit,
+            )?,
+            vec![partition_info],
+        )
+        .await?;
+    Ok(())
+}
+
+fn parse_compaction_condition(condition: &str) -> Result<Vec<(String, String)>> {
+    condition
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| match part.split_once('=') {
+            Some((column, value)) => Ok((column.trim().to_string(), value.trim().to_string())),
+            None => Err(LakeSoulError::Internal(format!(
+                "invalid compaction condition {}, expected column=value",
+                condition
+            ))),
+        })
+        .collect()
+}
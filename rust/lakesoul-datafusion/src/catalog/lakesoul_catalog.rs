@@ -2,18 +2,16 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::catalog::LakeSoulNamespace;
+use crate::catalog::{block_on, LakeSoulNamespace};
 use datafusion::catalog::schema::SchemaProvider;
 use datafusion::catalog::CatalogProvider;
 use datafusion::error::{DataFusionError, Result};
 use datafusion::prelude::SessionContext;
-use lakesoul_metadata::error::LakeSoulMetaDataError;
 use lakesoul_metadata::MetaDataClientRef;
 use proto::proto::entity::Namespace;
 use std::any::Any;
 use std::fmt::{Debug, Formatter};
 use std::sync::{Arc, RwLock};
-use tokio::runtime::Handle;
 
 /// A metadata wrapper
 /// may need a lock
@@ -44,13 +42,45 @@ impl LakeSoulCatalog {
         self.context.clone()
     }
 
-    fn get_all_namespace(&self) -> crate::error::Result<Vec<Namespace>> {
+    /// Drop the namespace `name`, and all of its tables if `cascade`, see [CatalogProvider::deregister_schema].
+    /// Returns `None` if the namespace does not exist.
+    ///
+    /// The namespace is kept if any of its tables fails to be dropped, see [LakeSoulNamespace::delete_all_tables].
+    pub async fn drop_schema(&self, name: &str, cascade: bool) -> Result<Option<LakeSoulNamespace>> {
         let client = self.metadata_client.clone();
-        futures::executor::block_on(async move {
-            Handle::current()
-                .spawn(async move { Ok(client.get_all_namespace().await?) })
-                .await?
-        })
+        if !client
+            .get_all_namespace()
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?
+            .iter()
+            .any(|np| np.namespace == name)
+        {
+            return Ok(None);
+        }
+        let namespace = LakeSoulNamespace::new(client.clone(), self.context.clone(), name);
+        if !client
+            .get_all_table_name_id_by_namespace(name)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?
+            .is_empty()
+        {
+            if !cascade {
+                return Err(DataFusionError::Execution(format!(
+                    "Cannot drop non-empty namespace '{}' without CASCADE",
+                    name
+                )));
+            }
+            namespace.delete_all_tables().await?;
+        }
+        client
+            .delete_namespace_by_namespace(name)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok(Some(namespace))
+    }
+
+    fn get_all_namespace(&self) -> crate::error::Result<Vec<Namespace>> {
+        Ok(block_on(self.metadata_client.get_all_namespace())??)
     }
 }
 
@@ -104,12 +134,7 @@ impl CatalogProvider for LakeSoulCatalog {
             comment: "created by lakesoul-datafusion".into(),
             domain: "public".into(),
         };
-        let _ = futures::executor::block_on(async move {
-            Handle::current()
-                .spawn(async move { client.create_namespace(np).await })
-                .await
-                .map_err(|e| LakeSoulMetaDataError::Other(Box::new(e)))?
-        });
+        let _ = block_on(client.create_namespace(np));
         Ok(schema)
     }
 
@@ -121,29 +146,9 @@ impl CatalogProvider for LakeSoulCatalog {
     ///
     /// Implementations of this method should return None if schema with `name`
     /// does not exist.
-    fn deregister_schema(&self, _name: &str, _cascade: bool) -> Result<Option<Arc<dyn SchemaProvider>>> {
-        // Not supported
-        // let _guard = self.catalog_lock.write();
-        // let client = self.metadata_client.clone();
-        // let schema: Option<Arc<dyn SchemaProvider>> = {
-        //     match self.get_all_namespace() {
-        //         Ok(v) if v.iter().any(|np| np.namespace == name) => Some(Arc::new(LakeSoulNamespace::new(
-        //             self.metadata_client.clone(),
-        //             self.context.clone(),
-        //             name,
-        //         ))),
-        //         _ => None,
-        //     }
-        // };
-        // let namespace = name.to_string();
-        // if let Some(s) = schema {
-        //     if !s.table_names().is_empty() && !cascade {
-        //         return Err(DataFusionError::External("can not delete".into()));
-        //     }
-        //     // delete all tables
-        //     return Ok(Some(s));
-        // }
-        // return Ok(None);
-        Err(DataFusionError::NotImplemented("Not supported".into()))
+    fn deregister_schema(&self, name: &str, cascade: bool) -> Result<Option<Arc<dyn SchemaProvider>>> {
+        let _guard = self.catalog_lock.write();
+        let namespace = block_on(self.drop_schema(name, cascade))??;
+        Ok(namespace.map(|namespace| Arc::new(namespace) as Arc<dyn SchemaProvider>))
     }
 }
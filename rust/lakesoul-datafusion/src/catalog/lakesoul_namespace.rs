@@ -2,9 +2,15 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::catalog::create_io_config_builder;
+use crate::catalog::{
+    block_on, create_io_config_builder, create_table_in_namespace, delete_table_data, DEFAULT_HASH_BUCKET_NUM,
+};
+use crate::datasource::system_table_provider::{split_system_table, LakeSoulSystemTableProvider, SystemTableType};
+use crate::datasource::table_provider::{split_table_tag, TableSnapshot};
+use crate::lakesoul_table::LakeSoulTable;
 use async_trait::async_trait;
 use datafusion::catalog::schema::SchemaProvider;
+use datafusion::common::Constraint;
 use datafusion::datasource::file_format::parquet::ParquetFormat;
 use datafusion::datasource::TableProvider;
 use datafusion::error::DataFusionError;
@@ -17,13 +23,12 @@ use lakesoul_metadata::MetaDataClientRef;
 use std::any::Any;
 use std::fmt::{Debug, Formatter};
 use std::sync::Arc;
-use tokio::runtime::Handle;
 use tokio::sync::RwLock;
 use tracing::debug;
 use tracing::field::debug;
 
 /// A [`SchemaProvider`] that query pg to automatically discover tables.
-/// Due to the restriction of datafusion 's api, "CREATE [EXTERNAL] Table ... " is not supported.
+/// Due to the restriction of datafusion 's api, "CREATE EXTERNAL Table ... " is not supported.
 /// May have race condition
 pub struct LakeSoulNamespace {
     metadata_client: MetaDataClientRef,
@@ -55,10 +60,120 @@ impl LakeSoulNamespace {
         &self.namespace
     }
 
-    /// Dangerous
-    /// Should use transaction?
-    fn _delete_all_tables(&self) -> Result<()> {
-        unimplemented!()
+    /// The [LakeSoulTableProvider](crate::datasource::table_provider::LakeSoulTableProvider)
+    /// reading the snapshot of `table_name` pinned by tag `tag_name`.
+    async fn tag_table(&self, table_name: &str, tag_name: &str) -> Option<Arc<dyn TableProvider>> {
+        let table_info = self
+            .metadata_client
+            .get_table_info_by_table_name(table_name, &self.namespace)
+            .await
+            .ok()?;
+        self.metadata_client
+            .get_tag(&table_info.table_id, tag_name)
+            .await
+            .ok()
+            .flatten()?;
+        debug!(
+            "call table() on tag {} of table: {}.{}",
+            tag_name, &self.namespace, table_name
+        );
+        let table = LakeSoulTable::try_new_with_client_and_table_info(self.metadata_client.clone(), table_info)
+            .await
+            .ok()?;
+        let provider = table
+            .as_provider(&self.context.state(), Some(TableSnapshot::Tag(tag_name.to_string())))
+            .await
+            .ok()?;
+        Some(Arc::new(provider))
+    }
+
+    /// The [LakeSoulTableProvider](crate::datasource::table_provider::LakeSoulTableProvider)
+    /// reading the latest versions of branch `branch_name` of `table_name`.
+    async fn branch_table(&self, table_name: &str, branch_name: &str) -> Option<Arc<dyn TableProvider>> {
+        let table = LakeSoulTable::for_namespace_and_name_with_client(
+            self.metadata_client.clone(),
+            &self.namespace,
+            table_name,
+        )
+        .await
+        .ok()?
+        .branch(branch_name)
+        .await
+        .ok()?;
+        debug!(
+            "call table() on branch {} of table: {}.{}",
+            branch_name, &self.namespace, table_name
+        );
+        let provider = table.as_provider(&self.context.state(), None).await.ok()?;
+        Some(Arc::new(provider))
+    }
+
+    /// The [LakeSoulSystemTableProvider] of type `table_type` of `table_name`.
+    async fn system_table(&self, table_name: &str, table_type: SystemTableType) -> Option<Arc<dyn TableProvider>> {
+        let table_info = self
+            .metadata_client
+            .get_table_info_by_table_name(table_name, &self.namespace)
+            .await
+            .ok()?;
+        debug!(
+            "call table() on system table {} of table: {}.{}",
+            table_type.name(),
+            &self.namespace,
+            table_name
+        );
+        Some(Arc::new(LakeSoulSystemTableProvider::new(
+            self.metadata_client.clone(),
+            Arc::new(table_info),
+            table_type,
+        )))
+    }
+
+    /// Drop all the tables of this namespace, deleting both their data directories and their metadata.
+    ///
+    /// The data of a table is deleted before its metadata, so a table failing to be dropped is still in the namespace
+    /// and can be dropped again. The other tables are dropped anyway, and the error names the tables kept.
+    pub(crate) async fn delete_all_tables(&self) -> Result<()> {
+        let _guard = self.namespace_lock.write().await;
+        let table_name_ids = self
+            .metadata_client
+            .get_all_table_name_id_by_namespace(&self.namespace)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let mut failures = vec![];
+        for table_name_id in table_name_ids {
+            let table_info = match self
+                .metadata_client
+                .get_table_info_by_table_name(&table_name_id.table_name, &self.namespace)
+                .await
+            {
+                Ok(table_info) => table_info,
+                Err(LakeSoulMetaDataError::NotFound(_)) => continue,
+                Err(e) => {
+                    failures.push(format!("{}: {}", table_name_id.table_name, e));
+                    continue;
+                }
+            };
+            debug!("drop table {}.{}", &self.namespace, &table_info.table_name);
+            let dropped = match delete_table_data(&table_info).await {
+                Ok(()) => self
+                    .metadata_client
+                    .delete_table_by_table_info_cascade(&table_info)
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            if let Err(e) = dropped {
+                failures.push(format!("{}: {}", table_info.table_name, e));
+            }
+        }
+        match failures.is_empty() {
+            true => Ok(()),
+            false => Err(DataFusionError::Execution(format!(
+                "Failed to drop tables of namespace '{}', which are kept: {}",
+                self.namespace,
+                failures.join(", ")
+            ))),
+        }
     }
 }
 
@@ -79,27 +194,35 @@ impl SchemaProvider for LakeSoulNamespace {
         let client = self.metadata_client.clone();
         let np = self.namespace.clone();
         let lock = self.namespace_lock.clone();
-        futures::executor::block_on(async move {
-            Handle::current()
-                .spawn(async move {
-                    let _guard = lock.read().await;
-                    client
-                        .get_all_table_name_id_by_namespace(&np)
-                        .await
-                        .expect("get all table name failed")
-                })
+        block_on(async move {
+            let _guard = lock.read().await;
+            client
+                .get_all_table_name_id_by_namespace(&np)
                 .await
-                .expect("spawn failed")
+                .expect("get all table name failed")
         })
+        .expect("block on metadata failed")
         .into_iter()
         .map(|v| v.table_name)
         .collect()
     }
 
     /// Search table by name
-    /// return LakeSoulListing table
+    /// return LakeSoulListing table,
+    /// or the [LakeSoulTableProvider](crate::datasource::table_provider::LakeSoulTableProvider)
+    /// of the tag, or else of the branch, for `{table_name}@{tag_name}` or `{table_name}@{branch_name}`,
+    /// or the [LakeSoulSystemTableProvider] for `{table_name}${system_table}`
     async fn table(&self, name: &str) -> Option<Arc<dyn TableProvider>> {
         let _guard = self.namespace_lock.read().await;
+        if let Some((table_name, table_type)) = split_system_table(name) {
+            return self.system_table(table_name, table_type).await;
+        }
+        if let (table_name, Some(tag_name)) = split_table_tag(name) {
+            return match self.tag_table(table_name, tag_name).await {
+                Some(provider) => Some(provider),
+                None => self.branch_table(table_name, tag_name).await,
+            };
+        }
         if self
             .metadata_client
             .get_table_info_by_table_name(name, &self.namespace)
@@ -142,10 +265,54 @@ impl SchemaProvider for LakeSoulNamespace {
 
     /// If supported by the implementation, adds a new table to this schema.
     /// If a table of the same name existed before, it returns "Table already exists" error.
-    #[allow(unused_variables)]
+    ///
+    /// This is called by `CREATE TABLE [AS SELECT]` of DataFusion with a [MemTable](datafusion::datasource::MemTable),
+    /// so a LakeSoul table is created by the schema and primary key of `table`, and the rows of `table` are inserted.
+    /// See [crate::planner::sql] for creating LakeSoul tables with range partitions and table options.
     fn register_table(&self, name: String, table: Arc<dyn TableProvider>) -> Result<Option<Arc<dyn TableProvider>>> {
-        // the type info of dyn TableProvider is not enough or use AST??????
-        unimplemented!("schema provider does not support registering tables")
+        let client = self.metadata_client.clone();
+        let np = self.namespace.clone();
+        let cxt = self.context.clone();
+        let lock = self.namespace_lock.clone();
+        block_on(async move {
+            let _guard = lock.write().await;
+            if client.get_table_name_id_by_table_name(&name, &np).await.is_ok() {
+                return Err(DataFusionError::Execution(format!(
+                    "Table '{}.{}' already exists",
+                    np, name
+                )));
+            }
+            let schema = table.schema();
+            let primary_keys = table
+                .constraints()
+                .into_iter()
+                .flat_map(|constraints| constraints.iter())
+                .filter_map(|constraint| match constraint {
+                    Constraint::PrimaryKey(indices) => Some(indices),
+                    Constraint::Unique(_) => None,
+                })
+                .flatten()
+                .map(|idx| schema.field(*idx).name().clone())
+                .collect::<Vec<_>>();
+            let hash_bucket_num = match primary_keys.is_empty() {
+                true => None,
+                false => Some(DEFAULT_HASH_BUCKET_NUM),
+            };
+            create_table_in_namespace(client.clone(), &np, &name, schema, &[], &primary_keys, hash_bucket_num)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+            // the rows are streamed into the hash sink of the table rather than collected,
+            // with the client of this namespace which has the metadata of the table
+            let lakesoul_table = LakeSoulTable::for_namespace_and_name_with_client(client, &np, &name)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            lakesoul_table
+                .upsert_dataframe(cxt.read_table(table)?)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            Ok(None)
+        })?
     }
     /// If supported by the implementation, removes an existing table from this schema and returns it.
     /// If no table of that name exists, returns Ok(None).
@@ -156,54 +323,49 @@ impl SchemaProvider for LakeSoulNamespace {
         let np = self.namespace.clone();
         let cxt = self.context.clone();
         let lock = self.namespace_lock.clone();
-        futures::executor::block_on(async move {
-            Handle::current()
-                .spawn(async move {
-                    // get table info
-                    let _guard = lock.write().await;
-                    match client.get_table_info_by_table_name(&table_name, &np).await {
-                        Ok(table_info) => {
-                            let config;
-                            if let Ok(config_builder) =
-                                create_io_config_builder(client.clone(), Some(&table_name), true, &np).await
-                            {
-                                config = config_builder.build();
-                            } else {
-                                return Err(DataFusionError::External("get table provider config failed".into()));
-                            }
-                            // Maybe should change
-                            let file_format = Arc::new(LakeSoulParquetFormat::new(
-                                Arc::new(ParquetFormat::new()),
-                                config.clone(),
-                            ));
-                            if let Ok(table_provider) = LakeSoulListingTable::new_with_config_and_format(
-                                &cxt.state(),
-                                config,
-                                file_format,
-                                // care this
-                                false,
-                            )
+        block_on(async move {
+            // get table info
+            let _guard = lock.write().await;
+            match client.get_table_info_by_table_name(&table_name, &np).await {
+                Ok(table_info) => {
+                    let config;
+                    if let Ok(config_builder) =
+                        create_io_config_builder(client.clone(), Some(&table_name), true, &np).await
+                    {
+                        config = config_builder.build();
+                    } else {
+                        return Err(DataFusionError::External("get table provider config failed".into()));
+                    }
+                    // Maybe should change
+                    let file_format = Arc::new(LakeSoulParquetFormat::new(
+                        Arc::new(ParquetFormat::new()),
+                        config.clone(),
+                    ));
+                    if let Ok(table_provider) = LakeSoulListingTable::new_with_config_and_format(
+                        &cxt.state(),
+                        config,
+                        file_format,
+                        // care this
+                        false,
+                    )
+                    .await
+                    {
+                        debug!("get table provider success");
+                        client
+                            .delete_table_by_table_info_cascade(&table_info)
                             .await
-                            {
-                                debug!("get table provider success");
-                                client
-                                    .delete_table_by_table_info_cascade(&table_info)
-                                    .await
-                                    .map_err(|_| DataFusionError::External("delete table info failed".into()))?;
-                                return Ok(Some(Arc::new(table_provider) as Arc<dyn TableProvider>));
-                            }
-                            debug("get table provider fail");
-                            Err(DataFusionError::External("get table provider failed".into()))
-                        }
-                        Err(e) => match e {
-                            LakeSoulMetaDataError::NotFound(_) => Ok(None),
-                            _ => Err(DataFusionError::External("get table info failed".into())),
-                        },
+                            .map_err(|_| DataFusionError::External("delete table info failed".into()))?;
+                        return Ok(Some(Arc::new(table_provider) as Arc<dyn TableProvider>));
                     }
-                })
-                .await
-                .map_err(|e| DataFusionError::External(Box::new(e)))?
-        })
+                    debug("get table provider fail");
+                    Err(DataFusionError::External("get table provider failed".into()))
+                }
+                Err(e) => match e {
+                    LakeSoulMetaDataError::NotFound(_) => Ok(None),
+                    _ => Err(DataFusionError::External("get table info failed".into())),
+                },
+            }
+        })?
     }
 
     fn table_exist(&self, name: &str) -> bool {
@@ -211,18 +373,14 @@ impl SchemaProvider for LakeSoulNamespace {
         let client = self.metadata_client.clone();
         let np = self.namespace.clone();
         let lock = self.namespace_lock.clone();
-        futures::executor::block_on(async move {
-            Handle::current()
-                .spawn(async move {
-                    let _guard = lock.read().await;
-                    client
-                        .get_all_table_name_id_by_namespace(&np)
-                        .await
-                        .expect("get table name failed")
-                })
+        block_on(async move {
+            let _guard = lock.read().await;
+            client
+                .get_all_table_name_id_by_namespace(&np)
                 .await
-                .expect("spawn failed")
+                .expect("get table name failed")
         })
+        .expect("block on metadata failed")
         .into_iter()
         .map(|v| v.table_name)
         .any(|s| s == name)
@@ -2,15 +2,21 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use datafusion::catalog::TableReference;
+use arrow::datatypes::SchemaRef;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::error::DataFusionError;
+use futures::TryStreamExt;
+use std::collections::HashMap;
 use std::env;
 use std::fmt::Debug;
+use std::future::Future;
 use std::sync::Arc;
 use std::time::SystemTime;
+use tokio::runtime::{Handle, RuntimeFlavor};
 
-use lakesoul_io::lakesoul_io_config::{LakeSoulIOConfig, LakeSoulIOConfigBuilder};
+use lakesoul_io::lakesoul_io_config::{create_session_context, LakeSoulIOConfig, LakeSoulIOConfigBuilder};
 use lakesoul_metadata::MetaDataClientRef;
-use proto::proto::entity::{CommitOp, DataCommitInfo, DataFileOp, FileOp, TableInfo, Uuid};
+use proto::proto::entity::{CommitOp, DataCommitInfo, DataFileOp, FileOp, PartitionInfo, TableInfo, Uuid};
 
 use crate::lakesoul_table::helpers::create_io_config_builder_from_table_info;
 use crate::serialize::arrow_java::ArrowJavaSchema;
@@ -20,52 +26,89 @@ use crate::error::{LakeSoulError, Result};
 // pub mod lakesoul_sink;
 // pub mod lakesoul_source;
 mod lakesoul_catalog;
-//  used in catalog_test, but still say unused_imports, I think it is a bug about rust-lint.
-// this is a workaround
-#[cfg(test)]
 pub use lakesoul_catalog::*;
 mod lakesoul_namespace;
 pub use lakesoul_namespace::*;
+mod branch;
+pub(crate) use branch::*;
+mod compaction;
+pub(crate) use compaction::*;
+mod orphan_files;
+pub use orphan_files::*;
+mod schema_evolution;
+pub(crate) use schema_evolution::*;
+mod snapshot_expiration;
+pub use snapshot_expiration::*;
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// hash bucket number of the tables with primary keys, if not specified on creating
+pub(crate) const DEFAULT_HASH_BUCKET_NUM: usize = 4;
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct LakeSoulTableProperty {
     #[serde(rename = "hashBucketNum")]
     pub hash_bucket_num: Option<usize>,
+    /// previous name -> current name of the columns renamed by `ALTER TABLE ... RENAME COLUMN`,
+    /// for reading the files written before renaming
+    #[serde(rename = "renamedColumns", default, skip_serializing_if = "HashMap::is_empty")]
+    pub renamed_columns: HashMap<String, String>,
+    /// names of the columns dropped by `ALTER TABLE ... DROP COLUMN`, which are still in the files written before dropping
+    #[serde(rename = "droppedColumns", default, skip_serializing_if = "Vec::is_empty")]
+    pub dropped_columns: Vec<String>,
 }
 
 pub(crate) async fn create_table(client: MetaDataClientRef, table_name: &str, config: LakeSoulIOConfig) -> Result<()> {
+    create_table_in_namespace(
+        client,
+        "default",
+        table_name,
+        config.target_schema(),
+        config.range_partitions_slice(),
+        config.primary_keys_slice(),
+        Some(DEFAULT_HASH_BUCKET_NUM),
+    )
+    .await
+}
+
+/// Create a LakeSoul table located at `$PWD/{namespace}/{table_name}`.
+pub(crate) async fn create_table_in_namespace(
+    client: MetaDataClientRef,
+    namespace: &str,
+    table_name: &str,
+    schema: SchemaRef,
+    range_partitions: &[String],
+    primary_keys: &[String],
+    hash_bucket_num: Option<usize>,
+) -> Result<()> {
+    if let Some(column) = range_partitions
+        .iter()
+        .chain(primary_keys)
+        .find(|column| schema.field_with_name(column).is_err())
+    {
+        return Err(LakeSoulError::DataFusionError(DataFusionError::Plan(format!(
+            "Partition column {} is not found in the schema of table {}",
+            column, table_name
+        ))));
+    }
     client
         .create_table(TableInfo {
             table_id: format!("table_{}", uuid::Uuid::new_v4()),
             table_name: table_name.to_string(),
             table_path: format!(
-                "file:{}/default/{}",
+                "file:{}/{}/{}",
                 env::current_dir()
                     .unwrap()
                     .to_str()
                     .ok_or(LakeSoulError::Internal("can not get $TMPDIR".to_string()))?,
+                namespace,
                 table_name
             ),
-            table_schema: serde_json::to_string::<ArrowJavaSchema>(&config.target_schema().into())?,
-            table_namespace: "default".to_string(),
+            table_schema: serde_json::to_string::<ArrowJavaSchema>(&schema.into())?,
+            table_namespace: namespace.to_string(),
             properties: serde_json::to_string(&LakeSoulTableProperty {
-                hash_bucket_num: Some(4),
+                hash_bucket_num,
+                ..Default::default()
             })?,
-            partitions: format!(
-                "{};{}",
-                config
-                    .range_partitions_slice()
-                    .iter()
-                    .map(String::as_str)
-                    .collect::<Vec<_>>()
-                    .join(","),
-                config
-                    .primary_keys_slice()
-                    .iter()
-                    .map(String::as_str)
-                    .collect::<Vec<_>>()
-                    .join(",")
-            ),
+            partitions: format!("{};{}", range_partitions.join(","), primary_keys.join(",")),
             domain: "public".to_string(),
         })
         .await?;
@@ -91,6 +134,45 @@ pub(crate) async fn create_io_config_builder(
     }
 }
 
+/// Run `future` to completion in a sync method of [LakeSoulCatalog] or [LakeSoulNamespace] called in the runtime of
+/// a session. The thread of a multi-thread runtime is handed over to its other tasks while blocked, but a blocked
+/// current-thread runtime could never run the connection of the metadata client, so this is an error instead.
+pub(crate) fn block_on<F: Future>(future: F) -> datafusion::error::Result<F::Output> {
+    let handle = Handle::try_current().map_err(|e| DataFusionError::External(Box::new(e)))?;
+    match handle.runtime_flavor() {
+        RuntimeFlavor::CurrentThread => Err(DataFusionError::NotImplemented(
+            "LakeSoul catalog requires a multi-thread tokio runtime".to_string(),
+        )),
+        _ => Ok(tokio::task::block_in_place(|| handle.block_on(future))),
+    }
+}
+
+/// Delete all the files under the path of a table, and the directory of the table on the local filesystem.
+pub(crate) async fn delete_table_data(table_info: &TableInfo) -> Result<()> {
+    let mut config = create_io_config_builder_from_table_info(Arc::new(table_info.clone()))?.build();
+    // register the object store of the table path
+    let sess_ctx = create_session_context(&mut config)?;
+    let table_path = ListingTableUrl::parse(&config.prefix)?;
+    let store = sess_ctx.runtime_env().object_store(&table_path)?;
+    let locations = store
+        .list(Some(table_path.prefix()))
+        .await
+        .map_err(DataFusionError::from)?
+        .map_ok(|meta| meta.location)
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(DataFusionError::from)?;
+    for location in locations {
+        store.delete(&location).await.map_err(DataFusionError::from)?;
+    }
+    if let Ok(dir) = AsRef::<url::Url>::as_ref(&table_path).to_file_path() {
+        if dir.exists() {
+            std::fs::remove_dir_all(dir).map_err(|e| DataFusionError::External(Box::new(e)))?;
+        }
+    }
+    Ok(())
+}
+
 pub(crate) fn parse_table_info_partitions(partitions: String) -> Result<(Vec<String>, Vec<String>)> {
     let (range_keys, hash_keys) = partitions.split_at(
         partitions
@@ -114,21 +196,18 @@ pub(crate) fn parse_table_info_partitions(partitions: String) -> Result<(Vec<Str
     ))
 }
 
+/// Append `files` to partition `partition_desc` of the table, or the branch, of `table_id`.
 pub(crate) async fn commit_data(
     client: MetaDataClientRef,
-    table_name: &str,
+    table_id: &str,
     partition_desc: String,
     files: &[String],
 ) -> Result<()> {
-    let table_ref = TableReference::from(table_name);
-    let table_name_id = client
-        .get_table_name_id_by_table_name(table_ref.table(), table_ref.schema().unwrap_or("default"))
-        .await?;
     client
-        .commit_data_commit_info(DataCommitInfo {
-            table_id: table_name_id.table_id,
+        .commit_data_commit_info(create_data_commit_info(
+            table_id.to_string(),
             partition_desc,
-            file_ops: files
+            files
                 .iter()
                 .map(|file| DataFileOp {
                     file_op: FileOp::Add as i32,
@@ -136,15 +215,105 @@ pub(crate) async fn commit_data(
                     ..Default::default()
                 })
                 .collect(),
-            commit_op: CommitOp::AppendCommit as i32,
-            timestamp: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64,
-            commit_id: {
-                let (high, low) = uuid::Uuid::new_v4().as_u64_pair();
-                Some(Uuid { high, low })
-            },
-            committed: false,
-            domain: "public".to_string(),
+            CommitOp::AppendCommit,
+        )?)
+        .await?;
+    Ok(())
+}
+
+/// Commit the files written by an overwrite into each partition at once, so that readers never see a half-replaced table.
+///
+/// Each partition in `partitioned_files` and each partition in `replaced_partitions`, which are read before writing,
+/// is replaced by the written files, i.e. the replaced partitions without written files become empty.
+pub(crate) async fn commit_overwrite_data(
+    client: MetaDataClientRef,
+    table_id: &str,
+    partitioned_files: HashMap<String, Vec<String>>,
+    replaced_partitions: Vec<PartitionInfo>,
+) -> Result<()> {
+    // the replaced partitions which are already empty are read but need no commit to be emptied
+    let mut data_commit_info_list = replaced_partitions
+        .iter()
+        .filter(|partition_info| {
+            !partition_info.snapshot.is_empty() && !partitioned_files.contains_key(&partition_info.partition_desc)
+        })
+        .map(|partition_info| {
+            create_data_commit_info(
+                table_id.to_string(),
+                partition_info.partition_desc.clone(),
+                vec![],
+                CommitOp::UpdateCommit,
+            )
         })
+        .collect::<Result<Vec<_>>>()?;
+    for (partition_desc, files) in partitioned_files {
+        data_commit_info_list.push(create_data_commit_info(
+            table_id.to_string(),
+            partition_desc,
+            files
+                .into_iter()
+                .map(|path| DataFileOp {
+                    file_op: FileOp::Add as i32,
+                    path,
+                    ..Default::default()
+                })
+                .collect(),
+            CommitOp::UpdateCommit,
+        )?);
+    }
+    client
+        .commit_data_commit_info_list(data_commit_info_list, replaced_partitions, CommitOp::UpdateCommit)
         .await?;
     Ok(())
 }
+
+pub(crate) fn create_data_commit_info(
+    table_id: String,
+    partition_desc: String,
+    file_ops: Vec<DataFileOp>,
+    commit_op: CommitOp,
+) -> Result<DataCommitInfo> {
+    Ok(DataCommitInfo {
+        table_id,
+        partition_desc,
+        file_ops,
+        commit_op: commit_op as i32,
+        timestamp: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64,
+        commit_id: {
+            let (high, low) = uuid::Uuid::new_v4().as_u64_pair();
+            Some(Uuid { high, low })
+        },
+        committed: false,
+        domain: "public".to_string(),
+    })
+}
+
+/// Directory of a partition relative to the table path, e.g. `range=1,hash=2` -> `/range=1/hash=2/`.
+pub(crate) fn partition_desc_to_sub_path(partition_desc: &str) -> String {
+    if partition_desc == "-5" {
+        "/".to_string()
+    } else {
+        format!("/{}/", partition_desc.replace(',', "/"))
+    }
+}
+
+/// Name of a data file written by `write_id` for the hash bucket `bucket_id`, e.g. `part-{write_id}_0001.parquet`.
+/// A file out of the hash buckets, i.e. of `bucket_id` -1, is named without a bucket id,
+/// so that it neither collides with the file of bucket 0 nor joins bucket 0 when read back.
+pub(crate) fn data_file_name(write_id: &str, bucket_id: isize) -> String {
+    match bucket_id {
+        -1 => format!("part-{}.parquet", write_id),
+        _ => format!("part-{}_{:0>4}.parquet", write_id, bucket_id),
+    }
+}
+
+/// Whether the partition desc has all the `(column, value)` of `partition_values`,
+/// e.g. `date=20240101,region=cn` matches `[("date", "20240101")]`.
+pub(crate) fn partition_desc_matches(partition_desc: &str, partition_values: &[(String, String)]) -> bool {
+    partition_values.iter().all(|(column, value)| {
+        partition_desc
+            .split(',')
+            .filter_map(|part| part.split_once('='))
+            .any(|(name, val)| name == column && val == value)
+    })
+}
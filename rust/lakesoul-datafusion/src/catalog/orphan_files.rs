@@ -0,0 +1,126 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::error::DataFusionError;
+use datafusion::sql::TableReference;
+use futures::TryStreamExt;
+use lakesoul_io::lakesoul_io_config::create_session_context;
+use lakesoul_metadata::{branch_table_id, MetaDataClientRef};
+use proto::proto::entity::{PartitionInfo, Uuid};
+use tracing::debug;
+
+use crate::error::Result;
+use crate::lakesoul_table::helpers::create_io_config_builder_from_table_info;
+
+/// What [remove_orphan_files] has removed, or would remove in a dry run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrphanFiles {
+    /// urls of the orphan files
+    pub files: Vec<String>,
+    /// total size of the orphan files in bytes
+    pub size: usize,
+}
+
+/// Remove the parquet files under the path of `table_name` which are not referenced by any version of the table
+/// or of its branches, e.g. the files left by aborted writes.
+///
+/// Only the files last modified before `older_than` in milliseconds are removed, so that the files of the
+/// writes in progress are kept. With `dry_run`, nothing is deleted and the result only reports the orphan files.
+pub async fn remove_orphan_files(
+    client: MetaDataClientRef,
+    table_name: &str,
+    older_than: i64,
+    dry_run: bool,
+) -> Result<OrphanFiles> {
+    let table_ref = TableReference::from(table_name);
+    let table_info = Arc::new(
+        client
+            .get_table_info_by_table_name(table_ref.table(), table_ref.schema().unwrap_or("default"))
+            .await?,
+    );
+
+    // the files of every data commit of every version of the table and of its branches,
+    // which are written under the path of the table
+    let mut table_ids = vec![table_info.table_id.clone()];
+    table_ids.extend(
+        client
+            .get_all_branches(&table_info.table_id)
+            .await?
+            .iter()
+            .map(|table_branch| branch_table_id(&table_branch.table_id, &table_branch.branch_name)),
+    );
+    let mut referenced = HashSet::new();
+    for table_id in &table_ids {
+        for partition_info in client.get_all_partition_info(table_id).await? {
+            let snapshot = client
+                .get_all_versions_of_partition(table_id, &partition_info.partition_desc)
+                .await?
+                .into_iter()
+                .flat_map(|version| version.snapshot)
+                .map(|commit_id| (commit_id.high, commit_id.low))
+                .collect::<HashSet<_>>();
+            if snapshot.is_empty() {
+                continue;
+            }
+            let data_commit_info_list = client
+                .get_data_commit_info_of_single_partition(&PartitionInfo {
+                    snapshot: snapshot.into_iter().map(|(high, low)| Uuid { high, low }).collect(),
+                    ..partition_info
+                })
+                .await?;
+            for file_op in data_commit_info_list
+                .into_iter()
+                .flat_map(|data_commit_info| data_commit_info.file_ops)
+            {
+                referenced.insert(ListingTableUrl::parse(&file_op.path)?.prefix().clone());
+            }
+        }
+    }
+
+    let mut config = create_io_config_builder_from_table_info(table_info.clone())?.build();
+    // register the object store of the table path
+    let sess_ctx = create_session_context(&mut config)?;
+    let table_path = ListingTableUrl::parse(&table_info.table_path)?;
+    let store = sess_ctx.runtime_env().object_store(&table_path)?;
+    let orphans = store
+        .list(Some(table_path.prefix()))
+        .await
+        .map_err(DataFusionError::from)?
+        .try_filter(|meta| {
+            futures::future::ready(
+                meta.location.extension() == Some("parquet")
+                    && meta.last_modified.timestamp_millis() < older_than
+                    && !referenced.contains(&meta.location),
+            )
+        })
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(DataFusionError::from)?;
+
+    let mut removed = OrphanFiles::default();
+    for meta in orphans {
+        if !dry_run {
+            match store.delete(&meta.location).await {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(e) => return Err(DataFusionError::from(e).into()),
+            }
+        }
+        removed
+            .files
+            .push(format!("{}{}", table_path.object_store().as_str(), meta.location));
+        removed.size += meta.size;
+    }
+    debug!(
+        "remove {} orphan files of {} bytes of table {}, dry run: {}",
+        removed.files.len(),
+        removed.size,
+        table_name,
+        dry_run
+    );
+    Ok(removed)
+}
@@ -0,0 +1,195 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::error::DataFusionError;
+use lakesoul_metadata::MetaDataClientRef;
+use tracing::debug;
+
+use crate::catalog::{parse_table_info_partitions, LakeSoulTableProperty};
+use crate::error::{LakeSoulError, Result};
+use crate::serialize::arrow_java::{schema_from_metadata_str, ArrowJavaSchema};
+
+/// A change of the schema of a LakeSoul table by `ALTER TABLE`.
+///
+/// The data files are never rewritten: the files written before a change are read into the new schema
+/// by name, i.e. a dropped column is ignored, an added column is null and a widened column is casted.
+/// So the name of a dropped column can not be added back, which would read the values of the dropped column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SchemaChange {
+    AddColumn { field: Field, if_not_exists: bool },
+    DropColumn { name: String, if_exists: bool },
+    RenameColumn { from: String, to: String },
+    AlterColumnType { name: String, data_type: DataType },
+}
+
+/// Apply `change` to the schema of table `namespace.table_name`,
+/// which is persisted with the `UpdateTableInfoById` DAO.
+///
+/// The previous names of a renamed column are kept in [LakeSoulTableProperty::renamed_columns],
+/// so that the files written before renaming are still readable, and they can not be used by other columns.
+/// Neither can the names of the dropped columns, which are kept in [LakeSoulTableProperty::dropped_columns].
+pub(crate) async fn alter_table_schema(
+    client: MetaDataClientRef,
+    namespace: &str,
+    table_name: &str,
+    change: SchemaChange,
+) -> Result<()> {
+    let table_info = client.get_table_info_by_table_name(table_name, namespace).await?;
+    let schema = schema_from_metadata_str(&table_info.table_schema);
+    let (range_partitions, primary_keys) = parse_table_info_partitions(table_info.partitions.clone())?;
+    let mut properties = serde_json::from_str::<LakeSoulTableProperty>(&table_info.properties)?;
+
+    let check_column = |name: &String, action: &str| {
+        if schema.field_with_name(name).is_err() {
+            plan_error(format!("Column {} not found in table {}", name, table_name))
+        } else if range_partitions.contains(name) || primary_keys.contains(name) {
+            plan_error(format!(
+                "Cannot {} column {} of table {}, which is a partition column",
+                action, name, table_name
+            ))
+        } else {
+            Ok(())
+        }
+    };
+    let check_new_name = |name: &String| {
+        if schema.field_with_name(name).is_ok() {
+            plan_error(format!("Column {} already exists in table {}", name, table_name))
+        } else if properties.renamed_columns.contains_key(name) {
+            plan_error(format!(
+                "Column name {} was used by a renamed column of table {}",
+                name, table_name
+            ))
+        } else if properties.dropped_columns.contains(name) {
+            plan_error(format!(
+                "Column name {} was used by a dropped column of table {}",
+                name, table_name
+            ))
+        } else {
+            Ok(())
+        }
+    };
+
+    let fields = schema.fields().iter().map(|field| field.as_ref().clone());
+    let (fields, properties_changed) = match change {
+        SchemaChange::AddColumn { field, if_not_exists } => {
+            if if_not_exists && schema.field_with_name(field.name()).is_ok() {
+                return Ok(());
+            }
+            check_new_name(field.name())?;
+            if !field.is_nullable() {
+                return plan_error(format!(
+                    "Added column {} of table {} must be nullable",
+                    field.name(),
+                    table_name
+                ));
+            }
+            (fields.chain([field]).collect::<Vec<_>>(), false)
+        }
+        SchemaChange::DropColumn { name, if_exists } => {
+            if if_exists && schema.field_with_name(&name).is_err() {
+                return Ok(());
+            }
+            check_column(&name, "drop")?;
+            if schema.fields().len() == 1 {
+                return plan_error(format!("Cannot drop the only column {} of table {}", name, table_name));
+            }
+            properties.dropped_columns.push(name.clone());
+            (fields.filter(|field| field.name() != &name).collect(), true)
+        }
+        SchemaChange::RenameColumn { from, to } => {
+            check_column(&from, "rename")?;
+            check_new_name(&to)?;
+            for current in properties.renamed_columns.values_mut() {
+                if current == &from {
+                    *current = to.clone();
+                }
+            }
+            properties.renamed_columns.insert(from.clone(), to.clone());
+            let fields = fields
+                .map(|field| match field.name() == &from {
+                    true => field.with_name(&to),
+                    false => field,
+                })
+                .collect();
+            (fields, true)
+        }
+        SchemaChange::AlterColumnType { name, data_type } => {
+            check_column(&name, "change the type of")?;
+            let from = schema.field_with_name(&name)?.data_type();
+            if from == &data_type {
+                return Ok(());
+            }
+            if !can_widen_type(from, &data_type) {
+                return plan_error(format!(
+                    "Cannot change the type of column {} of table {} from {} to {}",
+                    name, table_name, from, data_type
+                ));
+            }
+            let fields = fields
+                .map(|field| match field.name() == &name {
+                    true => field.with_data_type(data_type.clone()),
+                    false => field,
+                })
+                .collect();
+            (fields, false)
+        }
+    };
+
+    let schema: SchemaRef = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+    debug!("alter schema of table {}.{} to {:?}", namespace, table_name, schema);
+    if properties_changed {
+        // the previous and dropped names are persisted before the new schema,
+        // so that they are never used by another column in between
+        let mut table_properties = serde_json::from_str::<serde_json::Value>(&table_info.properties)?;
+        if let Some(table_properties) = table_properties.as_object_mut() {
+            table_properties.insert(
+                "renamedColumns".to_string(),
+                serde_json::to_value(&properties.renamed_columns)?,
+            );
+            table_properties.insert(
+                "droppedColumns".to_string(),
+                serde_json::to_value(&properties.dropped_columns)?,
+            );
+        }
+        client
+            .update_table_properties(&table_info.table_id, &table_properties.to_string())
+            .await?;
+    }
+    client
+        .update_table_schema(
+            &table_info.table_id,
+            &serde_json::to_string::<ArrowJavaSchema>(&schema.into())?,
+        )
+        .await?;
+    Ok(())
+}
+
+fn plan_error<T>(msg: String) -> Result<T> {
+    Err(LakeSoulError::DataFusionError(DataFusionError::Plan(msg)))
+}
+
+/// Whether the values of type `from` can be casted to type `to` without loss, e.g. int32 -> int64.
+pub(crate) fn can_widen_type(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+    match (from, to) {
+        (Int8, Int16 | Int32 | Int64) | (Int16, Int32 | Int64) | (Int32, Int64) => true,
+        (UInt8, UInt16 | UInt32 | UInt64 | Int16 | Int32 | Int64)
+        | (UInt16, UInt32 | UInt64 | Int32 | Int64)
+        | (UInt32, UInt64 | Int64) => true,
+        (Float16, Float32 | Float64) | (Float32, Float64) => true,
+        (
+            Decimal128(from_precision, from_scale),
+            Decimal128(to_precision, to_scale) | Decimal256(to_precision, to_scale),
+        )
+        | (Decimal256(from_precision, from_scale), Decimal256(to_precision, to_scale)) => {
+            // neither the integral part nor the fractional part is narrowed
+            to_scale >= from_scale
+                && *to_precision as i16 - *to_scale as i16 >= *from_precision as i16 - *from_scale as i16
+        }
+        _ => false,
+    }
+}
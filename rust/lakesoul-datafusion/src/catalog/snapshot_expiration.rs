@@ -0,0 +1,212 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::error::DataFusionError;
+use datafusion::sql::TableReference;
+use lakesoul_io::lakesoul_io_config::create_session_context;
+use lakesoul_metadata::MetaDataClientRef;
+use proto::proto::entity::{FileOp, PartitionInfo, TableInfo, Uuid};
+use tracing::debug;
+
+use crate::error::Result;
+use crate::lakesoul_table::helpers::create_io_config_builder_from_table_info;
+
+/// What [expire_snapshots] has removed, or would remove in a dry run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExpiredSnapshots {
+    /// (partition_desc, version) of the expired versions
+    pub versions: Vec<(String, i32)>,
+    /// number of the data commits only referenced by the expired versions
+    pub commits: usize,
+    /// data files only referenced by the expired versions
+    pub files: Vec<String>,
+}
+
+/// Expire the versions of each partition of `table_name` committed before `older_than` in milliseconds,
+/// except the latest `retain_last_n` versions, which are at least the latest version,
+/// the versions pinned by the tags of the table and the versions its branches are forked from.
+///
+/// The expired versions and the data commits only referenced by them are deleted from the metadata,
+/// and then the data files no longer referenced by any retained version are deleted from the object store,
+/// so that an interrupted expiration leaves orphan files rather than versions of missing files.
+/// The expired versions can no longer be read by time travel or incremental reads.
+///
+/// With `dry_run`, nothing is deleted and the result only reports what would be expired.
+pub async fn expire_snapshots(
+    client: MetaDataClientRef,
+    table_name: &str,
+    older_than: i64,
+    retain_last_n: usize,
+    dry_run: bool,
+) -> Result<ExpiredSnapshots> {
+    let table_ref = TableReference::from(table_name);
+    let table_info = client
+        .get_table_info_by_table_name(table_ref.table(), table_ref.schema().unwrap_or("default"))
+        .await?;
+
+    let tags = client.get_all_tags(&table_info.table_id).await?;
+    let branches = client.get_all_branches(&table_info.table_id).await?;
+    let mut expired = ExpiredSnapshots::default();
+    for partition_info in client.get_all_partition_info(&table_info.table_id).await? {
+        // the versions the branches are forked from are kept like the tagged versions
+        let tagged_versions = tags
+            .iter()
+            .map(|tag| &tag.partition_versions)
+            .chain(branches.iter().map(|branch| &branch.partition_versions))
+            .filter_map(|partition_versions| partition_versions.get(&partition_info.partition_desc).copied())
+            .collect::<HashSet<_>>();
+        expire_partition_snapshots(
+            client.clone(),
+            &table_info,
+            &partition_info.partition_desc,
+            &tagged_versions,
+            older_than,
+            retain_last_n.max(1),
+            dry_run,
+            &mut expired,
+        )
+        .await?;
+    }
+    debug!(
+        "expire {} versions, {} commits and {} files of table {}, dry run: {}",
+        expired.versions.len(),
+        expired.commits,
+        expired.files.len(),
+        table_name,
+        dry_run
+    );
+    if !dry_run {
+        delete_data_files(&table_info, &expired.files).await?;
+    }
+    Ok(expired)
+}
+
+async fn expire_partition_snapshots(
+    client: MetaDataClientRef,
+    table_info: &TableInfo,
+    partition_desc: &str,
+    tagged_versions: &HashSet<i32>,
+    older_than: i64,
+    retain_last_n: usize,
+    dry_run: bool,
+    expired: &mut ExpiredSnapshots,
+) -> Result<()> {
+    let mut versions = client
+        .get_all_versions_of_partition(&table_info.table_id, partition_desc)
+        .await?;
+    let mut retained = versions.split_off(versions.len().saturating_sub(retain_last_n));
+    let (expired_versions, newer_versions): (Vec<_>, Vec<_>) = versions
+        .into_iter()
+        .partition(|version| version.timestamp < older_than && !tagged_versions.contains(&version.version));
+    retained.extend(newer_versions);
+    if expired_versions.is_empty() {
+        return Ok(());
+    }
+
+    let retained_commits = retained
+        .iter()
+        .flat_map(|version| version.snapshot.iter())
+        .map(commit_id_pair)
+        .collect::<HashSet<_>>();
+    let mut expired_commits = HashSet::new();
+    let expired_snapshot = PartitionInfo {
+        table_id: table_info.table_id.clone(),
+        partition_desc: partition_desc.to_string(),
+        snapshot: expired_versions
+            .iter()
+            .flat_map(|version| version.snapshot.iter())
+            .filter(|commit_id| {
+                let commit_id = commit_id_pair(commit_id);
+                !retained_commits.contains(&commit_id) && expired_commits.insert(commit_id)
+            })
+            .cloned()
+            .collect(),
+        ..Default::default()
+    };
+    let retained_snapshot = PartitionInfo {
+        table_id: table_info.table_id.clone(),
+        partition_desc: partition_desc.to_string(),
+        snapshot: retained
+            .iter()
+            .flat_map(|version| version.snapshot.iter())
+            .cloned()
+            .collect(),
+        ..Default::default()
+    };
+    let retained_files = added_files(&client, &retained_snapshot).await?;
+    let expired_files = added_files(&client, &expired_snapshot)
+        .await?
+        .into_iter()
+        .filter(|path| !retained_files.contains(path))
+        .collect::<HashSet<_>>();
+
+    if !dry_run {
+        // the expired versions may be interleaved with the tagged versions, so they are deleted one by one
+        for version in &expired_versions {
+            client
+                .delete_partition_version(&table_info.table_id, partition_desc, version.version)
+                .await?;
+        }
+        for commit_id in &expired_snapshot.snapshot {
+            client
+                .delete_single_data_commit_info(
+                    &table_info.table_id,
+                    partition_desc,
+                    &uuid::Uuid::from_u64_pair(commit_id.high, commit_id.low).to_string(),
+                )
+                .await?;
+        }
+    }
+
+    expired.versions.extend(
+        expired_versions
+            .iter()
+            .map(|version| (partition_desc.to_string(), version.version)),
+    );
+    expired.commits += expired_snapshot.snapshot.len();
+    expired.files.extend(expired_files);
+    Ok(())
+}
+
+fn commit_id_pair(commit_id: &Uuid) -> (u64, u64) {
+    (commit_id.high, commit_id.low)
+}
+
+/// The paths of the files added by the data commits of `partition_info.snapshot`.
+pub(crate) async fn added_files(client: &MetaDataClientRef, partition_info: &PartitionInfo) -> Result<HashSet<String>> {
+    if partition_info.snapshot.is_empty() {
+        return Ok(HashSet::new());
+    }
+    Ok(client
+        .get_data_commit_info_of_single_partition(partition_info)
+        .await?
+        .into_iter()
+        .flat_map(|data_commit_info| data_commit_info.file_ops)
+        .filter(|file_op| file_op.file_op == FileOp::Add as i32)
+        .map(|file_op| file_op.path)
+        .collect())
+}
+
+/// Delete the data files of a table through the object store registered for the table, ignoring the missing files.
+pub(crate) async fn delete_data_files(table_info: &TableInfo, files: &[String]) -> Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let mut config = create_io_config_builder_from_table_info(Arc::new(table_info.clone()))?.build();
+    // register the object store of the table path
+    let sess_ctx = create_session_context(&mut config)?;
+    for file in files {
+        let url = ListingTableUrl::parse(file)?;
+        let store = sess_ctx.runtime_env().object_store(&url)?;
+        match store.delete(url.prefix()).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+            Err(e) => return Err(DataFusionError::from(e).into()),
+        }
+    }
+    Ok(())
+}
@@ -13,7 +13,6 @@ use async_trait::async_trait;
 
 use arrow::datatypes::{DataType, Field, Schema, SchemaBuilder, SchemaRef};
 use datafusion::common::{project_schema, FileType, Statistics};
-use datafusion::datasource::physical_plan::ParquetExec;
 use datafusion::error::DataFusionError;
 use datafusion::execution::TaskContext;
 use datafusion::physical_expr::PhysicalSortExpr;
@@ -33,7 +32,9 @@ use datafusion::{
     physical_plan::{ExecutionPlan, PhysicalExpr},
 };
 use futures::StreamExt;
-use lakesoul_io::datasource::file_format::{compute_project_column_indices, flatten_file_scan_config};
+use lakesoul_io::datasource::file_format::{
+    compute_project_column_indices, create_parquet_exec, flatten_file_scan_config, infer_evolved_schema,
+};
 use lakesoul_io::datasource::physical_plan::MergeParquetExec;
 use lakesoul_io::helpers::{
     columnar_values_to_partition_desc, columnar_values_to_sub_path, get_columnar_values,
@@ -50,7 +51,7 @@ use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 use tracing::debug;
 
-use crate::catalog::{commit_data, parse_table_info_partitions};
+use crate::catalog::{commit_data, commit_overwrite_data, parse_table_info_partitions, partition_desc_matches};
 use crate::lakesoul_table::helpers::create_io_config_builder_from_table_info;
 
 pub struct LakeSoulMetaDataParquetFormat {
@@ -102,7 +103,7 @@ impl FileFormat for LakeSoulMetaDataParquetFormat {
         store: &Arc<dyn ObjectStore>,
         objects: &[ObjectMeta],
     ) -> Result<SchemaRef> {
-        self.parquet_format.infer_schema(state, store, objects).await
+        infer_evolved_schema(&self.parquet_format, state, store, objects).await
     }
 
     async fn infer_stats(
@@ -168,11 +169,13 @@ impl FileFormat for LakeSoulMetaDataParquetFormat {
             let (partition_desc, partition_columnar_value) = partition_desc_from_file_scan_config(config)?;
             let partition_columnar_value = Arc::new(partition_columnar_value);
 
-            let parquet_exec = Arc::new(ParquetExec::new(
+            let parquet_exec = create_parquet_exec(
                 config.clone(),
                 predicate.clone(),
                 self.parquet_format.metadata_size_hint(state.config_options()),
-            ));
+                &table_schema,
+                self.conf.renamed_columns(),
+            )?;
             for field in parquet_exec.schema().fields().iter() {
                 if field.is_nullable() {
                     column_nullable.insert(field.name().clone());
@@ -240,16 +243,15 @@ impl FileFormat for LakeSoulMetaDataParquetFormat {
         conf: FileSinkConfig,
         order_requirements: Option<Vec<PhysicalSortRequirement>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        if conf.overwrite {
-            return Err(DataFusionError::NotImplemented(
-                "Overwrites are not implemented yet for Parquet".to_string(),
-            ));
-        }
-
-        Ok(
-            Arc::new(LakeSoulHashSinkExec::new(input, order_requirements, self.table_info(), self.client()).await?)
-                as _,
-        )
+        let insert_mode = match conf.overwrite {
+            true => InsertMode::OverwriteTable,
+            false => InsertMode::Append,
+        };
+        Ok(Arc::new(
+            LakeSoulHashSinkExec::new(input, order_requirements, self.table_info(), self.client())
+                .await?
+                .with_insert_mode(insert_mode),
+        ) as _)
     }
 
     fn file_type(&self) -> FileType {
@@ -257,6 +259,19 @@ impl FileFormat for LakeSoulMetaDataParquetFormat {
     }
 }
 
+/// How the data written by [LakeSoulHashSinkExec] is committed with the existing data of the table.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InsertMode {
+    /// Append the written files to the partitions.
+    Append,
+    /// Replace all the partitions of the table with the written files.
+    OverwriteTable,
+    /// Replace the partitions matching all the `(range column, value)`, i.e. `PARTITION (date='2024-01-01')`.
+    OverwriteStaticPartitions(Vec<(String, String)>),
+    /// Replace only the partitions present in the written data.
+    OverwriteDynamicPartitions,
+}
+
 // /// Execution plan for writing record batches to a [`LakeSoulParquetSink`]
 // ///
 // /// Returns a single row with the number of values written
@@ -275,11 +290,17 @@ pub struct LakeSoulHashSinkExec {
     metadata_client: MetaDataClientRef,
 
     range_partitions: Arc<Vec<String>>,
+
+    insert_mode: InsertMode,
 }
 
 impl Debug for LakeSoulHashSinkExec {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "LakeSoulHashSinkExec schema: {:?}", self.sink_schema)
+        write!(
+            f,
+            "LakeSoulHashSinkExec schema: {:?}, insert_mode: {:?}",
+            self.sink_schema, self.insert_mode
+        )
     }
 }
 
@@ -301,9 +322,19 @@ impl LakeSoulHashSinkExec {
             table_info,
             metadata_client,
             range_partitions,
+            insert_mode: InsertMode::Append,
         })
     }
 
+    pub fn with_insert_mode(mut self, insert_mode: InsertMode) -> Self {
+        self.insert_mode = insert_mode;
+        self
+    }
+
+    pub fn insert_mode(&self) -> &InsertMode {
+        &self.insert_mode
+    }
+
     /// Input execution plan
     pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
         &self.input
@@ -397,9 +428,20 @@ impl LakeSoulHashSinkExec {
     async fn wait_for_commit(
         join_handles: Vec<JoinHandle<Result<u64>>>,
         client: MetaDataClientRef,
+        table_info: Arc<TableInfo>,
         table_name: String,
+        insert_mode: InsertMode,
         partitioned_file_path_and_row_count: Arc<Mutex<HashMap<String, (Vec<String>, u64)>>>,
     ) -> Result<u64> {
+        // the partitions to be overwritten are read before the written files are known,
+        // so that any concurrent commit to them is detected as a conflict
+        let read_partitions = match insert_mode {
+            InsertMode::Append => vec![],
+            _ => client
+                .get_all_partition_info(&table_info.table_id)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?,
+        };
         let count =
             futures::future::join_all(join_handles)
                 .await
@@ -411,8 +453,38 @@ impl LakeSoulHashSinkExec {
                 })?;
         let partitioned_file_path_and_row_count = partitioned_file_path_and_row_count.lock().await;
 
+        if insert_mode != InsertMode::Append {
+            // every existing partition in the scope is read at its version, even if it has been emptied
+            let replaced_partitions = read_partitions
+                .into_iter()
+                .filter(|partition_info| match &insert_mode {
+                    InsertMode::Append => false,
+                    InsertMode::OverwriteTable => true,
+                    InsertMode::OverwriteStaticPartitions(partition_values) => {
+                        partition_desc_matches(&partition_info.partition_desc, partition_values)
+                    }
+                    InsertMode::OverwriteDynamicPartitions => {
+                        partitioned_file_path_and_row_count.contains_key(&partition_info.partition_desc)
+                    }
+                })
+                .collect::<Vec<_>>();
+            let partitioned_files = partitioned_file_path_and_row_count
+                .iter()
+                .map(|(partition_desc, (files, _))| (partition_desc.clone(), files.clone()))
+                .collect::<HashMap<_, _>>();
+            commit_overwrite_data(client, &table_info.table_id, partitioned_files, replaced_partitions)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            debug!(
+                "table: {} overwrite success at {:?}",
+                &table_name,
+                std::time::SystemTime::now()
+            );
+            return Ok(count);
+        }
+
         for (partition_desc, (files, _)) in partitioned_file_path_and_row_count.iter() {
-            commit_data(client.clone(), &table_name, partition_desc.clone(), files)
+            commit_data(client.clone(), &table_info.table_id, partition_desc.clone(), files)
                 .await
                 .map_err(|e| DataFusionError::External(Box::new(e)))?;
             debug!(
@@ -499,6 +571,7 @@ impl ExecutionPlan for LakeSoulHashSinkExec {
             table_info: self.table_info.clone(),
             range_partitions: self.range_partitions.clone(),
             metadata_client: self.metadata_client.clone(),
+            insert_mode: self.insert_mode.clone(),
         }))
     }
 
@@ -540,7 +613,9 @@ impl ExecutionPlan for LakeSoulHashSinkExec {
         let join_handle = tokio::spawn(Self::wait_for_commit(
             join_handles,
             self.metadata_client(),
+            self.table_info(),
             table_ref.to_string(),
+            self.insert_mode.clone(),
             partitioned_file_path_and_row_count,
         ));
 
@@ -576,13 +651,13 @@ impl ExecutionPlan for LakeSoulHashSinkExec {
     }
 }
 
-fn make_sink_batch(count: u64, msg: String) -> RecordBatch {
+pub(crate) fn make_sink_batch(count: u64, msg: String) -> RecordBatch {
     let count_array = Arc::new(UInt64Array::from(vec![count])) as ArrayRef;
     let msg_array = Arc::new(StringArray::from(vec![msg])) as ArrayRef;
     RecordBatch::try_from_iter_with_nullable(vec![("count", count_array, false), ("msg", msg_array, false)]).unwrap()
 }
 
-fn make_sink_schema() -> SchemaRef {
+pub(crate) fn make_sink_schema() -> SchemaRef {
     // define a schema.
     Arc::new(Schema::new(vec![
         Field::new("count", DataType::UInt64, false),
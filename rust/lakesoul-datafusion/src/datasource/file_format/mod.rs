@@ -5,4 +5,5 @@
 // mod write;
 mod metadata_format;
 
-pub use metadata_format::LakeSoulMetaDataParquetFormat;
+pub(crate) use metadata_format::{make_sink_batch, make_sink_schema};
+pub use metadata_format::{InsertMode, LakeSoulHashSinkExec, LakeSoulMetaDataParquetFormat};
@@ -3,4 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod file_format;
+pub mod physical_plan;
+pub mod streaming_table_provider;
+pub mod system_table_provider;
 pub mod table_provider;
@@ -0,0 +1,195 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use arrow::array::AsArray;
+use arrow::compute::{filter_record_batch, not, prep_null_mask_filter};
+use arrow::datatypes::SchemaRef;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::TaskContext;
+use datafusion::logical_expr::Expr;
+use datafusion::physical_expr::{PhysicalExpr, PhysicalSortExpr};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream};
+use futures::StreamExt;
+use lakesoul_metadata::MetaDataClientRef;
+use proto::proto::entity::{CommitOp, MetaInfo, PartitionInfo, TableInfo};
+use tracing::debug;
+
+use crate::datasource::file_format::{make_sink_batch, make_sink_schema};
+use crate::datasource::physical_plan::rewrite::PartitionRewriter;
+
+/// Execution plan of `DELETE FROM` a LakeSoul table.
+///
+/// Partitions matching `partition_filters` are dropped through metadata when there is no row-level `predicate`,
+/// otherwise the matched rows are removed by rewriting the affected files and committed as a DeleteCommit.
+///
+/// Returns a single row with the number of deleted rows,
+/// which are counted by scanning the files of the partitions dropped through metadata.
+pub struct LakeSoulDeleteExec {
+    client: MetaDataClientRef,
+    table_info: Arc<TableInfo>,
+    /// filters referring to range partition columns only
+    partition_filters: Vec<Expr>,
+    /// row-level predicate over [PartitionRewriter::schema]
+    predicate: Option<Arc<dyn PhysicalExpr>>,
+    sink_schema: SchemaRef,
+}
+
+impl Debug for LakeSoulDeleteExec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LakeSoulDeleteExec predicate: {:?}", self.predicate)
+    }
+}
+
+impl LakeSoulDeleteExec {
+    pub fn new(
+        client: MetaDataClientRef,
+        table_info: Arc<TableInfo>,
+        partition_filters: Vec<Expr>,
+        predicate: Option<Arc<dyn PhysicalExpr>>,
+    ) -> Self {
+        Self {
+            client,
+            table_info,
+            partition_filters,
+            predicate,
+            sink_schema: make_sink_schema(),
+        }
+    }
+
+    async fn delete(
+        client: MetaDataClientRef,
+        table_info: Arc<TableInfo>,
+        partition_filters: Vec<Expr>,
+        predicate: Option<Arc<dyn PhysicalExpr>>,
+    ) -> crate::error::Result<u64> {
+        let rewriter = PartitionRewriter::try_new(client.clone(), table_info.clone())?;
+        let partitions = rewriter.prune_partitions(&partition_filters).await?;
+
+        match predicate {
+            None => {
+                if partitions.is_empty() {
+                    return Ok(0);
+                }
+                let mut deleted_rows = 0;
+                for partition_info in &partitions {
+                    deleted_rows += rewriter.count_rows(partition_info).await?;
+                }
+                debug!(
+                    "drop {} partitions of table {}",
+                    partitions.len(),
+                    table_info.table_name
+                );
+                client
+                    .commit_data(
+                        MetaInfo {
+                            table_info: Some(table_info.as_ref().clone()),
+                            list_partition: partitions
+                                .iter()
+                                .map(|partition_info| PartitionInfo {
+                                    table_id: partition_info.table_id.clone(),
+                                    partition_desc: partition_info.partition_desc.clone(),
+                                    commit_op: CommitOp::DeleteCommit as i32,
+                                    ..Default::default()
+                                })
+                                .collect(),
+                            read_partition_info: partitions,
+                        },
+                        CommitOp::DeleteCommit,
+                    )
+                    .await?;
+                Ok(deleted_rows)
+            }
+            Some(predicate) => {
+                let mut deleted_rows = 0;
+                let mut data_commit_info_list = vec![];
+                let mut read_partition_info = vec![];
+                for partition_info in partitions {
+                    let (affected_rows, data_commit_info) = rewriter
+                        .rewrite(&partition_info, CommitOp::DeleteCommit, |batch| {
+                            let mask = predicate.evaluate(&batch)?.into_array(batch.num_rows())?;
+                            let keep = not(&prep_null_mask_filter(mask.as_boolean()))?;
+                            let remained = filter_record_batch(&batch, &keep)?;
+                            let deleted = (batch.num_rows() - remained.num_rows()) as u64;
+                            Ok((remained, deleted))
+                        })
+                        .await?;
+                    if let Some(data_commit_info) = data_commit_info {
+                        deleted_rows += affected_rows;
+                        data_commit_info_list.push(data_commit_info);
+                        read_partition_info.push(partition_info);
+                    }
+                }
+                client
+                    .commit_data_commit_info_list(data_commit_info_list, read_partition_info, CommitOp::DeleteCommit)
+                    .await?;
+                Ok(deleted_rows)
+            }
+        }
+    }
+}
+
+impl DisplayAs for LakeSoulDeleteExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LakeSoulDeleteExec")
+    }
+}
+
+impl ExecutionPlan for LakeSoulDeleteExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.sink_schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(self: Arc<Self>, _children: Vec<Arc<dyn ExecutionPlan>>) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(&self, partition: usize, _context: Arc<TaskContext>) -> Result<SendableRecordBatchStream> {
+        if partition != 0 {
+            return Err(DataFusionError::NotImplemented(
+                "LakeSoulDeleteExec can only be called on partition 0!".to_string(),
+            ));
+        }
+        let join_handle = tokio::spawn(Self::delete(
+            self.client.clone(),
+            self.table_info.clone(),
+            self.partition_filters.clone(),
+            self.predicate.clone(),
+        ));
+
+        let stream = futures::stream::once(async move {
+            match join_handle.await {
+                Ok(Ok(count)) => Ok(make_sink_batch(count, String::from(""))),
+                Ok(Err(e)) => Err(DataFusionError::External(Box::new(e))),
+                Err(e) => Err(DataFusionError::External(Box::new(e))),
+            }
+        })
+        .boxed();
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.sink_schema.clone(),
+            stream,
+        )))
+    }
+}
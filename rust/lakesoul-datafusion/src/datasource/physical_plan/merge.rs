@@ -0,0 +1,552 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use arrow::array::{AsArray, BooleanArray, Int8Array};
+use arrow::compute::filter_record_batch;
+use arrow::compute::kernels::cmp::{eq, neq};
+use arrow::datatypes::{Int8Type, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use arrow::row::{OwnedRow, RowConverter, SortField};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, Distribution, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+};
+use futures::StreamExt;
+use lakesoul_io::helpers::{columnar_values_to_partition_desc, columnar_values_to_sub_path, get_columnar_values};
+use lakesoul_io::lakesoul_reader::LakeSoulReader;
+use lakesoul_io::lakesoul_writer::{AsyncBatchWriter, MultiPartAsyncWriter};
+use lakesoul_metadata::transfusion::DataFileInfo;
+use lakesoul_metadata::MetaDataClientRef;
+use proto::proto::entity::{CommitOp, DataFileOp, FileOp, PartitionInfo, TableInfo};
+use rand::distributions::DistString;
+use tracing::debug;
+
+use crate::catalog::{create_data_commit_info, data_file_name, parse_table_info_partitions};
+use crate::datasource::file_format::{make_sink_batch, make_sink_schema};
+use crate::lakesoul_table::helpers::create_io_config_builder_from_table_info;
+use crate::serialize::arrow_java::schema_from_metadata_str;
+
+/// Column of the input of [LakeSoulMergeExec] holding the action of `MERGE INTO` on each row.
+pub(crate) const MERGE_ACTION_COLUMN: &str = "__lakesoul_merge_action";
+/// The target row is not changed.
+pub(crate) const MERGE_ACTION_KEEP: i8 = 0;
+/// The target row is updated.
+pub(crate) const MERGE_ACTION_UPDATE: i8 = 1;
+/// The source row is inserted.
+pub(crate) const MERGE_ACTION_INSERT: i8 = 2;
+/// The target row is deleted.
+pub(crate) const MERGE_ACTION_DELETE: i8 = 3;
+/// Column of the input of [LakeSoulMergeExec] identifying the target row of each row of a table without primary keys,
+/// which is identified by its primary keys otherwise.
+pub(crate) const MERGE_TARGET_ROW_COLUMN: &str = "__lakesoul_merge_target_row";
+
+/// How the rows of `MERGE INTO` are written into the target table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MergeMode {
+    /// The input only contains the changed rows, which are co-located with the hash buckets of
+    /// the target table by primary keys. Updated and inserted rows are upserted into their buckets,
+    /// and only the buckets containing deleted rows are rewritten.
+    Upsert,
+    /// The input contains every row of the target table,
+    /// and each hash bucket (the whole partition for a table without primary keys) having any changed row is rewritten.
+    Rewrite,
+}
+
+/// Execution plan writing the result of `MERGE INTO` into a LakeSoul table.
+///
+/// Each input partition is a hash bucket of the target table, sorted by range partitions and primary keys,
+/// with the [MERGE_ACTION_COLUMN] of each row. All the changed partitions are committed at once as an UpdateCommit
+/// against the versions of the partitions read by the input, so the commits after planning are rebased.
+///
+/// Returns a single row with the number of updated, inserted and deleted rows.
+pub struct LakeSoulMergeExec {
+    input: Arc<dyn ExecutionPlan>,
+    client: MetaDataClientRef,
+    table_info: Arc<TableInfo>,
+    mode: MergeMode,
+    /// the partitions of the target table read by the input
+    read_partitions: Vec<PartitionInfo>,
+    sink_schema: SchemaRef,
+}
+
+/// Files written for a range partition by an input partition.
+struct BucketOutcome {
+    bucket_id: isize,
+    files: Vec<String>,
+    /// whether the current files of the bucket are replaced by `files`
+    replace: bool,
+}
+
+/// Writer of the rows of a range partition in an input partition.
+#[derive(Default)]
+struct BucketWriter {
+    sub_path: String,
+    writer: Option<Box<MultiPartAsyncWriter>>,
+    changed: bool,
+    /// primary keys of the deleted rows
+    deleted_keys: Vec<RecordBatch>,
+}
+
+impl Debug for LakeSoulMergeExec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LakeSoulMergeExec mode: {:?}", self.mode)
+    }
+}
+
+impl LakeSoulMergeExec {
+    pub fn new(
+        input: Arc<dyn ExecutionPlan>,
+        client: MetaDataClientRef,
+        table_info: Arc<TableInfo>,
+        mode: MergeMode,
+        read_partitions: Vec<PartitionInfo>,
+    ) -> Self {
+        Self {
+            input,
+            client,
+            table_info,
+            mode,
+            read_partitions,
+            sink_schema: make_sink_schema(),
+        }
+    }
+
+    async fn merge(
+        input: Arc<dyn ExecutionPlan>,
+        context: Arc<TaskContext>,
+        client: MetaDataClientRef,
+        table_info: Arc<TableInfo>,
+        mode: MergeMode,
+        read_partitions: Vec<PartitionInfo>,
+    ) -> crate::error::Result<u64> {
+        let (_, primary_keys) = parse_table_info_partitions(table_info.partitions.clone())?;
+        let partitions = Arc::new(
+            read_partitions
+                .into_iter()
+                .map(|partition_info| (partition_info.partition_desc.clone(), partition_info))
+                .collect::<HashMap<_, _>>(),
+        );
+
+        let mut join_handles = vec![];
+        for partition in 0..input.output_partitioning().partition_count() {
+            join_handles.push(tokio::spawn(Self::merge_bucket(
+                input.clone(),
+                partition,
+                context.clone(),
+                client.clone(),
+                table_info.clone(),
+                mode,
+                partitions.clone(),
+            )));
+        }
+
+        let mut affected_rows = 0;
+        let mut outcomes = BTreeMap::<String, Vec<BucketOutcome>>::new();
+        for join_handle in join_handles {
+            let (rows, bucket_outcomes) = join_handle.await??;
+            affected_rows += rows;
+            for (partition_desc, outcome) in bucket_outcomes {
+                outcomes.entry(partition_desc).or_default().push(outcome);
+            }
+        }
+
+        let mut data_commit_info_list = vec![];
+        let mut read_partition_info = vec![];
+        for (partition_desc, outcomes) in outcomes {
+            let mut file_ops = vec![];
+            if let Some(partition_info) = partitions.get(&partition_desc) {
+                let replaced = outcomes
+                    .iter()
+                    .filter(|outcome| outcome.replace)
+                    .map(|outcome| outcome.bucket_id)
+                    .collect::<HashSet<_>>();
+                for path in client.get_data_files_of_single_partition(partition_info).await? {
+                    let file_op = match replaced.contains(&bucket_id_of_file(&path, &primary_keys)) {
+                        true => FileOp::Del,
+                        false => FileOp::Add,
+                    };
+                    file_ops.push(DataFileOp {
+                        file_op: file_op as i32,
+                        path,
+                        ..Default::default()
+                    });
+                }
+                read_partition_info.push(partition_info.clone());
+            }
+            file_ops.extend(
+                outcomes
+                    .into_iter()
+                    .flat_map(|outcome| outcome.files)
+                    .map(|path| DataFileOp {
+                        file_op: FileOp::Add as i32,
+                        path,
+                        ..Default::default()
+                    }),
+            );
+            data_commit_info_list.push(create_data_commit_info(
+                table_info.table_id.clone(),
+                partition_desc,
+                file_ops,
+                CommitOp::UpdateCommit,
+            )?);
+        }
+        debug!(
+            "merge {} rows into {} partitions of table {}",
+            affected_rows,
+            data_commit_info_list.len(),
+            table_info.table_name
+        );
+        client
+            .commit_data_commit_info_list(data_commit_info_list, read_partition_info, CommitOp::UpdateCommit)
+            .await?;
+        Ok(affected_rows)
+    }
+
+    async fn merge_bucket(
+        input: Arc<dyn ExecutionPlan>,
+        partition: usize,
+        context: Arc<TaskContext>,
+        client: MetaDataClientRef,
+        table_info: Arc<TableInfo>,
+        mode: MergeMode,
+        partitions: Arc<HashMap<String, PartitionInfo>>,
+    ) -> crate::error::Result<(u64, Vec<(String, BucketOutcome)>)> {
+        let (range_partitions, primary_keys) = parse_table_info_partitions(table_info.partitions.clone())?;
+        let range_partitions = Arc::new(range_partitions);
+        // all the rows of a table without primary keys are in a single input partition
+        let bucket_id = match primary_keys.is_empty() {
+            true => 0,
+            false => partition as isize,
+        };
+
+        let write_id = rand::distributions::Alphanumeric.sample_string(&mut rand::thread_rng(), 16);
+        let mut data = input.execute(partition, context.clone())?;
+        let schema = data.schema();
+        let action_idx = schema.index_of(MERGE_ACTION_COLUMN)?;
+        let target_row_idx = schema.index_of(MERGE_TARGET_ROW_COLUMN).ok();
+        let file_projection = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(idx, field)| {
+                *idx != action_idx && Some(*idx) != target_row_idx && !range_partitions.contains(field.name())
+            })
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        let key_projection = primary_keys
+            .iter()
+            .map(|pk| schema.index_of(pk))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        // the target rows updated or deleted so far, each of which may be matched by a single source row
+        let target_row_projection = match target_row_idx {
+            Some(idx) => vec![idx],
+            None => key_projection.clone(),
+        };
+        let target_row_converter = RowConverter::new(
+            target_row_projection
+                .iter()
+                .map(|idx| SortField::new(schema.field(*idx).data_type().clone()))
+                .collect(),
+        )?;
+        let mut matched_target_rows = HashSet::<OwnedRow>::new();
+
+        let mut affected_rows = 0;
+        let mut writers = BTreeMap::<String, BucketWriter>::new();
+        while let Some(batch) = data.next().await.transpose()? {
+            if batch.num_rows() == 0 {
+                continue;
+            }
+            let columnar_values = get_columnar_values(&batch, range_partitions.clone())?;
+            let partition_desc = columnar_values_to_partition_desc(&columnar_values);
+            let bucket_writer = writers.entry(partition_desc).or_insert_with(|| BucketWriter {
+                sub_path: columnar_values_to_sub_path(&columnar_values),
+                ..Default::default()
+            });
+
+            let actions = batch.column(action_idx).as_primitive::<Int8Type>();
+            let changed_rows = neq(actions, &Int8Array::new_scalar(MERGE_ACTION_KEEP))?.true_count();
+            affected_rows += changed_rows as u64;
+            bucket_writer.changed |= changed_rows > 0;
+
+            let deleted = eq(actions, &Int8Array::new_scalar(MERGE_ACTION_DELETE))?;
+            let updated = eq(actions, &Int8Array::new_scalar(MERGE_ACTION_UPDATE))?;
+            let matched = filter_record_batch(
+                &batch.project(&target_row_projection)?,
+                &arrow::compute::or(&deleted, &updated)?,
+            )?;
+            for row in target_row_converter.convert_columns(matched.columns())?.iter() {
+                if !matched_target_rows.insert(row.owned()) {
+                    return Err(DataFusionError::Execution(format!(
+                        "MERGE INTO table {} matches a target row with more than one source row",
+                        table_info.table_name
+                    ))
+                    .into());
+                }
+            }
+            if deleted.true_count() > 0 && mode == MergeMode::Upsert {
+                bucket_writer
+                    .deleted_keys
+                    .push(filter_record_batch(&batch.project(&key_projection)?, &deleted)?);
+            }
+
+            let written = filter_record_batch(&batch, &arrow::compute::not(&deleted)?)?.project(&file_projection)?;
+            if written.num_rows() == 0 {
+                continue;
+            }
+            if bucket_writer.writer.is_none() {
+                let file_absolute_path = format!(
+                    "{}{}{}",
+                    table_info.table_path,
+                    bucket_writer.sub_path,
+                    data_file_name(&write_id, bucket_id)
+                );
+                let mut config = create_io_config_builder_from_table_info(table_info.clone())?
+                    .with_files(vec![file_absolute_path])
+                    .with_schema(written.schema())
+                    .build();
+                bucket_writer.writer = Some(Box::new(
+                    MultiPartAsyncWriter::try_new_with_context(&mut config, context.clone()).await?,
+                ));
+            }
+            if let Some(writer) = bucket_writer.writer.as_mut() {
+                writer.write_record_batch(written).await?;
+            }
+        }
+
+        let mut outcomes = vec![];
+        for (partition_desc, bucket_writer) in writers {
+            if mode == MergeMode::Rewrite && !bucket_writer.changed {
+                // the bucket is written as is, keep its current files
+                if let Some(writer) = bucket_writer.writer {
+                    writer.abort_and_close().await?;
+                }
+                continue;
+            }
+
+            let mut files = vec![];
+            if !bucket_writer.deleted_keys.is_empty() {
+                if let Some(partition_info) = partitions.get(&partition_desc) {
+                    let rewritten = Self::rewrite_bucket_without_keys(
+                        client.clone(),
+                        table_info.clone(),
+                        partition_info,
+                        bucket_id,
+                        &primary_keys,
+                        &bucket_writer.deleted_keys,
+                        format!(
+                            "{}{}{}",
+                            table_info.table_path,
+                            bucket_writer.sub_path,
+                            data_file_name(
+                                &rand::distributions::Alphanumeric.sample_string(&mut rand::thread_rng(), 16),
+                                bucket_id
+                            )
+                        ),
+                    )
+                    .await?;
+                    files.extend(rewritten);
+                }
+            }
+            if let Some(writer) = bucket_writer.writer {
+                files.push(writer.absolute_path());
+                writer.flush_and_close().await?;
+            }
+            outcomes.push((
+                partition_desc,
+                BucketOutcome {
+                    bucket_id,
+                    files,
+                    replace: mode == MergeMode::Rewrite || !bucket_writer.deleted_keys.is_empty(),
+                },
+            ));
+        }
+        Ok((affected_rows, outcomes))
+    }
+
+    /// Rewrite the current files of a bucket into `file_absolute_path` without the rows of `deleted_keys`.
+    /// Returns the written file, which is `None` if no row remains.
+    async fn rewrite_bucket_without_keys(
+        client: MetaDataClientRef,
+        table_info: Arc<TableInfo>,
+        partition_info: &PartitionInfo,
+        bucket_id: isize,
+        primary_keys: &[String],
+        deleted_keys: &[RecordBatch],
+        file_absolute_path: String,
+    ) -> crate::error::Result<Option<String>> {
+        let files = client
+            .get_data_files_of_single_partition(partition_info)
+            .await?
+            .into_iter()
+            .filter(|path| bucket_id_of_file(path, primary_keys) == bucket_id)
+            .collect::<Vec<_>>();
+        if files.is_empty() {
+            return Ok(None);
+        }
+
+        let key_fields = deleted_keys[0]
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| SortField::new(field.data_type().clone()))
+            .collect::<Vec<_>>();
+        let converter = RowConverter::new(key_fields)?;
+        let mut keys = HashSet::<OwnedRow>::new();
+        for batch in deleted_keys {
+            keys.extend(
+                converter
+                    .convert_columns(batch.columns())?
+                    .iter()
+                    .map(|row| row.owned()),
+            );
+        }
+
+        let (range_partitions, _) = parse_table_info_partitions(table_info.partitions.clone())?;
+        let table_schema = schema_from_metadata_str(&table_info.table_schema);
+        let file_schema = SchemaRef::new(Schema::new(
+            table_schema
+                .fields()
+                .iter()
+                .filter(|field| !range_partitions.contains(field.name()))
+                .cloned()
+                .collect::<Vec<_>>(),
+        ));
+        let builder = create_io_config_builder_from_table_info(table_info.clone())?
+            .with_range_partitions(vec![])
+            .with_schema(file_schema.clone());
+        let mut reader = LakeSoulReader::new(builder.clone().with_files(files).build())?;
+        reader.start().await?;
+
+        let key_projection = primary_keys
+            .iter()
+            .map(|pk| file_schema.index_of(pk))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let mut writer: Option<Box<MultiPartAsyncWriter>> = None;
+        while let Some(batch) = reader.next_rb().await {
+            let batch = batch?;
+            let rows = converter.convert_columns(batch.project(&key_projection)?.columns())?;
+            let retained = rows
+                .iter()
+                .map(|row| Some(!keys.contains(&row.owned())))
+                .collect::<BooleanArray>();
+            let batch = filter_record_batch(&batch, &retained)?;
+            if batch.num_rows() == 0 {
+                continue;
+            }
+            if writer.is_none() {
+                writer = Some(Box::new(
+                    MultiPartAsyncWriter::try_new(builder.clone().with_files(vec![file_absolute_path.clone()]).build())
+                        .await?,
+                ));
+            }
+            if let Some(writer) = writer.as_mut() {
+                writer.write_record_batch(batch).await?;
+            }
+        }
+        match writer {
+            Some(writer) => {
+                writer.flush_and_close().await?;
+                Ok(Some(file_absolute_path))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Hash bucket of a data file, files of a table without primary keys are all in bucket 0.
+fn bucket_id_of_file(path: &str, primary_keys: &[String]) -> isize {
+    match primary_keys.is_empty() {
+        true => 0,
+        false => DataFileInfo::parse_bucket_id(path).unwrap_or(-1),
+    }
+}
+
+impl DisplayAs for LakeSoulMergeExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LakeSoulMergeExec: mode={:?}", self.mode)
+    }
+}
+
+impl ExecutionPlan for LakeSoulMergeExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.sink_schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn required_input_distribution(&self) -> Vec<Distribution> {
+        // each input partition is a hash bucket of the table
+        vec![Distribution::UnspecifiedDistribution]
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn benefits_from_input_partitioning(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(self: Arc<Self>, children: Vec<Arc<dyn ExecutionPlan>>) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self::new(
+            children[0].clone(),
+            self.client.clone(),
+            self.table_info.clone(),
+            self.mode,
+            self.read_partitions.clone(),
+        )))
+    }
+
+    fn execute(&self, partition: usize, context: Arc<TaskContext>) -> Result<SendableRecordBatchStream> {
+        if partition != 0 {
+            return Err(DataFusionError::NotImplemented(
+                "LakeSoulMergeExec can only be called on partition 0!".to_string(),
+            ));
+        }
+        let join_handle = tokio::spawn(Self::merge(
+            self.input.clone(),
+            context,
+            self.client.clone(),
+            self.table_info.clone(),
+            self.mode,
+            self.read_partitions.clone(),
+        ));
+
+        let stream = futures::stream::once(async move {
+            match join_handle.await {
+                Ok(Ok(count)) => Ok(make_sink_batch(count, String::from(""))),
+                Ok(Err(e)) => Err(DataFusionError::External(Box::new(e))),
+                Err(e) => Err(DataFusionError::External(Box::new(e))),
+            }
+        })
+        .boxed();
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.sink_schema.clone(),
+            stream,
+        )))
+    }
+}
@@ -0,0 +1,19 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod delete;
+mod merge;
+mod rewrite;
+mod streaming;
+mod update;
+
+pub use delete::LakeSoulDeleteExec;
+pub use merge::{LakeSoulMergeExec, MergeMode};
+pub(crate) use merge::{
+    MERGE_ACTION_COLUMN, MERGE_ACTION_DELETE, MERGE_ACTION_INSERT, MERGE_ACTION_KEEP, MERGE_ACTION_UPDATE,
+    MERGE_TARGET_ROW_COLUMN,
+};
+pub(crate) use rewrite::PartitionRewriter;
+pub use streaming::{LakeSoulStreamingExec, StreamingOffset};
+pub use update::LakeSoulUpdateExec;
@@ -0,0 +1,247 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use datafusion::logical_expr::Expr;
+use lakesoul_io::helpers::partition_desc_to_scalar_values;
+use lakesoul_io::lakesoul_reader::LakeSoulReader;
+use lakesoul_io::lakesoul_writer::{AsyncBatchWriter, MultiPartAsyncWriter};
+use lakesoul_metadata::transfusion::DataFileInfo;
+use lakesoul_metadata::MetaDataClientRef;
+use proto::proto::entity::{CommitOp, DataCommitInfo, DataFileOp, FileOp, PartitionInfo, TableInfo};
+use rand::distributions::DistString;
+use tracing::debug;
+
+use crate::catalog::{
+    create_data_commit_info, data_file_name, parse_table_info_partitions, partition_desc_to_sub_path,
+};
+use crate::error::Result;
+use crate::lakesoul_table::helpers::{create_io_config_builder_from_table_info, prune_partitions};
+use crate::serialize::arrow_java::schema_from_metadata_str;
+
+/// Copy-on-write rewriter of the data files of a LakeSoul table partition.
+///
+/// The files of a partition are read in groups, one hash bucket for a primary-key table
+/// and one file otherwise, and a group is only rewritten when the rewrite function touches any of its rows.
+pub(crate) struct PartitionRewriter {
+    client: MetaDataClientRef,
+    table_info: Arc<TableInfo>,
+    range_partitions: Vec<String>,
+    primary_keys: Vec<String>,
+    /// schema of the data files, i.e. the table schema without range partition columns
+    file_schema: SchemaRef,
+    /// `file_schema` followed by range partition columns
+    schema: SchemaRef,
+}
+
+impl PartitionRewriter {
+    pub(crate) fn try_new(client: MetaDataClientRef, table_info: Arc<TableInfo>) -> Result<Self> {
+        let (range_partitions, primary_keys) = parse_table_info_partitions(table_info.partitions.clone())?;
+        let table_schema = schema_from_metadata_str(&table_info.table_schema);
+        let file_schema = SchemaRef::new(Schema::new(
+            table_schema
+                .fields()
+                .iter()
+                .filter(|field| !range_partitions.contains(field.name()))
+                .cloned()
+                .collect::<Vec<_>>(),
+        ));
+        let mut fields = file_schema.fields().to_vec();
+        for range_partition in &range_partitions {
+            fields.push(Arc::new(table_schema.field_with_name(range_partition)?.clone()));
+        }
+        Ok(Self {
+            client,
+            table_info,
+            range_partitions,
+            primary_keys,
+            file_schema,
+            schema: SchemaRef::new(Schema::new(fields)),
+        })
+    }
+
+    /// Schema of the batches passed to the rewrite function.
+    pub(crate) fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    pub(crate) fn file_schema(&self) -> SchemaRef {
+        self.file_schema.clone()
+    }
+
+    pub(crate) fn range_partitions(&self) -> &[String] {
+        &self.range_partitions
+    }
+
+    /// Current partitions of the table with data, which match `partition_filters`.
+    pub(crate) async fn prune_partitions(&self, partition_filters: &[Expr]) -> Result<Vec<PartitionInfo>> {
+        let partition_cols = self
+            .range_partitions
+            .iter()
+            .map(|col| Ok((col.clone(), self.schema.field_with_name(col)?.data_type().clone())))
+            .collect::<Result<Vec<(String, DataType)>>>()?;
+        Ok(prune_partitions(
+            self.client.get_all_partition_info(&self.table_info.table_id).await?,
+            partition_filters,
+            &partition_cols,
+        )
+        .await?
+        .into_iter()
+        .filter(|partition_info| !partition_info.snapshot.is_empty())
+        .collect())
+    }
+
+    /// Rewrite the current files of `partition_info` with `rewrite`, which maps a batch of [Self::schema]
+    /// to a new batch of the same schema together with the number of affected rows.
+    ///
+    /// Returns the number of affected rows and the [DataCommitInfo] to commit,
+    /// which is `None` if no row has been affected.
+    pub(crate) async fn rewrite<F>(
+        &self,
+        partition_info: &PartitionInfo,
+        commit_op: CommitOp,
+        mut rewrite: F,
+    ) -> Result<(u64, Option<DataCommitInfo>)>
+    where
+        F: FnMut(RecordBatch) -> Result<(RecordBatch, u64)>,
+    {
+        let partition_desc = &partition_info.partition_desc;
+        let file_groups = self.file_groups(partition_info).await?;
+
+        let range_schema = SchemaRef::new(
+            self.schema
+                .project(&(self.file_schema.fields().len()..self.schema.fields().len()).collect::<Vec<_>>())?,
+        );
+        let range_values = partition_desc_to_scalar_values(range_schema, partition_desc.clone())?;
+        let file_projection = (0..self.file_schema.fields().len()).collect::<Vec<_>>();
+
+        let write_id = rand::distributions::Alphanumeric.sample_string(&mut rand::thread_rng(), 16);
+        let mut affected_rows = 0;
+        let mut file_ops = Vec::new();
+        for (group_id, files) in file_groups {
+            let builder = create_io_config_builder_from_table_info(self.table_info.clone())?
+                .with_range_partitions(vec![])
+                .with_schema(self.file_schema.clone());
+            let mut reader = LakeSoulReader::new(builder.clone().with_files(files.clone()).build())?;
+            reader.start().await?;
+            let file_absolute_path = format!(
+                "{}{}{}",
+                self.table_info.table_path,
+                partition_desc_to_sub_path(partition_desc),
+                data_file_name(&write_id, group_id)
+            );
+
+            // the rewritten rows are streamed into the new file, which is aborted if no row is affected
+            let mut writer: Option<Box<MultiPartAsyncWriter>> = None;
+            let mut group_affected_rows = 0;
+            while let Some(batch) = reader.next_rb().await {
+                let batch = batch?;
+                let mut columns = batch.columns().to_vec();
+                for value in &range_values {
+                    columns.push(value.to_array_of_size(batch.num_rows())?);
+                }
+                let (batch, affected) = rewrite(RecordBatch::try_new(self.schema.clone(), columns)?)?;
+                group_affected_rows += affected;
+                if batch.num_rows() == 0 {
+                    continue;
+                }
+                if writer.is_none() {
+                    writer = Some(Box::new(
+                        MultiPartAsyncWriter::try_new(
+                            builder.clone().with_files(vec![file_absolute_path.clone()]).build(),
+                        )
+                        .await?,
+                    ));
+                }
+                if let Some(writer) = writer.as_mut() {
+                    writer.write_record_batch(batch.project(&file_projection)?).await?;
+                }
+            }
+
+            if group_affected_rows == 0 {
+                if let Some(writer) = writer {
+                    writer.abort_and_close().await?;
+                }
+                file_ops.extend(files.into_iter().map(|path| DataFileOp {
+                    file_op: FileOp::Add as i32,
+                    path,
+                    ..Default::default()
+                }));
+                continue;
+            }
+            affected_rows += group_affected_rows;
+
+            if let Some(writer) = writer {
+                writer.flush_and_close().await?;
+                file_ops.push(DataFileOp {
+                    file_op: FileOp::Add as i32,
+                    path: file_absolute_path,
+                    ..Default::default()
+                });
+            }
+            file_ops.extend(files.into_iter().map(|path| DataFileOp {
+                file_op: FileOp::Del as i32,
+                path,
+                ..Default::default()
+            }));
+        }
+
+        if affected_rows == 0 {
+            return Ok((0, None));
+        }
+        debug!(
+            "rewrite {} rows of partition {} of table {}",
+            affected_rows, partition_desc, self.table_info.table_name
+        );
+        Ok((
+            affected_rows,
+            Some(create_data_commit_info(
+                self.table_info.table_id.clone(),
+                partition_desc.clone(),
+                file_ops,
+                commit_op,
+            )?),
+        ))
+    }
+
+    /// Number of the rows in the current files of `partition_info`, which are merged by primary keys like a scan.
+    pub(crate) async fn count_rows(&self, partition_info: &PartitionInfo) -> Result<u64> {
+        let mut rows = 0;
+        for files in self.file_groups(partition_info).await?.into_values() {
+            let builder = create_io_config_builder_from_table_info(self.table_info.clone())?
+                .with_range_partitions(vec![])
+                .with_schema(self.file_schema.clone())
+                .with_files(files);
+            let mut reader = LakeSoulReader::new(builder.build())?;
+            reader.start().await?;
+            while let Some(batch) = reader.next_rb().await {
+                rows += batch?.num_rows() as u64;
+            }
+        }
+        Ok(rows)
+    }
+
+    /// The current files of `partition_info` by group, see [PartitionRewriter].
+    async fn file_groups(&self, partition_info: &PartitionInfo) -> Result<BTreeMap<isize, Vec<String>>> {
+        let mut file_groups = BTreeMap::<isize, Vec<String>>::new();
+        for (idx, path) in self
+            .client
+            .get_data_files_of_single_partition(partition_info)
+            .await?
+            .into_iter()
+            .enumerate()
+        {
+            let group_id = match self.primary_keys.is_empty() {
+                true => idx as isize,
+                false => DataFileInfo::parse_bucket_id(&path).unwrap_or(-1),
+            };
+            file_groups.entry(group_id).or_default().push(path);
+        }
+        Ok(file_groups)
+    }
+}
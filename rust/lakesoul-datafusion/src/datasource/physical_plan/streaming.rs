@@ -0,0 +1,292 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use arrow::compute::concat_batches;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::{SessionContext, SessionState};
+use datafusion::execution::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream};
+use futures::StreamExt;
+use lakesoul_metadata::{CommitNotificationStream, MetaDataClientRef};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::lakesoul_table::incremental::incremental_dataframe_of_versions;
+use crate::lakesoul_table::LakeSoulTable;
+
+/// The offset of a streaming read of a LakeSoul table, i.e. the latest version read of each partition,
+/// which can be persisted to resume the streaming read by [LakeSoulStreamingTableProvider::with_offset].
+///
+/// [LakeSoulStreamingTableProvider::with_offset]: crate::datasource::streaming_table_provider::LakeSoulStreamingTableProvider::with_offset
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamingOffset {
+    /// partition_desc -> version
+    pub versions: HashMap<String, i32>,
+}
+
+impl StreamingOffset {
+    /// The offset of the latest version of each partition of table `table_id`, i.e. only the later commits are read.
+    pub async fn latest(client: MetaDataClientRef, table_id: &str) -> crate::error::Result<Self> {
+        Ok(Self {
+            versions: client
+                .get_all_partition_info(table_id)
+                .await?
+                .into_iter()
+                .map(|partition_info| (partition_info.partition_desc, partition_info.version))
+                .collect(),
+        })
+    }
+
+    /// The latest version read of the partition, which is -1 if none of its versions have been read.
+    pub fn version(&self, partition_desc: &str) -> i32 {
+        self.versions.get(partition_desc).copied().unwrap_or(-1)
+    }
+}
+
+/// Execution plan of an unbounded read of a LakeSoul table,
+/// which polls the metadata for the new versions of the partitions and returns the rows committed by them.
+/// With `commit_notification`, the polling is also woken up by the commit notifications of the table,
+/// see [lakesoul_metadata::notification].
+///
+/// Each batch holds the rows of the new versions of a single partition, see [LakeSoulTable::to_incremental_dataframe],
+/// and the shared [StreamingOffset] is advanced to these versions once the batch is returned.
+/// So the offset read after processing a batch is exactly where the streaming read should be resumed.
+pub struct LakeSoulStreamingExec {
+    table: Arc<LakeSoulTable>,
+    session_state: SessionState,
+    offset: Arc<Mutex<StreamingOffset>>,
+    /// schema of the rows returned by [LakeSoulTable::to_incremental_dataframe]
+    table_schema: SchemaRef,
+    projection: Option<Vec<usize>>,
+    projected_schema: SchemaRef,
+    poll_interval: Duration,
+    with_change_type: bool,
+    commit_notification: bool,
+}
+
+impl Debug for LakeSoulStreamingExec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LakeSoulStreamingExec table: {}", self.table.table_name())
+    }
+}
+
+impl LakeSoulStreamingExec {
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        table: Arc<LakeSoulTable>,
+        session_state: SessionState,
+        offset: Arc<Mutex<StreamingOffset>>,
+        table_schema: SchemaRef,
+        projection: Option<Vec<usize>>,
+        poll_interval: Duration,
+        with_change_type: bool,
+        commit_notification: bool,
+    ) -> Result<Self> {
+        let projected_schema = match &projection {
+            Some(projection) => Arc::new(table_schema.project(projection)?),
+            None => table_schema.clone(),
+        };
+        Ok(Self {
+            table,
+            session_state,
+            offset,
+            table_schema,
+            projection,
+            projected_schema,
+            poll_interval,
+            with_change_type,
+            commit_notification,
+        })
+    }
+
+    pub fn offset(&self) -> StreamingOffset {
+        self.offset.lock().unwrap().clone()
+    }
+}
+
+impl DisplayAs for LakeSoulStreamingExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LakeSoulStreamingExec: table={}", self.table.table_name())
+    }
+}
+
+impl ExecutionPlan for LakeSoulStreamingExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.projected_schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn unbounded_output(&self, _children: &[bool]) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(self: Arc<Self>, _children: Vec<Arc<dyn ExecutionPlan>>) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(&self, partition: usize, _context: Arc<TaskContext>) -> Result<SendableRecordBatchStream> {
+        if partition != 0 {
+            return Err(DataFusionError::NotImplemented(
+                "LakeSoulStreamingExec can only be called on partition 0!".to_string(),
+            ));
+        }
+        let poller = StreamingPoller {
+            table: self.table.clone(),
+            context: SessionContext::new_with_state(self.session_state.clone()),
+            offset: self.offset.clone(),
+            table_schema: self.table_schema.clone(),
+            projection: self.projection.clone(),
+            projected_schema: self.projected_schema.clone(),
+            poll_interval: self.poll_interval,
+            with_change_type: self.with_change_type,
+            commit_notification: self.commit_notification,
+            notifications: None,
+        };
+        // the stream ends after an error
+        let stream = futures::stream::unfold(Some(poller), |poller| async move {
+            let mut poller = poller?;
+            match poller.next_batch().await {
+                Ok(batch) => Some((Ok(batch), Some(poller))),
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+        .boxed();
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.projected_schema.clone(),
+            stream,
+        )))
+    }
+}
+
+struct StreamingPoller {
+    table: Arc<LakeSoulTable>,
+    context: SessionContext,
+    offset: Arc<Mutex<StreamingOffset>>,
+    table_schema: SchemaRef,
+    projection: Option<Vec<usize>>,
+    projected_schema: SchemaRef,
+    poll_interval: Duration,
+    with_change_type: bool,
+    commit_notification: bool,
+    notifications: Option<CommitNotificationStream>,
+}
+
+impl StreamingPoller {
+    /// Wait for the next partition with new versions, and return the rows committed by them.
+    async fn next_batch(&mut self) -> Result<RecordBatch> {
+        let client = self.table.client();
+        let table_id = self.table.table_info().table_id.clone();
+        if self.commit_notification && self.notifications.is_none() {
+            // subscribe before reading the partitions, so that no commit is missed
+            self.notifications = Some(
+                client
+                    .subscribe_commit_notifications()
+                    .await
+                    .map_err(|e| DataFusionError::External(Box::new(e)))?,
+            );
+        }
+        loop {
+            let all_partition_info = client
+                .get_all_partition_info(&table_id)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            let offset = self.offset.lock().unwrap().clone();
+            let Some(partition_info) = all_partition_info
+                .into_iter()
+                .find(|partition_info| partition_info.version > offset.version(&partition_info.partition_desc))
+            else {
+                self.wait_for_commit().await;
+                continue;
+            };
+
+            let start_version = offset.version(&partition_info.partition_desc);
+            let partition_desc = partition_info.partition_desc.clone();
+            let end_version = partition_info.version;
+            debug!(
+                "streaming read of table {} partition {} from version {} to {}",
+                self.table.table_name(),
+                partition_desc,
+                start_version,
+                end_version
+            );
+            let batches = incremental_dataframe_of_versions(
+                &self.table,
+                &self.context,
+                vec![(partition_info, start_version, end_version)],
+                self.with_change_type,
+            )
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?
+            .collect()
+            .await?;
+            let batch = concat_batches(&self.table_schema, &batches)?;
+            self.offset.lock().unwrap().versions.insert(partition_desc, end_version);
+            // e.g. the versions of compaction do not change any row
+            if batch.num_rows() == 0 {
+                continue;
+            }
+            let columns = match &self.projection {
+                Some(projection) => projection.iter().map(|idx| batch.column(*idx).clone()).collect(),
+                None => batch.columns().to_vec(),
+            };
+            return Ok(RecordBatch::try_new(self.projected_schema.clone(), columns)?);
+        }
+    }
+
+    /// Wait for the next commit notification of the table, or the poll interval.
+    async fn wait_for_commit(&mut self) {
+        let table_id = self.table.table_info().table_id.clone();
+        let Some(notifications) = &mut self.notifications else {
+            tokio::time::sleep(self.poll_interval).await;
+            return;
+        };
+        let subscribed = tokio::time::timeout(self.poll_interval, async {
+            loop {
+                match notifications.next().await {
+                    Some(Ok(notification)) if notification.table_id != table_id => continue,
+                    Some(Ok(_)) => return true,
+                    // the notifications in the meantime may be lost, so read the partitions again
+                    Some(Err(e)) => {
+                        warn!("commit notification of table {} failed: {}", table_id, e);
+                        return true;
+                    }
+                    None => return false,
+                }
+            }
+        })
+        .await
+        .unwrap_or(true);
+        if !subscribed {
+            // fall back to polling
+            self.notifications = None;
+            self.commit_notification = false;
+        }
+    }
+}
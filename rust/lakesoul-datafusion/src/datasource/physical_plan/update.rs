@@ -0,0 +1,187 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use arrow::array::{AsArray, BooleanArray};
+use arrow::compute::{prep_null_mask_filter, zip};
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::TaskContext;
+use datafusion::logical_expr::Expr;
+use datafusion::physical_expr::{PhysicalExpr, PhysicalSortExpr};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream};
+use futures::StreamExt;
+use lakesoul_metadata::MetaDataClientRef;
+use proto::proto::entity::{CommitOp, TableInfo};
+
+use crate::datasource::file_format::{make_sink_batch, make_sink_schema};
+use crate::datasource::physical_plan::rewrite::PartitionRewriter;
+
+/// Execution plan of `UPDATE` on a LakeSoul table without primary keys.
+///
+/// The files containing rows matching the `predicate` are copied with the `assignments` applied to those rows,
+/// and committed as an UpdateCommit replacing the original files.
+///
+/// Returns a single row with the number of updated rows.
+pub struct LakeSoulUpdateExec {
+    client: MetaDataClientRef,
+    table_info: Arc<TableInfo>,
+    /// filters referring to range partition columns only
+    partition_filters: Vec<Expr>,
+    /// row-level predicate over [PartitionRewriter::schema], all rows are updated if `None`
+    predicate: Option<Arc<dyn PhysicalExpr>>,
+    /// index of the updated column in [PartitionRewriter::schema] and its new value
+    assignments: Vec<(usize, Arc<dyn PhysicalExpr>)>,
+    sink_schema: SchemaRef,
+}
+
+impl Debug for LakeSoulUpdateExec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "LakeSoulUpdateExec predicate: {:?}, assignments: {:?}",
+            self.predicate, self.assignments
+        )
+    }
+}
+
+impl LakeSoulUpdateExec {
+    pub fn new(
+        client: MetaDataClientRef,
+        table_info: Arc<TableInfo>,
+        partition_filters: Vec<Expr>,
+        predicate: Option<Arc<dyn PhysicalExpr>>,
+        assignments: Vec<(usize, Arc<dyn PhysicalExpr>)>,
+    ) -> Self {
+        Self {
+            client,
+            table_info,
+            partition_filters,
+            predicate,
+            assignments,
+            sink_schema: make_sink_schema(),
+        }
+    }
+
+    async fn update(
+        client: MetaDataClientRef,
+        table_info: Arc<TableInfo>,
+        partition_filters: Vec<Expr>,
+        predicate: Option<Arc<dyn PhysicalExpr>>,
+        assignments: Vec<(usize, Arc<dyn PhysicalExpr>)>,
+    ) -> crate::error::Result<u64> {
+        let rewriter = PartitionRewriter::try_new(client.clone(), table_info.clone())?;
+        let partitions = rewriter.prune_partitions(&partition_filters).await?;
+
+        let mut updated_rows = 0;
+        let mut data_commit_info_list = vec![];
+        let mut read_partition_info = vec![];
+        for partition_info in partitions {
+            let (affected_rows, data_commit_info) = rewriter
+                .rewrite(&partition_info, CommitOp::UpdateCommit, |batch| {
+                    update_batch(batch, predicate.as_ref(), &assignments)
+                })
+                .await?;
+            if let Some(data_commit_info) = data_commit_info {
+                updated_rows += affected_rows;
+                data_commit_info_list.push(data_commit_info);
+                read_partition_info.push(partition_info);
+            }
+        }
+        client
+            .commit_data_commit_info_list(data_commit_info_list, read_partition_info, CommitOp::UpdateCommit)
+            .await?;
+        Ok(updated_rows)
+    }
+}
+
+/// Apply `assignments` to the rows of `batch` matching `predicate`.
+/// All the new values are evaluated against the original rows.
+fn update_batch(
+    batch: RecordBatch,
+    predicate: Option<&Arc<dyn PhysicalExpr>>,
+    assignments: &[(usize, Arc<dyn PhysicalExpr>)],
+) -> crate::error::Result<(RecordBatch, u64)> {
+    let num_rows = batch.num_rows();
+    let mask = match predicate {
+        Some(predicate) => prep_null_mask_filter(predicate.evaluate(&batch)?.into_array(num_rows)?.as_boolean()),
+        None => BooleanArray::from(vec![true; num_rows]),
+    };
+    let updated = mask.true_count() as u64;
+    if updated == 0 {
+        return Ok((batch, 0));
+    }
+    let mut columns = batch.columns().to_vec();
+    for (idx, value) in assignments {
+        let value = value.evaluate(&batch)?.into_array(num_rows)?;
+        columns[*idx] = zip(&mask, value.as_ref(), batch.column(*idx).as_ref())?;
+    }
+    Ok((RecordBatch::try_new(batch.schema(), columns)?, updated))
+}
+
+impl DisplayAs for LakeSoulUpdateExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LakeSoulUpdateExec")
+    }
+}
+
+impl ExecutionPlan for LakeSoulUpdateExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.sink_schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(self: Arc<Self>, _children: Vec<Arc<dyn ExecutionPlan>>) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(&self, partition: usize, _context: Arc<TaskContext>) -> Result<SendableRecordBatchStream> {
+        if partition != 0 {
+            return Err(DataFusionError::NotImplemented(
+                "LakeSoulUpdateExec can only be called on partition 0!".to_string(),
+            ));
+        }
+        let join_handle = tokio::spawn(Self::update(
+            self.client.clone(),
+            self.table_info.clone(),
+            self.partition_filters.clone(),
+            self.predicate.clone(),
+            self.assignments.clone(),
+        ));
+
+        let stream = futures::stream::once(async move {
+            match join_handle.await {
+                Ok(Ok(count)) => Ok(make_sink_batch(count, String::from(""))),
+                Ok(Err(e)) => Err(DataFusionError::External(Box::new(e))),
+                Err(e) => Err(DataFusionError::External(Box::new(e))),
+            }
+        })
+        .boxed();
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.sink_schema.clone(),
+            stream,
+        )))
+    }
+}
@@ -0,0 +1,130 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use async_trait::async_trait;
+use datafusion::datasource::TableProvider;
+use datafusion::error::Result;
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::{Expr, TableType};
+use datafusion::physical_plan::ExecutionPlan;
+
+use crate::datasource::physical_plan::{LakeSoulStreamingExec, StreamingOffset};
+use crate::lakesoul_table::incremental::CHANGE_TYPE_COLUMN;
+use crate::lakesoul_table::LakeSoulTable;
+
+/// Default interval of polling the metadata for new versions of the table.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Unbounded source of the rows committed to a LakeSoul table, see [LakeSoulStreamingExec].
+///
+/// The streaming read starts from the [StreamingOffset] given by [Self::with_offset], which reads the table from the
+/// first version by default, and the offset is advanced by the batches returned, see [Self::offset].
+pub struct LakeSoulStreamingTableProvider {
+    table: Arc<LakeSoulTable>,
+    schema: SchemaRef,
+    offset: Arc<Mutex<StreamingOffset>>,
+    poll_interval: Duration,
+    with_change_type: bool,
+    commit_notification: bool,
+}
+
+impl LakeSoulStreamingTableProvider {
+    pub async fn try_new(session_state: &SessionState, table: LakeSoulTable) -> crate::error::Result<Self> {
+        let schema = table.as_provider(session_state, None).await?.schema();
+        Ok(Self {
+            table: Arc::new(table),
+            schema,
+            offset: Default::default(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            with_change_type: false,
+            commit_notification: false,
+        })
+    }
+
+    /// Resume the streaming read from `offset`, e.g. [StreamingOffset::latest] to read only the later commits.
+    pub fn with_offset(self, offset: StreamingOffset) -> Self {
+        Self {
+            offset: Arc::new(Mutex::new(offset)),
+            ..self
+        }
+    }
+
+    pub fn with_poll_interval(self, poll_interval: Duration) -> Self {
+        Self { poll_interval, ..self }
+    }
+
+    /// Read the new versions once notified of their commits, instead of at the next poll interval.
+    /// The writers should enable the commit notification of their [MetaDataClient](lakesoul_metadata::MetaDataClient).
+    pub fn with_commit_notification(self, commit_notification: bool) -> Self {
+        Self {
+            commit_notification,
+            ..self
+        }
+    }
+
+    /// Also return the deleted rows and the change type of each row in the column [CHANGE_TYPE_COLUMN].
+    pub fn with_change_type(self, with_change_type: bool) -> Self {
+        let schema = match (self.with_change_type, with_change_type) {
+            (false, true) => {
+                let mut fields = self.schema.fields().to_vec();
+                fields.push(Arc::new(Field::new(CHANGE_TYPE_COLUMN, DataType::Utf8, false)));
+                Arc::new(Schema::new_with_metadata(fields, self.schema.metadata().clone()))
+            }
+            (true, false) => {
+                let fields = self.schema.fields()[..self.schema.fields().len() - 1].to_vec();
+                Arc::new(Schema::new_with_metadata(fields, self.schema.metadata().clone()))
+            }
+            _ => self.schema.clone(),
+        };
+        Self {
+            schema,
+            with_change_type,
+            ..self
+        }
+    }
+
+    /// The offset of the rows returned so far, which is shared by the scans of the provider.
+    pub fn offset(&self) -> StreamingOffset {
+        self.offset.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl TableProvider for LakeSoulStreamingTableProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(LakeSoulStreamingExec::try_new(
+            self.table.clone(),
+            state.clone(),
+            self.offset.clone(),
+            self.schema.clone(),
+            projection.cloned(),
+            self.poll_interval,
+            self.with_change_type,
+            self.commit_notification,
+        )?))
+    }
+}
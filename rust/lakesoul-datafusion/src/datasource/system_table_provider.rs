@@ -0,0 +1,276 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, Int32Array, Int64Array, ListBuilder, StringArray, StringBuilder, TimestampMillisecondArray,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use datafusion::datasource::TableProvider;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::{Expr, TableType};
+use datafusion::physical_plan::memory::MemoryExec;
+use datafusion::physical_plan::ExecutionPlan;
+use lakesoul_metadata::MetaDataClientRef;
+use proto::proto::entity::{PartitionInfo, TableInfo, Uuid};
+
+/// The delimiter between the table name and the name of a system table of the table, e.g. `t$files`.
+pub const SYSTEM_TABLE_DELIM: char = '$';
+
+/// The system tables exposing the metadata of a LakeSoul table, see [LakeSoulSystemTableProvider].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemTableType {
+    /// `t$files`: the file ops of the data commits in the latest version of each partition.
+    Files,
+    /// `t$partitions`: the latest version of each partition.
+    Partitions,
+    /// `t$snapshots`: every version of each partition with the ids of the data commits in its snapshot.
+    Snapshots,
+}
+
+impl SystemTableType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "files" => Some(Self::Files),
+            "partitions" => Some(Self::Partitions),
+            "snapshots" => Some(Self::Snapshots),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Files => "files",
+            Self::Partitions => "partitions",
+            Self::Snapshots => "snapshots",
+        }
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        let timestamp = || Field::new("timestamp", DataType::Timestamp(TimeUnit::Millisecond, None), false);
+        let fields = match self {
+            Self::Files => vec![
+                Field::new("partition_desc", DataType::Utf8, false),
+                Field::new("commit_id", DataType::Utf8, false),
+                Field::new("commit_op", DataType::Utf8, false),
+                Field::new("path", DataType::Utf8, false),
+                Field::new("file_op", DataType::Utf8, false),
+                Field::new("size", DataType::Int64, false),
+                Field::new("file_exist_cols", DataType::Utf8, false),
+            ],
+            Self::Partitions => vec![
+                Field::new("partition_desc", DataType::Utf8, false),
+                Field::new("version", DataType::Int32, false),
+                Field::new("commit_op", DataType::Utf8, false),
+                timestamp(),
+                Field::new("num_commits", DataType::Int64, false),
+                Field::new("domain", DataType::Utf8, false),
+            ],
+            Self::Snapshots => vec![
+                Field::new("partition_desc", DataType::Utf8, false),
+                Field::new("version", DataType::Int32, false),
+                Field::new("commit_op", DataType::Utf8, false),
+                timestamp(),
+                Field::new(
+                    "snapshot",
+                    DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+                    false,
+                ),
+            ],
+        };
+        Arc::new(Schema::new(fields))
+    }
+}
+
+/// Split a table name like `t$files` into the table name and the [SystemTableType], if any.
+pub fn split_system_table(name: &str) -> Option<(&str, SystemTableType)> {
+    match name.rsplit_once(SYSTEM_TABLE_DELIM) {
+        Some((table_name, system_table)) if !table_name.is_empty() => {
+            Some((table_name, SystemTableType::from_name(system_table)?))
+        }
+        _ => None,
+    }
+}
+
+/// Reads a system table of a LakeSoul table, see [SystemTableType].
+///
+/// The metadata is queried by each scan, so that a system table always reflects the latest commits.
+pub struct LakeSoulSystemTableProvider {
+    client: MetaDataClientRef,
+    table_info: Arc<TableInfo>,
+    table_type: SystemTableType,
+    schema: SchemaRef,
+}
+
+impl LakeSoulSystemTableProvider {
+    pub fn new(client: MetaDataClientRef, table_info: Arc<TableInfo>, table_type: SystemTableType) -> Self {
+        Self {
+            client,
+            table_info,
+            table_type,
+            schema: table_type.schema(),
+        }
+    }
+
+    pub fn system_table_type(&self) -> SystemTableType {
+        self.table_type
+    }
+
+    /// Query the metadata of the system table into a record batch.
+    pub async fn record_batch(&self) -> Result<RecordBatch> {
+        let table_id = &self.table_info.table_id;
+        let mut partition_info_list = self
+            .client
+            .get_all_partition_info(table_id)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        partition_info_list.sort_by(|a, b| a.partition_desc.cmp(&b.partition_desc));
+        match self.table_type {
+            SystemTableType::Files => self.files_batch(partition_info_list).await,
+            SystemTableType::Partitions => self.partitions_batch(partition_info_list),
+            SystemTableType::Snapshots => {
+                let mut versions = Vec::new();
+                for partition_info in partition_info_list {
+                    versions.extend(
+                        self.client
+                            .get_all_versions_of_partition(table_id, &partition_info.partition_desc)
+                            .await
+                            .map_err(|e| DataFusionError::External(Box::new(e)))?,
+                    );
+                }
+                self.snapshots_batch(versions)
+            }
+        }
+    }
+
+    async fn files_batch(&self, partition_info_list: Vec<PartitionInfo>) -> Result<RecordBatch> {
+        let mut partition_descs = vec![];
+        let mut commit_ids = vec![];
+        let mut commit_ops = vec![];
+        let mut paths = vec![];
+        let mut file_ops = vec![];
+        let mut sizes = vec![];
+        let mut file_exist_cols = vec![];
+        for partition_info in partition_info_list.iter().filter(|p| !p.snapshot.is_empty()) {
+            let data_commit_info_list = self
+                .client
+                .get_data_commit_info_of_single_partition(partition_info)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            for data_commit_info in data_commit_info_list {
+                let commit_id = data_commit_info.commit_id.as_ref().map(uuid_string).unwrap_or_default();
+                let commit_op = data_commit_info.commit_op().as_str_name();
+                for file_op in &data_commit_info.file_ops {
+                    partition_descs.push(data_commit_info.partition_desc.clone());
+                    commit_ids.push(commit_id.clone());
+                    commit_ops.push(commit_op);
+                    paths.push(file_op.path.clone());
+                    file_ops.push(file_op.file_op().as_str_name());
+                    sizes.push(file_op.size);
+                    file_exist_cols.push(file_op.file_exist_cols.clone());
+                }
+            }
+        }
+        Ok(RecordBatch::try_new(
+            self.schema.clone(),
+            vec![
+                Arc::new(StringArray::from(partition_descs)) as ArrayRef,
+                Arc::new(StringArray::from(commit_ids)),
+                Arc::new(StringArray::from(commit_ops)),
+                Arc::new(StringArray::from(paths)),
+                Arc::new(StringArray::from(file_ops)),
+                Arc::new(Int64Array::from(sizes)),
+                Arc::new(StringArray::from(file_exist_cols)),
+            ],
+        )?)
+    }
+
+    fn partitions_batch(&self, partition_info_list: Vec<PartitionInfo>) -> Result<RecordBatch> {
+        Ok(RecordBatch::try_new(
+            self.schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    partition_info_list.iter().map(|p| &p.partition_desc),
+                )) as ArrayRef,
+                Arc::new(Int32Array::from_iter_values(
+                    partition_info_list.iter().map(|p| p.version),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    partition_info_list.iter().map(|p| p.commit_op().as_str_name()),
+                )),
+                Arc::new(TimestampMillisecondArray::from_iter_values(
+                    partition_info_list.iter().map(|p| p.timestamp),
+                )),
+                Arc::new(Int64Array::from_iter_values(
+                    partition_info_list.iter().map(|p| p.snapshot.len() as i64),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    partition_info_list.iter().map(|p| &p.domain),
+                )),
+            ],
+        )?)
+    }
+
+    fn snapshots_batch(&self, versions: Vec<PartitionInfo>) -> Result<RecordBatch> {
+        let mut snapshots = ListBuilder::new(StringBuilder::new());
+        for version in &versions {
+            snapshots.append_value(version.snapshot.iter().map(|commit_id| Some(uuid_string(commit_id))));
+        }
+        Ok(RecordBatch::try_new(
+            self.schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    versions.iter().map(|p| &p.partition_desc),
+                )) as ArrayRef,
+                Arc::new(Int32Array::from_iter_values(versions.iter().map(|p| p.version))),
+                Arc::new(StringArray::from_iter_values(
+                    versions.iter().map(|p| p.commit_op().as_str_name()),
+                )),
+                Arc::new(TimestampMillisecondArray::from_iter_values(
+                    versions.iter().map(|p| p.timestamp),
+                )),
+                Arc::new(snapshots.finish()),
+            ],
+        )?)
+    }
+}
+
+fn uuid_string(commit_id: &Uuid) -> String {
+    uuid::Uuid::from_u64_pair(commit_id.high, commit_id.low).to_string()
+}
+
+#[async_trait]
+impl TableProvider for LakeSoulSystemTableProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::View
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let batch = self.record_batch().await?;
+        Ok(Arc::new(MemoryExec::try_new(
+            &[vec![batch]],
+            self.schema.clone(),
+            projection.cloned(),
+        )?))
+    }
+}
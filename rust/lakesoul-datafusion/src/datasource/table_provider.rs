@@ -34,7 +34,7 @@ use futures::StreamExt;
 use lakesoul_io::helpers::listing_table_from_lakesoul_io_config;
 use lakesoul_io::lakesoul_io_config::LakeSoulIOConfig;
 use lakesoul_metadata::MetaDataClientRef;
-use proto::proto::entity::TableInfo;
+use proto::proto::entity::{PartitionInfo, TableInfo};
 
 use crate::catalog::parse_table_info_partitions;
 use crate::lakesoul_table::helpers::{listing_partition_info, parse_partitions_for_partition_desc, prune_partitions};
@@ -42,6 +42,30 @@ use crate::serialize::arrow_java::schema_from_metadata_str;
 
 use super::file_format::LakeSoulMetaDataParquetFormat;
 
+/// The snapshot of a LakeSoul table to read, see [LakeSoulTableProvider::with_snapshot].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableSnapshot {
+    /// The latest version of each partition which is not greater than the version.
+    Version(i32),
+    /// The latest version of each partition committed not later than the timestamp in milliseconds.
+    Timestamp(i64),
+    /// The versions of the partitions pinned by the tag,
+    /// see [MetaDataClient::create_tag](lakesoul_metadata::MetaDataClient::create_tag).
+    Tag(String),
+}
+
+/// The delimiter between the table name and the tag name of a table reference reading a tag, e.g. `t@month_end`,
+/// or the branch name of a table reference reading and writing a branch, e.g. `t@dev`.
+pub const TABLE_TAG_DELIM: char = '@';
+
+/// Split a table name like `t@tag` or `t@branch` into the table name and the tag or branch name, if any.
+pub fn split_table_tag(name: &str) -> (&str, Option<&str>) {
+    match name.rsplit_once(TABLE_TAG_DELIM) {
+        Some((table_name, tag_name)) if !table_name.is_empty() && !tag_name.is_empty() => (table_name, Some(tag_name)),
+        _ => (name, None),
+    }
+}
+
 /// Reads data from LakeSoul
 ///
 /// # Features
@@ -63,6 +87,8 @@ pub struct LakeSoulTableProvider {
     file_schema: SchemaRef,
     primary_keys: Vec<String>,
     range_partitions: Vec<String>,
+    snapshot: Option<TableSnapshot>,
+    partitions: Option<Vec<PartitionInfo>>,
 }
 
 impl LakeSoulTableProvider {
@@ -110,9 +136,33 @@ impl LakeSoulTableProvider {
             file_schema,
             primary_keys: hash_partitions,
             range_partitions,
+            snapshot: None,
+            partitions: None,
         })
     }
 
+    /// Read the snapshot of the table instead of the latest version.
+    pub fn with_snapshot(mut self, snapshot: TableSnapshot) -> Self {
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    pub fn snapshot(&self) -> Option<&TableSnapshot> {
+        self.snapshot.as_ref()
+    }
+
+    /// Read the given partitions instead of the latest version, e.g. some of the commits of each partition.
+    /// The partitions without commits are ignored.
+    pub fn with_partitions(mut self, partitions: Vec<PartitionInfo>) -> Self {
+        self.partitions = Some(
+            partitions
+                .into_iter()
+                .filter(|partition_info| !partition_info.snapshot.is_empty())
+                .collect(),
+        );
+        self
+    }
+
     fn client(&self) -> MetaDataClientRef {
         self.client.clone()
     }
@@ -133,7 +183,7 @@ impl LakeSoulTableProvider {
         &self.table_info.table_namespace
     }
 
-    fn table_id(&self) -> &str {
+    pub(crate) fn table_id(&self) -> &str {
         &self.table_info.table_id
     }
 
@@ -211,25 +261,41 @@ impl LakeSoulTableProvider {
             return Ok((vec![], Statistics::new_unknown(&self.file_schema())));
         };
 
-        let all_partition_info = self.client.get_all_partition_info(self.table_id()).await.map_err(|_| {
-            DataFusionError::External(
+        let all_partition_info = match (&self.partitions, &self.snapshot) {
+            (Some(partitions), _) => Ok(partitions.clone()),
+            (None, None) => self.client.get_all_partition_info(self.table_id()).await,
+            (None, Some(TableSnapshot::Version(version))) => {
+                self.client
+                    .get_all_partition_info_as_of_version(self.table_id(), *version)
+                    .await
+            }
+            (None, Some(TableSnapshot::Timestamp(timestamp))) => {
+                self.client
+                    .get_all_partition_info_as_of_timestamp(self.table_id(), *timestamp)
+                    .await
+            }
+            (None, Some(TableSnapshot::Tag(tag_name))) => {
+                self.client
+                    .get_all_partition_info_of_tag(self.table_id(), tag_name)
+                    .await
+            }
+        }
+        .map_err(|e| {
+            DataFusionError::Context(
                 format!(
                     "get all partition_info of table {} failed",
                     &self.table_info().table_name
-                )
-                .into(),
+                ),
+                Box::new(DataFusionError::External(Box::new(e))),
             )
         })?;
 
         let prune_partition_info = prune_partitions(all_partition_info, filters, self.table_partition_cols())
             .await
-            .map_err(|_| {
-                DataFusionError::External(
-                    format!(
-                        "get all partition_info of table {} failed",
-                        &self.table_info().table_name
-                    )
-                    .into(),
+            .map_err(|e| {
+                DataFusionError::Context(
+                    format!("prune partitions of table {} failed", &self.table_info().table_name),
+                    Box::new(DataFusionError::External(Box::new(e))),
                 )
             })?;
 
@@ -362,6 +428,12 @@ impl TableProvider for LakeSoulTableProvider {
         input: Arc<dyn ExecutionPlan>,
         overwrite: bool,
     ) -> Result<Arc<dyn ExecutionPlan>> {
+        if self.snapshot.is_some() || self.partitions.is_some() {
+            return Err(DataFusionError::Plan(format!(
+                "Cannot insert into a snapshot of table {}",
+                self.table_name()
+            )));
+        }
         let table_path = &self.listing_table.table_paths()[0];
         // Get the object store for the table path.
         let _store = state.runtime_env().object_store(table_path)?;
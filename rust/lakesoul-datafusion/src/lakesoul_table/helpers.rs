@@ -37,12 +37,18 @@ use crate::{
 pub(crate) fn create_io_config_builder_from_table_info(table_info: Arc<TableInfo>) -> Result<LakeSoulIOConfigBuilder> {
     let (range_partitions, hash_partitions) = parse_table_info_partitions(table_info.partitions.clone())?;
     let properties = serde_json::from_str::<LakeSoulTableProperty>(&table_info.properties)?;
-    Ok(LakeSoulIOConfigBuilder::new()
+    let builder = LakeSoulIOConfigBuilder::new()
         .with_schema(schema_from_metadata_str(&table_info.table_schema))
         .with_prefix(table_info.table_path.clone())
         .with_primary_keys(hash_partitions)
         .with_range_partitions(range_partitions)
-        .with_hash_bucket_num(properties.hash_bucket_num.unwrap_or(1)))
+        .with_hash_bucket_num(properties.hash_bucket_num.unwrap_or(1));
+    Ok(properties
+        .renamed_columns
+        .into_iter()
+        .fold(builder, |builder, (previous, current)| {
+            builder.with_renamed_column(previous, current)
+        }))
 }
 
 pub async fn prune_partitions(
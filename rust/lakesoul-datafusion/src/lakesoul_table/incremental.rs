@@ -0,0 +1,191 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use datafusion::dataframe::DataFrame;
+use datafusion::execution::context::SessionContext;
+use datafusion::logical_expr::lit;
+use lakesoul_metadata::error::LakeSoulMetaDataError;
+use lakesoul_metadata::MetaDataClientRef;
+use proto::proto::entity::{CommitOp, PartitionInfo};
+use tracing::debug;
+
+use crate::datasource::table_provider::TableSnapshot;
+use crate::error::{LakeSoulError, Result};
+use crate::lakesoul_table::LakeSoulTable;
+
+/// The column of the change type of the rows of an incremental read, see [LakeSoulTable::to_incremental_dataframe].
+pub const CHANGE_TYPE_COLUMN: &str = "lakesoul_change_type";
+/// Change type of the rows appended by an AppendCommit.
+pub const CHANGE_TYPE_INSERT: &str = "insert";
+/// Change type of the rows upserted by a MergeCommit, or updated by an UpdateCommit.
+pub const CHANGE_TYPE_UPDATE: &str = "update";
+/// Change type of the rows removed by a DeleteCommit.
+pub const CHANGE_TYPE_DELETE: &str = "delete";
+
+/// Read the rows committed to `table` after `start` until `end`, with the [CHANGE_TYPE_COLUMN] if `with_change_type`.
+///
+/// For each partition, the versions after the version of `start` until the version of `end` are read by commit op:
+/// - the commits added by AppendCommit and MergeCommit are read as the inserted and upserted rows,
+/// - UpdateCommit and DeleteCommit rewrite the partition, whose changed rows are the difference from the previous
+///   version, i.e. the updated rows of the new version and the deleted rows of the previous version,
+/// - CompactionCommit does not change any row.
+///
+/// The deleted rows are only returned with the change type.
+pub(crate) async fn incremental_dataframe(
+    table: &LakeSoulTable,
+    context: &SessionContext,
+    start: TableSnapshot,
+    end: TableSnapshot,
+    with_change_type: bool,
+) -> Result<DataFrame> {
+    let client = table.client();
+    let mut version_ranges = vec![];
+    for partition_info in client.get_all_partition_info(&table.table_info().table_id).await? {
+        let start_version = version_of(&client, &partition_info, &start).await?;
+        let end_version = version_of(&client, &partition_info, &end).await?;
+        version_ranges.push((partition_info, start_version, end_version));
+    }
+    incremental_dataframe_of_versions(table, context, version_ranges, with_change_type).await
+}
+
+/// Read the rows committed to each partition of `version_ranges` after its start version until its end version,
+/// see [incremental_dataframe].
+pub(crate) async fn incremental_dataframe_of_versions(
+    table: &LakeSoulTable,
+    context: &SessionContext,
+    version_ranges: Vec<(PartitionInfo, i32, i32)>,
+    with_change_type: bool,
+) -> Result<DataFrame> {
+    let client = table.client();
+    let table_id = table.table_info().table_id.clone();
+
+    // commits adding rows of each partition
+    let mut appended = vec![];
+    let mut upserted = vec![];
+    // (commit op, previous version, rewritten version) of the partitions rewritten by UpdateCommit or DeleteCommit
+    let mut rewritten = vec![];
+    for (partition_info, start_version, end_version) in version_ranges {
+        if end_version <= start_version {
+            continue;
+        }
+        let mut versions = client
+            .get_incremental_partitions(
+                &table_id,
+                &partition_info.partition_desc,
+                start_version.max(0),
+                end_version,
+            )
+            .await?;
+        versions.sort_by_key(|version| version.version);
+
+        let mut partition_appended = PartitionInfo {
+            snapshot: vec![],
+            ..partition_info.clone()
+        };
+        let mut partition_upserted = partition_appended.clone();
+        let mut previous = partition_appended.clone();
+        for version in versions {
+            if version.version > start_version {
+                let previous_commits = previous
+                    .snapshot
+                    .iter()
+                    .map(|commit_id| (commit_id.high, commit_id.low))
+                    .collect::<HashSet<_>>();
+                let added_commits = version
+                    .snapshot
+                    .iter()
+                    .filter(|commit_id| !previous_commits.contains(&(commit_id.high, commit_id.low)))
+                    .cloned();
+                match CommitOp::try_from(version.commit_op) {
+                    Ok(CommitOp::AppendCommit) => partition_appended.snapshot.extend(added_commits),
+                    Ok(CommitOp::MergeCommit) => partition_upserted.snapshot.extend(added_commits),
+                    Ok(commit_op @ (CommitOp::UpdateCommit | CommitOp::DeleteCommit)) => {
+                        rewritten.push((commit_op, previous.clone(), version.clone()))
+                    }
+                    _ => {}
+                }
+            }
+            previous = version;
+        }
+        appended.push(partition_appended);
+        upserted.push(partition_upserted);
+    }
+    debug!(
+        "incremental read of table {}: {} rewritten versions",
+        table.table_name(),
+        rewritten.len()
+    );
+
+    let mut changes = vec![
+        (CHANGE_TYPE_INSERT, read_partitions(table, context, appended).await?),
+        (CHANGE_TYPE_UPDATE, read_partitions(table, context, upserted).await?),
+    ];
+    for (commit_op, previous, current) in rewritten {
+        let previous = read_partitions(table, context, vec![previous]).await?;
+        let current = read_partitions(table, context, vec![current]).await?;
+        match commit_op {
+            CommitOp::DeleteCommit => changes.push((CHANGE_TYPE_DELETE, previous.except(current)?)),
+            _ => changes.push((CHANGE_TYPE_UPDATE, current.except(previous)?)),
+        }
+    }
+
+    let mut dataframe: Option<DataFrame> = None;
+    for (change_type, changed) in changes {
+        let changed = match with_change_type {
+            true => changed.with_column(CHANGE_TYPE_COLUMN, lit(change_type))?,
+            false if change_type == CHANGE_TYPE_DELETE => continue,
+            false => changed,
+        };
+        dataframe = Some(match dataframe {
+            Some(dataframe) => dataframe.union(changed)?,
+            None => changed,
+        });
+    }
+    dataframe.ok_or(LakeSoulError::Internal("empty incremental read".to_string()))
+}
+
+/// The version of a partition as of `snapshot`, which is -1 if the partition did not exist.
+async fn version_of(
+    client: &MetaDataClientRef,
+    partition_info: &PartitionInfo,
+    snapshot: &TableSnapshot,
+) -> Result<i32> {
+    match snapshot {
+        TableSnapshot::Version(version) => Ok((*version).min(partition_info.version)),
+        TableSnapshot::Timestamp(timestamp) => Ok(client
+            // the version committed at `timestamp` is included
+            .get_latest_version_up_to_time(&partition_info.table_id, &partition_info.partition_desc, timestamp + 1)
+            .await?
+            .unwrap_or(-1)),
+        TableSnapshot::Tag(tag_name) => {
+            let table_tag = client
+                .get_tag(&partition_info.table_id, tag_name)
+                .await?
+                .ok_or_else(|| {
+                    LakeSoulMetaDataError::NotFound(format!("tag {} of table {}", tag_name, partition_info.table_id))
+                })?;
+            // the partitions created after tagging did not exist
+            Ok(table_tag
+                .partition_versions
+                .get(&partition_info.partition_desc)
+                .copied()
+                .unwrap_or(-1))
+        }
+    }
+}
+
+async fn read_partitions(
+    table: &LakeSoulTable,
+    context: &SessionContext,
+    partitions: Vec<PartitionInfo>,
+) -> Result<DataFrame> {
+    let provider = table
+        .as_provider(&context.state(), None)
+        .await?
+        .with_partitions(partitions);
+    Ok(context.read_table(Arc::new(provider))?)
+}
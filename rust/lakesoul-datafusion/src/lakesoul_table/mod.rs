@@ -3,11 +3,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod helpers;
+pub mod incremental;
 
 use std::{ops::Deref, sync::Arc};
 
 use arrow::datatypes::{Schema, SchemaRef};
 use arrow_cast::pretty::pretty_format_batches;
+use datafusion::error::DataFusionError;
 use datafusion::sql::TableReference;
 use datafusion::{
     dataframe::DataFrame,
@@ -15,20 +17,25 @@ use datafusion::{
     execution::context::{SessionContext, SessionState},
     logical_expr::LogicalPlanBuilder,
 };
-use lakesoul_io::{lakesoul_io_config::create_session_context_with_planner, lakesoul_reader::RecordBatch};
-use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
-use proto::proto::entity::TableInfo;
+use lakesoul_io::lakesoul_io_config::{create_session_context_with_planner, LakeSoulIOConfigBuilder};
+use lakesoul_io::lakesoul_reader::RecordBatch;
+use lakesoul_metadata::{branch_table_id, CommitHistory, MetaDataClient, MetaDataClientRef};
+use proto::proto::entity::{PartitionInfo, TableBranch, TableInfo, TableTag};
 use tracing::debug;
 
 use crate::{
-    catalog::{create_io_config_builder, parse_table_info_partitions, LakeSoulTableProperty},
-    error::Result,
+    catalog::{create_io_config_builder, delete_branch, parse_table_info_partitions, LakeSoulTableProperty},
+    error::{LakeSoulError, Result},
     planner::query_planner::LakeSoulQueryPlanner,
     serialize::arrow_java::schema_from_metadata_str,
 };
 
-use crate::datasource::table_provider::LakeSoulTableProvider;
+use crate::datasource::physical_plan::StreamingOffset;
+use crate::datasource::streaming_table_provider::LakeSoulStreamingTableProvider;
+use crate::datasource::table_provider::{split_table_tag, LakeSoulTableProvider, TableSnapshot, TABLE_TAG_DELIM};
+use crate::lakesoul_table::helpers::create_io_config_builder_from_table_info;
 
+#[derive(Clone)]
 pub struct LakeSoulTable {
     client: MetaDataClientRef,
     table_info: Arc<TableInfo>,
@@ -56,8 +63,24 @@ impl LakeSoulTable {
 
     pub async fn for_namespace_and_name(namespace: &str, table_name: &str) -> Result<Self> {
         let client = Arc::new(MetaDataClient::from_env().await?);
+        Self::for_namespace_and_name_with_client(client, namespace, table_name).await
+    }
+
+    /// The table `namespace.table_name` in the metadata of `client`, e.g. the client of a session,
+    /// instead of connecting to the metadata for each table.
+    /// A name like `{table_name}@{branch_name}` is the branch of the table, see [LakeSoulTable::branch].
+    pub async fn for_namespace_and_name_with_client(
+        client: MetaDataClientRef,
+        namespace: &str,
+        table_name: &str,
+    ) -> Result<Self> {
+        let (table_name, branch_name) = split_table_tag(table_name);
         let table_info = client.get_table_info_by_table_name(table_name, namespace).await?;
-        Self::try_new_with_client_and_table_info(client, table_info).await
+        let table = Self::try_new_with_client_and_table_info(client, table_info).await?;
+        match branch_name {
+            Some(branch_name) => table.branch(branch_name).await,
+            None => Ok(table),
+        }
     }
 
     pub async fn try_new_with_client_and_table_info(client: MetaDataClientRef, table_info: TableInfo) -> Result<Self> {
@@ -79,10 +102,11 @@ impl LakeSoulTable {
     }
 
     pub async fn upsert_dataframe(&self, dataframe: DataFrame) -> Result<()> {
-        let client = Arc::new(MetaDataClient::from_env().await?);
-        let builder = create_io_config_builder(client, None, false, self.table_namespace()).await?;
-        let sess_ctx =
-            create_session_context_with_planner(&mut builder.clone().build(), Some(LakeSoulQueryPlanner::new_ref()))?;
+        let builder = create_io_config_builder(self.client(), None, false, self.table_namespace()).await?;
+        let sess_ctx = create_session_context_with_planner(
+            &mut builder.clone().build(),
+            Some(LakeSoulQueryPlanner::new_ref_with_client(self.client())),
+        )?;
 
         let schema: Schema = dataframe.schema().into();
         let logical_plan = LogicalPlanBuilder::insert_into(
@@ -103,10 +127,11 @@ impl LakeSoulTable {
     }
 
     pub async fn execute_upsert(&self, record_batch: RecordBatch) -> Result<()> {
-        let client = Arc::new(MetaDataClient::from_env().await?);
-        let builder = create_io_config_builder(client, None, false, self.table_namespace()).await?;
-        let sess_ctx =
-            create_session_context_with_planner(&mut builder.clone().build(), Some(LakeSoulQueryPlanner::new_ref()))?;
+        let builder = create_io_config_builder(self.client(), None, false, self.table_namespace()).await?;
+        let sess_ctx = create_session_context_with_planner(
+            &mut builder.clone().build(),
+            Some(LakeSoulQueryPlanner::new_ref_with_client(self.client())),
+        )?;
 
         let schema = record_batch.schema();
         let logical_plan = LogicalPlanBuilder::insert_into(
@@ -128,26 +153,84 @@ impl LakeSoulTable {
     }
 
     pub async fn to_dataframe(&self, context: &SessionContext) -> Result<DataFrame> {
-        let config_builder =
-            create_io_config_builder(self.client(), Some(self.table_name()), true, self.table_namespace()).await?;
-        let provider = Arc::new(
-            LakeSoulTableProvider::try_new(
-                &context.state(),
-                self.client(),
-                config_builder.build(),
-                self.table_info(),
-                false,
-            )
-            .await?,
-        );
-        Ok(context.read_table(provider)?)
+        Ok(context.read_table(Arc::new(self.as_provider(&context.state(), None).await?))?)
+    }
+
+    /// Read the snapshot of the table as of `version`, see [TableSnapshot::Version].
+    pub async fn to_dataframe_as_of_version(&self, context: &SessionContext, version: i32) -> Result<DataFrame> {
+        let provider = self
+            .as_provider(&context.state(), Some(TableSnapshot::Version(version)))
+            .await?;
+        Ok(context.read_table(Arc::new(provider))?)
+    }
+
+    /// Read the snapshot of the table as of `timestamp` in milliseconds, see [TableSnapshot::Timestamp].
+    pub async fn to_dataframe_as_of_timestamp(&self, context: &SessionContext, timestamp: i64) -> Result<DataFrame> {
+        let provider = self
+            .as_provider(&context.state(), Some(TableSnapshot::Timestamp(timestamp)))
+            .await?;
+        Ok(context.read_table(Arc::new(provider))?)
+    }
+
+    /// Read the snapshot of the table pinned by tag `tag_name`, see [TableSnapshot::Tag].
+    pub async fn to_dataframe_of_tag(&self, context: &SessionContext, tag_name: &str) -> Result<DataFrame> {
+        let provider = self
+            .as_provider(&context.state(), Some(TableSnapshot::Tag(tag_name.to_string())))
+            .await?;
+        Ok(context.read_table(Arc::new(provider))?)
+    }
+
+    /// Read the rows committed after `start` until `end`, with the change type of each row if `with_change_type`,
+    /// see [incremental::incremental_dataframe].
+    pub async fn to_incremental_dataframe(
+        &self,
+        context: &SessionContext,
+        start: TableSnapshot,
+        end: TableSnapshot,
+        with_change_type: bool,
+    ) -> Result<DataFrame> {
+        incremental::incremental_dataframe(self, context, start, end, with_change_type).await
+    }
+
+    /// Create a [LakeSoulTableProvider] reading the latest version or the `snapshot` of the table.
+    pub async fn as_provider(
+        &self,
+        session_state: &SessionState,
+        snapshot: Option<TableSnapshot>,
+    ) -> Result<LakeSoulTableProvider> {
+        let config_builder = self.io_config_builder(true).await?;
+        let provider = LakeSoulTableProvider::try_new(
+            session_state,
+            self.client(),
+            config_builder.build(),
+            self.table_info(),
+            false,
+        )
+        .await?;
+        Ok(match snapshot {
+            Some(snapshot) => provider.with_snapshot(snapshot),
+            None => provider,
+        })
+    }
+
+    /// Create a [LakeSoulStreamingTableProvider] tailing the table from `offset`, or from the first version if `None`.
+    pub async fn as_streaming_provider(
+        &self,
+        session_state: &SessionState,
+        offset: Option<StreamingOffset>,
+    ) -> Result<LakeSoulStreamingTableProvider> {
+        let provider = LakeSoulStreamingTableProvider::try_new(session_state, self.clone()).await?;
+        Ok(match offset {
+            Some(offset) => provider.with_offset(offset),
+            None => provider,
+        })
     }
 
     pub async fn as_sink_provider(&self, session_state: &SessionState) -> Result<Arc<dyn TableProvider>> {
-        let config_builder =
-            create_io_config_builder(self.client(), Some(self.table_name()), false, self.table_namespace())
-                .await?
-                .with_prefix(self.table_info.table_path.clone());
+        let config_builder = self
+            .io_config_builder(false)
+            .await?
+            .with_prefix(self.table_info.table_path.clone());
         Ok(Arc::new(
             LakeSoulTableProvider::try_new(
                 session_state,
@@ -160,6 +243,169 @@ impl LakeSoulTable {
         ))
     }
 
+    /// Roll back partition `partition_desc` to its version `version`, see [MetaDataClient::rollback_partition].
+    /// Returns the new version of the partition, or `None` if `version` is already the latest version.
+    pub async fn rollback_partition(&self, partition_desc: &str, version: i32) -> Result<Option<PartitionInfo>> {
+        Ok(self
+            .client
+            .rollback_partition(&self.table_info.table_id, partition_desc, version)
+            .await?)
+    }
+
+    /// Roll back the table to its version as of `version`, see [MetaDataClient::rollback_to_version].
+    /// Returns the new versions of the partitions rolled back.
+    pub async fn rollback_to_version(&self, version: i32) -> Result<Vec<PartitionInfo>> {
+        Ok(self
+            .client
+            .rollback_to_version(&self.table_info.table_id, version)
+            .await?)
+    }
+
+    /// Roll back the table to its version as of `timestamp` in milliseconds,
+    /// see [MetaDataClient::rollback_to_timestamp].
+    /// Returns the new versions of the partitions rolled back.
+    pub async fn rollback_to_timestamp(&self, timestamp: i64) -> Result<Vec<PartitionInfo>> {
+        Ok(self
+            .client
+            .rollback_to_timestamp(&self.table_info.table_id, timestamp)
+            .await?)
+    }
+
+    /// Roll back the table to `snapshot`, see [TableSnapshot].
+    pub async fn rollback(&self, snapshot: TableSnapshot) -> Result<Vec<PartitionInfo>> {
+        match snapshot {
+            TableSnapshot::Version(version) => self.rollback_to_version(version).await,
+            TableSnapshot::Timestamp(timestamp) => self.rollback_to_timestamp(timestamp).await,
+            TableSnapshot::Tag(tag_name) => Ok(self
+                .client
+                .rollback_to_tag(&self.table_info.table_id, &tag_name)
+                .await?),
+        }
+    }
+
+    /// Create tag `tag_name` pinning the latest version, or the `snapshot`, of each partition of the table,
+    /// which can be read by [TableSnapshot::Tag] or as table `{table_name}@{tag_name}` in SQL.
+    pub async fn create_tag(&self, tag_name: &str, snapshot: Option<TableSnapshot>) -> Result<TableTag> {
+        if tag_name.is_empty() || tag_name.contains(TABLE_TAG_DELIM) {
+            return Err(LakeSoulError::DataFusionError(DataFusionError::Plan(format!(
+                "Invalid tag name '{}' of table {}",
+                tag_name, self.table_name
+            ))));
+        }
+        let partition_info_list = self.snapshot_partition_info(snapshot).await?;
+        Ok(self
+            .client
+            .create_tag(&self.table_info.table_id, tag_name, &partition_info_list)
+            .await?)
+    }
+
+    /// The latest version, or the version of `snapshot`, of each partition of the table.
+    async fn snapshot_partition_info(&self, snapshot: Option<TableSnapshot>) -> Result<Vec<PartitionInfo>> {
+        let table_id = &self.table_info.table_id;
+        Ok(match snapshot {
+            None => self.client.get_all_partition_info(table_id).await?,
+            Some(TableSnapshot::Version(version)) => {
+                self.client
+                    .get_all_partition_info_as_of_version(table_id, version)
+                    .await?
+            }
+            Some(TableSnapshot::Timestamp(timestamp)) => {
+                self.client
+                    .get_all_partition_info_as_of_timestamp(table_id, timestamp)
+                    .await?
+            }
+            Some(TableSnapshot::Tag(other)) => self.client.get_all_partition_info_of_tag(table_id, &other).await?,
+        })
+    }
+
+    /// Delete tag `tag_name`, so that the versions pinned by it can be expired.
+    /// Returns whether the tag existed.
+    pub async fn delete_tag(&self, tag_name: &str) -> Result<bool> {
+        Ok(self.client.delete_tag(&self.table_info.table_id, tag_name).await? > 0)
+    }
+
+    /// The tags of the table, ordered by name.
+    pub async fn tags(&self) -> Result<Vec<TableTag>> {
+        Ok(self.client.get_all_tags(&self.table_info.table_id).await?)
+    }
+
+    /// The branch `branch_name` of the table, which is read and written like a table named
+    /// `{table_name}@{branch_name}` without changing the versions of the table, see [LakeSoulTable::create_branch].
+    pub async fn branch(&self, branch_name: &str) -> Result<Self> {
+        let table_info = self
+            .client
+            .get_table_info_by_table_id(&branch_table_id(&self.table_info.table_id, branch_name))
+            .await?;
+        Self::try_new_with_client_and_table_info(
+            self.client(),
+            TableInfo {
+                table_name: format!("{}{}{}", self.table_name, TABLE_TAG_DELIM, branch_name),
+                ..table_info
+            },
+        )
+        .await
+    }
+
+    /// Create branch `branch_name` forked from the latest version, or the `snapshot`, of each partition of the table,
+    /// which can be read and written by [LakeSoulTable::branch] or as table `{table_name}@{branch_name}` in SQL.
+    pub async fn create_branch(&self, branch_name: &str, snapshot: Option<TableSnapshot>) -> Result<TableBranch> {
+        if branch_name.is_empty() || branch_name.contains(TABLE_TAG_DELIM) {
+            return Err(LakeSoulError::DataFusionError(DataFusionError::Plan(format!(
+                "Invalid branch name '{}' of table {}",
+                branch_name, self.table_name
+            ))));
+        }
+        let partition_info_list = self.snapshot_partition_info(snapshot).await?;
+        Ok(self
+            .client
+            .create_branch(&self.table_info.table_id, branch_name, &partition_info_list)
+            .await?)
+    }
+
+    /// Delete branch `branch_name` with the files written to it, so that the versions it is forked from can be expired.
+    /// Returns whether the branch existed.
+    pub async fn delete_branch(&self, branch_name: &str) -> Result<bool> {
+        delete_branch(self.client(), &self.table_info, branch_name).await
+    }
+
+    /// The branches of the table, ordered by name.
+    pub async fn branches(&self) -> Result<Vec<TableBranch>> {
+        Ok(self.client.get_all_branches(&self.table_info.table_id).await?)
+    }
+
+    /// The commit history of the table, optionally of partition `partition_desc` and of the versions committed
+    /// from `start_timestamp` until `end_timestamp` in milliseconds, see [MetaDataClient::list_commit_history].
+    pub async fn history(
+        &self,
+        partition_desc: Option<&str>,
+        start_timestamp: Option<i64>,
+        end_timestamp: Option<i64>,
+    ) -> Result<Vec<CommitHistory>> {
+        Ok(self
+            .client
+            .list_commit_history(
+                &self.table_info.table_id,
+                partition_desc,
+                start_timestamp,
+                end_timestamp,
+            )
+            .await?)
+    }
+
+    /// The io config of the latest table info, looked up by the table id so that a branch is configured like its table,
+    /// with the latest data files if `fetch_files`.
+    async fn io_config_builder(&self, fetch_files: bool) -> Result<LakeSoulIOConfigBuilder> {
+        let table_id = &self.table_info.table_id;
+        let table_info = self.client.get_table_info_by_table_id(table_id).await?;
+        let data_files = if fetch_files {
+            let partition_info_list = self.client.get_all_partition_info(table_id).await?;
+            self.client.get_data_files_of_partitions(partition_info_list).await?
+        } else {
+            vec![]
+        };
+        Ok(create_io_config_builder_from_table_info(Arc::new(table_info))?.with_files(data_files))
+    }
+
     pub fn table_name(&self) -> &str {
         &self.table_name
     }
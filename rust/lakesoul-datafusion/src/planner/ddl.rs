@@ -0,0 +1,445 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use arrow::datatypes::Schema;
+use datafusion::common::DFSchema;
+use datafusion::dataframe::DataFrame;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::SessionContext;
+use datafusion::logical_expr::{CreateMemoryTable, DdlStatement, EmptyRelation, LogicalPlan, LogicalPlanBuilder};
+use datafusion::sql::parser::Statement as DFStatement;
+use datafusion::sql::sqlparser::ast::{
+    AlterColumnOperation, AlterTableOperation, ColumnDef, ColumnOption, Ident, ObjectName, ObjectType, Query,
+    SqlOption, Statement, TableConstraint, Value,
+};
+use datafusion::sql::sqlparser::dialect::Dialect;
+use datafusion::sql::sqlparser::keywords::Keyword;
+use datafusion::sql::sqlparser::parser::{IsOptional, Parser};
+use datafusion::sql::sqlparser::tokenizer::Token;
+use datafusion::sql::TableReference;
+use lakesoul_metadata::MetaDataClientRef;
+
+use crate::catalog::{
+    alter_table_schema, create_table_in_namespace, delete_table_data, LakeSoulCatalog, SchemaChange,
+    DEFAULT_HASH_BUCKET_NUM,
+};
+use crate::planner::normalize_ident;
+
+/// option of `CREATE TABLE ... WITH (...)` for the hash bucket number of a table with primary keys
+pub const HASH_BUCKET_NUM_OPTION: &str = "hash_bucket_num";
+
+/// `CREATE TABLE` statement of a LakeSoul table:
+///
+/// ```sql
+/// CREATE TABLE [IF NOT EXISTS] name [(column_def, ... [, PRIMARY KEY (column, ...)])]
+///     [PRIMARY KEY (column, ...)]
+///     [PARTITIONED BY (column, ...)]
+///     [WITH (hash_bucket_num = 4)]
+///     [AS query]
+/// ```
+///
+/// The primary keys are the hash partitions of the table, and `PARTITIONED BY` are the range partitions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateLakeSoulTable {
+    pub name: ObjectName,
+    pub if_not_exists: bool,
+    pub columns: Vec<ColumnDef>,
+    pub constraints: Vec<TableConstraint>,
+    pub primary_keys: Vec<Ident>,
+    pub range_partitions: Vec<Ident>,
+    pub options: Vec<SqlOption>,
+    pub query: Option<Box<Query>>,
+}
+
+/// Parse a `CREATE TABLE` statement into [CreateLakeSoulTable], returns `None` for other statements.
+///
+/// The parser of DataFusion does not support `PARTITIONED BY` of column names and `WITH` options of `CREATE TABLE`.
+pub fn parse_create_table(sql: &str, dialect: &dyn Dialect) -> Result<Option<CreateLakeSoulTable>> {
+    let mut parser = Parser::new(dialect).try_with_sql(sql)?;
+    if !parser.parse_keywords(&[Keyword::CREATE, Keyword::TABLE]) {
+        return Ok(None);
+    }
+    let if_not_exists = parser.parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+    let name = parser.parse_object_name()?;
+    let (columns, constraints) = parser.parse_columns()?;
+
+    let mut primary_keys = vec![];
+    let mut range_partitions = vec![];
+    let mut options = vec![];
+    loop {
+        if parser.parse_keywords(&[Keyword::PRIMARY, Keyword::KEY]) {
+            primary_keys = parser.parse_parenthesized_column_list(IsOptional::Mandatory, false)?;
+        } else if parser.parse_keywords(&[Keyword::PARTITIONED, Keyword::BY]) {
+            range_partitions = parser.parse_parenthesized_column_list(IsOptional::Mandatory, false)?;
+        } else if parser.parse_keyword(Keyword::WITH) {
+            parser.prev_token();
+            options = parser.parse_options(Keyword::WITH)?;
+        } else {
+            break;
+        }
+    }
+    let query = match parser.parse_keyword(Keyword::AS) {
+        true => Some(Box::new(parser.parse_query()?)),
+        false => None,
+    };
+    while parser.consume_token(&Token::SemiColon) {}
+    if parser.peek_token().token != Token::EOF {
+        return Ok(parser.expected("end of statement", parser.peek_token())?);
+    }
+
+    Ok(Some(CreateLakeSoulTable {
+        name,
+        if_not_exists,
+        columns,
+        constraints,
+        primary_keys,
+        range_partitions,
+        options,
+        query,
+    }))
+}
+
+/// Whether the table of `CREATE TABLE` is in a [LakeSoulCatalog] of `ctx`.
+pub fn is_lakesoul_table(ctx: &SessionContext, name: &ObjectName) -> bool {
+    let (catalog, _, _) = resolve_table_name(ctx, &name.to_string());
+    ctx.catalog(&catalog)
+        .map(|catalog| catalog.as_any().is::<LakeSoulCatalog>())
+        .unwrap_or(false)
+}
+
+/// Whether the schema of `DROP SCHEMA` is in a [LakeSoulCatalog] of `ctx`.
+pub fn is_lakesoul_schema(ctx: &SessionContext, name: &ObjectName) -> bool {
+    let (catalog, _) = resolve_schema_name(ctx, name);
+    ctx.catalog(&catalog)
+        .map(|catalog| catalog.as_any().is::<LakeSoulCatalog>())
+        .unwrap_or(false)
+}
+
+/// Resolve `(catalog, namespace)` of a schema name with the default catalog of `ctx`.
+fn resolve_schema_name(ctx: &SessionContext, name: &ObjectName) -> (String, String) {
+    match name.0.as_slice() {
+        [catalog, schema] => (normalize_ident(catalog), normalize_ident(schema)),
+        idents => (
+            ctx.state().config().options().catalog.default_catalog.clone(),
+            idents.iter().map(normalize_ident).collect::<Vec<_>>().join("."),
+        ),
+    }
+}
+
+/// Resolve `(catalog, namespace, table)` of a table name with the default catalog and schema of `ctx`.
+fn resolve_table_name(ctx: &SessionContext, name: &str) -> (String, String, String) {
+    let state = ctx.state();
+    let catalog_options = &state.config().options().catalog;
+    let resolved =
+        TableReference::from(name).resolve(&catalog_options.default_catalog, &catalog_options.default_schema);
+    (
+        resolved.catalog.to_string(),
+        resolved.schema.to_string(),
+        resolved.table.to_string(),
+    )
+}
+
+/// Resolve the metadata client of the [LakeSoulCatalog], the namespace and the name of a table.
+pub(crate) fn resolve_lakesoul_table(ctx: &SessionContext, name: &str) -> Result<(MetaDataClientRef, String, String)> {
+    let (catalog_name, namespace, table_name) = resolve_table_name(ctx, name);
+    let catalog = ctx
+        .catalog(&catalog_name)
+        .ok_or(DataFusionError::Plan(format!("Catalog '{}' not found", catalog_name)))?;
+    let client = catalog
+        .as_any()
+        .downcast_ref::<LakeSoulCatalog>()
+        .ok_or(DataFusionError::Plan(format!(
+            "Catalog '{}' is not a LakeSoul catalog",
+            catalog_name
+        )))?
+        .metadata_client();
+    Ok((client, namespace, table_name))
+}
+
+fn empty_dataframe(ctx: &SessionContext) -> DataFrame {
+    DataFrame::new(
+        ctx.state(),
+        LogicalPlan::EmptyRelation(EmptyRelation {
+            produce_one_row: false,
+            schema: Arc::new(DFSchema::empty()),
+        }),
+    )
+}
+
+/// Derive the arrow fields of the column definitions by planning a `CREATE TABLE` of DataFusion.
+async fn columns_to_schema(ctx: &SessionContext, name: &ObjectName, columns: &[ColumnDef]) -> Result<Schema> {
+    let columns = columns.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+    match ctx
+        .state()
+        .create_logical_plan(&format!("CREATE TABLE {} ({})", name, columns))
+        .await?
+    {
+        LogicalPlan::Ddl(DdlStatement::CreateMemoryTable(CreateMemoryTable { input, .. })) => {
+            Ok(Schema::from(input.schema().as_ref()))
+        }
+        plan => Err(DataFusionError::Internal(format!(
+            "expect CreateMemoryTable, but got {}",
+            plan.display()
+        ))),
+    }
+}
+
+/// Create the LakeSoul table of [CreateLakeSoulTable] in its namespace,
+/// and insert the rows of the query of `CREATE TABLE ... AS query`.
+pub async fn create_table(ctx: &SessionContext, create_table: CreateLakeSoulTable) -> Result<DataFrame> {
+    let state = ctx.state();
+    let (client, namespace, table_name) = resolve_lakesoul_table(ctx, &create_table.name.to_string())?;
+    let (namespace, table_name) = (namespace.as_str(), table_name.as_str());
+    if client.get_namespace_by_namespace(namespace).await.is_err() {
+        return Err(DataFusionError::Plan(format!("Namespace '{}' not found", namespace)));
+    }
+    let empty = empty_dataframe(ctx);
+    if client
+        .get_table_name_id_by_table_name(table_name, namespace)
+        .await
+        .is_ok()
+    {
+        return match create_table.if_not_exists {
+            true => Ok(empty),
+            false => Err(DataFusionError::Execution(format!(
+                "Table '{}.{}' already exists",
+                namespace, table_name
+            ))),
+        };
+    }
+
+    let (input, schema) = match (&create_table.query, create_table.columns.is_empty()) {
+        (Some(query), true) => {
+            let plan = state
+                .statement_to_plan(DFStatement::Statement(Box::new(Statement::Query(query.clone()))))
+                .await?;
+            let schema = Arc::new(Schema::from(plan.schema().as_ref()));
+            (Some(plan), schema)
+        }
+        (None, false) => (
+            None,
+            Arc::new(columns_to_schema(ctx, &create_table.name, &create_table.columns).await?),
+        ),
+        (Some(_), false) => {
+            return Err(DataFusionError::NotImplemented(
+                "CREATE TABLE with both columns and query is not supported".to_string(),
+            ))
+        }
+        (None, true) => {
+            return Err(DataFusionError::Plan(
+                "CREATE TABLE requires columns or a query".to_string(),
+            ))
+        }
+    };
+
+    let mut primary_keys = create_table
+        .primary_keys
+        .iter()
+        .map(normalize_ident)
+        .collect::<Vec<_>>();
+    for constraint in &create_table.constraints {
+        match constraint {
+            TableConstraint::Unique {
+                columns,
+                is_primary: true,
+                ..
+            } => primary_keys.extend(columns.iter().map(normalize_ident)),
+            constraint => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported constraint of LakeSoul table: {}",
+                    constraint
+                )))
+            }
+        }
+    }
+    for column in &create_table.columns {
+        if column
+            .options
+            .iter()
+            .any(|option| matches!(option.option, ColumnOption::Unique { is_primary: true, .. }))
+        {
+            primary_keys.push(normalize_ident(&column.name));
+        }
+    }
+    let range_partitions = create_table
+        .range_partitions
+        .iter()
+        .map(normalize_ident)
+        .collect::<Vec<_>>();
+
+    let mut hash_bucket_num = None;
+    for option in &create_table.options {
+        match normalize_ident(&option.name).as_str() {
+            HASH_BUCKET_NUM_OPTION | "hashbucketnum" | "hashBucketNum" => {
+                let value = match &option.value {
+                    Value::Number(value, _) | Value::SingleQuotedString(value) => value.parse::<usize>().ok(),
+                    _ => None,
+                };
+                hash_bucket_num = Some(value.filter(|value| *value > 0).ok_or(DataFusionError::Plan(format!(
+                    "Invalid value of {}: {}",
+                    HASH_BUCKET_NUM_OPTION, option.value
+                )))?);
+            }
+            name => {
+                return Err(DataFusionError::Plan(format!(
+                    "Unsupported option of LakeSoul table: {}",
+                    name
+                )))
+            }
+        }
+    }
+    if hash_bucket_num.is_none() && !primary_keys.is_empty() {
+        hash_bucket_num = Some(DEFAULT_HASH_BUCKET_NUM);
+    }
+
+    create_table_in_namespace(
+        client.clone(),
+        namespace,
+        table_name,
+        schema.clone(),
+        &range_partitions,
+        &primary_keys,
+        hash_bucket_num,
+    )
+    .await
+    .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+    if let Some(input) = input {
+        let plan = LogicalPlanBuilder::insert_into(
+            input,
+            TableReference::partial(namespace.to_string(), table_name.to_string()),
+            schema.as_ref(),
+            false,
+        )?
+        .build()?;
+        if let Err(e) = DataFrame::new(state.clone(), plan).collect().await {
+            // the table is committed before the query runs, so a failed query must not leave it behind
+            let table_info = client
+                .get_table_info_by_table_name(table_name, namespace)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            delete_table_data(&table_info)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            client
+                .delete_table_by_table_info_cascade(&table_info)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            return Err(e);
+        }
+    }
+    Ok(empty)
+}
+
+/// Drop a namespace of a [LakeSoulCatalog] by `DROP SCHEMA [IF EXISTS] name [CASCADE | RESTRICT]`,
+/// which does not block the runtime like [CatalogProvider::deregister_schema](datafusion::catalog::CatalogProvider),
+/// see [LakeSoulCatalog::drop_schema].
+pub async fn drop_schema(ctx: &SessionContext, statement: Statement) -> Result<DataFrame> {
+    let Statement::Drop {
+        object_type: ObjectType::Schema,
+        if_exists,
+        names,
+        cascade,
+        ..
+    } = statement
+    else {
+        return Err(DataFusionError::Internal("expect DROP SCHEMA statement".to_string()));
+    };
+    let [name] = names.as_slice() else {
+        return Err(DataFusionError::NotImplemented(
+            "DROP SCHEMA of more than one schema is not supported".to_string(),
+        ));
+    };
+    let (catalog_name, namespace) = resolve_schema_name(ctx, name);
+    let catalog = ctx
+        .catalog(&catalog_name)
+        .ok_or(DataFusionError::Plan(format!("Catalog '{}' not found", catalog_name)))?;
+    let catalog = catalog
+        .as_any()
+        .downcast_ref::<LakeSoulCatalog>()
+        .ok_or(DataFusionError::Plan(format!(
+            "Catalog '{}' is not a LakeSoul catalog",
+            catalog_name
+        )))?;
+    match catalog.drop_schema(&namespace, cascade).await? {
+        None if !if_exists => Err(DataFusionError::Execution(format!(
+            "Namespace '{}' not found",
+            namespace
+        ))),
+        _ => Ok(empty_dataframe(ctx)),
+    }
+}
+
+/// Change the schema of a LakeSoul table by `ALTER TABLE`, i.e. `ADD COLUMN`, `DROP COLUMN`, `RENAME COLUMN`
+/// and `ALTER COLUMN ... [SET DATA] TYPE` widening the type of a column, see [SchemaChange].
+pub async fn alter_table(ctx: &SessionContext, statement: Statement) -> Result<DataFrame> {
+    let Statement::AlterTable { name, operation } = statement else {
+        return Err(DataFusionError::Internal("expect ALTER TABLE statement".to_string()));
+    };
+    let change = match operation {
+        AlterTableOperation::AddColumn {
+            if_not_exists,
+            column_def,
+            ..
+        } => {
+            if let Some(option) = column_def
+                .options
+                .iter()
+                .find(|option| !matches!(option.option, ColumnOption::Null | ColumnOption::NotNull))
+            {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported option of added column: {}",
+                    option
+                )));
+            }
+            let schema = columns_to_schema(ctx, &name, &[column_def]).await?;
+            SchemaChange::AddColumn {
+                field: schema.field(0).clone(),
+                if_not_exists,
+            }
+        }
+        AlterTableOperation::DropColumn {
+            column_name, if_exists, ..
+        } => SchemaChange::DropColumn {
+            name: normalize_ident(&column_name),
+            if_exists,
+        },
+        AlterTableOperation::RenameColumn {
+            old_column_name,
+            new_column_name,
+        } => SchemaChange::RenameColumn {
+            from: normalize_ident(&old_column_name),
+            to: normalize_ident(&new_column_name),
+        },
+        AlterTableOperation::AlterColumn {
+            column_name,
+            op: AlterColumnOperation::SetDataType { data_type, using: None },
+        } => {
+            let column_def = ColumnDef {
+                name: column_name.clone(),
+                data_type,
+                collation: None,
+                options: vec![],
+            };
+            let schema = columns_to_schema(ctx, &name, &[column_def]).await?;
+            SchemaChange::AlterColumnType {
+                name: normalize_ident(&column_name),
+                data_type: schema.field(0).data_type().clone(),
+            }
+        }
+        operation => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Unsupported ALTER TABLE operation of LakeSoul table: {}",
+                operation
+            )))
+        }
+    };
+    let (client, namespace, table_name) = resolve_lakesoul_table(ctx, &name.to_string())?;
+    alter_table_schema(client, &namespace, &table_name, change)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    Ok(empty_dataframe(ctx))
+}
@@ -0,0 +1,216 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use datafusion::common::{DFSchemaRef, OwnedTableReference, ScalarValue, ToDFSchema};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::{
+    cast, lit, Expr, Extension, LogicalPlan, LogicalPlanBuilder, UserDefinedLogicalNodeCore,
+};
+use datafusion::sql::parser::Statement as DFStatement;
+use datafusion::sql::sqlparser::ast::{BinaryOperator, Expr as SqlExpr, Statement, UnaryOperator, Value};
+use datafusion::sql::TableReference;
+use lakesoul_io::helpers::format_scalar_value;
+
+use crate::datasource::file_format::{make_sink_schema, InsertMode};
+use crate::planner::{normalize_ident, resolve_table};
+
+/// Logical plan node of `INSERT INTO` or `INSERT OVERWRITE` a LakeSoul table with a `PARTITION` clause,
+/// which is planned into a [LakeSoulHashSinkExec](crate::datasource::file_format::LakeSoulHashSinkExec)
+/// committing with the [InsertMode].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LakeSoulInsertNode {
+    pub table_name: OwnedTableReference,
+    pub insert_mode: InsertMode,
+    /// inserted rows of the table columns
+    pub input: Arc<LogicalPlan>,
+    schema: DFSchemaRef,
+}
+
+impl UserDefinedLogicalNodeCore for LakeSoulInsertNode {
+    fn name(&self) -> &str {
+        "LakeSoulInsert"
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![self.input.as_ref()]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        &self.schema
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "LakeSoulInsert: table={}, insert_mode={:?}",
+            self.table_name, self.insert_mode
+        )
+    }
+
+    fn from_template(&self, _exprs: &[Expr], inputs: &[LogicalPlan]) -> Self {
+        Self {
+            input: Arc::new(inputs[0].clone()),
+            ..self.clone()
+        }
+    }
+}
+
+/// Create the logical plan of an `INSERT` statement with a `PARTITION` clause on a LakeSoul table,
+/// e.g. `INSERT OVERWRITE TABLE t PARTITION (date='2024-01-01', region) SELECT ...`.
+///
+/// The range partition columns with a value in the `PARTITION` clause are static, and the others are dynamic.
+/// Following Hive, the columns of the source query are the non-partition columns of the table in order,
+/// followed by the dynamic partition columns in the order of the range partitions of the table.
+///
+/// `INSERT OVERWRITE` with only static partitions replaces the partitions matching the static values,
+/// otherwise only the partitions present in the inserted rows are replaced.
+pub(crate) async fn create_insert_plan(session_state: &SessionState, statement: Statement) -> Result<LogicalPlan> {
+    let Statement::Insert {
+        table_name: name,
+        columns,
+        overwrite,
+        source,
+        partitioned: Some(partitioned),
+        ..
+    } = statement
+    else {
+        return Err(DataFusionError::Internal(
+            "expect INSERT statement with PARTITION clause".to_string(),
+        ));
+    };
+    if !columns.is_empty() {
+        return Err(DataFusionError::NotImplemented(
+            "INSERT with both column list and PARTITION clause is not supported".to_string(),
+        ));
+    }
+
+    let table_name = TableReference::from(name.to_string().as_str()).to_owned_reference();
+    let lakesoul_table = resolve_table(session_state, &table_name, None).await?;
+    let table_schema = lakesoul_table.schema();
+    let range_partitions = lakesoul_table.range_partitions();
+
+    let mut static_values = HashMap::new();
+    for expr in partitioned {
+        let (column, value) = match expr {
+            SqlExpr::Identifier(ident) => (normalize_ident(&ident), None),
+            SqlExpr::BinaryOp {
+                left,
+                op: BinaryOperator::Eq,
+                right,
+            } => match *left {
+                SqlExpr::Identifier(ident) => (normalize_ident(&ident), Some(*right)),
+                left => {
+                    return Err(DataFusionError::Plan(format!(
+                        "Invalid column of PARTITION clause: {}",
+                        left
+                    )))
+                }
+            },
+            expr => return Err(DataFusionError::Plan(format!("Invalid PARTITION clause: {}", expr))),
+        };
+        if !range_partitions.contains(&column) {
+            return Err(DataFusionError::Plan(format!(
+                "{} is not a range partition column of table {}",
+                column, table_name
+            )));
+        }
+        if let Some(value) = value {
+            let data_type = table_schema.field_with_name(&column)?.data_type();
+            let value = match value {
+                SqlExpr::Value(Value::Null) => ScalarValue::try_from(data_type)?,
+                SqlExpr::Value(Value::SingleQuotedString(s) | Value::DoubleQuotedString(s) | Value::Number(s, _)) => {
+                    ScalarValue::try_from_string(s, data_type)?
+                }
+                SqlExpr::Value(Value::Boolean(b)) => ScalarValue::try_from_string(b.to_string(), data_type)?,
+                SqlExpr::UnaryOp {
+                    op: UnaryOperator::Minus,
+                    expr,
+                } if matches!(expr.as_ref(), SqlExpr::Value(Value::Number(..))) => {
+                    ScalarValue::try_from_string(format!("-{}", expr), data_type)?
+                }
+                value => {
+                    return Err(DataFusionError::Plan(format!(
+                        "Value of partition column {} must be a literal: {}",
+                        column, value
+                    )))
+                }
+            };
+            static_values.insert(column, value);
+        }
+    }
+
+    let dynamic_partitions = range_partitions
+        .iter()
+        .filter(|column| !static_values.contains_key(*column))
+        .cloned()
+        .collect::<Vec<_>>();
+    let insert_mode = match (overwrite, dynamic_partitions.is_empty()) {
+        (false, _) => InsertMode::Append,
+        (true, true) => InsertMode::OverwriteStaticPartitions(
+            range_partitions
+                .iter()
+                .map(|column| (column.clone(), format_scalar_value(&static_values[column])))
+                .collect(),
+        ),
+        (true, false) => InsertMode::OverwriteDynamicPartitions,
+    };
+
+    let plan = session_state
+        .statement_to_plan(DFStatement::Statement(Box::new(Statement::Query(source))))
+        .await?;
+    let source_columns = table_schema
+        .fields()
+        .iter()
+        .map(|field| field.name())
+        .filter(|column| !range_partitions.contains(column))
+        .chain(dynamic_partitions.iter())
+        .collect::<Vec<_>>();
+    let source_schema = plan.schema().clone();
+    if source_schema.fields().len() != source_columns.len() {
+        return Err(DataFusionError::Plan(format!(
+            "INSERT query of table {} must have {} columns, but got {}",
+            table_name,
+            source_columns.len(),
+            source_schema.fields().len()
+        )));
+    }
+
+    // cast the inserted values into the types of the table columns, and fill the static partition values
+    let exprs = table_schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let value = match static_values.get(field.name()) {
+                Some(value) => lit(value.clone()),
+                None => {
+                    let idx = source_columns
+                        .iter()
+                        .position(|column| *column == field.name())
+                        .ok_or(DataFusionError::Internal(format!("missing column {}", field.name())))?;
+                    Expr::Column(source_schema.field(idx).qualified_column())
+                }
+            };
+            Ok(cast(value, field.data_type().clone()).alias(field.name()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let input = LogicalPlanBuilder::from(plan).project(exprs)?.build()?;
+
+    Ok(LogicalPlan::Extension(Extension {
+        node: Arc::new(LakeSoulInsertNode {
+            table_name,
+            insert_mode,
+            input: Arc::new(input),
+            schema: make_sink_schema().to_dfschema_ref()?,
+        }),
+    }))
+}
@@ -0,0 +1,363 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+use arrow::datatypes::DataType;
+use datafusion::common::{Column, DFSchemaRef, OwnedTableReference, ToDFSchema};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::{cast, Expr, Extension, LogicalPlan, LogicalPlanBuilder, UserDefinedLogicalNodeCore};
+use datafusion::sql::sqlparser::ast::{BinaryOperator, Expr as SqlExpr, Ident, MergeClause, Statement, TableFactor};
+use datafusion::sql::TableReference;
+
+use crate::datasource::file_format::make_sink_schema;
+use crate::datasource::physical_plan::{
+    MergeMode, MERGE_ACTION_COLUMN, MERGE_ACTION_DELETE, MERGE_ACTION_INSERT, MERGE_ACTION_KEEP, MERGE_ACTION_UPDATE,
+    MERGE_TARGET_ROW_COLUMN,
+};
+use crate::planner::{normalize_ident, resolve_table};
+
+/// marks the rows of the target and source relations in the joined rows of `MERGE INTO`
+const TARGET_ROW_COLUMN: &str = "__lakesoul_target_row";
+const SOURCE_ROW_COLUMN: &str = "__lakesoul_source_row";
+
+/// Logical plan node of `MERGE INTO` a LakeSoul table, which is planned into a
+/// [LakeSoulMergeExec](crate::datasource::physical_plan::LakeSoulMergeExec).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LakeSoulMergeNode {
+    pub table_name: OwnedTableReference,
+    pub mode: MergeMode,
+    /// merged rows of the table columns and the [MERGE_ACTION_COLUMN]
+    pub input: Arc<LogicalPlan>,
+    schema: DFSchemaRef,
+}
+
+impl UserDefinedLogicalNodeCore for LakeSoulMergeNode {
+    fn name(&self) -> &str {
+        "LakeSoulMerge"
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![self.input.as_ref()]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        &self.schema
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LakeSoulMerge: table={}, mode={:?}", self.table_name, self.mode)
+    }
+
+    fn from_template(&self, _exprs: &[Expr], inputs: &[LogicalPlan]) -> Self {
+        Self {
+            input: Arc::new(inputs[0].clone()),
+            ..self.clone()
+        }
+    }
+}
+
+enum MergeAction {
+    Update(HashMap<String, SqlExpr>),
+    Delete,
+    Insert(HashMap<String, SqlExpr>),
+}
+
+struct MergeWhen {
+    matched: bool,
+    predicate: Option<SqlExpr>,
+    action: MergeAction,
+}
+
+/// Create the logical plan of a `MERGE INTO` statement on a LakeSoul table.
+///
+/// The target table is joined with the source relation on the `ON` condition, and each joined row is
+/// projected into the new row of the table and its [MERGE_ACTION_COLUMN] by the first matching `WHEN` clause.
+/// If the `ON` condition is an equi-join of all the primary keys of the target table, the merged rows are
+/// co-located with the hash buckets of the table, so only the changed rows are written (see [MergeMode::Upsert]).
+/// Otherwise all the rows of the target table are joined, and the changed buckets are rewritten.
+/// Updating or deleting a target row matched by more than one source row fails the execution.
+pub(crate) async fn create_merge_plan(session_state: &SessionState, statement: Statement) -> Result<LogicalPlan> {
+    let Statement::Merge {
+        table,
+        source,
+        on,
+        clauses,
+        ..
+    } = statement
+    else {
+        return Err(DataFusionError::Internal("expect MERGE INTO statement".to_string()));
+    };
+    let TableFactor::Table { name, alias, .. } = table else {
+        return Err(DataFusionError::NotImplemented(format!(
+            "Unsupported target of MERGE INTO: {}",
+            table
+        )));
+    };
+    let target = match alias {
+        Some(alias) => alias.name,
+        None => name
+            .0
+            .last()
+            .cloned()
+            .ok_or(DataFusionError::Plan("MERGE INTO requires a target table".to_string()))?,
+    };
+    let source_alias = match &source {
+        TableFactor::Table { name, alias, .. } => alias
+            .as_ref()
+            .map(|alias| alias.name.clone())
+            .or_else(|| name.0.last().cloned()),
+        TableFactor::Derived { alias, .. } => alias.as_ref().map(|alias| alias.name.clone()),
+        _ => None,
+    }
+    .ok_or(DataFusionError::Plan(format!(
+        "Source of MERGE INTO requires an alias: {}",
+        source
+    )))?;
+
+    let table_name = TableReference::from(name.to_string().as_str()).to_owned_reference();
+    let lakesoul_table = resolve_table(session_state, &table_name, None).await?;
+    let table_schema = lakesoul_table.schema();
+
+    let mut merge_whens = vec![];
+    for clause in clauses {
+        merge_whens.push(match clause {
+            MergeClause::MatchedUpdate { predicate, assignments } => {
+                let mut values = HashMap::new();
+                for assignment in assignments {
+                    let column = assignment
+                        .id
+                        .last()
+                        .map(normalize_ident)
+                        .ok_or(DataFusionError::Plan("Empty column of assignment".to_string()))?;
+                    if lakesoul_table.primary_keys().contains(&column)
+                        || lakesoul_table.range_partitions().contains(&column)
+                    {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "Updating primary key or range partition column {} of LakeSoul table is not supported",
+                            column
+                        )));
+                    }
+                    values.insert(column, assignment.value);
+                }
+                MergeWhen {
+                    matched: true,
+                    predicate,
+                    action: MergeAction::Update(values),
+                }
+            }
+            MergeClause::MatchedDelete(predicate) => MergeWhen {
+                matched: true,
+                predicate,
+                action: MergeAction::Delete,
+            },
+            MergeClause::NotMatched {
+                predicate,
+                columns,
+                values,
+            } => {
+                let [row] = values.rows.as_slice() else {
+                    return Err(DataFusionError::Plan(
+                        "MERGE INTO can only insert a single row".to_string(),
+                    ));
+                };
+                let columns = match columns.is_empty() {
+                    true => table_schema.fields().iter().map(|field| field.name().clone()).collect(),
+                    false => columns.iter().map(normalize_ident).collect::<Vec<_>>(),
+                };
+                if columns.len() != row.len() {
+                    return Err(DataFusionError::Plan(format!(
+                        "MERGE INTO inserts {} values into {} columns",
+                        row.len(),
+                        columns.len()
+                    )));
+                }
+                MergeWhen {
+                    matched: false,
+                    predicate,
+                    action: MergeAction::Insert(columns.into_iter().zip(row.iter().cloned()).collect()),
+                }
+            }
+        });
+    }
+    if merge_whens.is_empty() {
+        return Err(DataFusionError::Plan(
+            "MERGE INTO requires at least one WHEN clause".to_string(),
+        ));
+    }
+
+    let mode = match !lakesoul_table.primary_keys().is_empty()
+        && is_primary_key_join(&on, &target, lakesoul_table.primary_keys())
+    {
+        true => MergeMode::Upsert,
+        false => MergeMode::Rewrite,
+    };
+    let has_insert = merge_whens
+        .iter()
+        .any(|merge_when| matches!(merge_when.action, MergeAction::Insert(_)));
+    let join = match (mode, has_insert) {
+        (MergeMode::Upsert, true) => "RIGHT JOIN",
+        (MergeMode::Upsert, false) => "INNER JOIN",
+        (MergeMode::Rewrite, true) => "FULL JOIN",
+        (MergeMode::Rewrite, false) => "LEFT JOIN",
+    };
+
+    let matched =
+        format!("{target}.{TARGET_ROW_COLUMN} IS NOT NULL AND {source_alias}.{SOURCE_ROW_COLUMN} IS NOT NULL");
+    let not_matched = format!("{target}.{TARGET_ROW_COLUMN} IS NULL");
+    let conditions = merge_whens
+        .iter()
+        .map(|merge_when| {
+            let condition = match merge_when.matched {
+                true => &matched,
+                false => &not_matched,
+            };
+            match &merge_when.predicate {
+                Some(predicate) => format!("{condition} AND ({predicate})"),
+                None => condition.clone(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut projection = vec![];
+    for field in table_schema.fields() {
+        let column = Ident::with_quote('"', field.name());
+        let target_column = format!("{target}.{column}");
+        let cases = merge_whens
+            .iter()
+            .zip(&conditions)
+            .map(|(merge_when, condition)| {
+                let value = match &merge_when.action {
+                    MergeAction::Update(values) => values.get(field.name()).map(ToString::to_string),
+                    MergeAction::Delete => None,
+                    MergeAction::Insert(values) => Some(
+                        values
+                            .get(field.name())
+                            .map(ToString::to_string)
+                            .unwrap_or("NULL".to_string()),
+                    ),
+                };
+                format!("WHEN {condition} THEN {}", value.unwrap_or(target_column.clone()))
+            })
+            .collect::<Vec<_>>();
+        projection.push(format!("CASE {} ELSE {target_column} END AS {column}", cases.join(" ")));
+    }
+    let mut action_cases = merge_whens
+        .iter()
+        .zip(&conditions)
+        .map(|(merge_when, condition)| {
+            let action = match merge_when.action {
+                MergeAction::Update(_) => MERGE_ACTION_UPDATE,
+                MergeAction::Delete => MERGE_ACTION_DELETE,
+                MergeAction::Insert(_) => MERGE_ACTION_INSERT,
+            };
+            format!("WHEN {condition} THEN {action}")
+        })
+        .collect::<Vec<_>>();
+    if mode == MergeMode::Rewrite {
+        // the unchanged rows of the target table are rewritten as well
+        action_cases.push(format!(
+            "WHEN {target}.{TARGET_ROW_COLUMN} IS NOT NULL THEN {MERGE_ACTION_KEEP}"
+        ));
+    }
+    projection.push(format!("CASE {} END AS {MERGE_ACTION_COLUMN}", action_cases.join(" ")));
+    // the target rows of a table without primary keys are numbered to find the rows matched more than once
+    let has_primary_keys = !lakesoul_table.primary_keys().is_empty();
+    let target_row = match has_primary_keys {
+        true => "true",
+        false => {
+            projection.push(format!("{target}.{TARGET_ROW_COLUMN} AS {MERGE_TARGET_ROW_COLUMN}"));
+            "ROW_NUMBER() OVER ()"
+        }
+    };
+
+    let sql = format!(
+        "SELECT * FROM (SELECT {} \
+        FROM (SELECT {target}.*, {target_row} AS {TARGET_ROW_COLUMN} FROM {name} AS {target}) AS {target} \
+        {join} (SELECT {source_alias}.*, true AS {SOURCE_ROW_COLUMN} FROM {source}) AS {source_alias} \
+        ON {on}) AS __lakesoul_merge WHERE {MERGE_ACTION_COLUMN} IS NOT NULL",
+        projection.join(", ")
+    );
+    let plan = session_state.create_logical_plan(&sql).await?;
+
+    // cast the merged values into the types of the table columns
+    let mut exprs = table_schema
+        .fields()
+        .iter()
+        .map(|field| cast(Expr::Column(Column::from_name(field.name())), field.data_type().clone()).alias(field.name()))
+        .collect::<Vec<_>>();
+    exprs.push(cast(Expr::Column(Column::from_name(MERGE_ACTION_COLUMN)), DataType::Int8).alias(MERGE_ACTION_COLUMN));
+    if !has_primary_keys {
+        exprs.push(Expr::Column(Column::from_name(MERGE_TARGET_ROW_COLUMN)));
+    }
+    let input = LogicalPlanBuilder::from(plan).project(exprs)?.build()?;
+
+    Ok(LogicalPlan::Extension(Extension {
+        node: Arc::new(LakeSoulMergeNode {
+            table_name,
+            mode,
+            input: Arc::new(input),
+            schema: make_sink_schema().to_dfschema_ref()?,
+        }),
+    }))
+}
+
+/// Whether `on` is a conjunction of equalities between each primary key of the `target` table and the source.
+fn is_primary_key_join(on: &SqlExpr, target: &Ident, primary_keys: &[String]) -> bool {
+    let mut conjuncts = vec![];
+    split_sql_conjunction(on, &mut conjuncts);
+    let mut keys = HashSet::new();
+    for conjunct in conjuncts {
+        let SqlExpr::BinaryOp {
+            left,
+            op: BinaryOperator::Eq,
+            right,
+        } = conjunct
+        else {
+            return false;
+        };
+        match (target_column(left, target), target_column(right, target)) {
+            (Some(column), None) | (None, Some(column)) if primary_keys.contains(&column) => {
+                keys.insert(column);
+            }
+            _ => return false,
+        }
+    }
+    keys.len() == primary_keys.len()
+}
+
+fn split_sql_conjunction<'a>(expr: &'a SqlExpr, conjuncts: &mut Vec<&'a SqlExpr>) {
+    match expr {
+        SqlExpr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            split_sql_conjunction(left, conjuncts);
+            split_sql_conjunction(right, conjuncts);
+        }
+        SqlExpr::Nested(expr) => split_sql_conjunction(expr, conjuncts),
+        expr => conjuncts.push(expr),
+    }
+}
+
+/// Column of the `target` table referred by `expr`.
+fn target_column(expr: &SqlExpr, target: &Ident) -> Option<String> {
+    match expr {
+        SqlExpr::CompoundIdentifier(idents)
+            if idents.len() == 2 && normalize_ident(&idents[0]) == normalize_ident(target) =>
+        {
+            Some(normalize_ident(&idents[1]))
+        }
+        SqlExpr::Nested(expr) => target_column(expr, target),
+        _ => None,
+    }
+}
@@ -2,5 +2,152 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use std::sync::Arc;
+
+use datafusion::dataframe::DataFrame;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::{SessionContext, SessionState};
+use datafusion::sql::parser::Statement as DFStatement;
+use datafusion::sql::sqlparser::ast::{Ident, ObjectType, Statement};
+use datafusion::sql::sqlparser::dialect::dialect_from_str;
+use datafusion::sql::TableReference;
+use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
+
+use crate::catalog::LakeSoulCatalog;
+use crate::lakesoul_table::LakeSoulTable;
+
+pub mod ddl;
+pub mod insert;
+pub mod merge;
 mod physical_planner;
+pub mod procedure;
 pub mod query_planner;
+pub mod table_function;
+
+/// Create a [DataFrame] from a SQL statement, like [SessionContext::sql],
+/// but also plans the statements on LakeSoul tables which are not supported by DataFusion,
+/// i.e. `MERGE INTO`, `INSERT` with a `PARTITION` clause, `CREATE TABLE` with LakeSoul partitions and options,
+/// `ALTER TABLE` changing the schema of a LakeSoul table, `DROP SCHEMA` of a LakeSoul namespace, the table functions of LakeSoul in the `FROM` clause,
+/// e.g. [table_function::SNAPSHOT_TABLE_FUNCTION], and the procedures of LakeSoul called by `CALL`,
+/// e.g. [procedure::ROLLBACK_PROCEDURE].
+pub async fn sql(ctx: &SessionContext, sql: &str) -> Result<DataFrame> {
+    let state = ctx.state();
+    let dialect = state.config().options().sql_parser.dialect.clone();
+    if let Some(create_table) = ddl::parse_create_table(
+        sql,
+        dialect_from_str(&dialect)
+            .ok_or(DataFusionError::Plan(format!("Unsupported SQL dialect: {dialect}")))?
+            .as_ref(),
+    )? {
+        if ddl::is_lakesoul_table(ctx, &create_table.name) {
+            return ddl::create_table(ctx, create_table).await;
+        }
+    }
+    let mut statement = state.sql_to_statement(sql, &dialect)?;
+    let table_functions = match &mut statement {
+        DFStatement::Statement(statement) => table_function::register_table_functions(ctx, statement).await?,
+        _ => vec![],
+    };
+    let result = plan_statement(ctx, statement).await;
+    table_function::deregister_table_functions(ctx, &table_functions);
+    result
+}
+
+async fn plan_statement(ctx: &SessionContext, statement: DFStatement) -> Result<DataFrame> {
+    let state = ctx.state();
+    match statement {
+        DFStatement::Statement(statement) if matches!(statement.as_ref(), Statement::Merge { .. }) => {
+            let plan = merge::create_merge_plan(&state, *statement).await?;
+            Ok(DataFrame::new(state, plan))
+        }
+        DFStatement::Statement(statement)
+            if matches!(
+                statement.as_ref(),
+                Statement::Insert {
+                    partitioned: Some(_),
+                    ..
+                }
+            ) =>
+        {
+            let plan = insert::create_insert_plan(&state, *statement).await?;
+            Ok(DataFrame::new(state, plan))
+        }
+        DFStatement::Statement(statement)
+            if matches!(
+                statement.as_ref(),
+                Statement::AlterTable { name, .. } if ddl::is_lakesoul_table(ctx, name)
+            ) =>
+        {
+            ddl::alter_table(ctx, *statement).await
+        }
+        DFStatement::Statement(statement)
+            if matches!(
+                statement.as_ref(),
+                Statement::Drop {
+                    object_type: ObjectType::Schema,
+                    names,
+                    ..
+                } if names.len() == 1 && ddl::is_lakesoul_schema(ctx, &names[0])
+            ) =>
+        {
+            ddl::drop_schema(ctx, *statement).await
+        }
+        DFStatement::Statement(statement)
+            if matches!(
+                statement.as_ref(),
+                Statement::Call(function) if procedure::is_lakesoul_procedure(function)
+            ) =>
+        {
+            procedure::call_procedure(ctx, *statement).await
+        }
+        statement => {
+            let plan = state.statement_to_plan(statement).await?;
+            ctx.execute_logical_plan(plan).await
+        }
+    }
+}
+
+/// Normalize an identifier of SQL like DataFusion, which is lowercase unless quoted.
+pub(crate) fn normalize_ident(ident: &Ident) -> String {
+    match ident.quote_style {
+        Some(_) => ident.value.clone(),
+        None => ident.value.to_ascii_lowercase(),
+    }
+}
+
+/// The LakeSoul table `table_name` of a statement, whose metadata is read by `client` if any,
+/// otherwise by the client of the [LakeSoulCatalog] of the table in `session_state`,
+/// so that a session does not connect to the metadata for each statement.
+/// A table out of any [LakeSoulCatalog] is read by a client from the environment.
+pub(crate) async fn resolve_table(
+    session_state: &SessionState,
+    table_name: &TableReference<'_>,
+    client: Option<MetaDataClientRef>,
+) -> Result<LakeSoulTable> {
+    let catalog_client = || {
+        let catalog_name = table_name
+            .catalog()
+            .unwrap_or(session_state.config().options().catalog.default_catalog.as_str());
+        session_state
+            .catalog_list()
+            .catalog(catalog_name)?
+            .as_any()
+            .downcast_ref::<LakeSoulCatalog>()
+            .map(LakeSoulCatalog::metadata_client)
+    };
+    let client = match client.or_else(catalog_client) {
+        Some(client) => client,
+        None => Arc::new(
+            MetaDataClient::from_env()
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?,
+        ),
+    };
+    LakeSoulTable::for_namespace_and_name_with_client(
+        client,
+        table_name.schema().unwrap_or("default"),
+        table_name.table(),
+    )
+    .await
+    .map_err(|e| DataFusionError::External(Box::new(e)))
+}
@@ -4,14 +4,20 @@
 
 use std::sync::Arc;
 
-use arrow::datatypes::Schema;
+use arrow::datatypes::{Schema, SchemaRef};
 
-use datafusion::common::{DFSchema, SchemaExt};
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::common::{Column, DFSchema, SchemaExt, ToDFSchema};
+use datafusion::datasource::{provider_as_source, source_as_provider};
 use datafusion::error::{DataFusionError, Result};
 use datafusion::execution::context::SessionState;
-use datafusion::logical_expr::{Expr, LogicalPlan};
+use datafusion::logical_expr::expr::Alias;
+use datafusion::logical_expr::utils::conjunction;
+use datafusion::logical_expr::{lit, Expr, Extension, LogicalPlan, TableScan, TableSource};
+use datafusion::optimizer::utils::split_conjunction;
 use datafusion::physical_expr::PhysicalExpr;
 
+use datafusion::physical_plan::coalesce_partitions::CoalescePartitionsExec;
 use datafusion::physical_plan::sorts::sort::SortExec;
 use datafusion::physical_plan::{ExecutionPlan, Partitioning};
 use datafusion::physical_planner::{DefaultPhysicalPlanner, PhysicalPlanner};
@@ -21,21 +27,368 @@ use async_trait::async_trait;
 use datafusion::logical_expr::{DmlStatement, WriteOp};
 use lakesoul_io::helpers::{column_names_to_physical_expr, column_names_to_physical_sort_expr};
 use lakesoul_io::repartition::RepartitionByRangeAndHashExec;
+use lakesoul_metadata::MetaDataClientRef;
 
+use crate::datasource::file_format::{InsertMode, LakeSoulHashSinkExec};
+use crate::datasource::physical_plan::{LakeSoulDeleteExec, LakeSoulMergeExec, LakeSoulUpdateExec, PartitionRewriter};
+use crate::datasource::table_provider::LakeSoulTableProvider;
 use crate::lakesoul_table::LakeSoulTable;
+use crate::planner::insert::LakeSoulInsertNode;
+use crate::planner::merge::LakeSoulMergeNode;
+use crate::planner::resolve_table;
 
 pub struct LakeSoulPhysicalPlanner {
     default_planner: DefaultPhysicalPlanner,
+    client: Option<MetaDataClientRef>,
 }
 
 impl LakeSoulPhysicalPlanner {
-    pub fn new() -> Self {
+    /// A planner reading the metadata of LakeSoul tables by `client`,
+    /// or by the client of their [crate::catalog::LakeSoulCatalog] if `None`.
+    pub fn new(client: Option<MetaDataClientRef>) -> Self {
         Self {
             default_planner: DefaultPhysicalPlanner::default(),
+            client,
         }
     }
 }
 
+impl LakeSoulPhysicalPlanner {
+    /// Plan the upsert of the rows of `input` into `lakesoul_table`,
+    /// which are sorted and repartitioned by range partitions and hash buckets for partitioned tables.
+    /// The written files are committed with the existing data by `insert_mode`.
+    async fn create_upsert_plan(
+        &self,
+        lakesoul_table: &LakeSoulTable,
+        input: &Arc<LogicalPlan>,
+        session_state: &SessionState,
+        insert_mode: InsertMode,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match lakesoul_table.as_sink_provider(session_state).await {
+            Ok(provider) => {
+                let physical_input = self.create_physical_plan(input, session_state).await?;
+
+                if lakesoul_table.primary_keys().is_empty()
+                    && !lakesoul_table
+                        .schema()
+                        .logically_equivalent_names_and_types(&Schema::from(input.schema().as_ref()))
+                {
+                    return Err(DataFusionError::Plan(
+                        // Return an error if schema of the input query does not match with the table schema.
+                        "Inserting query must have the same schema with the table.".to_string(),
+                    ));
+                }
+
+                let physical_input =
+                    self.repartition_by_range_and_hash(lakesoul_table, input, physical_input, session_state)?;
+
+                match insert_mode {
+                    InsertMode::Append => provider.insert_into(session_state, physical_input, false).await,
+                    InsertMode::OverwriteTable => provider.insert_into(session_state, physical_input, true).await,
+                    insert_mode => Ok(Arc::new(
+                        LakeSoulHashSinkExec::new(
+                            physical_input,
+                            None,
+                            lakesoul_table.table_info(),
+                            lakesoul_table.client(),
+                        )
+                        .await?
+                        .with_insert_mode(insert_mode),
+                    ) as _),
+                }
+            }
+            Err(e) => Err(DataFusionError::External(Box::new(e))),
+        }
+    }
+
+    /// Sort and repartition the rows of `input` by range partitions and hash buckets of `lakesoul_table`,
+    /// so that each output partition is a hash bucket of the table.
+    fn repartition_by_range_and_hash(
+        &self,
+        lakesoul_table: &LakeSoulTable,
+        input: &LogicalPlan,
+        physical_input: Arc<dyn ExecutionPlan>,
+        session_state: &SessionState,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if lakesoul_table.primary_keys().is_empty() && lakesoul_table.range_partitions().is_empty() {
+            return Ok(physical_input);
+        }
+        let input_schema = physical_input.schema();
+        let input_dfschema = input.schema();
+        let sort_expr = column_names_to_physical_sort_expr(
+            [
+                lakesoul_table.range_partitions().clone(),
+                lakesoul_table.primary_keys().clone(),
+            ]
+            .concat()
+            .as_slice(),
+            input_dfschema,
+            &input_schema,
+            session_state,
+        )?;
+        let hash_partitioning_expr = column_names_to_physical_expr(
+            lakesoul_table.primary_keys(),
+            input_dfschema,
+            &input_schema,
+            session_state,
+        )?;
+
+        let hash_partitioning = Partitioning::Hash(hash_partitioning_expr, lakesoul_table.hash_bucket_num());
+        let range_partitioning_expr = column_names_to_physical_expr(
+            lakesoul_table.range_partitions(),
+            input_dfschema,
+            &input_schema,
+            session_state,
+        )?;
+        let sort_exec = Arc::new(SortExec::new(sort_expr, physical_input));
+        Ok(Arc::new(RepartitionByRangeAndHashExec::try_new(
+            sort_exec,
+            range_partitioning_expr,
+            hash_partitioning,
+        )?))
+    }
+
+    /// Plan the merge of `MERGE INTO`, the merged rows of a table without primary keys are in a single partition.
+    async fn create_merge_plan(
+        &self,
+        merge: &LakeSoulMergeNode,
+        session_state: &SessionState,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let lakesoul_table = resolve_table(session_state, &merge.table_name, self.client.clone()).await?;
+        // the target table is scanned at the versions the merge is committed against,
+        // so the commits after planning are kept by the rebase of the commit instead of being overwritten
+        let read_partitions = lakesoul_table
+            .client()
+            .get_all_partition_info(&lakesoul_table.table_info().table_id)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let provider = lakesoul_table
+            .as_provider(session_state, None)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?
+            .with_partitions(read_partitions.clone());
+        let input = pin_table_scans(
+            merge.input.as_ref(),
+            &lakesoul_table.table_info().table_id,
+            provider_as_source(Arc::new(provider)),
+        )?;
+        let physical_input = self.create_physical_plan(&input, session_state).await?;
+        let physical_input: Arc<dyn ExecutionPlan> = match lakesoul_table.primary_keys().is_empty()
+            && physical_input.output_partitioning().partition_count() > 1
+        {
+            true => Arc::new(CoalescePartitionsExec::new(physical_input)),
+            false => physical_input,
+        };
+        let physical_input =
+            self.repartition_by_range_and_hash(&lakesoul_table, &merge.input, physical_input, session_state)?;
+        Ok(Arc::new(LakeSoulMergeExec::new(
+            physical_input,
+            lakesoul_table.client(),
+            lakesoul_table.table_info(),
+            merge.mode,
+            read_partitions,
+        )))
+    }
+
+    /// Partition-only predicates drop the matched partitions through metadata,
+    /// other predicates delete rows by rewriting the affected files.
+    fn create_delete_plan(
+        &self,
+        lakesoul_table: &LakeSoulTable,
+        predicate: Option<Expr>,
+        session_state: &SessionState,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let (partition_filters, predicate) = split_partition_filters(lakesoul_table, predicate);
+        let predicate = match predicate {
+            Some(predicate) => {
+                let schema = rewrite_schema(lakesoul_table)?;
+                Some(self.create_physical_expr(&predicate, &schema.clone().to_dfschema()?, &schema, session_state)?)
+            }
+            None => None,
+        };
+        Ok(Arc::new(LakeSoulDeleteExec::new(
+            lakesoul_table.client(),
+            lakesoul_table.table_info(),
+            partition_filters,
+            predicate,
+        )))
+    }
+
+    /// Updated rows of a table with primary keys are upserted into the table,
+    /// otherwise the files containing updated rows are copied on write.
+    async fn create_update_plan(
+        &self,
+        lakesoul_table: &LakeSoulTable,
+        input: &Arc<LogicalPlan>,
+        session_state: &SessionState,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        // the input of an update is the projection of the new values over the filtered table scan,
+        // the projection is eliminated if it assigns no column
+        let (assignments, predicate) = match input.as_ref() {
+            LogicalPlan::Projection(projection) => (
+                collect_assignments(&projection.expr)?,
+                collect_predicate(projection.input.as_ref())?,
+            ),
+            plan => (vec![], collect_predicate(plan)?),
+        };
+        if let Some((column, _)) = assignments.iter().find(|(column, _)| {
+            lakesoul_table.primary_keys().contains(column) || lakesoul_table.range_partitions().contains(column)
+        }) {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Updating primary key or range partition column {} of LakeSoul table is not supported",
+                column
+            )));
+        }
+
+        if !lakesoul_table.primary_keys().is_empty() {
+            return self
+                .create_upsert_plan(lakesoul_table, input, session_state, InsertMode::Append)
+                .await;
+        }
+
+        let schema = rewrite_schema(lakesoul_table)?;
+        let dfschema = schema.clone().to_dfschema()?;
+        let (partition_filters, predicate) = split_partition_filters(lakesoul_table, predicate);
+        let predicate = predicate
+            .map(|predicate| self.create_physical_expr(&predicate, &dfschema, &schema, session_state))
+            .transpose()?;
+        let assignments = assignments
+            .into_iter()
+            .map(|(column, value)| {
+                Ok((
+                    schema.index_of(&column)?,
+                    self.create_physical_expr(&value, &dfschema, &schema, session_state)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(LakeSoulUpdateExec::new(
+            lakesoul_table.client(),
+            lakesoul_table.table_info(),
+            partition_filters,
+            predicate,
+            assignments,
+        )))
+    }
+}
+
+/// Schema of the rows of a LakeSoul table rewritten by DELETE and UPDATE.
+fn rewrite_schema(lakesoul_table: &LakeSoulTable) -> Result<SchemaRef> {
+    Ok(
+        PartitionRewriter::try_new(lakesoul_table.client(), lakesoul_table.table_info())
+            .map_err(|e| DataFusionError::External(Box::new(e)))?
+            .schema(),
+    )
+}
+
+/// Replace the sources of the scans of the LakeSoul table `table_id` in `plan` by `source`.
+fn pin_table_scans(plan: &LogicalPlan, table_id: &str, source: Arc<dyn TableSource>) -> Result<LogicalPlan> {
+    plan.clone().transform_up(&|plan| {
+        Ok(match plan {
+            LogicalPlan::TableScan(scan)
+                if source_as_provider(&scan.source)
+                    .ok()
+                    .and_then(|provider| {
+                        provider
+                            .as_any()
+                            .downcast_ref::<LakeSoulTableProvider>()
+                            .map(|provider| provider.table_id() == table_id)
+                    })
+                    .unwrap_or(false) =>
+            {
+                Transformed::Yes(LogicalPlan::TableScan(TableScan {
+                    source: source.clone(),
+                    ..scan
+                }))
+            }
+            plan => Transformed::No(plan),
+        })
+    })
+}
+
+/// Split the conjuncts of `predicate` referring to range partition columns only,
+/// which are used to prune partitions, and return the predicate if it still needs evaluation on rows.
+fn split_partition_filters(lakesoul_table: &LakeSoulTable, predicate: Option<Expr>) -> (Vec<Expr>, Option<Expr>) {
+    let is_partition_filter = |expr: &Expr| match expr.to_columns() {
+        Ok(columns) => columns
+            .iter()
+            .all(|column| lakesoul_table.range_partitions().contains(&column.name)),
+        Err(_) => false,
+    };
+    match predicate {
+        None => (vec![], None),
+        Some(predicate) => {
+            let partition_filters = split_conjunction(&predicate)
+                .into_iter()
+                .filter(|expr| is_partition_filter(expr))
+                .cloned()
+                .collect::<Vec<_>>();
+            if is_partition_filter(&predicate) {
+                (partition_filters, None)
+            } else {
+                (partition_filters, Some(predicate))
+            }
+        }
+    }
+}
+
+/// Collect the columns assigned by the projection of an update and their new values.
+fn collect_assignments(exprs: &[Expr]) -> Result<Vec<(String, Expr)>> {
+    let mut assignments = vec![];
+    for expr in exprs {
+        if let Expr::Alias(Alias { expr, name, .. }) = expr {
+            match expr.as_ref() {
+                Expr::Column(column) if &column.name == name => {}
+                value => assignments.push((name.clone(), unqualify_columns(value.clone())?)),
+            }
+        }
+    }
+    Ok(assignments)
+}
+
+/// Strip the table qualifier of columns, as the plans of LakeSoul tables are built over unqualified schemas.
+fn unqualify_columns(expr: Expr) -> Result<Expr> {
+    expr.transform(&|expr| {
+        Ok(match expr {
+            Expr::Column(column) => Transformed::Yes(Expr::Column(Column::from_name(column.name))),
+            expr => Transformed::No(expr),
+        })
+    })
+}
+
+/// Collect the predicate of a DML statement on its target table,
+/// including the filters that have been pushed down into the table scan by the optimizer.
+fn collect_predicate(plan: &LogicalPlan) -> Result<Option<Expr>> {
+    let mut filters = vec![];
+    collect_filters(plan, &mut filters)?;
+    Ok(conjunction(filters))
+}
+
+fn collect_filters(plan: &LogicalPlan, filters: &mut Vec<Expr>) -> Result<()> {
+    match plan {
+        LogicalPlan::Filter(filter) => {
+            filters.push(unqualify_columns(filter.predicate.clone())?);
+            collect_filters(filter.input.as_ref(), filters)
+        }
+        LogicalPlan::Projection(projection) => collect_filters(projection.input.as_ref(), filters),
+        LogicalPlan::SubqueryAlias(alias) => collect_filters(alias.input.as_ref(), filters),
+        LogicalPlan::TableScan(scan) => {
+            for filter in &scan.filters {
+                filters.push(unqualify_columns(filter.clone())?);
+            }
+            Ok(())
+        }
+        // an always false predicate is optimized into an empty relation
+        LogicalPlan::EmptyRelation(_) => {
+            filters.push(lit(false));
+            Ok(())
+        }
+        plan => Err(DataFusionError::NotImplemented(format!(
+            "Unsupported input of DML statement on LakeSoul table: {}",
+            plan.display()
+        ))),
+    }
+}
+
 #[async_trait]
 impl PhysicalPlanner for LakeSoulPhysicalPlanner {
     /// Create a physical plan from a logical plan
@@ -51,72 +404,56 @@ impl PhysicalPlanner for LakeSoulPhysicalPlanner {
                 input,
                 ..
             }) => {
-                let name = table_name.table();
-                let schema = table_name.schema();
-                // let schema = session_state.schema_for_ref(table_name)?;
-                let lakesoul_table = LakeSoulTable::for_namespace_and_name(schema.unwrap_or("default"), name)
+                let lakesoul_table = resolve_table(session_state, table_name, self.client.clone()).await?;
+
+                self.create_upsert_plan(&lakesoul_table, input, session_state, InsertMode::Append)
+                    .await
+            }
+            LogicalPlan::Dml(DmlStatement {
+                table_name,
+                op: WriteOp::InsertOverwrite,
+                input,
+                ..
+            }) => {
+                let lakesoul_table = resolve_table(session_state, table_name, self.client.clone()).await?;
+                self.create_upsert_plan(&lakesoul_table, input, session_state, InsertMode::OverwriteTable)
+                    .await
+            }
+            LogicalPlan::Dml(DmlStatement {
+                table_name,
+                op: WriteOp::Delete,
+                input,
+                ..
+            }) => {
+                let lakesoul_table = resolve_table(session_state, table_name, self.client.clone()).await?;
+                let predicate = collect_predicate(input)?;
+                self.create_delete_plan(&lakesoul_table, predicate, session_state)
+            }
+            LogicalPlan::Dml(DmlStatement {
+                table_name,
+                op: WriteOp::Update,
+                input,
+                ..
+            }) => {
+                let lakesoul_table = resolve_table(session_state, table_name, self.client.clone()).await?;
+                self.create_update_plan(&lakesoul_table, input, session_state).await
+            }
+            LogicalPlan::Extension(Extension { node }) => {
+                if let Some(merge) = node.as_any().downcast_ref::<LakeSoulMergeNode>() {
+                    self.create_merge_plan(merge, session_state).await
+                } else if let Some(insert) = node.as_any().downcast_ref::<LakeSoulInsertNode>() {
+                    let lakesoul_table = resolve_table(session_state, &insert.table_name, self.client.clone()).await?;
+                    self.create_upsert_plan(
+                        &lakesoul_table,
+                        &insert.input,
+                        session_state,
+                        insert.insert_mode.clone(),
+                    )
                     .await
-                    .map_err(|e| DataFusionError::External(Box::new(e)))?;
-
-                match lakesoul_table.as_sink_provider(session_state).await {
-                    Ok(provider) => {
-                        let physical_input = self.create_physical_plan(input, session_state).await?;
-
-                        if lakesoul_table.primary_keys().is_empty()
-                            && !lakesoul_table
-                                .schema()
-                                .logically_equivalent_names_and_types(&Schema::from(input.schema().as_ref()))
-                        {
-                            return Err(DataFusionError::Plan(
-                                // Return an error if schema of the input query does not match with the table schema.
-                                "Inserting query must have the same schema with the table.".to_string(),
-                            ));
-                        }
-
-                        let physical_input = if !lakesoul_table.primary_keys().is_empty()
-                            || !lakesoul_table.range_partitions().is_empty()
-                        {
-                            let input_schema = physical_input.schema();
-                            let input_dfschema = input.as_ref().schema();
-                            let sort_expr = column_names_to_physical_sort_expr(
-                                [
-                                    lakesoul_table.range_partitions().clone(),
-                                    lakesoul_table.primary_keys().clone(),
-                                ]
-                                .concat()
-                                .as_slice(),
-                                input_dfschema,
-                                &input_schema,
-                                session_state,
-                            )?;
-                            let hash_partitioning_expr = column_names_to_physical_expr(
-                                lakesoul_table.primary_keys(),
-                                input_dfschema,
-                                &input_schema,
-                                session_state,
-                            )?;
-
-                            let hash_partitioning =
-                                Partitioning::Hash(hash_partitioning_expr, lakesoul_table.hash_bucket_num());
-                            let range_partitioning_expr = column_names_to_physical_expr(
-                                lakesoul_table.range_partitions(),
-                                input_dfschema,
-                                &input_schema,
-                                session_state,
-                            )?;
-                            let sort_exec = Arc::new(SortExec::new(sort_expr, physical_input));
-                            Arc::new(RepartitionByRangeAndHashExec::try_new(
-                                sort_exec,
-                                range_partitioning_expr,
-                                hash_partitioning,
-                            )?)
-                        } else {
-                            physical_input
-                        };
-
-                        provider.insert_into(session_state, physical_input, false).await
-                    }
-                    Err(e) => return Err(DataFusionError::External(Box::new(e))),
+                } else {
+                    self.default_planner
+                        .create_physical_plan(logical_plan, session_state)
+                        .await
                 }
             }
             LogicalPlan::Statement(statement) => {
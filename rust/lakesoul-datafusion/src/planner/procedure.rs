@@ -0,0 +1,296 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int32Array, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::dataframe::DataFrame;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::SessionContext;
+use datafusion::sql::sqlparser::ast::{Function, FunctionArg, Statement};
+use proto::proto::entity::PartitionInfo;
+
+use crate::datasource::table_provider::TableSnapshot;
+use crate::planner::normalize_ident;
+use crate::planner::table_function::{lakesoul_table_arg, snapshot_arg, string_arg};
+
+/// Procedure rolling back a LakeSoul table to its snapshot as of a version or a timestamp,
+/// see [LakeSoulTable::rollback](crate::lakesoul_table::LakeSoulTable::rollback):
+///
+/// ```sql
+/// CALL lakesoul_rollback('[namespace.]table', version | timestamp)
+/// ```
+///
+/// Returns the partitions rolled back and their new versions, which can be rolled back again.
+pub const ROLLBACK_PROCEDURE: &str = "lakesoul_rollback";
+
+/// Procedure rolling back a partition of a LakeSoul table to its version,
+/// see [LakeSoulTable::rollback_partition](crate::lakesoul_table::LakeSoulTable::rollback_partition):
+///
+/// ```sql
+/// CALL lakesoul_rollback_partition('[namespace.]table', 'range=1', version)
+/// ```
+pub const ROLLBACK_PARTITION_PROCEDURE: &str = "lakesoul_rollback_partition";
+
+/// Procedure creating a tag pinning the latest version, or the version as of a version or a timestamp,
+/// of each partition of a LakeSoul table,
+/// see [LakeSoulTable::create_tag](crate::lakesoul_table::LakeSoulTable::create_tag):
+///
+/// ```sql
+/// CALL lakesoul_create_tag('[namespace.]table', 'tag'[, version | timestamp])
+/// ```
+///
+/// Returns the partitions and the versions pinned by the tag.
+pub const CREATE_TAG_PROCEDURE: &str = "lakesoul_create_tag";
+
+/// Procedure dropping a tag of a LakeSoul table,
+/// see [LakeSoulTable::delete_tag](crate::lakesoul_table::LakeSoulTable::delete_tag):
+///
+/// ```sql
+/// CALL lakesoul_drop_tag('[namespace.]table', 'tag')
+/// ```
+///
+/// Returns the partitions and the versions which were pinned by the tag.
+pub const DROP_TAG_PROCEDURE: &str = "lakesoul_drop_tag";
+
+/// Procedure creating a branch forked from the latest version, or the version as of a version or a timestamp,
+/// of each partition of a LakeSoul table, which is read and written as table `table@branch`,
+/// see [LakeSoulTable::create_branch](crate::lakesoul_table::LakeSoulTable::create_branch):
+///
+/// ```sql
+/// CALL lakesoul_create_branch('[namespace.]table', 'branch'[, version | timestamp])
+/// ```
+///
+/// Returns the partitions and the versions the branch is forked from.
+pub const CREATE_BRANCH_PROCEDURE: &str = "lakesoul_create_branch";
+
+/// Procedure dropping a branch of a LakeSoul table with the files written to it,
+/// see [LakeSoulTable::delete_branch](crate::lakesoul_table::LakeSoulTable::delete_branch):
+///
+/// ```sql
+/// CALL lakesoul_drop_branch('[namespace.]table', 'branch')
+/// ```
+///
+/// Returns the partitions and the versions the branch was forked from.
+pub const DROP_BRANCH_PROCEDURE: &str = "lakesoul_drop_branch";
+
+/// The procedures of LakeSoul called by `CALL`, which are not supported by DataFusion.
+const PROCEDURES: &[&str] = &[
+    ROLLBACK_PROCEDURE,
+    ROLLBACK_PARTITION_PROCEDURE,
+    CREATE_TAG_PROCEDURE,
+    DROP_TAG_PROCEDURE,
+    CREATE_BRANCH_PROCEDURE,
+    DROP_BRANCH_PROCEDURE,
+];
+
+/// Whether `function` of a `CALL` statement is a procedure of LakeSoul.
+pub(crate) fn is_lakesoul_procedure(function: &Function) -> bool {
+    matches!(function.name.0.as_slice(), [ident] if PROCEDURES.contains(&normalize_ident(ident).as_str()))
+}
+
+/// Call a procedure of LakeSoul, which is executed on planning like DDL.
+pub(crate) async fn call_procedure(ctx: &SessionContext, statement: Statement) -> Result<DataFrame> {
+    let Statement::Call(function) = statement else {
+        return Err(DataFusionError::Internal("expect CALL statement".to_string()));
+    };
+    let procedure = match function.name.0.as_slice() {
+        [ident] => normalize_ident(ident),
+        _ => String::new(),
+    };
+    let partition_versions = match procedure.as_str() {
+        ROLLBACK_PROCEDURE => rollback(ctx, &function.args).await?,
+        ROLLBACK_PARTITION_PROCEDURE => rollback_partition(ctx, &function.args).await?,
+        CREATE_TAG_PROCEDURE => create_tag(ctx, &function.args).await?,
+        DROP_TAG_PROCEDURE => drop_tag(ctx, &function.args).await?,
+        CREATE_BRANCH_PROCEDURE => create_branch(ctx, &function.args).await?,
+        DROP_BRANCH_PROCEDURE => drop_branch(ctx, &function.args).await?,
+        _ => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Procedure {} is not supported",
+                function.name
+            )))
+        }
+    };
+    partition_versions_dataframe(ctx, partition_versions)
+}
+
+async fn rollback(ctx: &SessionContext, args: &[FunctionArg]) -> Result<Vec<(String, i32)>> {
+    let [table, snapshot] = args else {
+        return Err(DataFusionError::Plan(format!(
+            "{} expects 2 arguments: table name and version or timestamp, got {}",
+            ROLLBACK_PROCEDURE,
+            args.len()
+        )));
+    };
+    let table = lakesoul_table_arg(ctx, ROLLBACK_PROCEDURE, table).await?;
+    let snapshot = snapshot_arg(ctx, ROLLBACK_PROCEDURE, snapshot).await?;
+    let rolled_back = table
+        .rollback(snapshot)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    Ok(partition_info_versions(rolled_back))
+}
+
+async fn rollback_partition(ctx: &SessionContext, args: &[FunctionArg]) -> Result<Vec<(String, i32)>> {
+    let [table, partition_desc, version] = args else {
+        return Err(DataFusionError::Plan(format!(
+            "{} expects 3 arguments: table name, partition and version, got {}",
+            ROLLBACK_PARTITION_PROCEDURE,
+            args.len()
+        )));
+    };
+    let table = lakesoul_table_arg(ctx, ROLLBACK_PARTITION_PROCEDURE, table).await?;
+    let partition_desc = string_arg(ctx, ROLLBACK_PARTITION_PROCEDURE, "partition", partition_desc).await?;
+    let TableSnapshot::Version(version) = snapshot_arg(ctx, ROLLBACK_PARTITION_PROCEDURE, version).await? else {
+        return Err(DataFusionError::Plan(format!(
+            "The version of {} must be an integer",
+            ROLLBACK_PARTITION_PROCEDURE
+        )));
+    };
+    let rolled_back = table
+        .rollback_partition(&partition_desc, version)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    Ok(partition_info_versions(rolled_back.into_iter().collect()))
+}
+
+async fn create_tag(ctx: &SessionContext, args: &[FunctionArg]) -> Result<Vec<(String, i32)>> {
+    let (table, tag_name, snapshot) = match args {
+        [table, tag_name] => (table, tag_name, None),
+        [table, tag_name, snapshot] => (table, tag_name, Some(snapshot)),
+        _ => {
+            return Err(DataFusionError::Plan(format!(
+                "{} expects 2 or 3 arguments: table name, tag name and optional version or timestamp, got {}",
+                CREATE_TAG_PROCEDURE,
+                args.len()
+            )))
+        }
+    };
+    let table = lakesoul_table_arg(ctx, CREATE_TAG_PROCEDURE, table).await?;
+    let tag_name = string_arg(ctx, CREATE_TAG_PROCEDURE, "tag", tag_name).await?;
+    let snapshot = match snapshot {
+        Some(snapshot) => Some(snapshot_arg(ctx, CREATE_TAG_PROCEDURE, snapshot).await?),
+        None => None,
+    };
+    let table_tag = table
+        .create_tag(&tag_name, snapshot)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    Ok(sorted_partition_versions(table_tag.partition_versions))
+}
+
+async fn drop_tag(ctx: &SessionContext, args: &[FunctionArg]) -> Result<Vec<(String, i32)>> {
+    let [table, tag_name] = args else {
+        return Err(DataFusionError::Plan(format!(
+            "{} expects 2 arguments: table name and tag name, got {}",
+            DROP_TAG_PROCEDURE,
+            args.len()
+        )));
+    };
+    let table = lakesoul_table_arg(ctx, DROP_TAG_PROCEDURE, table).await?;
+    let tag_name = string_arg(ctx, DROP_TAG_PROCEDURE, "tag", tag_name).await?;
+    let table_tag = table
+        .client()
+        .get_tag(&table.table_info().table_id, &tag_name)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?
+        .ok_or_else(|| DataFusionError::Plan(format!("Tag {} of table {} not found", tag_name, table.table_name())))?;
+    table
+        .delete_tag(&tag_name)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    Ok(sorted_partition_versions(table_tag.partition_versions))
+}
+
+async fn create_branch(ctx: &SessionContext, args: &[FunctionArg]) -> Result<Vec<(String, i32)>> {
+    let (table, branch_name, snapshot) = match args {
+        [table, branch_name] => (table, branch_name, None),
+        [table, branch_name, snapshot] => (table, branch_name, Some(snapshot)),
+        _ => {
+            return Err(DataFusionError::Plan(format!(
+                "{} expects 2 or 3 arguments: table name, branch name and optional version or timestamp, got {}",
+                CREATE_BRANCH_PROCEDURE,
+                args.len()
+            )))
+        }
+    };
+    let table = lakesoul_table_arg(ctx, CREATE_BRANCH_PROCEDURE, table).await?;
+    let branch_name = string_arg(ctx, CREATE_BRANCH_PROCEDURE, "branch", branch_name).await?;
+    let snapshot = match snapshot {
+        Some(snapshot) => Some(snapshot_arg(ctx, CREATE_BRANCH_PROCEDURE, snapshot).await?),
+        None => None,
+    };
+    let table_branch = table
+        .create_branch(&branch_name, snapshot)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    Ok(sorted_partition_versions(table_branch.partition_versions))
+}
+
+async fn drop_branch(ctx: &SessionContext, args: &[FunctionArg]) -> Result<Vec<(String, i32)>> {
+    let [table, branch_name] = args else {
+        return Err(DataFusionError::Plan(format!(
+            "{} expects 2 arguments: table name and branch name, got {}",
+            DROP_BRANCH_PROCEDURE,
+            args.len()
+        )));
+    };
+    let table = lakesoul_table_arg(ctx, DROP_BRANCH_PROCEDURE, table).await?;
+    let branch_name = string_arg(ctx, DROP_BRANCH_PROCEDURE, "branch", branch_name).await?;
+    let table_branch = table
+        .client()
+        .get_branch(&table.table_info().table_id, &branch_name)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?
+        .ok_or_else(|| {
+            DataFusionError::Plan(format!(
+                "Branch {} of table {} not found",
+                branch_name,
+                table.table_name()
+            ))
+        })?;
+    table
+        .delete_branch(&branch_name)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    Ok(sorted_partition_versions(table_branch.partition_versions))
+}
+
+fn sorted_partition_versions(partition_versions: HashMap<String, i32>) -> Vec<(String, i32)> {
+    let mut partition_versions = partition_versions.into_iter().collect::<Vec<_>>();
+    partition_versions.sort();
+    partition_versions
+}
+
+fn partition_info_versions(partition_info_list: Vec<PartitionInfo>) -> Vec<(String, i32)> {
+    partition_info_list
+        .into_iter()
+        .map(|partition_info| (partition_info.partition_desc, partition_info.version))
+        .collect()
+}
+
+/// The result of the procedures: the partitions rolled back and their new versions,
+/// or the partitions and the versions pinned by a tag or forked by a branch.
+fn partition_versions_dataframe(ctx: &SessionContext, partition_versions: Vec<(String, i32)>) -> Result<DataFrame> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("partition_desc", DataType::Utf8, false),
+        Field::new("version", DataType::Int32, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from_iter_values(
+                partition_versions.iter().map(|(partition_desc, _)| partition_desc),
+            )) as ArrayRef,
+            Arc::new(Int32Array::from_iter_values(
+                partition_versions.iter().map(|(_, version)| *version),
+            )),
+        ],
+    )?;
+    ctx.read_batch(batch)
+}
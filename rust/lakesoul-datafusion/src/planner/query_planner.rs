@@ -15,12 +15,21 @@ use crate::planner::physical_planner::LakeSoulPhysicalPlanner;
 use async_trait::async_trait;
 
 use lakesoul_io::datafusion;
+use lakesoul_metadata::MetaDataClientRef;
 
-pub struct LakeSoulQueryPlanner {}
+pub struct LakeSoulQueryPlanner {
+    client: Option<MetaDataClientRef>,
+}
 
 impl LakeSoulQueryPlanner {
+    /// A planner reading the metadata of LakeSoul tables by the client of their [crate::catalog::LakeSoulCatalog].
     pub fn new_ref() -> Arc<dyn QueryPlanner + Send + Sync> {
-        Arc::new(Self {})
+        Arc::new(Self { client: None })
+    }
+
+    /// A planner reading the metadata of all the LakeSoul tables by `client`.
+    pub fn new_ref_with_client(client: MetaDataClientRef) -> Arc<dyn QueryPlanner + Send + Sync> {
+        Arc::new(Self { client: Some(client) })
     }
 }
 
@@ -32,7 +41,7 @@ impl QueryPlanner for LakeSoulQueryPlanner {
         logical_plan: &LogicalPlan,
         session_state: &SessionState,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        let planner = LakeSoulPhysicalPlanner::new();
+        let planner = LakeSoulPhysicalPlanner::new(self.client.clone());
         planner.create_physical_plan(logical_plan, session_state).await
     }
 }
@@ -0,0 +1,395 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, AsArray, Int32Array, Int64Array, StringArray, TimestampMillisecondArray};
+use arrow::datatypes::{DataType, Field, Int32Type, Schema, TimeUnit, TimestampMillisecondType};
+use arrow::record_batch::RecordBatch;
+use arrow_cast::cast;
+use datafusion::catalog::schema::{MemorySchemaProvider, SchemaProvider};
+use datafusion::catalog::{CatalogProvider, MemoryCatalogProvider};
+use datafusion::datasource::TableProvider;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::SessionContext;
+use datafusion::sql::sqlparser::ast::{
+    FunctionArg, FunctionArgExpr, Ident, ObjectName, Statement, TableAlias, TableFactor, VisitMut, VisitorMut,
+};
+
+use crate::datasource::table_provider::TableSnapshot;
+use crate::lakesoul_table::LakeSoulTable;
+use crate::planner::ddl::resolve_lakesoul_table;
+use crate::planner::normalize_ident;
+
+/// Table function reading the snapshot of a LakeSoul table as of a version or a timestamp, see [TableSnapshot]:
+///
+/// ```sql
+/// SELECT * FROM lakesoul_snapshot('[namespace.]table', version | timestamp)
+/// ```
+///
+/// The version is an integer, and the timestamp is a `TIMESTAMP`, `DATE` or string, e.g.
+/// `lakesoul_snapshot('default.t', TIMESTAMP '2024-01-01 00:00:00')`.
+pub const SNAPSHOT_TABLE_FUNCTION: &str = "lakesoul_snapshot";
+
+/// Table function reading the rows committed to a LakeSoul table after the start version or timestamp
+/// until the end version or timestamp, see [LakeSoulTable::to_incremental_dataframe]:
+///
+/// ```sql
+/// SELECT * FROM lakesoul_incremental('[namespace.]table', start, end)
+/// ```
+///
+/// The start is exclusive and the end is inclusive, e.g. `lakesoul_incremental('t', -1, 0)` reads the first version.
+pub const INCREMENTAL_TABLE_FUNCTION: &str = "lakesoul_incremental";
+
+/// Table function like [INCREMENTAL_TABLE_FUNCTION], which also returns the deleted rows and the change type of
+/// each row in the column [CHANGE_TYPE_COLUMN](crate::lakesoul_table::incremental::CHANGE_TYPE_COLUMN).
+pub const CHANGES_TABLE_FUNCTION: &str = "lakesoul_changes";
+
+/// Table function listing the commit history of a LakeSoul table, see [LakeSoulTable::history]:
+///
+/// ```sql
+/// SELECT * FROM lakesoul_history('[namespace.]table'[, 'partition'[, start_timestamp[, end_timestamp]]])
+/// ```
+///
+/// Each row is a version of a partition with its commit op, timestamp, domain, and the number of data commits,
+/// the number of files and the total size of the files added by it, from the latest to the earliest version.
+/// The optional filters are `NULL` to be skipped, e.g. `lakesoul_history('t', NULL, TIMESTAMP '2024-01-01')`.
+pub const HISTORY_TABLE_FUNCTION: &str = "lakesoul_history";
+
+/// The table functions of LakeSoul in the `FROM` clause, which are not supported by DataFusion.
+const TABLE_FUNCTIONS: &[&str] = &[
+    SNAPSHOT_TABLE_FUNCTION,
+    INCREMENTAL_TABLE_FUNCTION,
+    CHANGES_TABLE_FUNCTION,
+    HISTORY_TABLE_FUNCTION,
+];
+
+/// The catalog of the tables returned by the table functions, which are registered while planning a statement.
+const TABLE_FUNCTION_CATALOG: &str = "lakesoul_table_function";
+const TABLE_FUNCTION_SCHEMA: &str = "public";
+
+/// A call of a table function, which is replaced by the table `table_name` in [TABLE_FUNCTION_CATALOG].
+struct TableFunctionCall {
+    table_name: String,
+    function: String,
+    args: Vec<FunctionArg>,
+}
+
+#[derive(Default)]
+struct TableFunctionVisitor {
+    calls: Vec<TableFunctionCall>,
+}
+
+impl VisitorMut for TableFunctionVisitor {
+    type Break = ();
+
+    fn pre_visit_table_factor(&mut self, table_factor: &mut TableFactor) -> ControlFlow<Self::Break> {
+        if let TableFactor::Table { name, alias, args, .. } = table_factor {
+            let function = match name.0.as_slice() {
+                [ident] if args.is_some() => normalize_ident(ident),
+                _ => return ControlFlow::Continue(()),
+            };
+            if TABLE_FUNCTIONS.contains(&function.as_str()) {
+                let table_name = format!("{}_{}", function, uuid::Uuid::new_v4().simple());
+                *name = ObjectName(vec![
+                    Ident::new(TABLE_FUNCTION_CATALOG),
+                    Ident::new(TABLE_FUNCTION_SCHEMA),
+                    Ident::new(&table_name),
+                ]);
+                // the columns are qualified by the function name like a table
+                if alias.is_none() {
+                    *alias = Some(TableAlias {
+                        name: Ident::new(&function),
+                        columns: vec![],
+                    });
+                }
+                self.calls.push(TableFunctionCall {
+                    table_name,
+                    function,
+                    args: args.take().unwrap_or_default(),
+                });
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Replace the calls of LakeSoul table functions in `statement` by the tables they return,
+/// which are registered in `ctx` until [deregister_table_functions] after planning the statement.
+///
+/// Returns the names of the registered tables.
+pub(crate) async fn register_table_functions(ctx: &SessionContext, statement: &mut Statement) -> Result<Vec<String>> {
+    let mut visitor = TableFunctionVisitor::default();
+    let _ = statement.visit(&mut visitor);
+    if visitor.calls.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut tables = Vec::with_capacity(visitor.calls.len());
+    for call in visitor.calls {
+        let table = match call.function.as_str() {
+            SNAPSHOT_TABLE_FUNCTION => snapshot_table(ctx, &call.args).await?,
+            INCREMENTAL_TABLE_FUNCTION | CHANGES_TABLE_FUNCTION => {
+                incremental_table(ctx, &call.function, &call.args).await?
+            }
+            HISTORY_TABLE_FUNCTION => history_table(ctx, &call.args).await?,
+            function => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Table function {} is not supported",
+                    function
+                )))
+            }
+        };
+        tables.push((call.table_name, table));
+    }
+
+    let schema = table_function_schema(ctx)?;
+    for (table_name, table) in &tables {
+        schema.register_table(table_name.clone(), table.clone())?;
+    }
+    Ok(tables.into_iter().map(|(table_name, _)| table_name).collect())
+}
+
+/// Deregister the tables of [register_table_functions].
+pub(crate) fn deregister_table_functions(ctx: &SessionContext, table_names: &[String]) {
+    if let Some(schema) = ctx
+        .catalog(TABLE_FUNCTION_CATALOG)
+        .and_then(|catalog| catalog.schema(TABLE_FUNCTION_SCHEMA))
+    {
+        for table_name in table_names {
+            let _ = schema.deregister_table(table_name);
+        }
+    }
+}
+
+fn table_function_schema(ctx: &SessionContext) -> Result<Arc<dyn SchemaProvider>> {
+    let catalog = match ctx.catalog(TABLE_FUNCTION_CATALOG) {
+        Some(catalog) => catalog,
+        None => {
+            let catalog: Arc<dyn CatalogProvider> = Arc::new(MemoryCatalogProvider::new());
+            catalog.register_schema(TABLE_FUNCTION_SCHEMA, Arc::new(MemorySchemaProvider::new()))?;
+            ctx.register_catalog(TABLE_FUNCTION_CATALOG, catalog.clone());
+            catalog
+        }
+    };
+    catalog
+        .schema(TABLE_FUNCTION_SCHEMA)
+        .ok_or(DataFusionError::Internal(format!(
+            "Schema {}.{} not found",
+            TABLE_FUNCTION_CATALOG, TABLE_FUNCTION_SCHEMA
+        )))
+}
+
+/// The table of [SNAPSHOT_TABLE_FUNCTION].
+async fn snapshot_table(ctx: &SessionContext, args: &[FunctionArg]) -> Result<Arc<dyn TableProvider>> {
+    let [table, snapshot] = args else {
+        return Err(DataFusionError::Plan(format!(
+            "{} expects 2 arguments: table name and version or timestamp, got {}",
+            SNAPSHOT_TABLE_FUNCTION,
+            args.len()
+        )));
+    };
+    let table = lakesoul_table_arg(ctx, SNAPSHOT_TABLE_FUNCTION, table).await?;
+    let snapshot = snapshot_arg(ctx, SNAPSHOT_TABLE_FUNCTION, snapshot).await?;
+    let provider = table
+        .as_provider(&ctx.state(), Some(snapshot))
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    Ok(Arc::new(provider))
+}
+
+/// The table of [INCREMENTAL_TABLE_FUNCTION] and [CHANGES_TABLE_FUNCTION].
+async fn incremental_table(
+    ctx: &SessionContext,
+    function: &str,
+    args: &[FunctionArg],
+) -> Result<Arc<dyn TableProvider>> {
+    let [table, start, end] = args else {
+        return Err(DataFusionError::Plan(format!(
+            "{} expects 3 arguments: table name, start and end version or timestamp, got {}",
+            function,
+            args.len()
+        )));
+    };
+    let table = lakesoul_table_arg(ctx, function, table).await?;
+    let start = snapshot_arg(ctx, function, start).await?;
+    let end = snapshot_arg(ctx, function, end).await?;
+    let dataframe = table
+        .to_incremental_dataframe(ctx, start, end, function == CHANGES_TABLE_FUNCTION)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    Ok(dataframe.into_view())
+}
+
+/// The table of [HISTORY_TABLE_FUNCTION].
+async fn history_table(ctx: &SessionContext, args: &[FunctionArg]) -> Result<Arc<dyn TableProvider>> {
+    let (table, filters) = match args {
+        [table, filters @ ..] if filters.len() <= 3 => (table, filters),
+        _ => {
+            return Err(DataFusionError::Plan(format!(
+                "{} expects 1 to 4 arguments: table name, and optional partition, start and end timestamp, got {}",
+                HISTORY_TABLE_FUNCTION,
+                args.len()
+            )))
+        }
+    };
+    let table = lakesoul_table_arg(ctx, HISTORY_TABLE_FUNCTION, table).await?;
+    let mut partition_desc = None;
+    let mut timestamps = [None, None];
+    for (idx, arg) in filters.iter().enumerate() {
+        let value = evaluate_arg(ctx, arg).await?;
+        if value.data_type() == &DataType::Null || value.is_null(0) {
+            continue;
+        }
+        if idx == 0 {
+            partition_desc = Some(string_arg(ctx, HISTORY_TABLE_FUNCTION, "partition", arg).await?);
+        } else {
+            let TableSnapshot::Timestamp(timestamp) = snapshot_arg(ctx, HISTORY_TABLE_FUNCTION, arg).await? else {
+                return Err(DataFusionError::Plan(format!(
+                    "The start and end of {} must be timestamps",
+                    HISTORY_TABLE_FUNCTION
+                )));
+            };
+            timestamps[idx - 1] = Some(timestamp);
+        }
+    }
+    let history = table
+        .history(partition_desc.as_deref(), timestamps[0], timestamps[1])
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("partition_desc", DataType::Utf8, false),
+        Field::new("version", DataType::Int32, false),
+        Field::new("commit_op", DataType::Utf8, false),
+        Field::new("timestamp", DataType::Timestamp(TimeUnit::Millisecond, None), false),
+        Field::new("domain", DataType::Utf8, false),
+        Field::new("num_commits", DataType::Int64, false),
+        Field::new("num_files", DataType::Int64, false),
+        Field::new("total_size", DataType::Int64, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from_iter_values(
+                history.iter().map(|commit| &commit.partition_desc),
+            )) as ArrayRef,
+            Arc::new(Int32Array::from_iter_values(
+                history.iter().map(|commit| commit.version),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                history.iter().map(|commit| commit.commit_op.as_str_name()),
+            )),
+            Arc::new(TimestampMillisecondArray::from_iter_values(
+                history.iter().map(|commit| commit.timestamp),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                history.iter().map(|commit| &commit.domain),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                history.iter().map(|commit| commit.num_commits as i64),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                history.iter().map(|commit| commit.num_files as i64),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                history.iter().map(|commit| commit.total_size),
+            )),
+        ],
+    )?;
+    Ok(ctx.read_batch(batch)?.into_view())
+}
+
+/// Resolve the [LakeSoulTable] of the table name argument of a table function.
+pub(crate) async fn lakesoul_table_arg(
+    ctx: &SessionContext,
+    function: &str,
+    arg: &FunctionArg,
+) -> Result<LakeSoulTable> {
+    let table = string_arg(ctx, function, "table name", arg).await?;
+    let (client, namespace, table_name) = resolve_lakesoul_table(ctx, &table)?;
+    let table_info = client
+        .get_table_info_by_table_name(&table_name, &namespace)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    LakeSoulTable::try_new_with_client_and_table_info(client, table_info)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))
+}
+
+/// Evaluate the string argument `name` of a table function.
+pub(crate) async fn string_arg(ctx: &SessionContext, function: &str, name: &str, arg: &FunctionArg) -> Result<String> {
+    let value = evaluate_arg(ctx, arg).await?;
+    match value.data_type() {
+        DataType::Utf8 | DataType::LargeUtf8 => {
+            Ok(cast(&value, &DataType::Utf8)?.as_string::<i32>().value(0).to_string())
+        }
+        data_type => Err(DataFusionError::Plan(format!(
+            "The {} of {} must be a string, got {}",
+            name, function, data_type
+        ))),
+    }
+}
+
+/// Evaluate the version or timestamp argument of a table function.
+pub(crate) async fn snapshot_arg(ctx: &SessionContext, function: &str, arg: &FunctionArg) -> Result<TableSnapshot> {
+    let snapshot = evaluate_arg(ctx, arg).await?;
+    match snapshot.data_type() {
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => {
+            let version = cast(&snapshot, &DataType::Int32)?;
+            if version.is_null(0) {
+                return Err(DataFusionError::Plan(format!(
+                    "Invalid version {} of {}",
+                    arg, function
+                )));
+            }
+            Ok(TableSnapshot::Version(version.as_primitive::<Int32Type>().value(0)))
+        }
+        DataType::Timestamp(_, _) | DataType::Date32 | DataType::Date64 | DataType::Utf8 | DataType::LargeUtf8 => {
+            let timestamp = cast(&snapshot, &DataType::Timestamp(TimeUnit::Millisecond, None))?;
+            if timestamp.is_null(0) {
+                return Err(DataFusionError::Plan(format!(
+                    "Invalid timestamp {} of {}",
+                    arg, function
+                )));
+            }
+            Ok(TableSnapshot::Timestamp(
+                timestamp.as_primitive::<TimestampMillisecondType>().value(0),
+            ))
+        }
+        data_type => Err(DataFusionError::Plan(format!(
+            "The snapshot of {} must be a version or a timestamp, got {}",
+            function, data_type
+        ))),
+    }
+}
+
+/// Evaluate a constant argument of a table function into an array of a single value.
+async fn evaluate_arg(ctx: &SessionContext, arg: &FunctionArg) -> Result<ArrayRef> {
+    let expr = match arg {
+        FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => expr,
+        _ => {
+            return Err(DataFusionError::Plan(format!(
+                "Unsupported argument {} of table function",
+                arg
+            )))
+        }
+    };
+    let batches = ctx.sql(&format!("SELECT {}", expr)).await?.collect().await?;
+    match batches.iter().find(|batch| batch.num_rows() > 0) {
+        Some(batch) if batch.num_rows() == 1 => Ok(batch.column(0).clone()),
+        _ => Err(DataFusionError::Plan(format!(
+            "Argument {} of table function is not a single value",
+            expr
+        ))),
+    }
+}
@@ -0,0 +1,357 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod branch_tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use arrow::record_batch::RecordBatch;
+    use datafusion::execution::context::SessionContext;
+    use lakesoul_metadata::error::LakeSoulMetaDataError;
+    use lakesoul_metadata::{branch_table_id, MemoryBackend, MetaDataClient, MetaDataClientRef};
+
+    use crate::catalog::expire_snapshots;
+    use crate::datasource::table_provider::TableSnapshot;
+    use crate::error::{LakeSoulError, Result};
+    use crate::lakesoul_table::LakeSoulTable;
+    use crate::planner::sql;
+    use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, create_context};
+
+    /// Create table `table_name` partitioned by `range` with primary key `id`, and upsert the batches one by one.
+    async fn create_table_with_versions(
+        client: MetaDataClientRef,
+        ctx: &SessionContext,
+        table_name: &str,
+        batches: Vec<RecordBatch>,
+    ) -> Result<LakeSoulTable> {
+        sql(
+            ctx,
+            &format!(
+                "create table lakesoul.default.{table_name} (range int, id int, value int) \
+                primary key (id) partitioned by (range)"
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        let lakesoul_table = LakeSoulTable::for_namespace_and_name_with_client(client, "default", table_name).await?;
+        for batch in batches {
+            lakesoul_table.execute_upsert(batch).await?;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        Ok(lakesoul_table)
+    }
+
+    /// range=1 and range=2 are created by the first version, range=1 is updated by the second version,
+    /// and range=3 is created by the third version.
+    fn versions() -> Vec<RecordBatch> {
+        let names = vec!["range", "id", "value"];
+        vec![
+            create_batch_i32(names.clone(), vec![&[1, 1, 2], &[1, 2, 3], &[1, 2, 3]]),
+            create_batch_i32(names.clone(), vec![&[1], &[1], &[10]]),
+            create_batch_i32(names, vec![&[3], &[4], &[4]]),
+        ]
+    }
+
+    async fn read_table(ctx: &SessionContext, table_name: &str) -> Result<Vec<RecordBatch>> {
+        Ok(sql(
+            ctx,
+            &format!("select range, id, value from lakesoul.default.\"{table_name}\""),
+        )
+        .await?
+        .collect()
+        .await?)
+    }
+
+    fn file_exists(path: &str) -> bool {
+        url::Url::parse(path).unwrap().to_file_path().unwrap().exists()
+    }
+
+    /// The latest data files of the table, or of the branch, of `table_id`.
+    async fn data_files(client: &MetaDataClientRef, table_id: &str) -> Result<HashSet<String>> {
+        let partition_info_list = client.get_all_partition_info(table_id).await?;
+        Ok(client
+            .get_data_files_of_partitions(partition_info_list)
+            .await?
+            .into_iter()
+            .collect())
+    }
+
+    /// The version 0 of each partition.
+    const FIRST_VERSIONS: &[&str] = &[
+        "+-------+----+-------+",
+        "| range | id | value |",
+        "+-------+----+-------+",
+        "| 1     | 1  | 1     |",
+        "| 1     | 2  | 2     |",
+        "| 2     | 3  | 3     |",
+        "| 3     | 4  | 4     |",
+        "+-------+----+-------+",
+    ];
+
+    /// The latest version of each partition.
+    const LATEST: &[&str] = &[
+        "+-------+----+-------+",
+        "| range | id | value |",
+        "+-------+----+-------+",
+        "| 1     | 1  | 10    |",
+        "| 1     | 2  | 2     |",
+        "| 2     | 3  | 3     |",
+        "| 3     | 4  | 4     |",
+        "+-------+----+-------+",
+    ];
+
+    /// The latest versions of the branch forked from [LATEST] after its writes.
+    const BRANCH_WRITES: &[&str] = &[
+        "+-------+----+-------+",
+        "| range | id | value |",
+        "+-------+----+-------+",
+        "| 1     | 1  | 100   |",
+        "| 1     | 2  | 2     |",
+        "| 2     | 3  | 3     |",
+        "| 2     | 6  | 6     |",
+        "| 3     | 4  | 4     |",
+        "| 4     | 5  | 5     |",
+        "+-------+----+-------+",
+    ];
+
+    async fn test_branch_writes() -> Result<()> {
+        let table_name = "test_branch_writes";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let ctx = create_context(client.clone()).await?;
+        let lakesoul_table = create_table_with_versions(client.clone(), &ctx, table_name, versions()).await?;
+
+        let branch = lakesoul_table.create_branch("dev", None).await?;
+        assert_eq!(branch.partition_versions.get("range=1"), Some(&1));
+        assert_eq!(branch.partition_versions.get("range=2"), Some(&0));
+        assert_eq!(branch.partition_versions.get("range=3"), Some(&0));
+        assert!(matches!(
+            lakesoul_table.create_branch("dev", None).await,
+            Err(LakeSoulError::MetaDataError(LakeSoulMetaDataError::AlreadyExists(_)))
+        ));
+        assert!(lakesoul_table.create_branch("dev@x", None).await.is_err());
+        assert!(lakesoul_table.branch("missing").await.is_err());
+        assert_batches_eq(
+            table_name,
+            LATEST,
+            &read_table(&ctx, &format!("{table_name}@dev")).await?,
+        );
+
+        // the writes to the branch do not change the table
+        let branch_table = LakeSoulTable::for_name(&format!("{table_name}@dev")).await?;
+        assert_eq!(branch_table.table_name(), format!("{table_name}@dev"));
+        branch_table
+            .execute_upsert(create_batch_i32(
+                vec!["range", "id", "value"],
+                vec![&[1, 4], &[1, 5], &[100, 5]],
+            ))
+            .await?;
+        ctx.register_batch(
+            "source",
+            create_batch_i32(vec!["range", "id", "value"], vec![&[2], &[6], &[6]]),
+        )?;
+        sql(
+            &ctx,
+            &format!("insert into lakesoul.default.\"{table_name}@dev\" select range, id, value from source"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(table_name, LATEST, &read_table(&ctx, table_name).await?);
+        assert_batches_eq(
+            table_name,
+            BRANCH_WRITES,
+            &read_table(&ctx, &format!("{table_name}@dev")).await?,
+        );
+
+        // the writes to the table do not change the branch
+        lakesoul_table
+            .execute_upsert(create_batch_i32(vec!["range", "id", "value"], vec![&[3], &[4], &[40]]))
+            .await?;
+        assert_batches_eq(
+            table_name,
+            BRANCH_WRITES,
+            &read_table(&ctx, &format!("{table_name}@dev")).await?,
+        );
+        let branch_versions = client
+            .get_all_versions_of_partition(
+                &branch_table_id(&lakesoul_table.table_info().table_id, "dev"),
+                "range=3",
+            )
+            .await?;
+        assert_eq!(branch_versions.len(), 1);
+
+        // deleting the branch deletes the files only written to it
+        let table_files = data_files(&client, &lakesoul_table.table_info().table_id).await?;
+        let branch_files = data_files(&client, &branch_table.table_info().table_id)
+            .await?
+            .into_iter()
+            .filter(|file| !table_files.contains(file))
+            .collect::<Vec<_>>();
+        assert!(!branch_files.is_empty());
+        assert!(lakesoul_table.delete_branch("dev").await?);
+        assert!(!lakesoul_table.delete_branch("dev").await?);
+        assert!(lakesoul_table.branches().await?.is_empty());
+        assert!(branch_files.iter().all(|file| !file_exists(file)));
+        assert!(table_files.iter().all(|file| file_exists(file)));
+        assert!(read_table(&ctx, &format!("{table_name}@dev")).await.is_err());
+        Ok(())
+    }
+
+    async fn test_branch_after_expiration() -> Result<()> {
+        let table_name = "test_branch_after_expiration";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let ctx = create_context(client.clone()).await?;
+        let lakesoul_table = create_table_with_versions(client.clone(), &ctx, table_name, versions()).await?;
+
+        lakesoul_table
+            .create_branch("first", Some(TableSnapshot::Version(0)))
+            .await?;
+        assert_eq!(
+            lakesoul_table
+                .branches()
+                .await?
+                .into_iter()
+                .map(|branch| branch.branch_name)
+                .collect::<Vec<_>>(),
+            vec!["first".to_string()]
+        );
+        assert_batches_eq(
+            table_name,
+            FIRST_VERSIONS,
+            &read_table(&ctx, &format!("{table_name}@first")).await?,
+        );
+
+        // the versions the branch is forked from are retained
+        let expired = expire_snapshots(
+            client.clone(),
+            table_name,
+            chrono::Utc::now().timestamp_millis(),
+            1,
+            false,
+        )
+        .await?;
+        assert!(!expired.versions.contains(&("range=1".to_string(), 0)));
+        assert_batches_eq(
+            table_name,
+            FIRST_VERSIONS,
+            &read_table(&ctx, &format!("{table_name}@first")).await?,
+        );
+        assert_batches_eq(table_name, LATEST, &read_table(&ctx, table_name).await?);
+
+        // the versions are expired once the branch is deleted
+        assert!(lakesoul_table.delete_branch("first").await?);
+        let expired = expire_snapshots(
+            client.clone(),
+            table_name,
+            chrono::Utc::now().timestamp_millis(),
+            1,
+            false,
+        )
+        .await?;
+        assert!(expired.versions.contains(&("range=1".to_string(), 0)));
+        assert_batches_eq(table_name, LATEST, &read_table(&ctx, table_name).await?);
+        Ok(())
+    }
+
+    async fn test_branch_procedures() -> Result<()> {
+        let table_name = "test_branch_procedures";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let ctx = create_context(client.clone()).await?;
+        create_table_with_versions(client, &ctx, table_name, versions()).await?;
+        let first_versions = &[
+            "+----------------+---------+",
+            "| partition_desc | version |",
+            "+----------------+---------+",
+            "| range=1        | 0       |",
+            "| range=2        | 0       |",
+            "| range=3        | 0       |",
+            "+----------------+---------+",
+        ];
+
+        let results = sql(
+            &ctx,
+            &format!("call lakesoul_create_branch('lakesoul.default.{table_name}', 'first', 0)"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(table_name, first_versions, &results);
+        assert_batches_eq(
+            table_name,
+            FIRST_VERSIONS,
+            &read_table(&ctx, &format!("{table_name}@first")).await?,
+        );
+        assert!(sql(
+            &ctx,
+            &format!("call lakesoul_create_branch('lakesoul.default.{table_name}', 'first')")
+        )
+        .await
+        .is_err());
+
+        let results = sql(
+            &ctx,
+            &format!("call lakesoul_drop_branch('lakesoul.default.{table_name}', 'first')"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(table_name, first_versions, &results);
+        assert!(sql(
+            &ctx,
+            &format!("call lakesoul_drop_branch('lakesoul.default.{table_name}', 'first')")
+        )
+        .await
+        .is_err());
+        Ok(())
+    }
+
+    /// A branch is kept in the metadata of the client like a table, e.g. in a memory backend.
+    async fn test_branch_in_memory_backend() -> Result<()> {
+        let table_name = "test_branch_in_memory_backend";
+        let client = Arc::new(MetaDataClient::from_backend(Arc::new(MemoryBackend::new())));
+        let ctx = create_context(client.clone()).await?;
+        let lakesoul_table = create_table_with_versions(client.clone(), &ctx, table_name, versions()).await?;
+
+        lakesoul_table.create_branch("dev", None).await?;
+        ctx.register_batch(
+            "source",
+            create_batch_i32(vec!["range", "id", "value"], vec![&[3], &[4], &[40]]),
+        )?;
+        sql(
+            &ctx,
+            &format!("insert into lakesoul.default.\"{table_name}@dev\" select range, id, value from source"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(table_name, LATEST, &read_table(&ctx, table_name).await?);
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+----+-------+",
+                "| range | id | value |",
+                "+-------+----+-------+",
+                "| 1     | 1  | 10    |",
+                "| 1     | 2  | 2     |",
+                "| 2     | 3  | 3     |",
+                "| 3     | 4  | 40    |",
+                "+-------+----+-------+",
+            ],
+            &read_table(&ctx, &format!("{table_name}@dev")).await?,
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_all_cases() -> Result<()> {
+        test_branch_writes().await?;
+        test_branch_after_expiration().await?;
+        test_branch_procedures().await?;
+        test_branch_in_memory_backend().await?;
+        Ok(())
+    }
+}
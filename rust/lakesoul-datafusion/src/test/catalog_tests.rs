@@ -7,14 +7,17 @@ mod catalog_tests {
     use crate::catalog::{LakeSoulCatalog, LakeSoulNamespace, LakeSoulTableProperty};
     use crate::lakesoul_table::LakeSoulTable;
     use crate::serialize::arrow_java::ArrowJavaSchema;
-    use arrow::array::{ArrayRef, Int32Array, RecordBatch};
+    use crate::test::utils::create_batch_i32;
     use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
     use datafusion::assert_batches_eq;
+    use datafusion::assert_batches_sorted_eq;
     use datafusion::catalog::schema::SchemaProvider;
     use datafusion::catalog::CatalogProvider;
+    use datafusion::datasource::MemTable;
+    use datafusion::execution::context::SessionContext;
     use lakesoul_io::lakesoul_io_config::create_session_context;
     use lakesoul_io::lakesoul_io_config::LakeSoulIOConfigBuilder;
-    use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
+    use lakesoul_metadata::{MemoryBackend, MetaDataClient, MetaDataClientRef};
     use proto::proto::entity::{Namespace, TableInfo};
     use rand::distributions::Alphanumeric;
     use rand::{thread_rng, Rng, SeedableRng};
@@ -25,23 +28,16 @@ mod catalog_tests {
     use tokio::runtime::Runtime;
     use tracing::debug;
 
-    fn create_batch_i32(names: Vec<&str>, values: Vec<&[i32]>) -> RecordBatch {
-        let values = values
-            .into_iter()
-            .map(|vec| Arc::new(Int32Array::from(Vec::from(vec))) as ArrayRef)
-            .collect::<Vec<ArrayRef>>();
-        let iter = names
-            .into_iter()
-            .zip(values)
-            .map(|(name, array)| (name, array, true))
-            .collect::<Vec<_>>();
-        RecordBatch::try_from_iter_with_nullable(iter).unwrap()
-    }
-
     async fn get_client() -> MetaDataClientRef {
         Arc::new(MetaDataClient::from_env().await.unwrap())
     }
 
+    /// Plan and execute `sql`, the DDL statements of DataFusion fail on planning rather than on collecting.
+    async fn run_sql(sc: &SessionContext, sql: &str) -> datafusion::error::Result<()> {
+        sc.sql(sql).await?.collect().await?;
+        Ok(())
+    }
+
     fn random_namespace(prefix: &str, hash_bucket_num: usize) -> Vec<Namespace> {
         let mut rng = ChaCha8Rng::from_rng(thread_rng()).unwrap();
         (0..rng.gen_range(1..10))
@@ -55,6 +51,7 @@ mod catalog_tests {
                 },
                 properties: serde_json::to_string(&LakeSoulTableProperty {
                     hash_bucket_num: Some(hash_bucket_num),
+                    ..Default::default()
                 })
                 .unwrap(),
                 comment: "this is comment".to_string(),
@@ -284,9 +281,203 @@ mod catalog_tests {
         });
     }
 
+    fn test_catalog_drop_schema() {
+        let rt = Runtime::new().unwrap();
+        rt.block_on(async {
+            let client = Arc::new(MetaDataClient::from_env().await.unwrap());
+            let batch = create_batch_i32(
+                vec!["range", "hash", "value"],
+                vec![&[20201101, 20201101, 20201101, 20201102], &[1, 2, 3, 4], &[1, 2, 3, 4]],
+            );
+            let schema = batch.schema();
+            let mut config = LakeSoulIOConfigBuilder::new().with_schema(schema.clone()).build();
+            let sc = Arc::new(create_session_context(&mut config).unwrap());
+            let catalog = Arc::new(LakeSoulCatalog::new(client.clone(), sc.clone()));
+            sc.register_catalog("test_catalog_drop_schema", catalog.clone());
+
+            let data = random_tables(random_namespace("drop", 4), schema.clone());
+            for (np, tables) in data.iter() {
+                let sql = format!("create schema test_catalog_drop_schema.{}", np.namespace);
+                sc.sql(&sql).await.unwrap().collect().await.unwrap();
+                for t in tables {
+                    client.create_table(t.clone()).await.unwrap();
+                    let lakesoul_table = LakeSoulTable::for_namespace_and_name(&np.namespace, &t.table_name)
+                        .await
+                        .unwrap();
+                    lakesoul_table.execute_upsert(batch.clone()).await.unwrap();
+                }
+            }
+            for (np, tables) in data.iter() {
+                for sql in [
+                    format!("drop schema test_catalog_drop_schema.{}", np.namespace),
+                    format!("drop schema test_catalog_drop_schema.{} restrict", np.namespace),
+                ] {
+                    // non-empty namespace can only be dropped with CASCADE
+                    assert!(run_sql(&sc, &sql).await.is_err());
+                    assert!(client.get_namespace_by_namespace(&np.namespace).await.is_ok());
+                }
+                let sql = format!("drop schema test_catalog_drop_schema.{} cascade", np.namespace);
+                sc.sql(&sql).await.unwrap().collect().await.unwrap();
+                assert!(client.get_namespace_by_namespace(&np.namespace).await.is_err());
+                assert!(catalog.schema(&np.namespace).is_none());
+                for t in tables {
+                    assert!(client
+                        .get_table_info_by_table_name(&t.table_name, &np.namespace)
+                        .await
+                        .is_err());
+                    assert!(!std::path::Path::new(t.table_path.trim_start_matches("file://")).exists());
+                }
+            }
+
+            // empty namespace is dropped without CASCADE
+            let np = &random_namespace("drop_empty", 4)[0].namespace;
+            let sql = format!("create schema test_catalog_drop_schema.{}", np);
+            sc.sql(&sql).await.unwrap().collect().await.unwrap();
+            let sql = format!("drop schema test_catalog_drop_schema.{}", np);
+            sc.sql(&sql).await.unwrap().collect().await.unwrap();
+            assert!(client.get_namespace_by_namespace(np).await.is_err());
+            let sql = format!("drop schema if exists test_catalog_drop_schema.{}", np);
+            sc.sql(&sql).await.unwrap().collect().await.unwrap();
+        });
+    }
+
+    /// A table whose data fails to be deleted is kept with its namespace, and the other tables are dropped.
+    fn test_catalog_drop_schema_fails_to_drop_table() {
+        let rt = Runtime::new().unwrap();
+        rt.block_on(async {
+            let client = Arc::new(MetaDataClient::from_env().await.unwrap());
+            let batch = create_batch_i32(vec!["range", "hash", "value"], vec![&[20201101], &[1], &[1]]);
+            let schema = batch.schema();
+            let mut config = LakeSoulIOConfigBuilder::new().with_schema(schema.clone()).build();
+            let sc = Arc::new(create_session_context(&mut config).unwrap());
+            let catalog = Arc::new(LakeSoulCatalog::new(client.clone(), sc.clone()));
+            sc.register_catalog("test_catalog_drop_schema_fails", catalog.clone());
+
+            let np = random_namespace("drop_fails", 4).swap_remove(0);
+            let mut tables = random_tables(vec![np.clone()], schema.clone()).swap_remove(0).1;
+            tables.truncate(2);
+            let sql = format!("create schema test_catalog_drop_schema_fails.{}", np.namespace);
+            sc.sql(&sql).await.unwrap().collect().await.unwrap();
+            // the data of a table on an unsupported object store cannot be deleted
+            let mut failing = tables[0].clone();
+            failing.table_name = format!("{}_failing", failing.table_name);
+            failing.table_id = format!("{}_failing", failing.table_id);
+            failing.table_path = "unsupported://lakesoul/test_data/failing".to_string();
+            client.create_table(failing.clone()).await.unwrap();
+            for t in &tables {
+                client.create_table(t.clone()).await.unwrap();
+                LakeSoulTable::for_namespace_and_name(&np.namespace, &t.table_name)
+                    .await
+                    .unwrap()
+                    .execute_upsert(batch.clone())
+                    .await
+                    .unwrap();
+            }
+
+            let sql = format!("drop schema test_catalog_drop_schema_fails.{} cascade", np.namespace);
+            assert!(run_sql(&sc, &sql).await.is_err());
+            assert!(client.get_namespace_by_namespace(&np.namespace).await.is_ok());
+            assert!(client
+                .get_table_info_by_table_name(&failing.table_name, &np.namespace)
+                .await
+                .is_ok());
+            for t in &tables {
+                assert!(client
+                    .get_table_info_by_table_name(&t.table_name, &np.namespace)
+                    .await
+                    .is_err());
+                assert!(!std::path::Path::new(t.table_path.trim_start_matches("file://")).exists());
+            }
+
+            // the namespace is dropped once the failing table is gone
+            client.delete_table_by_table_info_cascade(&failing).await.unwrap();
+            sc.sql(&sql).await.unwrap().collect().await.unwrap();
+            assert!(client.get_namespace_by_namespace(&np.namespace).await.is_err());
+        });
+    }
+
+    fn test_catalog_register_table() {
+        let rt = Runtime::new().unwrap();
+        rt.block_on(async {
+            let client = get_client().await;
+            let batch = create_batch_i32(vec!["id", "value"], vec![&[1, 2, 3], &[1, 2, 3]]);
+            let mut config = LakeSoulIOConfigBuilder::new().with_schema(batch.schema()).build();
+            let sc = Arc::new(create_session_context(&mut config).unwrap());
+            let schema = LakeSoulNamespace::new(client.clone(), sc.clone(), "default");
+
+            // a table of no rows is created empty
+            let empty = MemTable::try_new(batch.schema(), vec![vec![]]).unwrap();
+            assert!(schema
+                .register_table("test_catalog_register_empty_table".to_string(), Arc::new(empty))
+                .unwrap()
+                .is_none());
+            let record = LakeSoulTable::for_name("test_catalog_register_empty_table")
+                .await
+                .unwrap()
+                .to_dataframe(&sc)
+                .await
+                .unwrap()
+                .collect()
+                .await
+                .unwrap();
+            assert_eq!(record.iter().map(|batch| batch.num_rows()).sum::<usize>(), 0);
+
+            let table = MemTable::try_new(batch.schema(), vec![vec![batch.clone()]]).unwrap();
+            schema
+                .register_table("test_catalog_register_table".to_string(), Arc::new(table))
+                .unwrap();
+            let record = LakeSoulTable::for_name("test_catalog_register_table")
+                .await
+                .unwrap()
+                .to_dataframe(&sc)
+                .await
+                .unwrap()
+                .collect()
+                .await
+                .unwrap();
+            assert_batches_sorted_eq!(
+                [
+                    "+----+-------+",
+                    "| id | value |",
+                    "+----+-------+",
+                    "| 1  | 1     |",
+                    "| 2  | 2     |",
+                    "| 3  | 3     |",
+                    "+----+-------+",
+                ],
+                &record
+            );
+
+            // the rows are written with the client of the namespace, which here has the only copy of the metadata
+            let memory_client = Arc::new(MetaDataClient::from_backend(Arc::new(MemoryBackend::new())));
+            let schema = LakeSoulNamespace::new(memory_client.clone(), sc.clone(), "default");
+            let table = MemTable::try_new(batch.schema(), vec![vec![batch.clone()]]).unwrap();
+            schema
+                .register_table("test_catalog_register_memory_table".to_string(), Arc::new(table))
+                .unwrap();
+            let record = LakeSoulTable::for_namespace_and_name_with_client(
+                memory_client,
+                "default",
+                "test_catalog_register_memory_table",
+            )
+            .await
+            .unwrap()
+            .to_dataframe(&sc)
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+            assert_eq!(record.iter().map(|batch| batch.num_rows()).sum::<usize>(), 3);
+        });
+    }
+
     #[test]
     fn test_all_cases() {
         test_catalog_api();
         test_catalog_sql();
+        test_catalog_drop_schema();
+        test_catalog_drop_schema_fails_to_drop_table();
+        test_catalog_register_table();
     }
 }
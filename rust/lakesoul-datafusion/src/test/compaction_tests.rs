@@ -3,24 +3,62 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod compaction_tests {
+    use std::collections::{HashMap, HashSet};
     use std::sync::Arc;
 
     use arrow::array::StringArray;
-    use lakesoul_io::lakesoul_io_config::LakeSoulIOConfigBuilder;
-    
-    use arrow::record_batch::RecordBatch;
+    use lakesoul_io::lakesoul_io_config::create_session_context;
+
     use arrow::array::{ArrayRef, Int32Array};
-    use lakesoul_metadata::MetaDataClient;
+    use arrow::record_batch::RecordBatch;
+    use lakesoul_metadata::transfusion::DataFileInfo;
+    use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
 
     use crate::catalog::compaction_with_merge_op;
-    use crate::catalog::{create_table, compaction};
+    use crate::catalog::{compaction, create_io_config_builder};
     use crate::error::Result;
-
-    async fn check_record_batch() -> Result<()> {
+    use crate::lakesoul_table::LakeSoulTable;
+    use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, execute_upsert, init_table};
+
+    async fn check_record_batch(
+        client: MetaDataClientRef,
+        table_name: &str,
+        selected_cols: Vec<&str>,
+        expected: &[&str],
+    ) -> Result<()> {
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        let builder = create_io_config_builder(client, None, false, "default").await?;
+        let sess_ctx = create_session_context(&mut builder.clone().build())?;
+        let results = lakesoul_table
+            .to_dataframe(&sess_ctx)
+            .await?
+            .select_columns(&selected_cols)?
+            .collect()
+            .await?;
+        assert_batches_eq(table_name, expected, &results);
         Ok(())
     }
 
-    async fn check_data_info() -> Result<()> {
+    /// check that each bucket of the compacted partitions has exactly one data file
+    async fn check_data_info(client: MetaDataClientRef, table_name: &str, compacted: &[&str]) -> Result<()> {
+        let table_info = client.get_table_info_by_table_name(table_name, "default").await?;
+        for partition_info in client.get_all_partition_info(&table_info.table_id).await? {
+            if !compacted.contains(&partition_info.partition_desc.as_str()) {
+                continue;
+            }
+            let files = client.get_data_files_of_single_partition(&partition_info).await?;
+            let buckets = files
+                .iter()
+                .map(|file| DataFileInfo::parse_bucket_id(file))
+                .collect::<HashSet<_>>();
+            assert_eq!(
+                files.len(),
+                buckets.len(),
+                "partition {}",
+                partition_info.partition_desc
+            );
+        }
         Ok(())
     }
 
@@ -28,153 +66,252 @@ mod compaction_tests {
         let client = Arc::new(MetaDataClient::from_env().await?);
         let table_name = "test_simple_compaction";
 
-        let _record_batch_1 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(Int32Array::from(vec![1, 2, 3, 1, 1])) as ArrayRef, false),
-                ("hash", Arc::new(Int32Array::from(vec![1, 1, 1, 2, 3])) as ArrayRef, false),
-                ("value", Arc::new(Int32Array::from(vec![1, 1, 1, 2, 3])) as ArrayRef, true),
-            ]
-        )?;
-
-        create_table(client.clone(), table_name, LakeSoulIOConfigBuilder::default().build()).await?;
-
-
-        check_data_info().await?;
-
-        let _record_batch_2 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(Int32Array::from(vec![1, 2, 3, 1, 1])) as ArrayRef, false),
-                ("hash", Arc::new(Int32Array::from(vec![1, 1, 1, 2, 3])) as ArrayRef, false),
-                ("value", Arc::new(Int32Array::from(vec![1, 1, 1, 2, 3])) as ArrayRef, true),
-            ]
-        )?;
-
-
-        check_data_info().await?;
+        let record_batch_1 = create_batch_i32(
+            vec!["range", "hash", "value"],
+            vec![&[1, 2, 3, 1, 1], &[1, 1, 1, 2, 3], &[1, 1, 1, 2, 3]],
+        );
+        init_table(
+            client.clone(),
+            record_batch_1.schema(),
+            table_name,
+            vec!["range"],
+            vec!["hash"],
+        )
+        .await?;
+        execute_upsert(record_batch_1, table_name).await?;
+
+        let record_batch_2 = create_batch_i32(
+            vec!["range", "hash", "value"],
+            vec![&[1, 2, 3, 1, 1], &[1, 1, 1, 2, 3], &[11, 11, 11, 22, 33]],
+        );
+        execute_upsert(record_batch_2, table_name).await?;
 
         compaction(client.clone(), table_name, "").await?;
 
-        check_data_info().await?;
-
-        Ok(())
+        check_data_info(client.clone(), table_name, &["range=1", "range=2", "range=3"]).await?;
+        check_record_batch(
+            client,
+            table_name,
+            vec!["range", "hash", "value"],
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 1    | 11    |",
+                "| 1     | 2    | 22    |",
+                "| 1     | 3    | 33    |",
+                "| 2     | 1    | 11    |",
+                "| 3     | 1    | 11    |",
+                "+-------+------+-------+",
+            ],
+        )
+        .await
     }
 
     async fn test_simple_compaction_with_condition() -> Result<()> {
         let client = Arc::new(MetaDataClient::from_env().await?);
         let table_name = "test_simple_compaction_with_condition";
 
-        let _record_batch_1 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(Int32Array::from(vec![1, 1, 1, 1])) as ArrayRef, false),
-                ("hash", Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef, false),
-                ("value", Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef, true),
-            ]
-        )?;
-
-        let _record_batch_2 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(Int32Array::from(vec![1, 1, 1])) as ArrayRef, false),
-                ("hash", Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef, false),
-                ("name", Arc::new(Int32Array::from(vec![11, 22, 33])) as ArrayRef, true),
-            ]
-        )?;
-
-        create_table(client.clone(), table_name, LakeSoulIOConfigBuilder::default().build()).await?;
-
-
-        check_data_info().await?;
+        let record_batch_1 = create_batch_i32(
+            vec!["range", "hash", "value"],
+            vec![&[1, 1, 2, 2], &[1, 2, 1, 2], &[1, 2, 3, 4]],
+        );
+        init_table(
+            client.clone(),
+            record_batch_1.schema(),
+            table_name,
+            vec!["range"],
+            vec!["hash"],
+        )
+        .await?;
+        execute_upsert(record_batch_1, table_name).await?;
+
+        let record_batch_2 = create_batch_i32(
+            vec!["range", "hash", "value"],
+            vec![&[1, 1, 2, 2], &[1, 2, 1, 2], &[11, 22, 33, 44]],
+        );
+        execute_upsert(record_batch_2, table_name).await?;
 
         compaction(client.clone(), table_name, "range=1").await?;
 
-        check_data_info().await?;
-
-        check_data_info().await?;
-        
-        Ok(())
+        check_data_info(client.clone(), table_name, &["range=1"]).await?;
+        check_record_batch(
+            client,
+            table_name,
+            vec!["range", "hash", "value"],
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 1    | 11    |",
+                "| 1     | 2    | 22    |",
+                "| 2     | 1    | 33    |",
+                "| 2     | 2    | 44    |",
+                "+-------+------+-------+",
+            ],
+        )
+        .await
     }
 
     async fn test_upsert_after_compaction() -> Result<()> {
         let client = Arc::new(MetaDataClient::from_env().await?);
         let table_name = "test_upsert_after_compaction";
 
-        let _record_batch_1 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(Int32Array::from(vec![1, 1, 1, 1])) as ArrayRef, false),
-                ("hash", Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef, false),
-                ("value", Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef, true),
-            ]
-        )?;
-
-        let _record_batch_2 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(Int32Array::from(vec![1, 1, 1])) as ArrayRef, false),
-                ("hash", Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef, false),
-                ("value", Arc::new(Int32Array::from(vec![11, 22, 33])) as ArrayRef, true),
-            ]
-        )?;
-
-        let _record_batch_3 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(Int32Array::from(vec![1, 1, 1, 1])) as ArrayRef, false),
-                ("hash", Arc::new(Int32Array::from(vec![2, 3, 4, 5])) as ArrayRef, false),
-                ("value", Arc::new(Int32Array::from(vec![222, 333, 444, 555])) as ArrayRef, true),
-            ]
-        )?;
-
-        create_table(client.clone(), table_name, LakeSoulIOConfigBuilder::default().build()).await?;
-        
-        compaction(client.clone(), table_name, "range=1").await?;
-
-        check_record_batch().await?;
-
+        let record_batch_1 = create_batch_i32(
+            vec!["range", "hash", "value"],
+            vec![&[1, 1, 1, 1], &[1, 2, 3, 4], &[1, 2, 3, 4]],
+        );
+        init_table(
+            client.clone(),
+            record_batch_1.schema(),
+            table_name,
+            vec!["range"],
+            vec!["hash"],
+        )
+        .await?;
+        execute_upsert(record_batch_1, table_name).await?;
+
+        let record_batch_2 = create_batch_i32(
+            vec!["range", "hash", "value"],
+            vec![&[1, 1, 1], &[1, 2, 3], &[11, 22, 33]],
+        );
+        execute_upsert(record_batch_2, table_name).await?;
 
-        check_record_batch().await?;
+        compaction(client.clone(), table_name, "range=1").await?;
 
-        compaction(client, table_name, "range=1").await?;
+        let record_batch_3 = create_batch_i32(
+            vec!["range", "hash", "value"],
+            vec![&[1, 1, 1, 1], &[2, 3, 4, 5], &[222, 333, 444, 555]],
+        );
+        execute_upsert(record_batch_3, table_name).await?;
+
+        check_record_batch(
+            client.clone(),
+            table_name,
+            vec!["range", "hash", "value"],
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 1    | 11    |",
+                "| 1     | 2    | 222   |",
+                "| 1     | 3    | 333   |",
+                "| 1     | 4    | 444   |",
+                "| 1     | 5    | 555   |",
+                "+-------+------+-------+",
+            ],
+        )
+        .await?;
 
-        check_record_batch().await?;
+        compaction(client.clone(), table_name, "range=1").await?;
 
-        Ok(())
+        check_record_batch(
+            client,
+            table_name,
+            vec!["range", "hash", "value"],
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 1    | 11    |",
+                "| 1     | 2    | 222   |",
+                "| 1     | 3    | 333   |",
+                "| 1     | 4    | 444   |",
+                "| 1     | 5    | 555   |",
+                "+-------+------+-------+",
+            ],
+        )
+        .await
     }
 
     async fn test_simple_compaction_with_merge_operator() -> Result<()> {
         let client = Arc::new(MetaDataClient::from_env().await?);
         let table_name = "test_simple_compaction_with_merge_operator";
 
-        let _record_batch_1 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(Int32Array::from(vec![1, 2, 3, 1, 1])) as ArrayRef, false),
-                ("hash", Arc::new(Int32Array::from(vec![1, 1, 1, 2, 3])) as ArrayRef, false),
-                ("v1", Arc::new(Int32Array::from(vec![1, 1, 1, 2, 3])) as ArrayRef, true),
-                ("v2", Arc::new(StringArray::from(vec!["1", "1", "1", "2", "3"])) as ArrayRef, true),
-            ]
-        )?;
-
-        let _record_batch_2 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(Int32Array::from(vec![1, 2, 3, 1, 1])) as ArrayRef, false),
-                ("hash", Arc::new(Int32Array::from(vec![1, 1, 1, 2, 3])) as ArrayRef, false),
-                ("v1", Arc::new(Int32Array::from(vec![1, 1, 1, 2, 3])) as ArrayRef, true),
-                ("v2", Arc::new(StringArray::from(vec!["1", "1", "1", "2", "3"])) as ArrayRef, true),
-            ]
-        )?;
-
-        create_table(client.clone(), table_name, LakeSoulIOConfigBuilder::default().build()).await?;
-
-        compaction_with_merge_op(client.clone(), table_name, "").await?;
-
-        check_record_batch().await?;
-
-        Ok(())
+        let record_batch_1 = RecordBatch::try_from_iter_with_nullable(vec![
+            (
+                "range",
+                Arc::new(Int32Array::from(vec![1, 2, 3, 1, 1])) as ArrayRef,
+                false,
+            ),
+            (
+                "hash",
+                Arc::new(Int32Array::from(vec![1, 1, 1, 2, 3])) as ArrayRef,
+                false,
+            ),
+            ("v1", Arc::new(Int32Array::from(vec![1, 1, 1, 2, 3])) as ArrayRef, true),
+            (
+                "v2",
+                Arc::new(StringArray::from(vec!["1", "1", "1", "2", "3"])) as ArrayRef,
+                true,
+            ),
+        ])?;
+        init_table(
+            client.clone(),
+            record_batch_1.schema(),
+            table_name,
+            vec!["range"],
+            vec!["hash"],
+        )
+        .await?;
+        execute_upsert(record_batch_1, table_name).await?;
+
+        let record_batch_2 = RecordBatch::try_from_iter_with_nullable(vec![
+            (
+                "range",
+                Arc::new(Int32Array::from(vec![1, 2, 3, 1, 1])) as ArrayRef,
+                false,
+            ),
+            (
+                "hash",
+                Arc::new(Int32Array::from(vec![1, 1, 1, 2, 3])) as ArrayRef,
+                false,
+            ),
+            ("v1", Arc::new(Int32Array::from(vec![1, 1, 1, 2, 3])) as ArrayRef, true),
+            (
+                "v2",
+                Arc::new(StringArray::from(vec!["1", "1", "1", "2", "3"])) as ArrayRef,
+                true,
+            ),
+        ])?;
+        execute_upsert(record_batch_2, table_name).await?;
+
+        compaction_with_merge_op(
+            client.clone(),
+            table_name,
+            "",
+            HashMap::from([
+                ("v1".to_string(), "SumAll".to_string()),
+                ("v2".to_string(), "JoinedAllByComma".to_string()),
+            ]),
+        )
+        .await?;
+
+        check_record_batch(
+            client,
+            table_name,
+            vec!["range", "hash", "v1", "v2"],
+            &[
+                "+-------+------+----+-----+",
+                "| range | hash | v1 | v2  |",
+                "+-------+------+----+-----+",
+                "| 1     | 1    | 2  | 1,1 |",
+                "| 1     | 2    | 4  | 2,2 |",
+                "| 1     | 3    | 6  | 3,3 |",
+                "| 2     | 1    | 2  | 1,1 |",
+                "| 3     | 1    | 2  | 1,1 |",
+                "+-------+------+----+-----+",
+            ],
+        )
+        .await
     }
 
     #[tokio::test]
-    async fn test_all_cases()  -> Result<()> {
+    async fn test_all_cases() -> Result<()> {
         test_upsert_after_compaction().await?;
         test_simple_compaction_with_merge_operator().await?;
         test_simple_compaction_with_condition().await?;
         test_simple_compaction().await?;
         Ok(())
     }
-    
-}
\ No newline at end of file
+}
@@ -0,0 +1,94 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod connection_pool_tests {
+    use std::sync::Arc;
+
+    use datafusion::execution::context::SessionContext;
+    use lakesoul_metadata::{MetaDataClient, PostgresBackend};
+
+    use crate::error::Result;
+    use crate::lakesoul_table::LakeSoulTable;
+    use crate::planner::sql;
+    use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, create_context};
+
+    /// Create table `table_name` with `num_partitions` partitions of one row each.
+    async fn create_table(ctx: &SessionContext, table_name: &str, num_partitions: i32) -> Result<()> {
+        sql(
+            ctx,
+            &format!(
+                "create table lakesoul.default.{table_name} (range int, id int, value int) \
+                primary key (id) partitioned by (range)"
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        let ids = (0..num_partitions).collect::<Vec<_>>();
+        LakeSoulTable::for_name(table_name)
+            .await?
+            .execute_upsert(create_batch_i32(vec!["range", "id", "value"], vec![&ids, &ids, &ids]))
+            .await
+    }
+
+    async fn test_concurrent_queries() -> Result<()> {
+        let table_name = "test_pool_concurrent_queries";
+        let client = Arc::new(MetaDataClient::from_env().await?.with_pool_size(2)?);
+        assert_eq!(client.pool_size(), 2);
+        let ctx = create_context(client.clone()).await?;
+        create_table(&ctx, table_name, 16).await?;
+        let table_id = client
+            .get_table_info_by_table_name(table_name, "default")
+            .await?
+            .table_id;
+
+        // more concurrent queries than connections, which wait for the connections in use
+        let results = futures::future::try_join_all((0..32).map(|_| client.get_all_partition_info(&table_id))).await?;
+        assert!(results
+            .iter()
+            .all(|partition_info_list| partition_info_list.len() == 16));
+
+        // a scan lists the partitions concurrently
+        let results = sql(
+            &ctx,
+            &format!("select count(*) as rows, sum(value) as total from lakesoul.default.{table_name}"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+------+-------+",
+                "| rows | total |",
+                "+------+-------+",
+                "| 16   | 120   |",
+                "+------+-------+",
+            ],
+            &results,
+        );
+        Ok(())
+    }
+
+    async fn test_single_connection() -> Result<()> {
+        let client = Arc::new(MetaDataClient::from_env().await?.with_pool_size(1)?);
+        let namespaces = futures::future::try_join_all((0..8).map(|_| client.get_all_namespace())).await?;
+        assert!(namespaces.iter().all(|namespace| namespace == &namespaces[0]));
+        assert!(MetaDataClient::from_env().await?.with_pool_size(0).is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_all_cases() -> Result<()> {
+        // only the postgres backend has a connection pool
+        let client = MetaDataClient::from_env().await?;
+        if !client.backend().as_any().is::<PostgresBackend>() {
+            return Ok(());
+        }
+        test_concurrent_queries().await?;
+        test_single_connection().await?;
+        Ok(())
+    }
+}
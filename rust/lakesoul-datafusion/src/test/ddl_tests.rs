@@ -0,0 +1,505 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod ddl_tests {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int32Array, Int64Array};
+    use arrow::record_batch::RecordBatch;
+    use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
+
+    use crate::catalog::{compaction, LakeSoulTableProperty};
+    use crate::error::Result;
+    use crate::lakesoul_table::LakeSoulTable;
+    use crate::planner::sql;
+    use crate::test::utils::{check_table, create_batch_i32, create_context};
+
+    async fn execute_sql(client: MetaDataClientRef, source: Option<RecordBatch>, sql_text: &str) -> Result<()> {
+        let sess_ctx = create_context(client).await?;
+        if let Some(source) = source {
+            sess_ctx.register_batch("source", source)?;
+        }
+        sql(&sess_ctx, sql_text).await?.collect().await?;
+        Ok(())
+    }
+
+    async fn test_create_table_with_partitions_and_options() -> Result<()> {
+        let table_name = "test_create_table_with_partitions_and_options";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        execute_sql(
+            client.clone(),
+            None,
+            &format!(
+                "create table lakesoul.default.{table_name} (range int, id int, value int) \
+                primary key (id) partitioned by (range) with (hash_bucket_num = 2)"
+            ),
+        )
+        .await?;
+
+        let table_info = client.get_table_info_by_table_name(table_name, "default").await?;
+        assert_eq!(table_info.partitions, "range;id");
+        let properties = serde_json::from_str::<LakeSoulTableProperty>(&table_info.properties)?;
+        assert_eq!(properties.hash_bucket_num, Some(2));
+        assert!(client
+            .get_table_name_id_by_table_name(table_name, "default")
+            .await
+            .is_ok());
+
+        execute_sql(
+            client.clone(),
+            Some(create_batch_i32(
+                vec!["range", "id", "value"],
+                vec![&[1, 1, 2], &[1, 2, 3], &[1, 2, 3]],
+            )),
+            &format!("insert into lakesoul.default.{table_name} partition (range) select id, value, range from source"),
+        )
+        .await?;
+        check_table(
+            client,
+            table_name,
+            vec!["range", "id", "value"],
+            &[
+                "+-------+----+-------+",
+                "| range | id | value |",
+                "+-------+----+-------+",
+                "| 1     | 1  | 1     |",
+                "| 1     | 2  | 2     |",
+                "| 2     | 3  | 3     |",
+                "+-------+----+-------+",
+            ],
+        )
+        .await
+    }
+
+    async fn test_create_table_if_not_exists() -> Result<()> {
+        let table_name = "test_create_table_if_not_exists";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let create = format!("create table lakesoul.default.{table_name} (id int primary key, value int)");
+        execute_sql(client.clone(), None, &create).await?;
+        assert!(execute_sql(client.clone(), None, &create).await.is_err());
+        execute_sql(
+            client.clone(),
+            None,
+            &format!("create table if not exists lakesoul.default.{table_name} (id int, value int)"),
+        )
+        .await?;
+
+        let table_info = client.get_table_info_by_table_name(table_name, "default").await?;
+        assert_eq!(table_info.partitions, ";id");
+        Ok(())
+    }
+
+    async fn test_create_table_as_select() -> Result<()> {
+        let table_name = "test_create_table_as_select";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        execute_sql(
+            client.clone(),
+            Some(create_batch_i32(
+                vec!["range", "id", "value"],
+                vec![&[1, 1, 2], &[1, 2, 3], &[1, 2, 3]],
+            )),
+            &format!(
+                "create table lakesoul.default.{table_name} primary key (id) partitioned by (range) \
+                as select range, id, value * 10 as value from source"
+            ),
+        )
+        .await?;
+
+        let table_info = client.get_table_info_by_table_name(table_name, "default").await?;
+        assert_eq!(table_info.partitions, "range;id");
+        check_table(
+            client,
+            table_name,
+            vec!["range", "id", "value"],
+            &[
+                "+-------+----+-------+",
+                "| range | id | value |",
+                "+-------+----+-------+",
+                "| 1     | 1  | 10    |",
+                "| 1     | 2  | 20    |",
+                "| 2     | 3  | 30    |",
+                "+-------+----+-------+",
+            ],
+        )
+        .await
+    }
+
+    async fn test_create_table_fails_with_unknown_partition_column() -> Result<()> {
+        let table_name = "test_create_table_fails_with_unknown_partition_column";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let result = execute_sql(
+            client.clone(),
+            None,
+            &format!("create table lakesoul.default.{table_name} (id int, value int) partitioned by (date)"),
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(client
+            .get_table_name_id_by_table_name(table_name, "default")
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    async fn test_create_table_as_select_fails_with_failing_query() -> Result<()> {
+        let table_name = "test_create_table_as_select_fails_with_failing_query";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let source = create_batch_i32(vec!["range", "id", "value"], vec![&[1, 1, 2], &[1, 2, 3], &[1, 2, 3]]);
+        // the division by zero of id 2 fails the query after the table is created
+        let result = execute_sql(
+            client.clone(),
+            Some(source.clone()),
+            &format!(
+                "create table lakesoul.default.{table_name} primary key (id) \
+                as select range, id, value / (id - 2) as value from source"
+            ),
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(client
+            .get_table_name_id_by_table_name(table_name, "default")
+            .await
+            .is_err());
+
+        // so the same table can be created again
+        execute_sql(
+            client.clone(),
+            Some(source),
+            &format!(
+                "create table lakesoul.default.{table_name} primary key (id) as select range, id, value from source"
+            ),
+        )
+        .await?;
+        check_table(
+            client,
+            table_name,
+            vec!["range", "id", "value"],
+            &[
+                "+-------+----+-------+",
+                "| range | id | value |",
+                "+-------+----+-------+",
+                "| 1     | 1  | 1     |",
+                "| 1     | 2  | 2     |",
+                "| 2     | 3  | 3     |",
+                "+-------+----+-------+",
+            ],
+        )
+        .await
+    }
+
+    async fn test_alter_table_add_and_drop_column() -> Result<()> {
+        let table_name = "test_alter_table_add_and_drop_column";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        execute_sql(
+            client.clone(),
+            None,
+            &format!("create table lakesoul.default.{table_name} (id int primary key, value int)"),
+        )
+        .await?;
+        LakeSoulTable::for_name(table_name)
+            .await?
+            .execute_upsert(create_batch_i32(vec!["id", "value"], vec![&[1, 2], &[1, 2]]))
+            .await?;
+
+        execute_sql(
+            client.clone(),
+            None,
+            &format!("alter table lakesoul.default.{table_name} add column extra int"),
+        )
+        .await?;
+        // a column can not be added twice
+        assert!(execute_sql(
+            client.clone(),
+            None,
+            &format!("alter table lakesoul.default.{table_name} add column extra int"),
+        )
+        .await
+        .is_err());
+        LakeSoulTable::for_name(table_name)
+            .await?
+            .execute_upsert(create_batch_i32(vec!["id", "value", "extra"], vec![&[3], &[3], &[30]]))
+            .await?;
+        check_table(
+            client.clone(),
+            table_name,
+            vec!["id", "value", "extra"],
+            &[
+                "+----+-------+-------+",
+                "| id | value | extra |",
+                "+----+-------+-------+",
+                "| 1  | 1     |       |",
+                "| 2  | 2     |       |",
+                "| 3  | 3     | 30    |",
+                "+----+-------+-------+",
+            ],
+        )
+        .await?;
+
+        execute_sql(
+            client.clone(),
+            None,
+            &format!("alter table lakesoul.default.{table_name} drop column value"),
+        )
+        .await?;
+        // primary keys can not be dropped
+        assert!(execute_sql(
+            client.clone(),
+            None,
+            &format!("alter table lakesoul.default.{table_name} drop column id"),
+        )
+        .await
+        .is_err());
+        // the name of a dropped column can not be used again, which would read the dropped values
+        assert!(execute_sql(
+            client.clone(),
+            None,
+            &format!("alter table lakesoul.default.{table_name} add column value int"),
+        )
+        .await
+        .is_err());
+        assert!(execute_sql(
+            client.clone(),
+            None,
+            &format!("alter table lakesoul.default.{table_name} rename column extra to value"),
+        )
+        .await
+        .is_err());
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        assert_eq!(lakesoul_table.schema().fields().len(), 2);
+        assert!(lakesoul_table.schema().field_with_name("value").is_err());
+        check_table(
+            client,
+            table_name,
+            vec!["id", "extra"],
+            &[
+                "+----+-------+",
+                "| id | extra |",
+                "+----+-------+",
+                "| 1  |       |",
+                "| 2  |       |",
+                "| 3  | 30    |",
+                "+----+-------+",
+            ],
+        )
+        .await
+    }
+
+    async fn test_alter_table_rename_column() -> Result<()> {
+        let table_name = "test_alter_table_rename_column";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        execute_sql(
+            client.clone(),
+            None,
+            &format!("create table lakesoul.default.{table_name} (id int primary key, value int)"),
+        )
+        .await?;
+        LakeSoulTable::for_name(table_name)
+            .await?
+            .execute_upsert(create_batch_i32(vec!["id", "value"], vec![&[1, 2], &[1, 2]]))
+            .await?;
+
+        execute_sql(
+            client.clone(),
+            None,
+            &format!("alter table lakesoul.default.{table_name} rename column value to amount"),
+        )
+        .await?;
+        LakeSoulTable::for_name(table_name)
+            .await?
+            .execute_upsert(create_batch_i32(vec!["id", "amount"], vec![&[2, 3], &[20, 30]]))
+            .await?;
+        check_table(
+            client.clone(),
+            table_name,
+            vec!["id", "amount"],
+            &[
+                "+----+--------+",
+                "| id | amount |",
+                "+----+--------+",
+                "| 1  | 1      |",
+                "| 2  | 20     |",
+                "| 3  | 30     |",
+                "+----+--------+",
+            ],
+        )
+        .await?;
+
+        execute_sql(
+            client.clone(),
+            None,
+            &format!("alter table lakesoul.default.{table_name} rename column amount to total"),
+        )
+        .await?;
+        // the previous names of a renamed column can not be used again
+        assert!(execute_sql(
+            client.clone(),
+            None,
+            &format!("alter table lakesoul.default.{table_name} add column value int"),
+        )
+        .await
+        .is_err());
+        check_table(
+            client,
+            table_name,
+            vec!["id", "total"],
+            &[
+                "+----+-------+",
+                "| id | total |",
+                "+----+-------+",
+                "| 1  | 1     |",
+                "| 2  | 20    |",
+                "| 3  | 30    |",
+                "+----+-------+",
+            ],
+        )
+        .await
+    }
+
+    /// Create table `table_name` of two upserts, and rename its column `value` to `amount`.
+    async fn init_renamed_table(client: MetaDataClientRef, table_name: &str) -> Result<()> {
+        execute_sql(
+            client.clone(),
+            None,
+            &format!("create table lakesoul.default.{table_name} (id int primary key, value int)"),
+        )
+        .await?;
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        lakesoul_table
+            .execute_upsert(create_batch_i32(vec!["id", "value"], vec![&[1, 2, 3], &[1, 2, 3]]))
+            .await?;
+        lakesoul_table
+            .execute_upsert(create_batch_i32(vec!["id", "value"], vec![&[2, 3, 4], &[20, 30, 40]]))
+            .await?;
+        execute_sql(
+            client,
+            None,
+            &format!("alter table lakesoul.default.{table_name} rename column value to amount"),
+        )
+        .await
+    }
+
+    async fn test_alter_table_rename_column_then_compact() -> Result<()> {
+        let table_name = "test_alter_table_rename_column_then_compact";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        init_renamed_table(client.clone(), table_name).await?;
+        // the compacted files are written by the current name
+        compaction(client.clone(), table_name, "").await?;
+        check_table(
+            client,
+            table_name,
+            vec!["id", "amount"],
+            &[
+                "+----+--------+",
+                "| id | amount |",
+                "+----+--------+",
+                "| 1  | 1      |",
+                "| 2  | 20     |",
+                "| 3  | 30     |",
+                "| 4  | 40     |",
+                "+----+--------+",
+            ],
+        )
+        .await
+    }
+
+    async fn test_alter_table_rename_column_then_delete() -> Result<()> {
+        let table_name = "test_alter_table_rename_column_then_delete";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        init_renamed_table(client.clone(), table_name).await?;
+        // the rewritten files keep the values of the renamed column
+        execute_sql(
+            client.clone(),
+            None,
+            &format!("delete from lakesoul.default.{table_name} where amount = 20"),
+        )
+        .await?;
+        check_table(
+            client,
+            table_name,
+            vec!["id", "amount"],
+            &[
+                "+----+--------+",
+                "| id | amount |",
+                "+----+--------+",
+                "| 1  | 1      |",
+                "| 3  | 30     |",
+                "| 4  | 40     |",
+                "+----+--------+",
+            ],
+        )
+        .await
+    }
+
+    async fn test_alter_table_widen_column_type() -> Result<()> {
+        let table_name = "test_alter_table_widen_column_type";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        execute_sql(
+            client.clone(),
+            None,
+            &format!("create table lakesoul.default.{table_name} (id int primary key, value int)"),
+        )
+        .await?;
+        LakeSoulTable::for_name(table_name)
+            .await?
+            .execute_upsert(create_batch_i32(vec!["id", "value"], vec![&[1, 2], &[1, 2]]))
+            .await?;
+
+        execute_sql(
+            client.clone(),
+            None,
+            &format!("alter table lakesoul.default.{table_name} alter column value set data type bigint"),
+        )
+        .await?;
+        for sql_text in [
+            // narrowing
+            format!("alter table lakesoul.default.{table_name} alter column value set data type int"),
+            format!("alter table lakesoul.default.{table_name} alter column value set data type varchar"),
+            // primary key
+            format!("alter table lakesoul.default.{table_name} alter column id set data type bigint"),
+        ] {
+            assert!(execute_sql(client.clone(), None, &sql_text).await.is_err());
+        }
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        assert_eq!(
+            lakesoul_table.schema().field_with_name("value")?.data_type(),
+            &arrow::datatypes::DataType::Int64
+        );
+        lakesoul_table
+            .execute_upsert(RecordBatch::try_from_iter_with_nullable(vec![
+                ("id", Arc::new(Int32Array::from(vec![3])) as ArrayRef, true),
+                ("value", Arc::new(Int64Array::from(vec![1i64 << 40])) as ArrayRef, true),
+            ])?)
+            .await?;
+        check_table(
+            client,
+            table_name,
+            vec!["id", "value"],
+            &[
+                "+----+---------------+",
+                "| id | value         |",
+                "+----+---------------+",
+                "| 1  | 1             |",
+                "| 2  | 2             |",
+                "| 3  | 1099511627776 |",
+                "+----+---------------+",
+            ],
+        )
+        .await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_all_cases() -> Result<()> {
+        test_create_table_with_partitions_and_options().await?;
+        test_create_table_if_not_exists().await?;
+        test_create_table_as_select().await?;
+        test_create_table_fails_with_unknown_partition_column().await?;
+        test_create_table_as_select_fails_with_failing_query().await?;
+        test_alter_table_add_and_drop_column().await?;
+        test_alter_table_rename_column().await?;
+        test_alter_table_rename_column_then_compact().await?;
+        test_alter_table_rename_column_then_delete().await?;
+        test_alter_table_widen_column_type().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod delete_tests {
+    use std::sync::Arc;
+
+    use arrow::array::AsArray;
+    use arrow::datatypes::UInt64Type;
+    use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
+
+    use crate::error::Result;
+    use crate::planner::sql;
+    use crate::test::utils::{check_table, create_batch_i32, create_context, execute_upsert, init_table};
+
+    /// Execute a `DELETE` statement, returning the number of deleted rows.
+    async fn execute_delete(client: MetaDataClientRef, sql_text: &str) -> Result<u64> {
+        let sess_ctx = create_context(client).await?;
+        let batches = sql(&sess_ctx, sql_text).await?.collect().await?;
+        Ok(batches
+            .iter()
+            .map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<UInt64Type>()
+                    .iter()
+                    .flatten()
+                    .sum::<u64>()
+            })
+            .sum())
+    }
+
+    async fn test_delete_partition() -> Result<()> {
+        let table_name = "test_delete_partition";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let record_batch = create_batch_i32(
+            vec!["range", "id", "value"],
+            vec![&[1, 1, 2, 2], &[1, 2, 3, 4], &[1, 2, 3, 4]],
+        );
+        init_table(client.clone(), record_batch.schema(), table_name, vec!["range"], vec![]).await?;
+        execute_upsert(record_batch, table_name).await?;
+
+        let deleted = execute_delete(
+            client.clone(),
+            &format!("delete from lakesoul.default.{table_name} where range = 1"),
+        )
+        .await?;
+        // the partition is dropped through metadata, whose rows are counted by a scan
+        assert_eq!(deleted, 2);
+
+        check_table(
+            client,
+            table_name,
+            vec!["range", "id", "value"],
+            &[
+                "+-------+----+-------+",
+                "| range | id | value |",
+                "+-------+----+-------+",
+                "| 2     | 3  | 3     |",
+                "| 2     | 4  | 4     |",
+                "+-------+----+-------+",
+            ],
+        )
+        .await
+    }
+
+    async fn test_delete_rows() -> Result<()> {
+        let table_name = "test_delete_rows";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let record_batch = create_batch_i32(
+            vec!["range", "id", "value"],
+            vec![&[1, 1, 2, 2], &[1, 2, 3, 4], &[1, 2, 3, 4]],
+        );
+        init_table(client.clone(), record_batch.schema(), table_name, vec!["range"], vec![]).await?;
+        execute_upsert(record_batch, table_name).await?;
+
+        let deleted = execute_delete(
+            client.clone(),
+            &format!("delete from lakesoul.default.{table_name} where range = 1 and value > 1"),
+        )
+        .await?;
+        assert_eq!(deleted, 1);
+
+        check_table(
+            client,
+            table_name,
+            vec!["range", "id", "value"],
+            &[
+                "+-------+----+-------+",
+                "| range | id | value |",
+                "+-------+----+-------+",
+                "| 1     | 1  | 1     |",
+                "| 2     | 3  | 3     |",
+                "| 2     | 4  | 4     |",
+                "+-------+----+-------+",
+            ],
+        )
+        .await
+    }
+
+    async fn test_delete_rows_of_primary_key_table() -> Result<()> {
+        let table_name = "test_delete_rows_of_primary_key_table";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let record_batch = create_batch_i32(vec!["id", "value"], vec![&[1, 2, 3, 4], &[1, 2, 3, 4]]);
+        init_table(client.clone(), record_batch.schema(), table_name, vec![], vec!["id"]).await?;
+        execute_upsert(record_batch, table_name).await?;
+        execute_upsert(
+            create_batch_i32(vec!["id", "value"], vec![&[2, 3], &[22, 33]]),
+            table_name,
+        )
+        .await?;
+
+        let deleted = execute_delete(
+            client.clone(),
+            &format!("delete from lakesoul.default.{table_name} where value = 22"),
+        )
+        .await?;
+        assert_eq!(deleted, 1);
+
+        check_table(
+            client,
+            table_name,
+            vec!["id", "value"],
+            &[
+                "+----+-------+",
+                "| id | value |",
+                "+----+-------+",
+                "| 1  | 1     |",
+                "| 3  | 33    |",
+                "| 4  | 4     |",
+                "+----+-------+",
+            ],
+        )
+        .await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_all_cases() -> Result<()> {
+        test_delete_partition().await?;
+        test_delete_rows().await?;
+        test_delete_rows_of_primary_key_table().await?;
+        Ok(())
+    }
+}
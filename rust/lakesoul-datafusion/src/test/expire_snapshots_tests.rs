@@ -0,0 +1,143 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod expire_snapshots_tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use arrow::record_batch::RecordBatch;
+    use lakesoul_io::lakesoul_io_config::create_session_context;
+    use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
+
+    use crate::catalog::{compaction, create_io_config_builder, expire_snapshots};
+    use crate::error::Result;
+    use crate::lakesoul_table::LakeSoulTable;
+    use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, init_table};
+
+    async fn do_upsert(record_batch: RecordBatch, table_name: &str) -> Result<()> {
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        lakesoul_table.execute_upsert(record_batch).await?;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        Ok(())
+    }
+
+    fn file_exists(path: &str) -> bool {
+        url::Url::parse(path).unwrap().to_file_path().unwrap().exists()
+    }
+
+    /// Read table `table_name` as of `version`, or the latest version if `None`.
+    async fn read_table(client: MetaDataClientRef, table_name: &str, version: Option<i32>) -> Result<Vec<RecordBatch>> {
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        let builder = create_io_config_builder(client, None, false, "default").await?;
+        let sess_ctx = create_session_context(&mut builder.clone().build())?;
+        let dataframe = match version {
+            Some(version) => lakesoul_table.to_dataframe_as_of_version(&sess_ctx, version).await?,
+            None => lakesoul_table.to_dataframe(&sess_ctx).await?,
+        };
+        Ok(dataframe.select_columns(&["range", "hash", "value"])?.collect().await?)
+    }
+
+    /// Upsert two versions into partition range=1 and compact them into the third version.
+    async fn create_table_with_compaction(client: MetaDataClientRef, table_name: &str) -> Result<()> {
+        let names = vec!["range", "hash", "value"];
+        let batch = create_batch_i32(names.clone(), vec![&[1, 1], &[1, 2], &[1, 2]]);
+        init_table(client.clone(), batch.schema(), table_name, vec!["range"], vec!["hash"]).await?;
+        do_upsert(batch, table_name).await?;
+        do_upsert(create_batch_i32(names, vec![&[1, 1], &[1, 3], &[11, 3]]), table_name).await?;
+        compaction(client, table_name, "").await?;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        Ok(())
+    }
+
+    const LATEST: &[&str] = &[
+        "+-------+------+-------+",
+        "| range | hash | value |",
+        "+-------+------+-------+",
+        "| 1     | 1    | 11    |",
+        "| 1     | 2    | 2     |",
+        "| 1     | 3    | 3     |",
+        "+-------+------+-------+",
+    ];
+
+    async fn test_expire_snapshots() -> Result<()> {
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let table_name = "test_expire_snapshots";
+        create_table_with_compaction(client.clone(), table_name).await?;
+        let table_info = client.get_table_info_by_table_name(table_name, "default").await?;
+        let latest_files = client.get_data_files_by_table_name(table_name, "default").await?;
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let expired = expire_snapshots(client.clone(), table_name, now, 1, false).await?;
+        assert_eq!(
+            expired.versions,
+            vec![("range=1".to_string(), 0), ("range=1".to_string(), 1)]
+        );
+        assert_eq!(expired.commits, 2);
+        assert!(!expired.files.is_empty());
+        assert!(expired.files.iter().all(|file| !latest_files.contains(file)));
+        assert!(expired.files.iter().all(|file| !file_exists(file)));
+        assert!(latest_files.iter().all(|file| file_exists(file)));
+
+        let versions = client
+            .get_all_versions_of_partition(&table_info.table_id, "range=1")
+            .await?;
+        assert_eq!(
+            versions.iter().map(|version| version.version).collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_batches_eq(table_name, LATEST, &read_table(client.clone(), table_name, None).await?);
+        // the expired versions can not be read anymore
+        let results = read_table(client.clone(), table_name, Some(0)).await?;
+        assert!(results.iter().all(|batch| batch.num_rows() == 0));
+
+        // nothing is left to expire
+        let expired = expire_snapshots(client, table_name, now, 1, false).await?;
+        assert!(expired.versions.is_empty() && expired.files.is_empty());
+        Ok(())
+    }
+
+    async fn test_expire_snapshots_dry_run() -> Result<()> {
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let table_name = "test_expire_snapshots_dry_run";
+        create_table_with_compaction(client.clone(), table_name).await?;
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let expired = expire_snapshots(client.clone(), table_name, now, 1, true).await?;
+        assert_eq!(expired.versions.len(), 2);
+        assert!(expired.files.iter().all(|file| file_exists(file)));
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 1    | 1     |",
+                "| 1     | 2    | 2     |",
+                "+-------+------+-------+",
+            ],
+            &read_table(client.clone(), table_name, Some(0)).await?,
+        );
+
+        // the retained versions are not expired
+        let expired = expire_snapshots(client.clone(), table_name, now, 3, true).await?;
+        assert!(expired.versions.is_empty());
+        let expired = expire_snapshots(client.clone(), table_name, 0, 1, true).await?;
+        assert!(expired.versions.is_empty());
+        // the version 1 is retained, which references the commit of version 0
+        let expired = expire_snapshots(client.clone(), table_name, now, 2, true).await?;
+        assert_eq!(expired.versions, vec![("range=1".to_string(), 0)]);
+        assert_eq!(expired.commits, 0);
+        assert!(expired.files.is_empty());
+        assert_batches_eq(table_name, LATEST, &read_table(client, table_name, None).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_all_cases() -> Result<()> {
+        test_expire_snapshots().await?;
+        test_expire_snapshots_dry_run().await?;
+        Ok(())
+    }
+}
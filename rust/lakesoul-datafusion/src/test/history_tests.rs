@@ -0,0 +1,192 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod history_tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use datafusion::execution::context::SessionContext;
+    use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
+    use proto::proto::entity::CommitOp;
+
+    use crate::catalog::compaction;
+    use crate::error::Result;
+    use crate::lakesoul_table::LakeSoulTable;
+    use crate::planner::sql;
+    use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, create_context};
+
+    /// Create table `table_name` partitioned by `range` with primary key `id`, upsert three versions
+    /// and compact range=1, returns the timestamps in milliseconds after each commit.
+    async fn create_table_with_history(
+        client: MetaDataClientRef,
+        ctx: &SessionContext,
+        table_name: &str,
+    ) -> Result<Vec<i64>> {
+        sql(
+            ctx,
+            &format!(
+                "create table lakesoul.default.{table_name} (range int, id int, value int) \
+                primary key (id) partitioned by (range)"
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        let names = vec!["range", "id", "value"];
+        let mut timestamps = vec![];
+        for batch in [
+            create_batch_i32(names.clone(), vec![&[1, 1, 2], &[1, 2, 3], &[1, 2, 3]]),
+            create_batch_i32(names.clone(), vec![&[1], &[1], &[10]]),
+            create_batch_i32(names, vec![&[3], &[4], &[4]]),
+        ] {
+            lakesoul_table.execute_upsert(batch).await?;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            timestamps.push(chrono::Utc::now().timestamp_millis());
+        }
+        compaction(client, table_name, "range=1").await?;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        timestamps.push(chrono::Utc::now().timestamp_millis());
+        Ok(timestamps)
+    }
+
+    async fn test_list_commit_history() -> Result<()> {
+        let table_name = "test_list_commit_history";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let ctx = create_context(client.clone()).await?;
+        let timestamps = create_table_with_history(client.clone(), &ctx, table_name).await?;
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+
+        let history = lakesoul_table.history(None, None, None).await?;
+        assert_eq!(history.len(), 5);
+        // from the latest to the earliest
+        assert_eq!(history[0].partition_desc, "range=1");
+        assert_eq!(history[0].version, 2);
+        assert_eq!(history[0].commit_op, CommitOp::CompactionCommit);
+        assert_eq!(history[1].partition_desc, "range=3");
+        assert_eq!(history[1].commit_op, CommitOp::AppendCommit);
+        assert!(history.windows(2).all(|pair| pair[0].timestamp >= pair[1].timestamp));
+        assert!(history
+            .iter()
+            .all(|commit| commit.num_commits == 1 && commit.num_files > 0));
+
+        let history = lakesoul_table.history(Some("range=1"), None, None).await?;
+        assert_eq!(
+            history.iter().map(|commit| commit.version).collect::<Vec<_>>(),
+            vec![2, 1, 0]
+        );
+
+        // the versions committed from the second upsert until the compaction
+        let history = lakesoul_table
+            .history(None, Some(timestamps[0]), Some(timestamps[2]))
+            .await?;
+        assert_eq!(
+            history
+                .iter()
+                .map(|commit| (commit.partition_desc.as_str(), commit.version))
+                .collect::<Vec<_>>(),
+            vec![("range=3", 0), ("range=1", 1)]
+        );
+        assert!(lakesoul_table
+            .history(Some("range=2"), Some(timestamps[3]), None)
+            .await?
+            .is_empty());
+        Ok(())
+    }
+
+    async fn test_history_table_function() -> Result<()> {
+        let table_name = "test_history_table_function";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let ctx = create_context(client.clone()).await?;
+        let timestamps = create_table_with_history(client, &ctx, table_name).await?;
+
+        let results = sql(
+            &ctx,
+            &format!(
+                "select partition_desc, version, commit_op, num_commits \
+                from lakesoul_history('lakesoul.default.{table_name}')"
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----------------+---------+------------------+-------------+",
+                "| partition_desc | version | commit_op        | num_commits |",
+                "+----------------+---------+------------------+-------------+",
+                "| range=1        | 0       | AppendCommit     | 1           |",
+                "| range=1        | 1       | AppendCommit     | 1           |",
+                "| range=1        | 2       | CompactionCommit | 1           |",
+                "| range=2        | 0       | AppendCommit     | 1           |",
+                "| range=3        | 0       | AppendCommit     | 1           |",
+                "+----------------+---------+------------------+-------------+",
+            ],
+            &results,
+        );
+
+        let results = sql(
+            &ctx,
+            &format!(
+                "select partition_desc, version, commit_op \
+                from lakesoul_history('lakesoul.default.{table_name}', NULL, to_timestamp_millis({}))",
+                timestamps[2]
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----------------+---------+------------------+",
+                "| partition_desc | version | commit_op        |",
+                "+----------------+---------+------------------+",
+                "| range=1        | 2       | CompactionCommit |",
+                "+----------------+---------+------------------+",
+            ],
+            &results,
+        );
+
+        let results = sql(
+            &ctx,
+            &format!(
+                "select count(*) as versions \
+                from lakesoul_history('lakesoul.default.{table_name}', 'range=1', NULL, to_timestamp_millis({}))",
+                timestamps[1]
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----------+",
+                "| versions |",
+                "+----------+",
+                "| 2        |",
+                "+----------+",
+            ],
+            &results,
+        );
+
+        assert!(sql(
+            &ctx,
+            &format!("select * from lakesoul_history('lakesoul.default.{table_name}', 'range=1', 0)")
+        )
+        .await
+        .is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_all_cases() -> Result<()> {
+        test_list_commit_history().await?;
+        test_history_table_function().await?;
+        Ok(())
+    }
+}
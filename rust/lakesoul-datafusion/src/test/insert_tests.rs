@@ -14,22 +14,21 @@ mod insert_tests {
     };
     use datafusion::logical_expr::Expr;
     use datafusion::prelude::col;
-    use lakesoul_io::lakesoul_io_config::{create_session_context, LakeSoulIOConfigBuilder};
+    use lakesoul_io::lakesoul_io_config::{
+        create_session_context, create_session_context_with_planner, LakeSoulIOConfigBuilder,
+    };
     use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
 
     use crate::lakesoul_table::LakeSoulTable;
+    use crate::planner::query_planner::LakeSoulQueryPlanner;
+    use crate::planner::sql;
     use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, execute_sql, execute_upsert, init_table};
     use crate::{
-        catalog::{create_io_config_builder, create_table},
+        catalog::{create_io_config_builder, create_table, LakeSoulCatalog},
         error::Result,
     };
 
-    async fn init_table(client: MetaDataClientRef, schema: SchemaRef, table_name: &str) -> Result<()> {
-        let builder = LakeSoulIOConfigBuilder::new().with_schema(schema.clone());
-        // .with_primary_keys(pks);
-        create_table(client, table_name, builder.build()).await
-    }
-
     async fn init_partitioned_table(
         client: MetaDataClientRef,
         schema: SchemaRef,
@@ -44,9 +43,16 @@ mod insert_tests {
         create_table(client, table_name, builder.build()).await
     }
 
-    async fn do_insert(record_batch: RecordBatch, table_name: &str) -> Result<()> {
-        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
-        lakesoul_table.execute_upsert(record_batch).await
+    async fn do_insert_overwrite(client: MetaDataClientRef, source: RecordBatch, sql_text: &str) -> Result<()> {
+        let builder = create_io_config_builder(client.clone(), None, false, "default").await?;
+        let sess_ctx = Arc::new(create_session_context_with_planner(
+            &mut builder.build(),
+            Some(LakeSoulQueryPlanner::new_ref()),
+        )?);
+        sess_ctx.register_catalog("lakesoul", Arc::new(LakeSoulCatalog::new(client, sess_ctx.clone())));
+        sess_ctx.register_batch("source", source)?;
+        sql(&sess_ctx, sql_text).await?.collect().await?;
+        Ok(())
     }
 
     async fn check_insert(
@@ -83,25 +89,12 @@ mod insert_tests {
         Ok(())
     }
 
-    fn create_batch_i32(names: Vec<&str>, values: Vec<&[i32]>) -> RecordBatch {
-        let values: Vec<Arc<dyn Array>> = values
-            .into_iter()
-            .map(|vec| Arc::new(Int32Array::from(Vec::from(vec))) as ArrayRef)
-            .collect::<Vec<ArrayRef>>();
-        let iter = names
-            .into_iter()
-            .zip(values)
-            .map(|(name, array)| (name, array, true))
-            .collect::<Vec<_>>();
-        RecordBatch::try_from_iter_with_nullable(iter).unwrap()
-    }
-
     async fn test_insert_into_append() -> Result<()> {
         let table_name = "test_insert_into_append";
         let client = Arc::new(MetaDataClient::from_env().await?);
         let record_batch = create_batch_i32(vec!["id", "data"], vec![&[1, 2, 3], &[1, 2, 3]]);
-        init_table(client.clone(), record_batch.schema(), table_name).await?;
-        do_insert(record_batch, table_name).await?;
+        init_table(client.clone(), record_batch.schema(), table_name, vec![], vec![]).await?;
+        execute_upsert(record_batch, table_name).await?;
         check_insert(
             client.clone(),
             table_name,
@@ -124,8 +117,8 @@ mod insert_tests {
         let table_name = "test_insert_into_append_by_position";
         let client = Arc::new(MetaDataClient::from_env().await?);
         let record_batch = create_batch_i32(vec!["id", "data"], vec![&[1, 2, 3], &[1, 2, 3]]);
-        init_table(client.clone(), record_batch.schema(), table_name).await?;
-        do_insert(record_batch, table_name).await?;
+        init_table(client.clone(), record_batch.schema(), table_name, vec![], vec![]).await?;
+        execute_upsert(record_batch, table_name).await?;
         check_insert(
             client.clone(),
             table_name,
@@ -149,7 +142,7 @@ mod insert_tests {
         let client = Arc::new(MetaDataClient::from_env().await?);
         let record_batch = create_batch_i32(vec!["id", "data"], vec![&[1, 2, 3], &[1, 2, 3]]);
         init_partitioned_table(client.clone(), record_batch.schema(), table_name, vec!["id"]).await?;
-        do_insert(record_batch, table_name).await?;
+        execute_upsert(record_batch, table_name).await?;
         check_insert(
             client.clone(),
             table_name,
@@ -172,8 +165,8 @@ mod insert_tests {
         let table_name = "test_insert_into_append_non_partitioned_table_and_read_with_filter";
         let client = Arc::new(MetaDataClient::from_env().await?);
         let record_batch = create_batch_i32(vec!["id", "data"], vec![&[1, 2, 3], &[1, 2, 3]]);
-        init_table(client.clone(), record_batch.schema(), table_name).await?;
-        do_insert(record_batch, table_name).await?;
+        init_table(client.clone(), record_batch.schema(), table_name, vec![], vec![]).await?;
+        execute_upsert(record_batch, table_name).await?;
         check_insert(
             client.clone(),
             table_name,
@@ -196,7 +189,7 @@ mod insert_tests {
         let client = Arc::new(MetaDataClient::from_env().await?);
         let record_batch = create_batch_i32(vec!["id", "data"], vec![&[1, 2, 3], &[1, 2, 3]]);
         init_partitioned_table(client.clone(), record_batch.schema(), table_name, vec!["id"]).await?;
-        do_insert(record_batch, table_name).await?;
+        execute_upsert(record_batch, table_name).await?;
         check_insert(
             client.clone(),
             table_name,
@@ -214,18 +207,16 @@ mod insert_tests {
         .await
     }
 
-    // todo: insert_overwrite is not supported by datafusion 27.0
-    // #[tokio::test]
     async fn test_insert_into_overwrite_non_partitioned_table() -> Result<()> {
         let table_name = "test_insert_into_overwrite_non_partitioned_table";
         let client = Arc::new(MetaDataClient::from_env().await?);
         let record_batch = create_batch_i32(vec!["id", "data"], vec![&[1, 2, 3], &[1, 2, 3]]);
-        init_table(client.clone(), record_batch.schema(), table_name).await?;
-        do_insert(record_batch, table_name).await?;
-        // todo: should do_insert_overwrite
-        do_insert(
+        init_table(client.clone(), record_batch.schema(), table_name, vec![], vec![]).await?;
+        execute_upsert(record_batch, table_name).await?;
+        do_insert_overwrite(
+            client.clone(),
             create_batch_i32(vec!["id", "data"], vec![&[4, 5, 6], &[4, 5, 6]]),
-            table_name,
+            &format!("insert overwrite lakesoul.default.{table_name} select * from source"),
         )
         .await?;
         check_insert(
@@ -246,6 +237,165 @@ mod insert_tests {
         .await
     }
 
+    async fn test_insert_overwrite_partitioned_table() -> Result<()> {
+        let table_name = "test_insert_overwrite_partitioned_table";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let record_batch = create_batch_i32(
+            vec!["id", "data", "range"],
+            vec![&[1, 2, 3, 4], &[1, 2, 3, 4], &[1, 1, 2, 3]],
+        );
+        init_partitioned_table(client.clone(), record_batch.schema(), table_name, vec!["range"]).await?;
+        execute_upsert(record_batch, table_name).await?;
+        do_insert_overwrite(
+            client.clone(),
+            create_batch_i32(vec!["id", "data", "range"], vec![&[5, 6], &[5, 6], &[2, 4]]),
+            &format!("insert overwrite lakesoul.default.{table_name} select * from source"),
+        )
+        .await?;
+        check_insert(
+            client.clone(),
+            table_name,
+            vec!["range", "id", "data"],
+            None,
+            &[
+                "+-------+----+------+",
+                "| range | id | data |",
+                "+-------+----+------+",
+                "| 2     | 5  | 5    |",
+                "| 4     | 6  | 6    |",
+                "+-------+----+------+",
+            ],
+        )
+        .await
+    }
+
+    async fn test_insert_overwrite_static_partition() -> Result<()> {
+        let table_name = "test_insert_overwrite_static_partition";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let record_batch = create_batch_i32(
+            vec!["range", "id", "data"],
+            vec![&[1, 1, 2, 3], &[1, 2, 3, 4], &[1, 2, 3, 4]],
+        );
+        init_partitioned_table(client.clone(), record_batch.schema(), table_name, vec!["range"]).await?;
+        execute_upsert(record_batch, table_name).await?;
+        do_insert_overwrite(
+            client.clone(),
+            create_batch_i32(vec!["id", "data"], vec![&[5, 6], &[5, 6]]),
+            &format!("insert overwrite table lakesoul.default.{table_name} partition (range=1) select * from source"),
+        )
+        .await?;
+        // the overwritten partition becomes empty if nothing is inserted
+        do_insert_overwrite(
+            client.clone(),
+            create_batch_i32(vec!["id", "data"], vec![&[7], &[7]]),
+            &format!(
+                "insert overwrite table lakesoul.default.{table_name} partition (range=3) select * from source where id < 0"
+            ),
+        )
+        .await?;
+        check_insert(
+            client.clone(),
+            table_name,
+            vec!["range", "id", "data"],
+            None,
+            &[
+                "+-------+----+------+",
+                "| range | id | data |",
+                "+-------+----+------+",
+                "| 1     | 5  | 5    |",
+                "| 1     | 6  | 6    |",
+                "| 2     | 3  | 3    |",
+                "+-------+----+------+",
+            ],
+        )
+        .await
+    }
+
+    async fn test_insert_overwrite_dynamic_partition() -> Result<()> {
+        let table_name = "test_insert_overwrite_dynamic_partition";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let record_batch = create_batch_i32(
+            vec!["range", "id", "data"],
+            vec![&[1, 1, 2, 3], &[1, 2, 3, 4], &[1, 2, 3, 4]],
+        );
+        init_partitioned_table(client.clone(), record_batch.schema(), table_name, vec!["range"]).await?;
+        execute_upsert(record_batch, table_name).await?;
+        do_insert_overwrite(
+            client.clone(),
+            create_batch_i32(vec!["id", "data", "range"], vec![&[5, 6], &[5, 6], &[2, 4]]),
+            &format!("insert overwrite table lakesoul.default.{table_name} partition (range) select * from source"),
+        )
+        .await?;
+        check_insert(
+            client.clone(),
+            table_name,
+            vec!["range", "id", "data"],
+            None,
+            &[
+                "+-------+----+------+",
+                "| range | id | data |",
+                "+-------+----+------+",
+                "| 1     | 1  | 1    |",
+                "| 1     | 2  | 2    |",
+                "| 2     | 5  | 5    |",
+                "| 3     | 4  | 4    |",
+                "| 4     | 6  | 6    |",
+                "+-------+----+------+",
+            ],
+        )
+        .await
+    }
+
+    async fn test_insert_overwrite_partition_emptied_by_delete() -> Result<()> {
+        let table_name = "test_insert_overwrite_partition_emptied_by_delete";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let record_batch = create_batch_i32(
+            vec!["range", "id", "data"],
+            vec![&[1, 1, 2, 3], &[1, 2, 3, 4], &[1, 2, 3, 4]],
+        );
+        init_partitioned_table(client.clone(), record_batch.schema(), table_name, vec!["range"]).await?;
+        execute_upsert(record_batch, table_name).await?;
+        execute_sql(
+            client.clone(),
+            &format!("delete from lakesoul.default.{table_name} where range = 1"),
+        )
+        .await?;
+        do_insert_overwrite(
+            client.clone(),
+            create_batch_i32(vec!["id", "data"], vec![&[5, 6], &[5, 6]]),
+            &format!("insert overwrite table lakesoul.default.{table_name} partition (range=1) select * from source"),
+        )
+        .await?;
+        execute_sql(
+            client.clone(),
+            &format!("delete from lakesoul.default.{table_name} where range = 2"),
+        )
+        .await?;
+        do_insert_overwrite(
+            client.clone(),
+            create_batch_i32(vec!["id", "data", "range"], vec![&[7], &[7], &[2]]),
+            &format!("insert overwrite table lakesoul.default.{table_name} partition (range) select * from source"),
+        )
+        .await?;
+        check_insert(
+            client.clone(),
+            table_name,
+            vec!["range", "id", "data"],
+            None,
+            &[
+                "+-------+----+------+",
+                "| range | id | data |",
+                "+-------+----+------+",
+                "| 1     | 5  | 5    |",
+                "| 1     | 6  | 6    |",
+                "| 2     | 7  | 7    |",
+                "| 3     | 4  | 4    |",
+                "+-------+----+------+",
+            ],
+        )
+        .await
+    }
+
     async fn test_insert_into_fails_when_missing_a_column() -> Result<()> {
         let table_name = "test_insert_into_fails_when_missing_a_column";
         let client = Arc::new(MetaDataClient::from_env().await?);
@@ -259,9 +409,11 @@ mod insert_tests {
                     .collect::<Vec<Field>>(),
             )),
             table_name,
+            vec![],
+            vec![],
         )
         .await?;
-        match do_insert(record_batch, table_name).await {
+        match execute_upsert(record_batch, table_name).await {
             Err(e) => {
                 dbg!(&e);
                 Ok(())
@@ -290,9 +442,11 @@ mod insert_tests {
                     .collect::<Vec<Field>>(),
             )),
             table_name,
+            vec![],
+            vec![],
         )
         .await?;
-        match do_insert(record_batch, table_name).await {
+        match execute_upsert(record_batch, table_name).await {
             Err(e) => {
                 dbg!(&e);
                 Ok(())
@@ -501,8 +655,8 @@ mod insert_tests {
             // ("UInt64Dictionary", Arc::new(UInt64DictionaryArray::from_iter([Some("a"), None])) as ArrayRef, true),
         ];
         let record_batch = RecordBatch::try_from_iter_with_nullable(iter).unwrap();
-        init_table(client.clone(), record_batch.schema(), table_name).await?;
-        do_insert(record_batch, table_name).await?;
+        init_table(client.clone(), record_batch.schema(), table_name, vec![], vec![]).await?;
+        execute_upsert(record_batch, table_name).await?;
         check_insert(client.clone(), table_name, vec![], None, &[
             "+---------+--------+------------+---------------------+---------------+--------------+-----------------+---------------+---------+---------+------+-------+-------+-------+--------------------+------+-------------+-------------+-----------+--------+-------------------+-------------------+--------------+-------------------+--------------------+----------------------------+-------------------------+-------------------------------+---------------------+-------+--------+--------+--------+",
             "| Boolean | Binary | Date32     | Date64              | Decimal128    | Decimal256   | FixedSizeBinary | FixedSizeList | Float32 | Float64 | Int8 | Int16 | Int32 | Int64 | Map                | Null | LargeBinary | LargeString | List      | String | Struct            | Time32Millisecond | Time32Second | Time64Microsecond | Time64Nanosecond   | TimestampMicrosecond       | TimestampMillisecond    | TimestampNanosecond           | TimestampSecond     | UInt8 | UInt16 | UInt32 | UInt64 |",
@@ -514,7 +668,7 @@ mod insert_tests {
     }
 
     // #[tokio::test]
-    #[test_log::test(tokio::test)]
+    #[test_log::test(tokio::test(flavor = "multi_thread"))]
     async fn test_all_cases() -> Result<()> {
         test_insert_into_append().await?;
         test_insert_into_append_by_position().await?;
@@ -528,9 +682,11 @@ mod insert_tests {
         test_datatypes().await?;
 
         // overwrite case
-        // todo: insert_overwrite is not supported by datafusion 27.0
-
-        // test_insert_into_overwrite_non_partitioned_table().await?;
+        test_insert_into_overwrite_non_partitioned_table().await?;
+        test_insert_overwrite_partitioned_table().await?;
+        test_insert_overwrite_static_partition().await?;
+        test_insert_overwrite_dynamic_partition().await?;
+        test_insert_overwrite_partition_emptied_by_delete().await?;
         // test_insert_into_overwrite_by_position().await?;
 
         // todo:
@@ -0,0 +1,124 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod memory_backend_tests {
+    use std::sync::Arc;
+
+    use lakesoul_metadata::{MemoryBackend, MetaDataClient};
+
+    use crate::error::Result;
+    use crate::planner::sql;
+    use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, create_context};
+
+    async fn test_create_insert_select() -> Result<()> {
+        let table_name = "test_memory_backend_create_insert_select";
+        let client = Arc::new(MetaDataClient::from_backend(Arc::new(MemoryBackend::new())));
+        let ctx = create_context(client.clone()).await?;
+        ctx.register_batch(
+            "source",
+            create_batch_i32(vec!["range", "id", "value"], vec![&[1, 1, 2], &[1, 2, 3], &[1, 2, 3]]),
+        )?;
+        sql(
+            &ctx,
+            &format!(
+                "create table lakesoul.default.{table_name} (range int, id int, value int) \
+                primary key (id) partitioned by (range)"
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        for _ in 0..2 {
+            sql(
+                &ctx,
+                &format!("insert into lakesoul.default.{table_name} select range, id, value from source"),
+            )
+            .await?
+            .collect()
+            .await?;
+        }
+
+        // the metadata is only in the memory backend of this client
+        let table_id = client
+            .get_table_info_by_table_name(table_name, "default")
+            .await?
+            .table_id;
+        assert_eq!(client.get_all_partition_info(&table_id).await?.len(), 2);
+        assert!(MetaDataClient::from_env()
+            .await?
+            .get_table_info_by_table_name(table_name, "default")
+            .await
+            .is_err());
+
+        let results = sql(
+            &ctx,
+            &format!("select range, id, value from lakesoul.default.{table_name}"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+----+-------+",
+                "| range | id | value |",
+                "+-------+----+-------+",
+                "| 1     | 1  | 1     |",
+                "| 1     | 2  | 2     |",
+                "| 2     | 3  | 3     |",
+                "+-------+----+-------+",
+            ],
+            &results,
+        );
+        Ok(())
+    }
+
+    /// DELETE and UPDATE are planned with the client of the catalog instead of a client from the environment.
+    async fn test_delete_and_update() -> Result<()> {
+        let table_name = "test_memory_backend_delete_and_update";
+        let client = Arc::new(MetaDataClient::from_backend(Arc::new(MemoryBackend::new())));
+        let ctx = create_context(client.clone()).await?;
+        ctx.register_batch(
+            "source",
+            create_batch_i32(vec!["range", "id", "value"], vec![&[1, 1, 2], &[1, 2, 3], &[1, 2, 3]]),
+        )?;
+        for sql_text in [
+            format!("create table lakesoul.default.{table_name} (range int, id int, value int) partitioned by (range)"),
+            format!("insert into lakesoul.default.{table_name} select range, id, value from source"),
+            format!("delete from lakesoul.default.{table_name} where id = 1"),
+            format!("update lakesoul.default.{table_name} set value = 30 where id = 3"),
+        ] {
+            sql(&ctx, &sql_text).await?.collect().await?;
+        }
+
+        let results = sql(
+            &ctx,
+            &format!("select range, id, value from lakesoul.default.{table_name}"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+----+-------+",
+                "| range | id | value |",
+                "+-------+----+-------+",
+                "| 1     | 2  | 2     |",
+                "| 2     | 3  | 30    |",
+                "+-------+----+-------+",
+            ],
+            &results,
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_all_cases() -> Result<()> {
+        test_create_insert_select().await?;
+        test_delete_and_update().await?;
+        Ok(())
+    }
+}
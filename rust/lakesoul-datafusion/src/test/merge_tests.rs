@@ -0,0 +1,226 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod merge_tests {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int32Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::execution::context::SessionContext;
+    use datafusion::physical_plan::collect;
+    use lakesoul_io::lakesoul_io_config::create_session_context_with_planner;
+    use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
+
+    use crate::catalog::{create_io_config_builder, LakeSoulCatalog};
+    use crate::error::Result;
+    use crate::planner::query_planner::LakeSoulQueryPlanner;
+    use crate::planner::sql;
+    use crate::test::utils::{check_table, create_batch_i32, execute_upsert, init_table};
+
+    async fn merge_context(client: MetaDataClientRef, source: RecordBatch) -> Result<Arc<SessionContext>> {
+        let builder = create_io_config_builder(client.clone(), None, false, "default").await?;
+        let sess_ctx = Arc::new(create_session_context_with_planner(
+            &mut builder.build(),
+            Some(LakeSoulQueryPlanner::new_ref()),
+        )?);
+        sess_ctx.register_catalog("lakesoul", Arc::new(LakeSoulCatalog::new(client, sess_ctx.clone())));
+        sess_ctx.register_batch("source", source)?;
+        Ok(sess_ctx)
+    }
+
+    async fn execute_merge(client: MetaDataClientRef, source: RecordBatch, sql: &str) -> Result<()> {
+        let sess_ctx = merge_context(client, source).await?;
+        sql(&sess_ctx, sql).await?.collect().await?;
+        Ok(())
+    }
+
+    fn create_source_batch(ids: &[i32], values: &[i32], ops: &[&str]) -> RecordBatch {
+        RecordBatch::try_from_iter_with_nullable(vec![
+            ("id", Arc::new(Int32Array::from(Vec::from(ids))) as ArrayRef, true),
+            ("value", Arc::new(Int32Array::from(Vec::from(values))) as ArrayRef, true),
+            ("op", Arc::new(StringArray::from(Vec::from(ops))) as ArrayRef, true),
+        ])
+        .unwrap()
+    }
+
+    async fn test_merge_primary_key_table() -> Result<()> {
+        let table_name = "test_merge_primary_key_table";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let record_batch = create_batch_i32(vec!["id", "value"], vec![&[1, 2, 3, 4], &[1, 2, 3, 4]]);
+        init_table(client.clone(), record_batch.schema(), table_name, vec![], vec!["id"]).await?;
+        execute_upsert(record_batch, table_name).await?;
+
+        execute_merge(
+            client.clone(),
+            create_source_batch(
+                &[2, 3, 5, 6],
+                &[22, 33, 55, 66],
+                &["update", "delete", "insert", "ignore"],
+            ),
+            &format!(
+                "merge into lakesoul.default.{table_name} as t using source as s on t.id = s.id \
+                when matched and s.op = 'delete' then delete \
+                when matched then update set value = s.value \
+                when not matched and s.op = 'insert' then insert (id, value) values (s.id, s.value)"
+            ),
+        )
+        .await?;
+
+        check_table(
+            client,
+            table_name,
+            vec!["id", "value"],
+            &[
+                "+----+-------+",
+                "| id | value |",
+                "+----+-------+",
+                "| 1  | 1     |",
+                "| 2  | 22    |",
+                "| 4  | 4     |",
+                "| 5  | 55    |",
+                "+----+-------+",
+            ],
+        )
+        .await
+    }
+
+    async fn test_merge_range_partitioned_table() -> Result<()> {
+        let table_name = "test_merge_range_partitioned_table";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let record_batch = create_batch_i32(
+            vec!["range", "id", "value"],
+            vec![&[1, 1, 2, 2], &[1, 2, 3, 4], &[1, 2, 3, 4]],
+        );
+        init_table(client.clone(), record_batch.schema(), table_name, vec!["range"], vec![]).await?;
+        execute_upsert(record_batch, table_name).await?;
+
+        execute_merge(
+            client.clone(),
+            create_source_batch(&[1, 4, 5], &[11, 44, 55], &["update", "delete", "insert"]),
+            &format!(
+                "merge into lakesoul.default.{table_name} as t using source as s on t.id = s.id \
+                when matched and s.op = 'delete' then delete \
+                when matched then update set value = t.value + s.value \
+                when not matched then insert values (3, s.id, s.value)"
+            ),
+        )
+        .await?;
+
+        check_table(
+            client,
+            table_name,
+            vec!["range", "id", "value"],
+            &[
+                "+-------+----+-------+",
+                "| range | id | value |",
+                "+-------+----+-------+",
+                "| 1     | 1  | 12    |",
+                "| 1     | 2  | 2     |",
+                "| 2     | 3  | 3     |",
+                "| 3     | 5  | 55    |",
+                "+-------+----+-------+",
+            ],
+        )
+        .await
+    }
+
+    /// The rows appended after the merge is planned are kept, as the rewritten partition is committed
+    /// against the version read by the merge.
+    async fn test_merge_keeps_append_after_planning() -> Result<()> {
+        let table_name = "test_merge_keeps_append_after_planning";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let record_batch = create_batch_i32(vec!["range", "id", "value"], vec![&[1, 1], &[1, 2], &[1, 2]]);
+        init_table(client.clone(), record_batch.schema(), table_name, vec!["range"], vec![]).await?;
+        execute_upsert(record_batch, table_name).await?;
+
+        let sess_ctx = merge_context(
+            client.clone(),
+            create_source_batch(&[1, 2], &[11, 0], &["update", "delete"]),
+        )
+        .await?;
+        let plan = sql(
+            &sess_ctx,
+            &format!(
+                "merge into lakesoul.default.{table_name} as t using source as s on t.id = s.id \
+                when matched and s.op = 'delete' then delete \
+                when matched then update set value = s.value"
+            ),
+        )
+        .await?
+        .create_physical_plan()
+        .await?;
+        execute_upsert(
+            create_batch_i32(vec!["range", "id", "value"], vec![&[1], &[3], &[3]]),
+            table_name,
+        )
+        .await?;
+        collect(plan, sess_ctx.task_ctx()).await?;
+
+        check_table(
+            client,
+            table_name,
+            vec!["range", "id", "value"],
+            &[
+                "+-------+----+-------+",
+                "| range | id | value |",
+                "+-------+----+-------+",
+                "| 1     | 1  | 11    |",
+                "| 1     | 3  | 3     |",
+                "+-------+----+-------+",
+            ],
+        )
+        .await
+    }
+
+    /// A target row matched by several source rows can be neither updated nor deleted, with or without primary keys.
+    async fn test_merge_fails_with_multiple_matches() -> Result<()> {
+        for (table_name, primary_keys) in [
+            ("test_merge_fails_with_multiple_matches", vec!["id"]),
+            ("test_merge_fails_with_multiple_matches_without_pk", vec![]),
+        ] {
+            let client = Arc::new(MetaDataClient::from_env().await?);
+            let record_batch = create_batch_i32(vec!["id", "value"], vec![&[1, 2], &[1, 2]]);
+            init_table(client.clone(), record_batch.schema(), table_name, vec![], primary_keys).await?;
+            execute_upsert(record_batch, table_name).await?;
+
+            for action in ["update set value = s.value", "delete"] {
+                let result = execute_merge(
+                    client.clone(),
+                    create_source_batch(&[2, 2], &[22, 222], &["update", "update"]),
+                    &format!(
+                        "merge into lakesoul.default.{table_name} as t using source as s on t.id = s.id \
+                        when matched then {action}"
+                    ),
+                )
+                .await;
+                assert!(result.is_err());
+            }
+
+            check_table(
+                client,
+                table_name,
+                vec!["id", "value"],
+                &[
+                    "+----+-------+",
+                    "| id | value |",
+                    "+----+-------+",
+                    "| 1  | 1     |",
+                    "| 2  | 2     |",
+                    "+----+-------+",
+                ],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_all_cases() -> Result<()> {
+        test_merge_primary_key_table().await?;
+        test_merge_range_partitioned_table().await?;
+        test_merge_keeps_append_after_planning().await?;
+        test_merge_fails_with_multiple_matches().await?;
+        Ok(())
+    }
+}
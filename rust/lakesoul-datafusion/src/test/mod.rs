@@ -8,11 +8,27 @@ use tracing::debug;
 
 use lakesoul_metadata::MetaDataClient;
 
+mod utils;
+
 mod hash_tests;
 mod insert_tests;
 mod upsert_tests;
-// mod compaction_tests;
-// mod streaming_tests;
+mod compaction_tests;
+mod delete_tests;
+mod update_tests;
+mod merge_tests;
+mod ddl_tests;
+mod time_travel_tests;
+mod streaming_tests;
+mod expire_snapshots_tests;
+mod orphan_files_tests;
+mod rollback_tests;
+mod tag_tests;
+mod branch_tests;
+mod history_tests;
+mod system_tables_tests;
+mod connection_pool_tests;
+mod memory_backend_tests;
 #[cfg(feature = "ci")]
 mod integration_tests;
 
@@ -0,0 +1,88 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod orphan_files_tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use lakesoul_io::lakesoul_io_config::create_session_context;
+    use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
+
+    use crate::catalog::{create_io_config_builder, remove_orphan_files};
+    use crate::error::Result;
+    use crate::lakesoul_table::LakeSoulTable;
+    use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, execute_upsert, init_table};
+
+    async fn check_record_batch(client: MetaDataClientRef, table_name: &str, expected: &[&str]) -> Result<()> {
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        let builder = create_io_config_builder(client, None, false, "default").await?;
+        let sess_ctx = create_session_context(&mut builder.clone().build())?;
+        let results = lakesoul_table
+            .to_dataframe(&sess_ctx)
+            .await?
+            .select_columns(&["range", "hash", "value"])?
+            .collect()
+            .await?;
+        assert_batches_eq(table_name, expected, &results);
+        Ok(())
+    }
+
+    async fn table_dir(client: MetaDataClientRef, table_name: &str) -> Result<PathBuf> {
+        let table_info = client.get_table_info_by_table_name(table_name, "default").await?;
+        Ok(url::Url::parse(&table_info.table_path).unwrap().to_file_path().unwrap())
+    }
+
+    async fn test_remove_orphan_files() -> Result<()> {
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let table_name = "test_remove_orphan_files";
+        let batch = create_batch_i32(vec!["range", "hash", "value"], vec![&[1, 1], &[1, 2], &[1, 2]]);
+        init_table(client.clone(), batch.schema(), table_name, vec!["range"], vec!["hash"]).await?;
+        execute_upsert(batch, table_name).await?;
+
+        // the files of an aborted write, and a file which is not a data file
+        let dir = table_dir(client.clone(), table_name).await?;
+        let orphan = dir.join("range=1").join("part-orphan_0000.parquet");
+        std::fs::write(&orphan, b"orphan").unwrap();
+        std::fs::write(dir.join("range=1").join("orphan.txt"), b"orphan").unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let now = chrono::Utc::now().timestamp_millis();
+
+        // the files modified later than `older_than` are kept
+        let removed = remove_orphan_files(client.clone(), table_name, now - 60_000, false).await?;
+        assert!(removed.files.is_empty());
+        assert!(orphan.exists());
+
+        let removed = remove_orphan_files(client.clone(), table_name, now, true).await?;
+        assert_eq!(removed.files.len(), 1);
+        assert!(removed.files[0].ends_with("range=1/part-orphan_0000.parquet"));
+        assert_eq!(removed.size, 6);
+        assert!(orphan.exists());
+
+        let removed = remove_orphan_files(client.clone(), table_name, now, false).await?;
+        assert_eq!(removed.files.len(), 1);
+        assert!(!orphan.exists());
+        assert!(dir.join("range=1").join("orphan.txt").exists());
+        check_record_batch(
+            client,
+            table_name,
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 1    | 1     |",
+                "| 1     | 2    | 2     |",
+                "+-------+------+-------+",
+            ],
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_all_cases() -> Result<()> {
+        test_remove_orphan_files().await?;
+        Ok(())
+    }
+}
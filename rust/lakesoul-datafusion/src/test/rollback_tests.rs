@@ -0,0 +1,237 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod rollback_tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use arrow::record_batch::RecordBatch;
+    use datafusion::execution::context::SessionContext;
+    use lakesoul_metadata::MetaDataClient;
+    use proto::proto::entity::CommitOp;
+
+    use crate::error::Result;
+    use crate::lakesoul_table::LakeSoulTable;
+    use crate::planner::sql;
+    use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, create_context};
+
+    /// Create table `table_name` partitioned by `range` with primary key `id`, and upsert the batches one by one,
+    /// returns the timestamps in milliseconds after each upsert.
+    async fn create_table_with_versions(
+        ctx: &SessionContext,
+        table_name: &str,
+        batches: Vec<RecordBatch>,
+    ) -> Result<Vec<i64>> {
+        sql(
+            ctx,
+            &format!(
+                "create table lakesoul.default.{table_name} (range int, id int, value int) \
+                primary key (id) partitioned by (range)"
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        let mut timestamps = vec![];
+        for batch in batches {
+            lakesoul_table.execute_upsert(batch).await?;
+            timestamps.push(chrono::Utc::now().timestamp_millis());
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        Ok(timestamps)
+    }
+
+    /// range=1 and range=2 are created by the first version, range=1 is updated by the second version,
+    /// and range=3 is created by the third version.
+    fn versions() -> Vec<RecordBatch> {
+        let names = vec!["range", "id", "value"];
+        vec![
+            create_batch_i32(names.clone(), vec![&[1, 1, 2], &[1, 2, 3], &[1, 2, 3]]),
+            create_batch_i32(names.clone(), vec![&[1], &[1], &[10]]),
+            create_batch_i32(names, vec![&[3], &[4], &[4]]),
+        ]
+    }
+
+    async fn read_table(ctx: &SessionContext, table_name: &str) -> Result<Vec<RecordBatch>> {
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        Ok(lakesoul_table
+            .to_dataframe(ctx)
+            .await?
+            .select_columns(&["range", "id", "value"])?
+            .collect()
+            .await?)
+    }
+
+    const FIRST_VERSION: &[&str] = &[
+        "+-------+----+-------+",
+        "| range | id | value |",
+        "+-------+----+-------+",
+        "| 1     | 1  | 1     |",
+        "| 1     | 2  | 2     |",
+        "| 2     | 3  | 3     |",
+        "+-------+----+-------+",
+    ];
+
+    /// The latest version with range=1 rolled back to its first version.
+    const RANGE_1_ROLLED_BACK: &[&str] = &[
+        "+-------+----+-------+",
+        "| range | id | value |",
+        "+-------+----+-------+",
+        "| 1     | 1  | 1     |",
+        "| 1     | 2  | 2     |",
+        "| 2     | 3  | 3     |",
+        "| 3     | 4  | 4     |",
+        "+-------+----+-------+",
+    ];
+
+    const LATEST: &[&str] = &[
+        "+-------+----+-------+",
+        "| range | id | value |",
+        "+-------+----+-------+",
+        "| 1     | 1  | 10    |",
+        "| 1     | 2  | 2     |",
+        "| 2     | 3  | 3     |",
+        "| 3     | 4  | 4     |",
+        "+-------+----+-------+",
+    ];
+
+    async fn test_rollback_partition() -> Result<()> {
+        let table_name = "test_rollback_partition";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let ctx = create_context(client.clone()).await?;
+        create_table_with_versions(&ctx, table_name, versions()).await?;
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        let table_id = lakesoul_table.table_info().table_id.clone();
+
+        let rolled_back = lakesoul_table.rollback_partition("range=1", 0).await?.unwrap();
+        assert_eq!(rolled_back.version, 2);
+        let versions = client.get_all_versions_of_partition(&table_id, "range=1").await?;
+        assert_eq!(
+            versions.iter().map(|version| version.version).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(versions[2].commit_op(), CommitOp::UpdateCommit);
+        assert_eq!(versions[2].snapshot, versions[0].snapshot);
+        assert_batches_eq(table_name, RANGE_1_ROLLED_BACK, &read_table(&ctx, table_name).await?);
+
+        // the rollback is undone by rolling back to the version before it
+        let rolled_back = lakesoul_table.rollback_partition("range=1", 1).await?.unwrap();
+        assert_eq!(rolled_back.version, 3);
+        assert_batches_eq(table_name, LATEST, &read_table(&ctx, table_name).await?);
+
+        // nothing to roll back to the latest version
+        assert!(lakesoul_table.rollback_partition("range=1", 3).await?.is_none());
+        assert!(lakesoul_table.rollback_partition("range=1", 10).await.is_err());
+        Ok(())
+    }
+
+    async fn test_rollback_to_timestamp() -> Result<()> {
+        let table_name = "test_rollback_to_timestamp";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let ctx = create_context(client.clone()).await?;
+        let timestamps = create_table_with_versions(&ctx, table_name, versions()).await?;
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        let table_id = lakesoul_table.table_info().table_id.clone();
+
+        // range=3 is created after the timestamp, so it is rolled back to an empty snapshot
+        let mut rolled_back = lakesoul_table
+            .rollback_to_timestamp(timestamps[0])
+            .await?
+            .into_iter()
+            .map(|partition_info| (partition_info.partition_desc, partition_info.version))
+            .collect::<Vec<_>>();
+        rolled_back.sort();
+        assert_eq!(
+            rolled_back,
+            vec![("range=1".to_string(), 2), ("range=3".to_string(), 1)]
+        );
+        let range_3 = client
+            .get_partition_info_by_version(&table_id, "range=3", 1)
+            .await?
+            .unwrap();
+        assert!(range_3.snapshot.is_empty());
+        assert_batches_eq(table_name, FIRST_VERSION, &read_table(&ctx, table_name).await?);
+
+        // undo the rollback
+        let rolled_back = lakesoul_table.rollback_to_timestamp(timestamps[2]).await?;
+        assert_eq!(rolled_back.len(), 2);
+        assert_batches_eq(table_name, LATEST, &read_table(&ctx, table_name).await?);
+        assert!(lakesoul_table
+            .rollback_to_timestamp(chrono::Utc::now().timestamp_millis())
+            .await?
+            .is_empty());
+        Ok(())
+    }
+
+    async fn test_rollback_procedure() -> Result<()> {
+        let table_name = "test_rollback_procedure";
+        let ctx = create_context(Arc::new(MetaDataClient::from_env().await?)).await?;
+        create_table_with_versions(&ctx, table_name, versions()).await?;
+
+        let results = sql(
+            &ctx,
+            &format!("call lakesoul_rollback('lakesoul.default.{table_name}', 0)"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----------------+---------+",
+                "| partition_desc | version |",
+                "+----------------+---------+",
+                "| range=1        | 2       |",
+                "+----------------+---------+",
+            ],
+            &results,
+        );
+        // the versions are of each partition, so range=2 and range=3 at version 0 are unchanged
+        assert_batches_eq(table_name, RANGE_1_ROLLED_BACK, &read_table(&ctx, table_name).await?);
+
+        let results = sql(
+            &ctx,
+            &format!("call lakesoul_rollback_partition('lakesoul.default.{table_name}', 'range=1', 1)"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----------------+---------+",
+                "| partition_desc | version |",
+                "+----------------+---------+",
+                "| range=1        | 3       |",
+                "+----------------+---------+",
+            ],
+            &results,
+        );
+        assert_batches_eq(table_name, LATEST, &read_table(&ctx, table_name).await?);
+
+        assert!(sql(
+            &ctx,
+            &format!("call lakesoul_rollback('lakesoul.default.{table_name}')")
+        )
+        .await
+        .is_err());
+        assert!(sql(
+            &ctx,
+            &format!("call lakesoul_rollback_partition('lakesoul.default.{table_name}', 'range=1', '2024-01-01')")
+        )
+        .await
+        .is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_all_cases() -> Result<()> {
+        test_rollback_partition().await?;
+        test_rollback_to_timestamp().await?;
+        test_rollback_procedure().await?;
+        Ok(())
+    }
+}
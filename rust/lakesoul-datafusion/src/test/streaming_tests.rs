@@ -3,179 +3,611 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod streaming_tests {
-    use std::collections::HashMap;
     use std::sync::Arc;
-    use std::time::SystemTime;
+    use std::time::Duration;
 
     use arrow::record_batch::RecordBatch;
-    use arrow::array::{ArrayRef, Int32Array};
-
-    use arrow::array::StringArray;
-    use arrow::datatypes::SchemaRef;
-    use datafusion::assert_batches_eq;
-    use datafusion::execution::context::SessionContext;
-    use lakesoul_io::lakesoul_io_config::{LakeSoulIOConfigBuilder, create_session_context};
+    use datafusion::physical_plan::SendableRecordBatchStream;
+    use futures::StreamExt;
     use lakesoul_metadata::MetaDataClient;
+    use proto::proto::entity::CommitOp;
 
-    use crate::catalog::{create_table, create_table_with_config_map};
-    use crate::error::Result;
+    use crate::datasource::physical_plan::StreamingOffset;
+    use crate::datasource::table_provider::TableSnapshot;
+    use crate::error::{LakeSoulError, Result};
+    use crate::lakesoul_table::incremental::CHANGE_TYPE_COLUMN;
     use crate::lakesoul_table::LakeSoulTable;
+    use crate::planner::sql;
+    use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, create_context, init_table};
+
+    /// Upsert `record_batch` into table `table_name`, returns the timestamp in milliseconds after committing.
+    async fn do_upsert(record_batch: RecordBatch, table_name: &str) -> Result<i64> {
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        lakesoul_table.execute_upsert(record_batch).await?;
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        Ok(timestamp)
+    }
 
     async fn test_snapshot_read_with_single_partition() -> Result<()> {
         let client = Arc::new(MetaDataClient::from_env().await?);
         let table_name = "test_snapshot_read_with_single_partition";
-
-        let record_batch_1 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(StringArray::from(vec!["range1", "range2"])) as ArrayRef, false),
-                ("hash", Arc::new(StringArray::from(vec!["hash1-1", "hash2-1"])) as ArrayRef, false),
-                ("op", Arc::new(StringArray::from(vec!["insert", "insert"])) as ArrayRef, true),
-            ]
-        )?;
-
-        let record_batch_2 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(StringArray::from(vec!["range1", "range1", "range2", "range2"])) as ArrayRef, false),
-                ("hash", Arc::new(StringArray::from(vec!["hash1-1", "hash1-5", "hash2-1", "hash2-5"])) as ArrayRef, false),
-                ("op", Arc::new(StringArray::from(vec!["delete", "insert", "delete", "insert"])) as ArrayRef, true),
-            ]
-        )?;
-
-        let record_batch_3 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(StringArray::from(vec!["range1", "range2"])) as ArrayRef, false),
-                ("hash", Arc::new(StringArray::from(vec!["hash1-2", "hash2-2"])) as ArrayRef, false),
-                ("op", Arc::new(StringArray::from(vec!["update", "update"])) as ArrayRef, true),
-            ]
-        )?;
-
-        let schema = record_batch_1.schema();
-        create_table_with_config_map(client.clone(), table_name, 
-            [
-                    ("schema".to_string(), serde_json::to_string(&schema).unwrap()),
-                    ("rangePartitions".to_string(), "range".to_string()),
-                    ("hashPartitions".to_string(), "hash".to_string()),
-                    ("hashBucketNum".to_string(), "2".to_string()),
-                ].into_iter().collect()).await?;
-
-        let context = create_session_context(&mut LakeSoulIOConfigBuilder::default().build())?;
-
-        // let table = LakeSoulTable::for_path_snapshot(table_name.to_string());
+        let names = vec!["range", "hash", "value"];
+        let batch = create_batch_i32(names.clone(), vec![&[1, 1], &[1, 2], &[1, 2]]);
+        init_table(client.clone(), batch.schema(), table_name, vec!["range"], vec!["hash"]).await?;
+        do_upsert(batch, table_name).await?;
+        do_upsert(
+            create_batch_i32(names.clone(), vec![&[1, 1], &[1, 3], &[11, 3]]),
+            table_name,
+        )
+        .await?;
+
+        let ctx = create_context(client).await?;
         let table = LakeSoulTable::for_name(table_name).await?;
-
-        table.execute_upsert(record_batch_1).await?;
-
-        table.execute_upsert(record_batch_2).await?;
-
-        let current = SystemTime::now();
-        
-        table.execute_upsert(record_batch_3).await?;
-
-
-        let result = table.to_dataframe(&context).await?.collect().await?;
-
-        assert_batches_eq!(
+        let results = table
+            .to_dataframe_as_of_version(&ctx, 0)
+            .await?
+            .select_columns(&names)?
+            .collect()
+            .await?;
+        assert_batches_eq(
+            table_name,
             &[
-                "+--------+---------+--------+",
-                "| range  | hash    | op     |",
-                "+--------+---------+--------+",
-                "| range1 | hash1-1 | delete |",
-                "| range1 | hash1-2 | update |",
-                "| range1 | hash1-5 | insert |",
-                "+--------+---------+--------+",
-            ], 
-            &result
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 1    | 1     |",
+                "| 1     | 2    | 2     |",
+                "+-------+------+-------+",
+            ],
+            &results,
+        );
+        let results = table
+            .to_dataframe_as_of_version(&ctx, 1)
+            .await?
+            .select_columns(&names)?
+            .collect()
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 1    | 11    |",
+                "| 1     | 2    | 2     |",
+                "| 1     | 3    | 3     |",
+                "+-------+------+-------+",
+            ],
+            &results,
         );
-
-
         Ok(())
     }
 
     async fn test_snapshot_read_with_multi_partition() -> Result<()> {
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let table_name = "test_snapshot_read_with_multi_partition";
+        let names = vec!["range", "hash", "value"];
+        let batch = create_batch_i32(names.clone(), vec![&[1, 2], &[1, 1], &[1, 1]]);
+        init_table(client.clone(), batch.schema(), table_name, vec!["range"], vec!["hash"]).await?;
+        let timestamp = do_upsert(batch, table_name).await?;
+        // only the partition range=2 has version 1
+        do_upsert(create_batch_i32(names.clone(), vec![&[2], &[1], &[22]]), table_name).await?;
+        do_upsert(create_batch_i32(names.clone(), vec![&[3], &[1], &[3]]), table_name).await?;
+
+        let ctx = create_context(client).await?;
+        let table = LakeSoulTable::for_name(table_name).await?;
+        let expected = &[
+            "+-------+------+-------+",
+            "| range | hash | value |",
+            "+-------+------+-------+",
+            "| 1     | 1    | 1     |",
+            "| 2     | 1    | 1     |",
+            "+-------+------+-------+",
+        ];
+        let results = table
+            .to_dataframe_as_of_timestamp(&ctx, timestamp)
+            .await?
+            .select_columns(&names)?
+            .collect()
+            .await?;
+        assert_batches_eq(table_name, expected, &results);
+        // the partition range=3 is created by its version 0
+        let results = table
+            .to_dataframe_as_of_version(&ctx, 0)
+            .await?
+            .select_columns(&names)?
+            .collect()
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 1    | 1     |",
+                "| 2     | 1    | 1     |",
+                "| 3     | 1    | 3     |",
+                "+-------+------+-------+",
+            ],
+            &results,
+        );
         Ok(())
     }
 
     async fn test_snapshot_read_without_partition() -> Result<()> {
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let table_name = "test_snapshot_read_without_partition";
+        let names = vec!["hash", "value"];
+        let batch = create_batch_i32(names.clone(), vec![&[1, 2], &[1, 2]]);
+        init_table(client.clone(), batch.schema(), table_name, vec![], vec!["hash"]).await?;
+        let timestamp = do_upsert(batch, table_name).await?;
+        do_upsert(create_batch_i32(names.clone(), vec![&[2, 3], &[20, 3]]), table_name).await?;
+
+        let ctx = create_context(client).await?;
+        let results = sql(
+            &ctx,
+            &format!(
+                "select hash, value from lakesoul_snapshot('lakesoul.default.{}', to_timestamp_millis({}))",
+                table_name, timestamp
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+------+-------+",
+                "| hash | value |",
+                "+------+-------+",
+                "| 1    | 1     |",
+                "| 2    | 2     |",
+                "+------+-------+",
+            ],
+            &results,
+        );
         Ok(())
     }
 
     async fn test_incremental_read_with_single_partition() -> Result<()> {
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let table_name = "test_incremental_read_with_single_partition";
+        let names = vec!["range", "hash", "value"];
+        let batch = create_batch_i32(names.clone(), vec![&[1, 1], &[1, 2], &[1, 2]]);
+        init_table(client.clone(), batch.schema(), table_name, vec!["range"], vec!["hash"]).await?;
+        do_upsert(batch, table_name).await?;
+        do_upsert(
+            create_batch_i32(names.clone(), vec![&[1, 1], &[1, 3], &[11, 3]]),
+            table_name,
+        )
+        .await?;
+        do_upsert(create_batch_i32(names.clone(), vec![&[1], &[2], &[22]]), table_name).await?;
+
+        let ctx = create_context(client).await?;
+        let table = LakeSoulTable::for_name(table_name).await?;
+        let results = table
+            .to_incremental_dataframe(&ctx, TableSnapshot::Version(0), TableSnapshot::Version(2), false)
+            .await?
+            .select_columns(&names)?
+            .collect()
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 1    | 11    |",
+                "| 1     | 2    | 22    |",
+                "| 1     | 3    | 3     |",
+                "+-------+------+-------+",
+            ],
+            &results,
+        );
+
+        // version 3 is a DeleteCommit and version 4 is an UpdateCommit
+        sql(
+            &ctx,
+            &format!("delete from lakesoul.default.{table_name} where hash = 1"),
+        )
+        .await?
+        .collect()
+        .await?;
+        sql(
+            &ctx,
+            &format!("update lakesoul.default.{table_name} set value = 100 where hash = 3"),
+        )
+        .await?
+        .collect()
+        .await?;
+
+        let table = LakeSoulTable::for_name(table_name).await?;
+        let mut columns = names.clone();
+        columns.push(CHANGE_TYPE_COLUMN);
+        let results = table
+            .to_incremental_dataframe(&ctx, TableSnapshot::Version(-1), TableSnapshot::Version(4), true)
+            .await?
+            .select_columns(&columns)?
+            .collect()
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+------+-------+----------------------+",
+                "| range | hash | value | lakesoul_change_type |",
+                "+-------+------+-------+----------------------+",
+                "| 1     | 1    | 11    | delete               |",
+                "| 1     | 1    | 11    | insert               |",
+                "| 1     | 2    | 22    | insert               |",
+                "| 1     | 3    | 100   | update               |",
+                "| 1     | 3    | 3     | insert               |",
+                "+-------+------+-------+----------------------+",
+            ],
+            &results,
+        );
+        // the deleted rows are not returned without the change type
+        let results = table
+            .to_incremental_dataframe(&ctx, TableSnapshot::Version(2), TableSnapshot::Version(4), false)
+            .await?
+            .select_columns(&names)?
+            .collect()
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 3    | 100   |",
+                "+-------+------+-------+",
+            ],
+            &results,
+        );
         Ok(())
     }
 
     async fn test_incremental_read_with_multi_partition() -> Result<()> {
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let table_name = "test_incremental_read_with_multi_partition";
+        let names = vec!["range", "hash", "value"];
+        let batch = create_batch_i32(names.clone(), vec![&[1, 2], &[1, 1], &[1, 1]]);
+        init_table(client.clone(), batch.schema(), table_name, vec!["range"], vec!["hash"]).await?;
+        let start = do_upsert(batch, table_name).await?;
+        do_upsert(create_batch_i32(names.clone(), vec![&[2], &[2], &[2]]), table_name).await?;
+        let end = do_upsert(create_batch_i32(names.clone(), vec![&[3], &[3], &[3]]), table_name).await?;
+        do_upsert(create_batch_i32(names.clone(), vec![&[1], &[4], &[4]]), table_name).await?;
+
+        let ctx = create_context(client).await?;
+        let table = LakeSoulTable::for_name(table_name).await?;
+        let results = table
+            .to_incremental_dataframe(
+                &ctx,
+                TableSnapshot::Timestamp(start),
+                TableSnapshot::Timestamp(end),
+                false,
+            )
+            .await?
+            .select_columns(&names)?
+            .collect()
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 2     | 2    | 2     |",
+                "| 3     | 3    | 3     |",
+                "+-------+------+-------+",
+            ],
+            &results,
+        );
         Ok(())
     }
 
     async fn test_incremental_read_without_partition() -> Result<()> {
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let table_name = "test_incremental_read_without_partition";
+        let names = vec!["hash", "value"];
+        let batch = create_batch_i32(names.clone(), vec![&[1, 2], &[1, 2]]);
+        init_table(client.clone(), batch.schema(), table_name, vec![], vec!["hash"]).await?;
+        do_upsert(batch, table_name).await?;
+        do_upsert(create_batch_i32(names.clone(), vec![&[2, 3], &[20, 3]]), table_name).await?;
+
+        let ctx = create_context(client).await?;
+        let results = sql(
+            &ctx,
+            &format!("select hash, value from lakesoul_incremental('lakesoul.default.{table_name}', 0, 1)"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+------+-------+",
+                "| hash | value |",
+                "+------+-------+",
+                "| 2    | 20    |",
+                "| 3    | 3     |",
+                "+------+-------+",
+            ],
+            &results,
+        );
+        let results = sql(
+            &ctx,
+            &format!(
+                "select hash, value, lakesoul_change_type from lakesoul_changes('lakesoul.default.{table_name}', -1, 1)"
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+------+-------+----------------------+",
+                "| hash | value | lakesoul_change_type |",
+                "+------+-------+----------------------+",
+                "| 1    | 1     | insert               |",
+                "| 2    | 20    | insert               |",
+                "| 3    | 3     | insert               |",
+                "+------+-------+----------------------+",
+            ],
+            &results,
+        );
         Ok(())
     }
 
+    /// Pull batches from the unbounded `stream` until `num_rows` rows are returned.
+    async fn next_rows(stream: &mut SendableRecordBatchStream, num_rows: usize) -> Result<Vec<RecordBatch>> {
+        let mut batches: Vec<RecordBatch> = vec![];
+        while batches.iter().map(|batch| batch.num_rows()).sum::<usize>() < num_rows {
+            match tokio::time::timeout(Duration::from_secs(30), stream.next())
+                .await
+                .map_err(|_| LakeSoulError::Internal(format!("timeout waiting for {} rows", num_rows)))?
+            {
+                Some(batch) => batches.push(batch?),
+                None => break,
+            }
+        }
+        Ok(batches)
+    }
+
     async fn test_streaming_read_with_single_partition() -> Result<()> {
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let table_name = "test_streaming_read_with_single_partition";
+        let names = vec!["range", "hash", "value"];
+        let batch = create_batch_i32(names.clone(), vec![&[1, 1], &[1, 2], &[1, 2]]);
+        init_table(client.clone(), batch.schema(), table_name, vec!["range"], vec!["hash"]).await?;
+        do_upsert(batch, table_name).await?;
+
+        let ctx = create_context(client).await?;
+        let table = LakeSoulTable::for_name(table_name).await?;
+        let provider = Arc::new(
+            table
+                .as_streaming_provider(&ctx.state(), None)
+                .await?
+                .with_poll_interval(Duration::from_millis(100)),
+        );
+        let mut stream = ctx
+            .read_table(provider.clone())?
+            .select_columns(&names)?
+            .execute_stream()
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 1    | 1     |",
+                "| 1     | 2    | 2     |",
+                "+-------+------+-------+",
+            ],
+            &next_rows(&mut stream, 2).await?,
+        );
+        do_upsert(
+            create_batch_i32(names.clone(), vec![&[1, 1], &[1, 3], &[11, 3]]),
+            table_name,
+        )
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 1    | 11    |",
+                "| 1     | 3    | 3     |",
+                "+-------+------+-------+",
+            ],
+            &next_rows(&mut stream, 2).await?,
+        );
+        let offset = provider.offset();
+        assert_eq!(offset.versions.values().collect::<Vec<_>>(), vec![&1]);
+        drop(stream);
+
+        // restart from the offset, which only returns the later commits
+        do_upsert(create_batch_i32(names.clone(), vec![&[1], &[2], &[22]]), table_name).await?;
+        let provider = table
+            .as_streaming_provider(&ctx.state(), Some(offset))
+            .await?
+            .with_poll_interval(Duration::from_millis(100));
+        let mut stream = ctx
+            .read_table(Arc::new(provider))?
+            .select_columns(&names)?
+            .execute_stream()
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+------+-------+",
+                "| range | hash | value |",
+                "+-------+------+-------+",
+                "| 1     | 2    | 22    |",
+                "+-------+------+-------+",
+            ],
+            &next_rows(&mut stream, 1).await?,
+        );
         Ok(())
     }
 
     async fn test_streaming_read_with_multi_partition() -> Result<()> {
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let table_name = "test_streaming_read_with_multi_partition";
+        let names = vec!["range", "hash", "value"];
+        let batch = create_batch_i32(names.clone(), vec![&[1, 2], &[1, 1], &[1, 1]]);
+        init_table(client.clone(), batch.schema(), table_name, vec!["range"], vec!["hash"]).await?;
+        do_upsert(batch, table_name).await?;
+
+        let ctx = create_context(client).await?;
+        let table = LakeSoulTable::for_name(table_name).await?;
+        let provider = Arc::new(
+            table
+                .as_streaming_provider(&ctx.state(), None)
+                .await?
+                .with_poll_interval(Duration::from_millis(100))
+                .with_change_type(true),
+        );
+        ctx.register_table("streaming_changes", provider.clone())?;
+        let mut stream = ctx
+            .sql(&format!(
+                "select range, hash, value, {CHANGE_TYPE_COLUMN} from streaming_changes"
+            ))
+            .await?
+            .execute_stream()
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+------+-------+----------------------+",
+                "| range | hash | value | lakesoul_change_type |",
+                "+-------+------+-------+----------------------+",
+                "| 1     | 1    | 1     | insert               |",
+                "| 2     | 1    | 1     | insert               |",
+                "+-------+------+-------+----------------------+",
+            ],
+            &next_rows(&mut stream, 2).await?,
+        );
+        assert_eq!(provider.offset().versions.len(), 2);
+
+        sql(
+            &ctx,
+            &format!("delete from lakesoul.default.{table_name} where range = 2"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+-------+------+-------+----------------------+",
+                "| range | hash | value | lakesoul_change_type |",
+                "+-------+------+-------+----------------------+",
+                "| 2     | 1    | 1     | delete               |",
+                "+-------+------+-------+----------------------+",
+            ],
+            &next_rows(&mut stream, 1).await?,
+        );
+        ctx.deregister_table("streaming_changes")?;
         Ok(())
     }
 
     async fn test_streaming_read_without_partition() -> Result<()> {
         let client = Arc::new(MetaDataClient::from_env().await?);
         let table_name = "test_streaming_read_without_partition";
+        let names = vec!["hash", "value"];
+        let batch = create_batch_i32(names.clone(), vec![&[1, 2], &[1, 2]]);
+        init_table(client.clone(), batch.schema(), table_name, vec![], vec!["hash"]).await?;
+        do_upsert(batch, table_name).await?;
 
-        let record_batch_1 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(Int32Array::from(vec![1, 1, 1, 1])) as ArrayRef, false),
-                ("hash", Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef, false),
-                ("value", Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef, true),
-            ]
-        )?;
-
-        let record_batch_2 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(Int32Array::from(vec![1, 1, 1])) as ArrayRef, false),
-                ("hash", Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef, false),
-                ("value", Arc::new(Int32Array::from(vec![11, 22, 33])) as ArrayRef, true),
-            ]
-        )?;
-
-        let record_batch_3 = RecordBatch::try_from_iter_with_nullable(
-            vec![
-                ("range", Arc::new(Int32Array::from(vec![1, 1, 1, 1])) as ArrayRef, false),
-                ("hash", Arc::new(Int32Array::from(vec![2, 3, 4, 5])) as ArrayRef, false),
-                ("value", Arc::new(Int32Array::from(vec![222, 333, 444, 555])) as ArrayRef, true),
-            ]
-        )?;
-
-        create_table_with_config_map(client.clone(), table_name, [("a".to_string(), "value".to_string())].into_iter().collect()).await?;
-
-        let context = create_session_context(&mut LakeSoulIOConfigBuilder::default().build())?;
-
-        let table = LakeSoulTable::for_path_snapshot(table_name.to_string()).await?;
-
-        table.execute_upsert(record_batch_1).await?;
-
-        table.execute_upsert(record_batch_2).await?;
-
-        let current = SystemTime::now();
-
-        let join_handle = tokio::spawn(async {
-            
-        });
-        
-        // table.execute_upsert(record_batch_3).await?;
-
-        
-
-        assert_batches_eq!(&[""], &[record_batch_3]);
-
+        let ctx = create_context(client.clone()).await?;
+        let table = LakeSoulTable::for_name(table_name).await?;
+        // skip the rows committed before
+        let offset = StreamingOffset::latest(client, &table.table_info().table_id).await?;
+        // the offset can be persisted and restored
+        let offset: StreamingOffset = serde_json::from_str(&serde_json::to_string(&offset)?)?;
+        let provider = table
+            .as_streaming_provider(&ctx.state(), Some(offset))
+            .await?
+            .with_poll_interval(Duration::from_millis(100));
+        let mut stream = ctx.read_table(Arc::new(provider))?.execute_stream().await?;
+
+        do_upsert(create_batch_i32(names.clone(), vec![&[2, 3], &[20, 3]]), table_name).await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+------+-------+",
+                "| hash | value |",
+                "+------+-------+",
+                "| 2    | 20    |",
+                "| 3    | 3     |",
+                "+------+-------+",
+            ],
+            &next_rows(&mut stream, 2).await?,
+        );
         Ok(())
     }
 
+    async fn test_streaming_read_with_commit_notification() -> Result<()> {
+        let client = Arc::new(MetaDataClient::from_env().await?.with_commit_notification(true));
+        let table_name = "test_streaming_read_with_commit_notification";
+        let names = vec!["hash", "value"];
+        let batch = create_batch_i32(names.clone(), vec![&[1, 2], &[1, 2]]);
+        init_table(client.clone(), batch.schema(), table_name, vec![], vec!["hash"]).await?;
+        let table = LakeSoulTable::try_new_with_client_and_table_info(
+            client.clone(),
+            client.get_table_info_by_table_name(table_name, "default").await?,
+        )
+        .await?;
+        let table_id = table.table_info().table_id.clone();
+
+        let mut notifications = client.subscribe_commit_notifications().await?;
+        table.execute_upsert(batch).await?;
+        let notification = loop {
+            let notification = tokio::time::timeout(Duration::from_secs(30), notifications.next())
+                .await
+                .map_err(|_| LakeSoulError::Internal("timeout waiting for commit notification".to_string()))?
+                .ok_or(LakeSoulError::Internal("commit notifications closed".to_string()))??;
+            if notification.table_id == table_id {
+                break notification;
+            }
+        };
+        assert_eq!(notification.version, 0);
+        assert_eq!(notification.commit_op, CommitOp::AppendCommit);
+
+        // the streaming read is woken up by the commit instead of the poll interval
+        let ctx = create_context(client.clone()).await?;
+        let offset = StreamingOffset::latest(client.clone(), &table_id).await?;
+        let provider = table
+            .as_streaming_provider(&ctx.state(), Some(offset))
+            .await?
+            .with_poll_interval(Duration::from_secs(600))
+            .with_commit_notification(true);
+        let mut stream = ctx.read_table(Arc::new(provider))?.execute_stream().await?;
+        table
+            .execute_upsert(create_batch_i32(names.clone(), vec![&[2, 3], &[20, 3]]))
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+------+-------+",
+                "| hash | value |",
+                "+------+-------+",
+                "| 2    | 20    |",
+                "| 3    | 3     |",
+                "+------+-------+",
+            ],
+            &next_rows(&mut stream, 2).await?,
+        );
+        Ok(())
+    }
 
-
-    #[tokio::test]
-    async fn test_all_cases()  -> Result<()> {
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_all_cases() -> Result<()> {
         test_snapshot_read_with_single_partition().await?;
         test_snapshot_read_with_multi_partition().await?;
         test_snapshot_read_without_partition().await?;
@@ -185,6 +617,7 @@ mod streaming_tests {
         test_streaming_read_with_single_partition().await?;
         test_streaming_read_with_multi_partition().await?;
         test_streaming_read_without_partition().await?;
+        test_streaming_read_with_commit_notification().await?;
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,176 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod system_tables_tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use arrow::array::{Array, AsArray};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::execution::context::SessionContext;
+    use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
+
+    use crate::catalog::compaction;
+    use crate::datasource::system_table_provider::{LakeSoulSystemTableProvider, SystemTableType};
+    use crate::error::Result;
+    use crate::lakesoul_table::LakeSoulTable;
+    use crate::planner::sql;
+    use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, create_context};
+
+    /// Create table `table_name` partitioned by `range` with primary key `id`, upsert range=1 twice and range=2 once,
+    /// and compact range=1.
+    async fn create_table(client: MetaDataClientRef, ctx: &SessionContext, table_name: &str) -> Result<()> {
+        sql(
+            ctx,
+            &format!(
+                "create table lakesoul.default.{table_name} (range int, id int, value int) \
+                primary key (id) partitioned by (range)"
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        let names = vec!["range", "id", "value"];
+        for batch in [
+            create_batch_i32(names.clone(), vec![&[1, 1, 2], &[1, 2, 3], &[1, 2, 3]]),
+            create_batch_i32(names, vec![&[1], &[1], &[10]]),
+        ] {
+            lakesoul_table.execute_upsert(batch).await?;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        compaction(client, table_name, "range=1").await
+    }
+
+    async fn query(ctx: &SessionContext, query: &str) -> Result<Vec<RecordBatch>> {
+        Ok(sql(ctx, query).await?.collect().await?)
+    }
+
+    async fn test_partitions_and_snapshots() -> Result<()> {
+        let table_name = "test_system_table_partitions";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let ctx = create_context(client.clone()).await?;
+        create_table(client.clone(), &ctx, table_name).await?;
+
+        let results = query(
+            &ctx,
+            &format!(
+                "select partition_desc, version, commit_op, num_commits \
+                from lakesoul.default.\"{table_name}$partitions\""
+            ),
+        )
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----------------+---------+------------------+-------------+",
+                "| partition_desc | version | commit_op        | num_commits |",
+                "+----------------+---------+------------------+-------------+",
+                "| range=1        | 2       | CompactionCommit | 1           |",
+                "| range=2        | 0       | AppendCommit     | 1           |",
+                "+----------------+---------+------------------+-------------+",
+            ],
+            &results,
+        );
+
+        let results = query(
+            &ctx,
+            &format!(
+                "select partition_desc, version, commit_op \
+                from lakesoul.default.\"{table_name}$snapshots\" where partition_desc = 'range=1'"
+            ),
+        )
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----------------+---------+------------------+",
+                "| partition_desc | version | commit_op        |",
+                "+----------------+---------+------------------+",
+                "| range=1        | 0       | AppendCommit     |",
+                "| range=1        | 1       | AppendCommit     |",
+                "| range=1        | 2       | CompactionCommit |",
+                "+----------------+---------+------------------+",
+            ],
+            &results,
+        );
+
+        // the snapshot of each version lists the ids of its data commits
+        let table_info = client.get_table_info_by_table_name(table_name, "default").await?;
+        let batch = LakeSoulSystemTableProvider::new(client.clone(), Arc::new(table_info), SystemTableType::Snapshots)
+            .record_batch()
+            .await?;
+        let snapshots = batch.column_by_name("snapshot").unwrap().as_list::<i32>();
+        let snapshot_sizes = (0..snapshots.len())
+            .map(|idx| snapshots.value(idx).len())
+            .collect::<Vec<_>>();
+        assert_eq!(snapshot_sizes, vec![1, 2, 1, 1]);
+        let compacted = snapshots.value(2);
+        let compacted_commit_id = compacted.as_string::<i32>().value(0);
+        assert!(uuid::Uuid::parse_str(compacted_commit_id).is_ok());
+        Ok(())
+    }
+
+    async fn test_files() -> Result<()> {
+        let table_name = "test_system_table_files";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let ctx = create_context(client.clone()).await?;
+        create_table(client, &ctx, table_name).await?;
+
+        let results = query(
+            &ctx,
+            &format!(
+                "select distinct partition_desc, commit_op, file_op \
+                from lakesoul.default.\"{table_name}$files\""
+            ),
+        )
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----------------+------------------+---------+",
+                "| partition_desc | commit_op        | file_op |",
+                "+----------------+------------------+---------+",
+                "| range=1        | CompactionCommit | add     |",
+                "| range=2        | AppendCommit     | add     |",
+                "+----------------+------------------+---------+",
+            ],
+            &results,
+        );
+
+        // the files are those of the latest version of each partition
+        let results = query(
+            &ctx,
+            &format!("select path from lakesoul.default.\"{table_name}$files\""),
+        )
+        .await?;
+        for batch in &results {
+            for path in batch.column(0).as_string::<i32>().iter().flatten() {
+                assert!(url::Url::parse(path).unwrap().to_file_path().unwrap().exists());
+            }
+        }
+
+        assert!(query(
+            &ctx,
+            &format!("select * from lakesoul.default.\"{table_name}$unknown\"")
+        )
+        .await
+        .is_err());
+        assert!(query(
+            &ctx,
+            "select * from lakesoul.default.\"test_system_table_missing$files\""
+        )
+        .await
+        .is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_all_cases() -> Result<()> {
+        test_partitions_and_snapshots().await?;
+        test_files().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod tag_tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use arrow::record_batch::RecordBatch;
+    use datafusion::execution::context::SessionContext;
+    use lakesoul_metadata::MetaDataClient;
+
+    use crate::catalog::{compaction, expire_snapshots};
+    use crate::datasource::table_provider::TableSnapshot;
+    use crate::error::Result;
+    use crate::lakesoul_table::LakeSoulTable;
+    use crate::planner::sql;
+    use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, create_context};
+
+    /// Create table `table_name` partitioned by `range` with primary key `id`, and upsert the batches one by one.
+    async fn create_table_with_versions(
+        ctx: &SessionContext,
+        table_name: &str,
+        batches: Vec<RecordBatch>,
+    ) -> Result<()> {
+        sql(
+            ctx,
+            &format!(
+                "create table lakesoul.default.{table_name} (range int, id int, value int) \
+                primary key (id) partitioned by (range)"
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        for batch in batches {
+            lakesoul_table.execute_upsert(batch).await?;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        Ok(())
+    }
+
+    /// range=1 and range=2 are created by the first version, range=1 is updated by the second version,
+    /// and range=3 is created by the third version.
+    fn versions() -> Vec<RecordBatch> {
+        let names = vec!["range", "id", "value"];
+        vec![
+            create_batch_i32(names.clone(), vec![&[1, 1, 2], &[1, 2, 3], &[1, 2, 3]]),
+            create_batch_i32(names.clone(), vec![&[1], &[1], &[10]]),
+            create_batch_i32(names, vec![&[3], &[4], &[4]]),
+        ]
+    }
+
+    async fn read_tag(ctx: &SessionContext, table_name: &str, tag_name: &str) -> Result<Vec<RecordBatch>> {
+        Ok(sql(
+            ctx,
+            &format!("select range, id, value from lakesoul.default.\"{table_name}@{tag_name}\""),
+        )
+        .await?
+        .collect()
+        .await?)
+    }
+
+    /// The version 0 of each partition.
+    const FIRST_VERSIONS: &[&str] = &[
+        "+-------+----+-------+",
+        "| range | id | value |",
+        "+-------+----+-------+",
+        "| 1     | 1  | 1     |",
+        "| 1     | 2  | 2     |",
+        "| 2     | 3  | 3     |",
+        "| 3     | 4  | 4     |",
+        "+-------+----+-------+",
+    ];
+
+    const TAGGED: &[&str] = &[
+        "+-------+----+-------+",
+        "| range | id | value |",
+        "+-------+----+-------+",
+        "| 1     | 1  | 10    |",
+        "| 1     | 2  | 2     |",
+        "| 2     | 3  | 3     |",
+        "| 3     | 4  | 4     |",
+        "+-------+----+-------+",
+    ];
+
+    async fn test_tag_after_compaction_and_expiration() -> Result<()> {
+        let table_name = "test_tag_after_compaction_and_expiration";
+        let client = Arc::new(MetaDataClient::from_env().await?);
+        let ctx = create_context(client.clone()).await?;
+        create_table_with_versions(&ctx, table_name, versions()).await?;
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+
+        let tag = lakesoul_table.create_tag("month_end", None).await?;
+        assert_eq!(tag.partition_versions.get("range=1"), Some(&1));
+        assert_eq!(tag.partition_versions.get("range=2"), Some(&0));
+        assert_eq!(tag.partition_versions.get("range=3"), Some(&0));
+        assert!(lakesoul_table.create_tag("month_end", None).await.is_err());
+        assert!(lakesoul_table.create_tag("month@end", None).await.is_err());
+
+        lakesoul_table
+            .execute_upsert(create_batch_i32(
+                vec!["range", "id", "value"],
+                vec![&[1, 2], &[1, 3], &[100, 30]],
+            ))
+            .await?;
+        compaction(client.clone(), table_name, "").await?;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_batches_eq(table_name, TAGGED, &read_tag(&ctx, table_name, "month_end").await?);
+
+        // the versions pinned by the tag are retained
+        let expired = expire_snapshots(
+            client.clone(),
+            table_name,
+            chrono::Utc::now().timestamp_millis(),
+            1,
+            false,
+        )
+        .await?;
+        assert!(expired.versions.contains(&("range=1".to_string(), 0)));
+        assert!(!expired.versions.contains(&("range=1".to_string(), 1)));
+        assert!(!expired.versions.contains(&("range=2".to_string(), 0)));
+        assert_batches_eq(table_name, TAGGED, &read_tag(&ctx, table_name, "month_end").await?);
+        assert_batches_eq(
+            table_name,
+            TAGGED,
+            &lakesoul_table
+                .to_dataframe_of_tag(&ctx, "month_end")
+                .await?
+                .select_columns(&["range", "id", "value"])?
+                .collect()
+                .await?,
+        );
+
+        // the versions are expired once the tag is deleted
+        assert!(lakesoul_table.delete_tag("month_end").await?);
+        assert!(!lakesoul_table.delete_tag("month_end").await?);
+        assert!(lakesoul_table.tags().await?.is_empty());
+        let expired = expire_snapshots(
+            client.clone(),
+            table_name,
+            chrono::Utc::now().timestamp_millis(),
+            1,
+            false,
+        )
+        .await?;
+        assert!(expired.versions.contains(&("range=1".to_string(), 1)));
+        assert!(read_tag(&ctx, table_name, "month_end").await.is_err());
+        Ok(())
+    }
+
+    async fn test_tag_as_of_version() -> Result<()> {
+        let table_name = "test_tag_as_of_version";
+        let ctx = create_context(Arc::new(MetaDataClient::from_env().await?)).await?;
+        create_table_with_versions(&ctx, table_name, versions()).await?;
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+
+        lakesoul_table
+            .create_tag("first", Some(TableSnapshot::Version(0)))
+            .await?;
+        lakesoul_table.create_tag("latest", None).await?;
+        assert_eq!(
+            lakesoul_table
+                .tags()
+                .await?
+                .into_iter()
+                .map(|tag| tag.tag_name)
+                .collect::<Vec<_>>(),
+            vec!["first".to_string(), "latest".to_string()]
+        );
+        assert_batches_eq(table_name, FIRST_VERSIONS, &read_tag(&ctx, table_name, "first").await?);
+
+        // roll back the table to the tag
+        let rolled_back = lakesoul_table.rollback(TableSnapshot::Tag("first".to_string())).await?;
+        assert_eq!(rolled_back.len(), 1);
+        assert_eq!(rolled_back[0].partition_desc, "range=1");
+        assert_batches_eq(table_name, TAGGED, &read_tag(&ctx, table_name, "latest").await?);
+        assert_batches_eq(
+            table_name,
+            FIRST_VERSIONS,
+            &sql(
+                &ctx,
+                &format!("select range, id, value from lakesoul.default.{table_name}"),
+            )
+            .await?
+            .collect()
+            .await?,
+        );
+        Ok(())
+    }
+
+    async fn test_tag_procedures() -> Result<()> {
+        let table_name = "test_tag_procedures";
+        let ctx = create_context(Arc::new(MetaDataClient::from_env().await?)).await?;
+        create_table_with_versions(&ctx, table_name, versions()).await?;
+        let first_versions = &[
+            "+----------------+---------+",
+            "| partition_desc | version |",
+            "+----------------+---------+",
+            "| range=1        | 0       |",
+            "| range=2        | 0       |",
+            "| range=3        | 0       |",
+            "+----------------+---------+",
+        ];
+
+        let results = sql(
+            &ctx,
+            &format!("call lakesoul_create_tag('lakesoul.default.{table_name}', 'first', 0)"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(table_name, first_versions, &results);
+        assert_batches_eq(table_name, FIRST_VERSIONS, &read_tag(&ctx, table_name, "first").await?);
+        assert!(sql(
+            &ctx,
+            &format!("call lakesoul_create_tag('lakesoul.default.{table_name}', 'first')")
+        )
+        .await
+        .is_err());
+
+        let results = sql(
+            &ctx,
+            &format!("call lakesoul_drop_tag('lakesoul.default.{table_name}', 'first')"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(table_name, first_versions, &results);
+        assert!(sql(
+            &ctx,
+            &format!("call lakesoul_drop_tag('lakesoul.default.{table_name}', 'first')")
+        )
+        .await
+        .is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_all_cases() -> Result<()> {
+        test_tag_after_compaction_and_expiration().await?;
+        test_tag_as_of_version().await?;
+        test_tag_procedures().await?;
+        Ok(())
+    }
+}
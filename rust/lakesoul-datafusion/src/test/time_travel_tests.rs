@@ -0,0 +1,212 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod time_travel_tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use arrow::record_batch::RecordBatch;
+    use datafusion::execution::context::SessionContext;
+    use lakesoul_metadata::MetaDataClient;
+
+    use crate::error::Result;
+    use crate::lakesoul_table::LakeSoulTable;
+    use crate::planner::sql;
+    use crate::test::assert_batches_eq;
+    use crate::test::utils::{create_batch_i32, create_context};
+
+    /// Create table `table_name` with primary key `id`, and upsert the batches one by one,
+    /// returns the timestamps in milliseconds after each upsert.
+    async fn create_table_with_versions(
+        ctx: &SessionContext,
+        table_name: &str,
+        batches: Vec<RecordBatch>,
+    ) -> Result<Vec<i64>> {
+        sql(
+            ctx,
+            &format!("create table lakesoul.default.{table_name} (id int, value int) primary key (id)"),
+        )
+        .await?
+        .collect()
+        .await?;
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        let mut timestamps = vec![];
+        for batch in batches {
+            lakesoul_table.execute_upsert(batch).await?;
+            timestamps.push(chrono::Utc::now().timestamp_millis());
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        Ok(timestamps)
+    }
+
+    fn versions() -> Vec<RecordBatch> {
+        vec![
+            create_batch_i32(vec!["id", "value"], vec![&[1, 2], &[1, 2]]),
+            create_batch_i32(vec!["id", "value"], vec![&[1, 3], &[10, 3]]),
+            create_batch_i32(vec!["id", "value"], vec![&[2], &[20]]),
+        ]
+    }
+
+    async fn test_read_as_of_version() -> Result<()> {
+        let table_name = "test_read_as_of_version";
+        let ctx = create_context(Arc::new(MetaDataClient::from_env().await?)).await?;
+        create_table_with_versions(&ctx, table_name, versions()).await?;
+
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        let results = lakesoul_table
+            .to_dataframe_as_of_version(&ctx, 0)
+            .await?
+            .collect()
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----+-------+",
+                "| id | value |",
+                "+----+-------+",
+                "| 1  | 1     |",
+                "| 2  | 2     |",
+                "+----+-------+",
+            ],
+            &results,
+        );
+        let results = lakesoul_table
+            .to_dataframe_as_of_version(&ctx, 1)
+            .await?
+            .collect()
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----+-------+",
+                "| id | value |",
+                "+----+-------+",
+                "| 1  | 10    |",
+                "| 2  | 2     |",
+                "| 3  | 3     |",
+                "+----+-------+",
+            ],
+            &results,
+        );
+        let results = lakesoul_table.to_dataframe(&ctx).await?.collect().await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----+-------+",
+                "| id | value |",
+                "+----+-------+",
+                "| 1  | 10    |",
+                "| 2  | 20    |",
+                "| 3  | 3     |",
+                "+----+-------+",
+            ],
+            &results,
+        );
+        Ok(())
+    }
+
+    async fn test_read_as_of_timestamp() -> Result<()> {
+        let table_name = "test_read_as_of_timestamp";
+        let ctx = create_context(Arc::new(MetaDataClient::from_env().await?)).await?;
+        let before = chrono::Utc::now().timestamp_millis() - 1;
+        let timestamps = create_table_with_versions(&ctx, table_name, versions()).await?;
+
+        let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+        let results = lakesoul_table
+            .to_dataframe_as_of_timestamp(&ctx, timestamps[1])
+            .await?
+            .collect()
+            .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----+-------+",
+                "| id | value |",
+                "+----+-------+",
+                "| 1  | 10    |",
+                "| 2  | 2     |",
+                "| 3  | 3     |",
+                "+----+-------+",
+            ],
+            &results,
+        );
+        // nothing was committed before the first upsert
+        let results = lakesoul_table
+            .to_dataframe_as_of_timestamp(&ctx, before)
+            .await?
+            .collect()
+            .await?;
+        assert!(results.iter().all(|batch| batch.num_rows() == 0));
+        Ok(())
+    }
+
+    async fn test_snapshot_table_function() -> Result<()> {
+        let table_name = "test_snapshot_table_function";
+        let ctx = create_context(Arc::new(MetaDataClient::from_env().await?)).await?;
+        let timestamps = create_table_with_versions(&ctx, table_name, versions()).await?;
+
+        let results = sql(
+            &ctx,
+            &format!("select id, value from lakesoul_snapshot('lakesoul.default.{table_name}', 0)"),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----+-------+",
+                "| id | value |",
+                "+----+-------+",
+                "| 1  | 1     |",
+                "| 2  | 2     |",
+                "+----+-------+",
+            ],
+            &results,
+        );
+
+        let results = sql(
+            &ctx,
+            &format!(
+                "select s.id, s.value from lakesoul_snapshot('lakesoul.default.{}', to_timestamp_millis({})) s \
+                where s.id > 1",
+                table_name, timestamps[1]
+            ),
+        )
+        .await?
+        .collect()
+        .await?;
+        assert_batches_eq(
+            table_name,
+            &[
+                "+----+-------+",
+                "| id | value |",
+                "+----+-------+",
+                "| 2  | 2     |",
+                "| 3  | 3     |",
+                "+----+-------+",
+            ],
+            &results,
+        );
+
+        assert!(sql(&ctx, "select * from lakesoul_snapshot('no_such_table', 0)")
+            .await
+            .is_err());
+        assert!(sql(
+            &ctx,
+            &format!("select * from lakesoul_snapshot('lakesoul.default.{table_name}')")
+        )
+        .await
+        .is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_all_cases() -> Result<()> {
+        test_read_as_of_version().await?;
+        test_read_as_of_timestamp().await?;
+        test_snapshot_table_function().await?;
+        Ok(())
+    }
+}
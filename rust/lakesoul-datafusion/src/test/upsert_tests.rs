@@ -5,10 +5,12 @@ mod upsert_with_io_config_tests {
     use chrono::naive::NaiveDate;
     use std::env;
     use std::path::PathBuf;
-    use std::sync::Arc;
     use std::time::SystemTime;
 
-    use arrow::array::{ArrayRef, Int32Array, StringArray, TimestampMicrosecondArray};
+    use crate::test::utils::{
+        create_batch_i32, create_batch_i32_and_timestamp, create_batch_optional_i32, create_batch_str_or_i32,
+        create_batch_string, StrOrI32,
+    };
     use arrow::datatypes::{Field, Schema, SchemaRef, TimeUnit};
     use arrow::record_batch::RecordBatch;
     use datafusion::assert_batches_eq;
@@ -17,11 +19,6 @@ mod upsert_with_io_config_tests {
     use lakesoul_io::lakesoul_writer::SyncSendableMutableLakeSoulWriter;
     use tokio::runtime::Builder;
 
-    enum StrOrI32 {
-        V1(&'static str),
-        V2(i32),
-    }
-
     fn init_table(batch: RecordBatch, table_name: &str, pks: Vec<String>) -> LakeSoulIOConfigBuilder {
         let builder = LakeSoulIOConfigBuilder::new()
             .with_schema(batch.schema())
@@ -93,96 +90,6 @@ mod upsert_with_io_config_tests {
         builder
     }
 
-    fn create_batch_i32(names: Vec<&str>, values: Vec<&[i32]>) -> RecordBatch {
-        let values = values
-            .into_iter()
-            .map(|vec| Arc::new(Int32Array::from(Vec::from(vec))) as ArrayRef)
-            .collect::<Vec<ArrayRef>>();
-        let iter = names
-            .into_iter()
-            .zip(values)
-            .map(|(name, array)| (name, array, true))
-            .collect::<Vec<_>>();
-        RecordBatch::try_from_iter_with_nullable(iter).unwrap()
-    }
-
-    fn create_batch_optional_i32(names: Vec<&str>, values: Vec<&[Option<i32>]>) -> RecordBatch {
-        let values = values
-            .into_iter()
-            .map(|vec| Arc::new(Int32Array::from(Vec::from(vec))) as ArrayRef)
-            .collect::<Vec<ArrayRef>>();
-        let iter = names
-            .into_iter()
-            .zip(values)
-            .map(|(name, array)| (name, array, true))
-            .collect::<Vec<_>>();
-        RecordBatch::try_from_iter_with_nullable(iter).unwrap()
-    }
-
-    fn create_batch_string(names: Vec<&str>, values: Vec<&[&str]>) -> RecordBatch {
-        let values = values
-            .into_iter()
-            .map(|vec| Arc::new(StringArray::from(Vec::from(vec))) as ArrayRef)
-            .collect::<Vec<ArrayRef>>();
-        let iter = names
-            .into_iter()
-            .zip(values)
-            .map(|(name, array)| (name, array, true))
-            .collect::<Vec<_>>();
-        RecordBatch::try_from_iter_with_nullable(iter).unwrap()
-    }
-
-    fn create_batch_i32_and_timestamp(names: Vec<&str>, values: Vec<&[i32]>, timestamp: Vec<i64>) -> RecordBatch {
-        let mut values = values
-            .into_iter()
-            .map(|vec| Arc::new(Int32Array::from(Vec::from(vec))) as ArrayRef)
-            .collect::<Vec<ArrayRef>>();
-        let timestamp = Arc::new(TimestampMicrosecondArray::from(timestamp)) as ArrayRef;
-        values.push(timestamp);
-        let iter = names
-            .into_iter()
-            .zip(values)
-            .map(|(name, array)| (name, array, true))
-            .collect::<Vec<_>>();
-        RecordBatch::try_from_iter_with_nullable(iter).unwrap()
-    }
-
-    fn create_batch_str_or_i32(names: Vec<&str>, values: Vec<&[StrOrI32]>) -> RecordBatch {
-        let values = values
-            .into_iter()
-            .map(|vec| match vec[0] {
-                StrOrI32::V1(_) => {
-                    let vec = vec
-                        .iter()
-                        .map(|val| match val {
-                            StrOrI32::V1(v1) => Some(*v1),
-                            StrOrI32::V2(_v2) => None,
-                        })
-                        .map(|val| val.unwrap())
-                        .collect::<Vec<&str>>();
-                    Arc::new(StringArray::from(vec)) as ArrayRef
-                }
-                StrOrI32::V2(_) => {
-                    let vec = vec
-                        .iter()
-                        .map(|val| match val {
-                            StrOrI32::V1(_v1) => None,
-                            StrOrI32::V2(v2) => Some(v2),
-                        })
-                        .map(|val| *val.unwrap())
-                        .collect::<Vec<i32>>();
-                    Arc::new(Int32Array::from(vec)) as ArrayRef
-                }
-            })
-            .collect::<Vec<ArrayRef>>();
-        let iter = names
-            .into_iter()
-            .zip(values)
-            .map(|(name, array)| (name, array, true))
-            .collect::<Vec<_>>();
-        RecordBatch::try_from_iter_with_nullable(iter).unwrap()
-    }
-
     fn check_upsert_i32_and_timestamp(
         batch: RecordBatch,
         table_name: &str,
@@ -1567,7 +1474,6 @@ mod upsert_with_metadata_tests {
 
     use arrow::datatypes::DataType;
 
-    use arrow::array::{ArrayRef, Int32Array, StringArray, TimestampMicrosecondArray};
     use arrow::datatypes::{Field, Schema, SchemaRef, TimeUnit};
     use arrow::record_batch::RecordBatch;
 
@@ -1580,101 +1486,10 @@ mod upsert_with_metadata_tests {
     use lakesoul_metadata::{MetaDataClient, MetaDataClientRef};
 
     use crate::catalog::{create_io_config_builder, create_table};
-
-    enum StrOrI32 {
-        V1(&'static str),
-        V2(i32),
-    }
-
-    fn create_batch_i32(names: Vec<&str>, values: Vec<&[i32]>) -> RecordBatch {
-        let values = values
-            .into_iter()
-            .map(|vec| Arc::new(Int32Array::from(Vec::from(vec))) as ArrayRef)
-            .collect::<Vec<ArrayRef>>();
-        let iter = names
-            .into_iter()
-            .zip(values)
-            .map(|(name, array)| (name, array, true))
-            .collect::<Vec<_>>();
-        RecordBatch::try_from_iter_with_nullable(iter).unwrap()
-    }
-
-    fn create_batch_optional_i32(names: Vec<&str>, values: Vec<&[Option<i32>]>) -> RecordBatch {
-        let values = values
-            .into_iter()
-            .map(|vec| Arc::new(Int32Array::from(Vec::from(vec))) as ArrayRef)
-            .collect::<Vec<ArrayRef>>();
-        let iter = names
-            .into_iter()
-            .zip(values)
-            .map(|(name, array)| (name, array, true))
-            .collect::<Vec<_>>();
-        RecordBatch::try_from_iter_with_nullable(iter).unwrap()
-    }
-
-    fn create_batch_i32_and_timestamp(names: Vec<&str>, values: Vec<&[i32]>, timestamp: Vec<i64>) -> RecordBatch {
-        let mut values = values
-            .into_iter()
-            .map(|vec| Arc::new(Int32Array::from(Vec::from(vec))) as ArrayRef)
-            .collect::<Vec<ArrayRef>>();
-        let timestamp = Arc::new(TimestampMicrosecondArray::from(timestamp)) as ArrayRef;
-        values.push(timestamp);
-        let iter = names
-            .into_iter()
-            .zip(values)
-            .map(|(name, array)| (name, array, true))
-            .collect::<Vec<_>>();
-        RecordBatch::try_from_iter_with_nullable(iter).unwrap()
-    }
-
-    fn create_batch_string(names: Vec<&str>, values: Vec<&[&str]>) -> RecordBatch {
-        let values = values
-            .into_iter()
-            .map(|vec| Arc::new(StringArray::from(Vec::from(vec))) as ArrayRef)
-            .collect::<Vec<ArrayRef>>();
-        let iter = names
-            .into_iter()
-            .zip(values)
-            .map(|(name, array)| (name, array, true))
-            .collect::<Vec<_>>();
-        RecordBatch::try_from_iter_with_nullable(iter).unwrap()
-    }
-
-    fn create_batch_str_or_i32(names: Vec<&str>, values: Vec<&[StrOrI32]>) -> RecordBatch {
-        let values = values
-            .into_iter()
-            .map(|vec| match vec[0] {
-                StrOrI32::V1(_) => {
-                    let vec = vec
-                        .iter()
-                        .map(|val| match val {
-                            StrOrI32::V1(v1) => Some(*v1),
-                            StrOrI32::V2(_v2) => None,
-                        })
-                        .map(|val| val.unwrap())
-                        .collect::<Vec<&str>>();
-                    Arc::new(StringArray::from(vec)) as ArrayRef
-                }
-                StrOrI32::V2(_) => {
-                    let vec = vec
-                        .iter()
-                        .map(|val| match val {
-                            StrOrI32::V1(_v1) => None,
-                            StrOrI32::V2(v2) => Some(v2),
-                        })
-                        .map(|val| *val.unwrap())
-                        .collect::<Vec<i32>>();
-                    Arc::new(Int32Array::from(vec)) as ArrayRef
-                }
-            })
-            .collect::<Vec<ArrayRef>>();
-        let iter = names
-            .into_iter()
-            .zip(values)
-            .map(|(name, array)| (name, array, true))
-            .collect::<Vec<_>>();
-        RecordBatch::try_from_iter_with_nullable(iter).unwrap()
-    }
+    use crate::test::utils::{
+        create_batch_i32, create_batch_i32_and_timestamp, create_batch_optional_i32, create_batch_str_or_i32,
+        create_batch_string, StrOrI32,
+    };
 
     async fn execute_upsert(record_batch: RecordBatch, table_name: &str, _client: MetaDataClientRef) -> Result<()> {
         let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
@@ -0,0 +1,161 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The fixtures shared by the tests.
+
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int32Array, StringArray, TimestampMicrosecondArray};
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use datafusion::execution::context::SessionContext;
+use lakesoul_io::lakesoul_io_config::{
+    create_session_context, create_session_context_with_planner, LakeSoulIOConfigBuilder,
+};
+use lakesoul_metadata::MetaDataClientRef;
+
+use crate::catalog::{create_io_config_builder, create_table, LakeSoulCatalog};
+use crate::error::Result;
+use crate::lakesoul_table::LakeSoulTable;
+use crate::planner::query_planner::LakeSoulQueryPlanner;
+use crate::planner::sql;
+use crate::test::assert_batches_eq;
+
+pub enum StrOrI32 {
+    V1(&'static str),
+    V2(i32),
+}
+
+pub fn create_batch_i32(names: Vec<&str>, values: Vec<&[i32]>) -> RecordBatch {
+    let iter = names
+        .into_iter()
+        .zip(values)
+        .map(|(name, vec)| (name, Arc::new(Int32Array::from(Vec::from(vec))) as ArrayRef, true))
+        .collect::<Vec<_>>();
+    RecordBatch::try_from_iter_with_nullable(iter).unwrap()
+}
+
+pub fn create_batch_optional_i32(names: Vec<&str>, values: Vec<&[Option<i32>]>) -> RecordBatch {
+    let iter = names
+        .into_iter()
+        .zip(values)
+        .map(|(name, vec)| (name, Arc::new(Int32Array::from(Vec::from(vec))) as ArrayRef, true))
+        .collect::<Vec<_>>();
+    RecordBatch::try_from_iter_with_nullable(iter).unwrap()
+}
+
+pub fn create_batch_string(names: Vec<&str>, values: Vec<&[&str]>) -> RecordBatch {
+    let iter = names
+        .into_iter()
+        .zip(values)
+        .map(|(name, vec)| (name, Arc::new(StringArray::from(Vec::from(vec))) as ArrayRef, true))
+        .collect::<Vec<_>>();
+    RecordBatch::try_from_iter_with_nullable(iter).unwrap()
+}
+
+/// A batch of the i32 columns `values` followed by a timestamp column in microseconds.
+pub fn create_batch_i32_and_timestamp(names: Vec<&str>, values: Vec<&[i32]>, timestamp: Vec<i64>) -> RecordBatch {
+    let mut values = values
+        .into_iter()
+        .map(|vec| Arc::new(Int32Array::from(Vec::from(vec))) as ArrayRef)
+        .collect::<Vec<ArrayRef>>();
+    values.push(Arc::new(TimestampMicrosecondArray::from(timestamp)) as ArrayRef);
+    let iter = names
+        .into_iter()
+        .zip(values)
+        .map(|(name, array)| (name, array, true))
+        .collect::<Vec<_>>();
+    RecordBatch::try_from_iter_with_nullable(iter).unwrap()
+}
+
+/// A batch of string or i32 columns, by the type of the first value of each column.
+pub fn create_batch_str_or_i32(names: Vec<&str>, values: Vec<&[StrOrI32]>) -> RecordBatch {
+    let values = values
+        .into_iter()
+        .map(|vec| match vec[0] {
+            StrOrI32::V1(_) => Arc::new(StringArray::from(
+                vec.iter()
+                    .map(|val| match val {
+                        StrOrI32::V1(v1) => *v1,
+                        StrOrI32::V2(_) => panic!("mixed string and i32 values"),
+                    })
+                    .collect::<Vec<&str>>(),
+            )) as ArrayRef,
+            StrOrI32::V2(_) => Arc::new(Int32Array::from(
+                vec.iter()
+                    .map(|val| match val {
+                        StrOrI32::V1(_) => panic!("mixed string and i32 values"),
+                        StrOrI32::V2(v2) => *v2,
+                    })
+                    .collect::<Vec<i32>>(),
+            )) as ArrayRef,
+        })
+        .collect::<Vec<ArrayRef>>();
+    let iter = names
+        .into_iter()
+        .zip(values)
+        .map(|(name, array)| (name, array, true))
+        .collect::<Vec<_>>();
+    RecordBatch::try_from_iter_with_nullable(iter).unwrap()
+}
+
+/// A session with the LakeSoul planner and the `lakesoul` catalog of `client`.
+/// The catalog blocks on the metadata in its sync methods, so the session is used in a multi-thread runtime,
+/// see [crate::catalog::block_on].
+pub async fn create_context(client: MetaDataClientRef) -> Result<Arc<SessionContext>> {
+    let builder = create_io_config_builder(client.clone(), None, false, "default").await?;
+    let sess_ctx = Arc::new(create_session_context_with_planner(
+        &mut builder.build(),
+        Some(LakeSoulQueryPlanner::new_ref()),
+    )?);
+    sess_ctx.register_catalog("lakesoul", Arc::new(LakeSoulCatalog::new(client, sess_ctx.clone())));
+    Ok(sess_ctx)
+}
+
+/// Create table `table_name` of `schema` in the default namespace.
+pub async fn init_table(
+    client: MetaDataClientRef,
+    schema: SchemaRef,
+    table_name: &str,
+    range_partitions: Vec<&str>,
+    primary_keys: Vec<&str>,
+) -> Result<()> {
+    let builder = LakeSoulIOConfigBuilder::new()
+        .with_schema(schema)
+        .with_range_partitions(range_partitions.into_iter().map(String::from).collect())
+        .with_primary_keys(primary_keys.into_iter().map(String::from).collect());
+    create_table(client, table_name, builder.build()).await
+}
+
+pub async fn execute_upsert(record_batch: RecordBatch, table_name: &str) -> Result<()> {
+    let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+    lakesoul_table.execute_upsert(record_batch).await
+}
+
+/// Execute `sql_text` in a session of [create_context].
+pub async fn execute_sql(client: MetaDataClientRef, sql_text: &str) -> Result<()> {
+    let sess_ctx = create_context(client).await?;
+    sql(&sess_ctx, sql_text).await?.collect().await?;
+    Ok(())
+}
+
+/// Check the `selected_cols` of table `table_name` against `expected`, ignoring the order of rows.
+pub async fn check_table(
+    client: MetaDataClientRef,
+    table_name: &str,
+    selected_cols: Vec<&str>,
+    expected: &[&str],
+) -> Result<()> {
+    let lakesoul_table = LakeSoulTable::for_name(table_name).await?;
+    let builder = create_io_config_builder(client, None, false, "default").await?;
+    let sess_ctx = create_session_context(&mut builder.build())?;
+    let results = lakesoul_table
+        .to_dataframe(&sess_ctx)
+        .await?
+        .select_columns(&selected_cols)?
+        .collect()
+        .await?;
+    assert_batches_eq(table_name, expected, &results);
+    Ok(())
+}
@@ -6,10 +6,10 @@ use std::any::Any;
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use arrow::datatypes::SchemaRef;
+use arrow::datatypes::{FieldRef, Schema, SchemaRef};
 
 use datafusion::datasource::file_format::{parquet::ParquetFormat, FileFormat};
-use datafusion::datasource::physical_plan::{FileScanConfig, FileSinkConfig};
+use datafusion::datasource::physical_plan::{FileScanConfig, FileSinkConfig, ParquetExec};
 use datafusion::execution::context::SessionState;
 
 use datafusion::physical_expr::PhysicalSortRequirement;
@@ -17,6 +17,7 @@ use datafusion::physical_plan::projection::ProjectionExec;
 use datafusion::physical_plan::{ExecutionPlan, PhysicalExpr};
 use datafusion_common::{project_schema, FileType, Result, Statistics};
 
+use futures::{StreamExt, TryStreamExt};
 use object_store::{ObjectMeta, ObjectStore};
 
 use async_trait::async_trait;
@@ -67,7 +68,7 @@ impl FileFormat for LakeSoulParquetFormat {
         store: &Arc<dyn ObjectStore>,
         objects: &[ObjectMeta],
     ) -> Result<SchemaRef> {
-        self.parquet_format.infer_schema(state, store, objects).await
+        infer_evolved_schema(&self.parquet_format, state, store, objects).await
     }
 
     async fn infer_stats(
@@ -157,6 +158,102 @@ impl FileFormat for LakeSoulParquetFormat {
     }
 }
 
+/// The number of the files of which the schema is fetched concurrently, as [ParquetFormat::infer_schema] does.
+const SCHEMA_INFERENCE_CONCURRENCY: usize = 32;
+
+/// Infer the schema of the parquet files `objects` of a table, whose schema may have evolved between the files.
+///
+/// Unlike [ParquetFormat::infer_schema], the schemas of the files are not merged strictly,
+/// as a column has a different type in the files written before and after widening it.
+/// The type in the last file is kept, which is overridden by the target schema on reading.
+pub async fn infer_evolved_schema(
+    format: &ParquetFormat,
+    state: &SessionState,
+    store: &Arc<dyn ObjectStore>,
+    objects: &[ObjectMeta],
+) -> Result<SchemaRef> {
+    let schemas = futures::stream::iter(objects)
+        .map(|object| format.infer_schema(state, store, std::slice::from_ref(object)))
+        .buffered(SCHEMA_INFERENCE_CONCURRENCY)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let mut fields: Vec<FieldRef> = vec![];
+    for schema in &schemas {
+        for field in schema.fields() {
+            match fields.iter().position(|merged| merged.name() == field.name()) {
+                Some(idx) => {
+                    let nullable = field.is_nullable() || fields[idx].is_nullable();
+                    fields[idx] = Arc::new(field.as_ref().clone().with_nullable(nullable));
+                }
+                None => fields.push(field.clone()),
+            }
+        }
+    }
+    Ok(Arc::new(Schema::new(fields)))
+}
+
+/// The columns of a file written before renaming, i.e. `(index, current name)` of the fields of `file_schema`
+/// named by a previous name of a column in `table_schema`, which is not in `file_schema` by its current name.
+pub fn renamed_file_columns(
+    file_schema: &Schema,
+    table_schema: &Schema,
+    renamed_columns: &HashMap<String, String>,
+) -> Vec<(usize, String)> {
+    file_schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, field)| {
+            renamed_columns
+                .get(field.name())
+                .filter(|current| {
+                    table_schema.field_with_name(current).is_ok() && file_schema.field_with_name(current).is_err()
+                })
+                .map(|current| (idx, current.clone()))
+        })
+        .collect()
+}
+
+/// Create the [ParquetExec] of a single file, in which the columns renamed after writing the file
+/// are read by their previous names and aliased to the current names.
+pub fn create_parquet_exec(
+    mut config: FileScanConfig,
+    predicate: Option<Arc<dyn PhysicalExpr>>,
+    metadata_size_hint: Option<usize>,
+    table_schema: &Schema,
+    renamed_columns: &HashMap<String, String>,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let renamed = renamed_file_columns(&config.file_schema, table_schema, renamed_columns);
+    if renamed.is_empty() {
+        return Ok(Arc::new(ParquetExec::new(config, predicate, metadata_size_hint)));
+    }
+    let aliases = renamed
+        .iter()
+        .map(|(idx, current)| (config.file_schema.field(*idx).name().clone(), current.clone()))
+        .collect::<HashMap<_, _>>();
+    if let Some(projection) = config.projection.as_mut() {
+        projection.extend(renamed.iter().map(|(idx, _)| *idx));
+        projection.sort_unstable();
+        projection.dedup();
+    }
+    // the predicate refers to the current names, which are not in the file
+    let parquet_exec: Arc<dyn ExecutionPlan> = Arc::new(ParquetExec::new(config, None, metadata_size_hint));
+    let file_schema = parquet_exec.schema();
+    let projection_expr = file_schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let name = aliases.get(field.name()).unwrap_or(field.name()).clone();
+            Ok((
+                datafusion::physical_expr::expressions::col(field.name(), &file_schema)?,
+                name,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Arc::new(ProjectionExec::try_new(projection_expr, parquet_exec)?))
+}
+
 pub async fn flatten_file_scan_config(
     state: &SessionState,
     format: Arc<ParquetFormat>,
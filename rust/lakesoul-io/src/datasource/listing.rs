@@ -68,8 +68,11 @@ impl LakeSoulListingTable {
     pub fn compute_table_schema(file_schema: SchemaRef, config: &LakeSoulIOConfig) -> SchemaRef {
         let target_schema = uniform_schema(config.target_schema());
         let mut builder = SchemaBuilder::from(target_schema.fields());
+        // the columns of the files written before renaming are read by their current names
         for field in file_schema.fields() {
-            if target_schema.field_with_name(field.name()).is_err() {
+            if target_schema.field_with_name(field.name()).is_err()
+                && !config.renamed_columns().contains_key(field.name())
+            {
                 builder.push(field.clone());
             }
         }
@@ -9,7 +9,7 @@ use arrow_schema::{Field, Schema, SchemaRef};
 use datafusion::dataframe::DataFrame;
 use datafusion::logical_expr::Expr;
 use datafusion::{
-    datasource::physical_plan::{FileScanConfig, ParquetExec},
+    datasource::physical_plan::FileScanConfig,
     execution::TaskContext,
     physical_expr::PhysicalSortExpr,
     physical_plan::{DisplayAs, DisplayFormatType, ExecutionPlan, PhysicalExpr, SendableRecordBatchStream},
@@ -18,6 +18,7 @@ use datafusion_common::{DFSchemaRef, DataFusionError, Result};
 use datafusion_substrait::substrait::proto::Plan;
 use log::debug;
 
+use crate::datasource::file_format::create_parquet_exec;
 use crate::default_column_stream::empty_schema_stream::EmptySchemaStream;
 use crate::default_column_stream::DefaultColumnStream;
 use crate::filter::parser::Parser as FilterParser;
@@ -46,7 +47,13 @@ impl MergeParquetExec {
         // source file parquet scan
         let mut inputs = Vec::<Arc<dyn ExecutionPlan>>::new();
         for config in flatten_configs {
-            let single_exec = Arc::new(ParquetExec::new(config, predicate.clone(), metadata_size_hint));
+            let single_exec = create_parquet_exec(
+                config,
+                predicate.clone(),
+                metadata_size_hint,
+                &schema,
+                io_config.renamed_columns(),
+            )?;
             inputs.push(single_exec);
         }
         let schema = SchemaRef::new(Schema::new(
@@ -90,6 +90,9 @@ pub struct LakeSoulIOConfig {
     // default column value
     pub(crate) default_column_value: HashMap<String, String>,
 
+    // previous name -> current name of the renamed columns, for reading the files written before renaming
+    pub(crate) renamed_columns: HashMap<String, String>,
+
     // tokio runtime related configs
     #[derivative(Default(value = "2"))]
     pub(crate) thread_num: usize,
@@ -126,6 +129,10 @@ impl LakeSoulIOConfig {
     pub fn aux_sort_cols_slice(&self) -> &[String] {
         &self.aux_sort_cols
     }
+
+    pub fn renamed_columns(&self) -> &HashMap<String, String> {
+        &self.renamed_columns
+    }
 }
 
 #[derive(Derivative, Debug)]
@@ -251,6 +258,11 @@ impl LakeSoulIOConfigBuilder {
         self
     }
 
+    pub fn with_renamed_column(mut self, previous_name: String, current_name: String) -> Self {
+        self.config.renamed_columns.insert(previous_name, current_name);
+        self
+    }
+
     pub fn with_object_store_option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.config.object_store_options.insert(key.into(), value.into());
         self
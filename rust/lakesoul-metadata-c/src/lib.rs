@@ -312,7 +312,11 @@ pub extern "C" fn create_tokio_postgres_client(
     let config = string_from_ptr(config);
     let runtime = unsafe { NonNull::new_unchecked(runtime.as_ref().ptr as *mut Runtime).as_ref() };
 
-    let result = runtime.block_on(async { lakesoul_metadata::create_connection(config).await });
+    // the TLS options of lakesoul_home apply unless given by the config
+    let result = runtime.block_on(async {
+        let options = lakesoul_metadata::tls::tls_options_from_env()?;
+        lakesoul_metadata::create_connection(lakesoul_metadata::tls::with_tls_options(config, &options)?).await
+    });
 
     let result = match result {
         Ok(client) => {
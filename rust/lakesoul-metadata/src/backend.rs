@@ -0,0 +1,160 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The storage of the metadata behind a [MetaDataClient](crate::MetaDataClient).
+//!
+//! A [MetaDataBackend] executes the DAO operations of [DaoType](crate::DaoType) by their type index,
+//! with the params joined by [PARAM_DELIM](crate::PARAM_DELIM) or the entities wrapped by a [JniWrapper],
+//! just like the JNI entry points. [PostgresBackend] runs them on Postgres, while
+//! [MemoryBackend](crate::memory_backend::MemoryBackend) keeps the metadata in memory,
+//! e.g. for tests and single-node tools.
+
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::ops::DerefMut;
+
+use async_trait::async_trait;
+use prost::Message;
+
+use proto::proto::entity::{JniWrapper, PartitionInfo};
+
+use crate::error::Result;
+use crate::notification::{subscribe_commit_notifications, CommitNotificationStream};
+use crate::pool::ConnectionPool;
+use crate::{
+    clean_meta_for_test, execute_insert, execute_query, execute_query_scalar, execute_update,
+    transaction_insert_partition_info,
+};
+
+/// The DAO operations of the metadata, see the [module docs](self).
+///
+/// A backend must insert the partition versions of `TransactionInsertPartitionInfo`, and the data commits of
+/// `TransactionInsertDataCommitInfo`, all or none in a transaction, returning 0 if any of them already exists,
+/// since the commit of [MetaDataClient](crate::MetaDataClient) detects a conflicting commit by that.
+#[async_trait]
+pub trait MetaDataBackend: Debug + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+
+    /// Maximum number of operations executed concurrently.
+    fn pool_size(&self) -> usize;
+
+    async fn execute_query(&self, query_type: i32, joined_string: String) -> Result<JniWrapper>;
+
+    async fn execute_insert(&self, insert_type: i32, wrapper: JniWrapper) -> Result<i32>;
+
+    async fn execute_update(&self, update_type: i32, joined_string: String) -> Result<i32>;
+
+    async fn execute_query_scalar(&self, query_type: i32, joined_string: String) -> Result<Option<String>>;
+
+    /// Delete all the metadata.
+    async fn clean_meta_for_test(&self) -> Result<i32>;
+
+    /// Insert the partition versions like `execute_insert` of `TransactionInsertPartitionInfo`,
+    /// notifying the subscribers of the versions if and only if they are committed, see [crate::notification].
+    async fn transaction_insert_partition_info_with_notification(
+        &self,
+        partition_info_list: Vec<PartitionInfo>,
+    ) -> Result<i32>;
+
+    async fn subscribe_commit_notifications(&self) -> Result<CommitNotificationStream>;
+}
+
+/// The metadata in Postgres, queried on a [ConnectionPool].
+pub struct PostgresBackend {
+    pool: ConnectionPool,
+}
+
+impl Debug for PostgresBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PostgresBackend")
+            .field("pool_size", &self.pool.pool_size())
+            .finish()
+    }
+}
+
+impl PostgresBackend {
+    /// Create a backend of the pg `config` with at most `pool_size` connections.
+    pub async fn try_new(config: String, pool_size: usize) -> Result<Self> {
+        Ok(Self {
+            pool: ConnectionPool::try_new(config, pool_size).await?,
+        })
+    }
+
+    /// A backend of `pool_size` connections of the same config, see [ConnectionPool::with_pool_size].
+    pub fn with_pool_size(&self, pool_size: usize) -> Result<Self> {
+        Ok(Self {
+            pool: self.pool.with_pool_size(pool_size)?,
+        })
+    }
+
+    pub fn pool(&self) -> &ConnectionPool {
+        &self.pool
+    }
+}
+
+#[async_trait]
+impl MetaDataBackend for PostgresBackend {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn pool_size(&self) -> usize {
+        self.pool.pool_size()
+    }
+
+    async fn execute_query(&self, query_type: i32, joined_string: String) -> Result<JniWrapper> {
+        let mut connection = self.pool.get().await?;
+        let connection = connection.deref_mut();
+        let encoded = execute_query(&connection.client, &mut connection.prepared, query_type, joined_string).await?;
+        Ok(JniWrapper::decode(prost::bytes::Bytes::from(encoded))?)
+    }
+
+    async fn execute_insert(&self, insert_type: i32, wrapper: JniWrapper) -> Result<i32> {
+        let mut connection = self.pool.get().await?;
+        let connection = connection.deref_mut();
+        execute_insert(&mut connection.client, &mut connection.prepared, insert_type, wrapper).await
+    }
+
+    async fn execute_update(&self, update_type: i32, joined_string: String) -> Result<i32> {
+        let mut connection = self.pool.get().await?;
+        let connection = connection.deref_mut();
+        execute_update(
+            &mut connection.client,
+            &mut connection.prepared,
+            update_type,
+            joined_string,
+        )
+        .await
+    }
+
+    async fn execute_query_scalar(&self, query_type: i32, joined_string: String) -> Result<Option<String>> {
+        let mut connection = self.pool.get().await?;
+        let connection = connection.deref_mut();
+        execute_query_scalar(
+            &mut connection.client,
+            &mut connection.prepared,
+            query_type,
+            joined_string,
+        )
+        .await
+    }
+
+    async fn clean_meta_for_test(&self) -> Result<i32> {
+        clean_meta_for_test(&self.pool.get().await?.client).await
+    }
+
+    async fn transaction_insert_partition_info_with_notification(
+        &self,
+        partition_info_list: Vec<PartitionInfo>,
+    ) -> Result<i32> {
+        let count =
+            transaction_insert_partition_info(&mut self.pool.get().await?.client, partition_info_list, true).await?;
+        Ok(count as i32)
+    }
+
+    /// Listen on a new connection of the same config.
+    async fn subscribe_commit_notifications(&self) -> Result<CommitNotificationStream> {
+        subscribe_commit_notifications(self.pool.config()).await
+    }
+}
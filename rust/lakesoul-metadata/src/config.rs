@@ -0,0 +1,442 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The configuration of the metadata, loaded by [MetaDataConfig::from_env] as the JVM clients do.
+//!
+//! The config file is located by the `LAKESOUL_HOME` env, case insensitive, e.g.
+//! ```properties
+//! lakesoul.pg.url=jdbc:postgresql://localhost:5432/lakesoul_test?stringtype=unspecified
+//! lakesoul.pg.username=lakesoul_test
+//! lakesoul.pg.password=lakesoul_test
+//! ```
+//! Each setting is taken from the first of
+//! 1. its property in the config file, e.g. `lakesoul.pg.url`,
+//! 2. its env, e.g. `LAKESOUL_PG_URL`,
+//! 3. for the user and the password only, the user info or the `user` and `password` parameters of the url,
+//!    which are percent-decoded,
+//! 4. its default, see [MetaDataConfig::default].
+//!
+//! | property | env | |
+//! |---|---|---|
+//! | `lakesoul.metadata.backend` | `LAKESOUL_METADATA_BACKEND` | `postgres` or `memory`, see [MetaDataBackendKind] |
+//! | `lakesoul.pg.url` | `LAKESOUL_PG_URL` | `[jdbc:]postgresql://host[:port]/dbname[?params]`, port 5432 by default |
+//! | `lakesoul.pg.username` | `LAKESOUL_PG_USERNAME` | |
+//! | `lakesoul.pg.password` | `LAKESOUL_PG_PASSWORD` | |
+//! | `lakesoul.pg.connect_timeout` | `LAKESOUL_PG_CONNECT_TIMEOUT` | in seconds, 0 or absent for none |
+//! | `lakesoul.pg.statement_timeout` | `LAKESOUL_PG_STATEMENT_TIMEOUT` | in milliseconds, 0 or absent for none |
+//! | `lakesoul.pg.sslmode`, etc. | `LAKESOUL_PG_SSLMODE`, etc. | see [crate::tls], also url parameters |
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use percent_encoding::percent_decode_str;
+use url::Url;
+
+use crate::error::{LakeSoulMetaDataError, Result};
+use crate::tls::TLS_OPTIONS;
+
+/// The env locating the config file, case insensitive.
+pub const LAKESOUL_HOME_ENV: &str = "LAKESOUL_HOME";
+
+pub const METADATA_BACKEND: &str = "lakesoul.metadata.backend";
+pub const PG_URL: &str = "lakesoul.pg.url";
+pub const PG_USERNAME: &str = "lakesoul.pg.username";
+pub const PG_PASSWORD: &str = "lakesoul.pg.password";
+pub const PG_CONNECT_TIMEOUT: &str = "lakesoul.pg.connect_timeout";
+pub const PG_STATEMENT_TIMEOUT: &str = "lakesoul.pg.statement_timeout";
+
+pub const DEFAULT_PG_URL: &str = "jdbc:postgresql://127.0.0.1:5432/lakesoul_test?stringtype=unspecified";
+pub const DEFAULT_PG_PORT: u16 = 5432;
+pub const DEFAULT_PG_USERNAME: &str = "lakesoul_test";
+pub const DEFAULT_PG_PASSWORD: &str = "lakesoul_test";
+
+/// Where the metadata is kept, see [crate::backend].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MetaDataBackendKind {
+    #[default]
+    Postgres,
+    /// The [MemoryBackend](crate::MemoryBackend) shared by the process.
+    Memory,
+}
+
+impl FromStr for MetaDataBackendKind {
+    type Err = LakeSoulMetaDataError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "postgres" => Ok(Self::Postgres),
+            "memory" => Ok(Self::Memory),
+            _ => Err(LakeSoulMetaDataError::Config(format!(
+                "invalid {} '{}', expected 'postgres' or 'memory'",
+                METADATA_BACKEND, s
+            ))),
+        }
+    }
+}
+
+/// The configuration of the metadata, see the [module docs](self).
+#[derive(Clone, PartialEq, Eq)]
+pub struct MetaDataConfig {
+    pub backend: MetaDataBackendKind,
+    pub host: String,
+    pub port: u16,
+    pub dbname: String,
+    pub user: String,
+    pub password: String,
+    pub connect_timeout: Option<Duration>,
+    pub statement_timeout: Option<Duration>,
+    /// The TLS options of the connection string, e.g. `("sslmode", "verify-full")`, see [crate::tls].
+    pub tls_options: Vec<(String, String)>,
+}
+
+impl Debug for MetaDataConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetaDataConfig")
+            .field("backend", &self.backend)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("dbname", &self.dbname)
+            .field("user", &self.user)
+            .field("password", &"***")
+            .field("connect_timeout", &self.connect_timeout)
+            .field("statement_timeout", &self.statement_timeout)
+            .field("tls_options", &self.tls_options)
+            .finish()
+    }
+}
+
+impl Default for MetaDataConfig {
+    /// The local database of the quick start.
+    fn default() -> Self {
+        Self {
+            backend: MetaDataBackendKind::default(),
+            host: "127.0.0.1".to_string(),
+            port: DEFAULT_PG_PORT,
+            dbname: "lakesoul_test".to_string(),
+            user: DEFAULT_PG_USERNAME.to_string(),
+            password: DEFAULT_PG_PASSWORD.to_string(),
+            connect_timeout: None,
+            statement_timeout: None,
+            tls_options: vec![],
+        }
+    }
+}
+
+impl MetaDataConfig {
+    /// Load the config file of the `LAKESOUL_HOME` env, if set, and the env, see the [module docs](self).
+    pub fn from_env() -> Result<Self> {
+        let properties = match config_path() {
+            Some(path) => std::fs::read_to_string(&path).map_err(|e| {
+                LakeSoulMetaDataError::Config(format!(
+                    "failed to read the config file {} of {}: {}",
+                    path.display(),
+                    LAKESOUL_HOME_ENV,
+                    e
+                ))
+            })?,
+            None => String::new(),
+        };
+        Self::load(&properties, |name| std::env::var(name).ok())
+    }
+
+    /// Load the config file `properties` only, ignoring the env.
+    pub fn from_properties(properties: &str) -> Result<Self> {
+        Self::load(properties, |_| None)
+    }
+
+    /// Load the config file `properties`, falling back to `env` by the env name of each property.
+    pub fn load(properties: &str, env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let properties = parse_properties(properties);
+        let setting = |name: &str| {
+            properties
+                .get(name)
+                .cloned()
+                .or_else(|| env(&env_name(name)))
+                .filter(|value| !value.is_empty())
+        };
+
+        let backend = match setting(METADATA_BACKEND) {
+            Some(backend) => backend.parse()?,
+            None => MetaDataBackendKind::default(),
+        };
+        let url = setting(PG_URL).unwrap_or_else(|| DEFAULT_PG_URL.to_string());
+        let pg_url = PgUrl::parse(&url)?;
+        let timeout = |name: &str, unit: &str, duration: fn(u64) -> Duration| -> Result<Option<Duration>> {
+            match setting(name) {
+                Some(value) => match value.parse::<u64>() {
+                    Ok(0) => Ok(None),
+                    Ok(n) => Ok(Some(duration(n))),
+                    Err(_) => Err(LakeSoulMetaDataError::Config(format!(
+                        "invalid {} '{}', expected a number of {}",
+                        name, value, unit
+                    ))),
+                },
+                None => Ok(None),
+            }
+        };
+        let connect_timeout = timeout(PG_CONNECT_TIMEOUT, "seconds", Duration::from_secs)?;
+        let statement_timeout = timeout(PG_STATEMENT_TIMEOUT, "milliseconds", Duration::from_millis)?;
+        let tls_options = TLS_OPTIONS
+            .iter()
+            .filter_map(|(option, name)| {
+                setting(name)
+                    .or_else(|| pg_url.param(option))
+                    .map(|value| (option.to_string(), value))
+            })
+            .collect();
+
+        Ok(Self {
+            backend,
+            user: setting(PG_USERNAME)
+                .or(pg_url.user)
+                .unwrap_or_else(|| DEFAULT_PG_USERNAME.to_string()),
+            password: setting(PG_PASSWORD)
+                .or(pg_url.password)
+                .unwrap_or_else(|| DEFAULT_PG_PASSWORD.to_string()),
+            host: pg_url.host,
+            port: pg_url.port,
+            dbname: pg_url.dbname,
+            connect_timeout,
+            statement_timeout,
+            tls_options,
+        })
+    }
+
+    /// The connection string of tokio_postgres, with the TLS options of [crate::tls].
+    pub fn pg_config(&self) -> String {
+        let mut options = vec![
+            ("host", self.host.clone()),
+            ("port", self.port.to_string()),
+            ("dbname", self.dbname.clone()),
+            ("user", self.user.clone()),
+            ("password", self.password.clone()),
+        ];
+        if let Some(timeout) = self.connect_timeout {
+            options.push(("connect_timeout", timeout.as_secs().to_string()));
+        }
+        if let Some(timeout) = self.statement_timeout {
+            options.push(("options", format!("-c statement_timeout={}", timeout.as_millis())));
+        }
+        options.extend(
+            self.tls_options
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.clone())),
+        );
+        options
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, quote(value)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// The parts of `lakesoul.pg.url` in the connection string.
+struct PgUrl {
+    host: String,
+    port: u16,
+    dbname: String,
+    user: Option<String>,
+    password: Option<String>,
+    params: Vec<(String, String)>,
+}
+
+impl PgUrl {
+    fn parse(url: &str) -> Result<Self> {
+        let invalid = |reason: &str| LakeSoulMetaDataError::Config(format!("invalid {} '{}': {}", PG_URL, url, reason));
+        let parsed = Url::parse(url.strip_prefix("jdbc:").unwrap_or(url)).map_err(|e| invalid(&e.to_string()))?;
+        if !matches!(parsed.scheme(), "postgresql" | "postgres") {
+            return Err(invalid("expected a postgresql:// url"));
+        }
+        let host = match parsed.host_str() {
+            Some(host) if !host.is_empty() => host.trim_start_matches('[').trim_end_matches(']').to_string(),
+            _ => return Err(invalid("missing host")),
+        };
+        let dbname = match parsed.path_segments().and_then(|mut segments| segments.next()) {
+            Some(dbname) if !dbname.is_empty() => decode(dbname)?,
+            _ => return Err(invalid("missing database name")),
+        };
+        let params = parsed
+            .query_pairs()
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect::<Vec<_>>();
+        let param = |key: &str| params.iter().find(|(k, _)| k == key).map(|(_, value)| value.clone());
+        let user = match parsed.username() {
+            "" => param("user"),
+            user => Some(decode(user)?),
+        };
+        let password = match parsed.password() {
+            Some(password) => Some(decode(password)?),
+            None => param("password"),
+        };
+        Ok(Self {
+            host,
+            port: parsed.port().unwrap_or(DEFAULT_PG_PORT),
+            dbname,
+            user,
+            password,
+            params,
+        })
+    }
+
+    fn param(&self, key: &str) -> Option<String> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+/// The config file of the `LAKESOUL_HOME` env.
+fn config_path() -> Option<PathBuf> {
+    std::env::vars_os()
+        .find(|(key, _)| {
+            key.to_str()
+                .map_or(false, |key| key.eq_ignore_ascii_case(LAKESOUL_HOME_ENV))
+        })
+        .map(|(_, value)| PathBuf::from(value))
+}
+
+/// The env of the property `name`, e.g. `LAKESOUL_PG_URL` of `lakesoul.pg.url`.
+fn env_name(name: &str) -> String {
+    name.replace('.', "_").to_uppercase()
+}
+
+/// Parse the `key=value` or `key:value` lines of a properties file, skipping the comments by `#` or `!`.
+/// The last one of the duplicated keys wins.
+fn parse_properties(properties: &str) -> HashMap<String, String> {
+    properties
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))
+        .filter_map(|line| {
+            line.find(|c| c == '=' || c == ':')
+                .map(|idx| (line[..idx].trim().to_string(), line[idx + 1..].trim().to_string()))
+        })
+        .collect()
+}
+
+fn decode(s: &str) -> Result<String> {
+    Ok(percent_decode_str(s)
+        .decode_utf8()
+        .map_err(|e| LakeSoulMetaDataError::Config(format!("invalid percent-encoded '{}': {}", s, e)))?
+        .into_owned())
+}
+
+/// Quote a value of the connection string as parsed by tokio_postgres.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use super::{MetaDataBackendKind, MetaDataConfig};
+
+    #[test]
+    fn test_default() {
+        let config = MetaDataConfig::from_properties("").unwrap();
+        assert_eq!(config, MetaDataConfig::default());
+        assert_eq!(
+            config.pg_config(),
+            "host='127.0.0.1' port='5432' dbname='lakesoul_test' user='lakesoul_test' password='lakesoul_test'"
+        );
+    }
+
+    #[test]
+    fn test_properties() {
+        let config = MetaDataConfig::from_properties(
+            "# comment\n\
+            lakesoul.pg.driver=com.lakesoul.shaded.org.postgresql.Driver\n\
+            lakesoul.pg.url = jdbc:postgresql://db.example.com/lakesoul?stringtype=unspecified&sslmode=require\n\
+            lakesoul.pg.username=lakesoul\n\
+            lakesoul.pg.password=p@ss word's=\n\
+            lakesoul.pg.connect_timeout=10\n\
+            lakesoul.pg.statement_timeout=0\n\
+            lakesoul.pg.sslrootcert=/etc/lakesoul/ca.pem\n",
+        )
+        .unwrap();
+        assert_eq!(config.host, "db.example.com");
+        assert_eq!(config.port, 5432);
+        assert_eq!(config.dbname, "lakesoul");
+        assert_eq!(config.user, "lakesoul");
+        assert_eq!(config.password, "p@ss word's=");
+        assert_eq!(config.connect_timeout, Some(Duration::from_secs(10)));
+        assert_eq!(config.statement_timeout, None);
+        assert_eq!(
+            config.tls_options,
+            vec![
+                ("sslmode".to_string(), "require".to_string()),
+                ("sslrootcert".to_string(), "/etc/lakesoul/ca.pem".to_string()),
+            ]
+        );
+        assert_eq!(
+            config.pg_config(),
+            "host='db.example.com' port='5432' dbname='lakesoul' user='lakesoul' password='p@ss word\\'s=' \
+            connect_timeout='10' sslmode='require' sslrootcert='/etc/lakesoul/ca.pem'"
+        );
+        assert!(!format!("{:?}", config).contains("p@ss"));
+    }
+
+    #[test]
+    fn test_url_user_info() {
+        let config =
+            MetaDataConfig::from_properties("lakesoul.pg.url=postgresql://lake%40soul:p%40ss%3Aword@[::1]:5433/meta")
+                .unwrap();
+        assert_eq!(config.host, "::1");
+        assert_eq!(config.port, 5433);
+        assert_eq!(config.user, "lake@soul");
+        assert_eq!(config.password, "p@ss:word");
+
+        let config = MetaDataConfig::from_properties(
+            "lakesoul.pg.url=jdbc:postgresql://127.0.0.1/meta?user=lakesoul&password=a%26b\n\
+            lakesoul.pg.username=admin",
+        )
+        .unwrap();
+        assert_eq!(config.user, "admin");
+        assert_eq!(config.password, "a&b");
+    }
+
+    #[test]
+    fn test_env() {
+        let env = HashMap::from([
+            ("LAKESOUL_PG_URL", "jdbc:postgresql://env-host:6432/env_db"),
+            ("LAKESOUL_PG_PASSWORD", "env_password"),
+            ("LAKESOUL_PG_STATEMENT_TIMEOUT", "30000"),
+            ("LAKESOUL_METADATA_BACKEND", "memory"),
+        ]);
+        let config = MetaDataConfig::load("lakesoul.pg.password=file_password", |name| {
+            env.get(name).map(|value| value.to_string())
+        })
+        .unwrap();
+        assert_eq!(config.backend, MetaDataBackendKind::Memory);
+        assert_eq!(config.host, "env-host");
+        assert_eq!(config.port, 6432);
+        assert_eq!(config.dbname, "env_db");
+        assert_eq!(config.user, "lakesoul_test");
+        // the config file takes precedence over the env
+        assert_eq!(config.password, "file_password");
+        assert_eq!(config.statement_timeout, Some(Duration::from_secs(30)));
+        assert!(config.pg_config().contains("options='-c statement_timeout=30000'"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for properties in [
+            "lakesoul.pg.url=jdbc:mysql://127.0.0.1:3306/lakesoul",
+            "lakesoul.pg.url=jdbc:postgresql://127.0.0.1:5432",
+            "lakesoul.pg.url=127.0.0.1:5432/lakesoul",
+            "lakesoul.pg.connect_timeout=10s",
+            "lakesoul.metadata.backend=sqlite",
+        ] {
+            let e = MetaDataConfig::from_properties(properties).unwrap_err();
+            let key = properties.split_once('=').unwrap().0;
+            assert!(e.to_string().contains(key), "{}", e);
+        }
+    }
+}
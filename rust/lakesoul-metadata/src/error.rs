@@ -5,6 +5,7 @@
 use std::{io, num, result, sync::Arc};
 
 use thiserror::Error;
+use tokio_postgres::error::SqlState;
 
 /// Result type for operations that could result in an [LakeSoulMetaDataError]
 pub type Result<T, E = LakeSoulMetaDataError> = result::Result<T, E>;
@@ -41,12 +42,106 @@ pub enum LakeSoulMetaDataError {
     Internal(String),
     #[error("Not found error: {0}")]
     NotFound(String),
+    #[error("Already exists: {0}")]
+    AlreadyExists(String),
+    #[error("Commit conflict: {0}")]
+    CommitConflict(String),
+    #[error("Version expired: {0}")]
+    VersionExpired(String),
+    #[error("TLS error: {0}")]
+    Tls(String),
+    #[error("Config error: {0}")]
+    Config(String),
     #[error("Other error: {0}")]
     Other(#[from] GenericError),
 }
 
+impl LakeSoulMetaDataError {
+    /// Whether the error is transient, e.g. a lost connection, a serialization failure or a deadlock,
+    /// so that the operation may succeed when retried.
+    /// The other errors, e.g. a constraint violation or [Self::NotFound], are permanent.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::PostgresError(e) => is_transient_postgres_error(e),
+            Self::IoError(e) => is_transient_io_error(e),
+            _ => false,
+        }
+    }
+
+    /// Whether the error is a violation of a unique constraint, e.g. inserting a duplicate primary key.
+    pub fn is_unique_violation(&self) -> bool {
+        match self {
+            Self::PostgresError(e) => e.code() == Some(&SqlState::UNIQUE_VIOLATION),
+            Self::IoError(e) => e.kind() == io::ErrorKind::AlreadyExists,
+            _ => false,
+        }
+    }
+}
+
+fn is_transient_postgres_error(e: &tokio_postgres::Error) -> bool {
+    if e.is_closed() {
+        return true;
+    }
+    match e.code() {
+        Some(code) => [
+            SqlState::T_R_SERIALIZATION_FAILURE,
+            SqlState::T_R_DEADLOCK_DETECTED,
+            SqlState::CONNECTION_EXCEPTION,
+            SqlState::CONNECTION_DOES_NOT_EXIST,
+            SqlState::CONNECTION_FAILURE,
+            SqlState::SQLCLIENT_UNABLE_TO_ESTABLISH_SQLCONNECTION,
+            SqlState::ADMIN_SHUTDOWN,
+            SqlState::CRASH_SHUTDOWN,
+            SqlState::CANNOT_CONNECT_NOW,
+            SqlState::TOO_MANY_CONNECTIONS,
+        ]
+        .contains(code),
+        // an error without SQLSTATE is raised by the client, e.g. by the io of the connection
+        None => std::error::Error::source(e)
+            .and_then(|source| source.downcast_ref::<io::Error>())
+            .map_or(false, is_transient_io_error),
+    }
+}
+
+fn is_transient_io_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::UnexpectedEof
+    )
+}
+
 impl From<io::ErrorKind> for LakeSoulMetaDataError {
     fn from(kind: io::ErrorKind) -> Self {
         Self::from(io::Error::from(kind))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::LakeSoulMetaDataError;
+
+    #[test]
+    fn test_is_transient() {
+        assert!(LakeSoulMetaDataError::from(io::ErrorKind::ConnectionRefused).is_transient());
+        assert!(LakeSoulMetaDataError::from(io::ErrorKind::ConnectionReset).is_transient());
+        assert!(!LakeSoulMetaDataError::from(io::ErrorKind::InvalidInput).is_transient());
+        assert!(!LakeSoulMetaDataError::NotFound("table".to_string()).is_transient());
+        assert!(!LakeSoulMetaDataError::CommitConflict("table".to_string()).is_transient());
+    }
+
+    #[test]
+    fn test_is_unique_violation() {
+        assert!(LakeSoulMetaDataError::from(io::ErrorKind::AlreadyExists).is_unique_violation());
+        assert!(!LakeSoulMetaDataError::from(io::ErrorKind::NotFound).is_unique_violation());
+        assert!(!LakeSoulMetaDataError::AlreadyExists("tag".to_string()).is_unique_violation());
+    }
+}
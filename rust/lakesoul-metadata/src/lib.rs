@@ -11,15 +11,30 @@ pub use tokio::runtime::{Builder, Runtime};
 use tokio::spawn;
 pub use tokio_postgres::{Client, NoTls, Statement};
 use tokio_postgres::{Error, Row};
+use tracing::{error, warn};
 
+pub use backend::{MetaDataBackend, PostgresBackend};
+pub use config::{MetaDataBackendKind, MetaDataConfig};
 use error::{LakeSoulMetaDataError, Result};
-pub use metadata_client::{MetaDataClient, MetaDataClientRef};
+pub use memory_backend::MemoryBackend;
+pub use metadata_client::{
+    branch_table_id, split_branch_table_id, CommitHistory, MetaDataClient, MetaDataClientRef, BRANCH_TABLE_ID_DELIM,
+};
+use notification::notify_commits;
+pub use notification::{CommitNotification, CommitNotificationStream, COMMIT_NOTIFICATION_CHANNEL};
 use proto::proto::entity;
 
 pub mod transfusion;
 
+pub mod backend;
+pub mod config;
 pub mod error;
+pub mod memory_backend;
 mod metadata_client;
+pub mod notification;
+pub mod pool;
+pub mod retry;
+pub mod tls;
 
 pub const DAO_TYPE_QUERY_ONE_OFFSET: i32 = 0;
 pub const DAO_TYPE_QUERY_LIST_OFFSET: i32 = 100;
@@ -41,6 +56,8 @@ enum ResultType {
     TablePathIdWithOnlyPath,
     PartitionInfoWithOnlyCommitOp,
     PartitionInfoWithoutTimestamp,
+    TableTag,
+    TableBranch,
 }
 
 #[derive(FromSql, ToSql, Debug, PartialEq)]
@@ -91,6 +108,9 @@ pub enum DaoType {
 
     SelectOneDataCommitInfoByTableIdAndPartitionDescAndCommitId = DAO_TYPE_QUERY_ONE_OFFSET + 9,
 
+    SelectTableTagByTableIdAndTagName = DAO_TYPE_QUERY_ONE_OFFSET + 11,
+    SelectTableBranchByTableIdAndBranchName = DAO_TYPE_QUERY_ONE_OFFSET + 12,
+
     // ==== Query List ====
     ListNamespaces = DAO_TYPE_QUERY_LIST_OFFSET,
     ListTableNameByNamespace = DAO_TYPE_QUERY_LIST_OFFSET + 1,
@@ -108,6 +128,12 @@ pub enum DaoType {
     // Query DataCommitInfo List
     ListDataCommitInfoByTableIdAndPartitionDescAndCommitList = DAO_TYPE_QUERY_LIST_OFFSET + 10,
 
+    // Query TableTag List
+    ListTableTagByTableId = DAO_TYPE_QUERY_LIST_OFFSET + 11,
+
+    // Query TableBranch List
+    ListTableBranchByTableId = DAO_TYPE_QUERY_LIST_OFFSET + 12,
+
     // ==== Insert One ====
     InsertNamespace = DAO_TYPE_INSERT_ONE_OFFSET,
     InsertTablePathId = DAO_TYPE_INSERT_ONE_OFFSET + 1,
@@ -115,10 +141,13 @@ pub enum DaoType {
     InsertTableInfo = DAO_TYPE_INSERT_ONE_OFFSET + 3,
     InsertPartitionInfo = DAO_TYPE_INSERT_ONE_OFFSET + 4,
     InsertDataCommitInfo = DAO_TYPE_INSERT_ONE_OFFSET + 5,
+    InsertTableTag = DAO_TYPE_INSERT_ONE_OFFSET + 6,
+    InsertTableBranch = DAO_TYPE_INSERT_ONE_OFFSET + 7,
 
     // ==== Transaction Insert List ====
     TransactionInsertPartitionInfo = DAO_TYPE_TRANSACTION_INSERT_LIST_OFFSET,
     TransactionInsertDataCommitInfo = DAO_TYPE_TRANSACTION_INSERT_LIST_OFFSET + 1,
+    TransactionInsertTable = DAO_TYPE_TRANSACTION_INSERT_LIST_OFFSET + 2,
 
     // ==== Query SCALAR ====
     GetLatestTimestampFromPartitionInfo = DAO_TYPE_QUERY_SCALAR_OFFSET,
@@ -151,6 +180,14 @@ pub enum DaoType {
     DeleteDataCommitInfoByTableIdAndPartitionDescAndCommitIdList = DAO_TYPE_UPDATE_OFFSET + 13,
     DeleteDataCommitInfoByTableIdAndPartitionDesc = DAO_TYPE_UPDATE_OFFSET + 14,
     DeleteDataCommitInfoByTableId = DAO_TYPE_UPDATE_OFFSET + 15,
+    // Update TableTag
+    DeleteTableTagByTableIdAndTagName = DAO_TYPE_UPDATE_OFFSET + 16,
+    DeleteTableTagByTableId = DAO_TYPE_UPDATE_OFFSET + 17,
+    // Update PartitionInfo
+    DeletePartitionInfoByTableIdAndPartitionDescAndVersion = DAO_TYPE_UPDATE_OFFSET + 18,
+    // Update TableBranch
+    DeleteTableBranchByTableIdAndBranchName = DAO_TYPE_UPDATE_OFFSET + 19,
+    DeleteTableBranchByTableId = DAO_TYPE_UPDATE_OFFSET + 20,
 }
 
 pub type PreparedStatementMap = HashMap<DaoType, Statement>;
@@ -258,6 +295,26 @@ async fn get_prepared_statement(
                     from data_commit_info
                     where table_id = $1::TEXT and partition_desc = $2::TEXT and commit_id = $3::UUID",
 
+                // Select TableTag
+                DaoType::SelectTableTagByTableIdAndTagName =>
+                    "select table_id, tag_name, partition_versions, timestamp, domain
+                    from table_tag
+                    where table_id = $1::TEXT and tag_name = $2::TEXT",
+                DaoType::ListTableTagByTableId =>
+                    "select table_id, tag_name, partition_versions, timestamp, domain
+                    from table_tag
+                    where table_id = $1::TEXT",
+
+                // Select TableBranch
+                DaoType::SelectTableBranchByTableIdAndBranchName =>
+                    "select table_id, branch_name, partition_versions, timestamp, domain
+                    from table_branch
+                    where table_id = $1::TEXT and branch_name = $2::TEXT",
+                DaoType::ListTableBranchByTableId =>
+                    "select table_id, branch_name, partition_versions, timestamp, domain
+                    from table_branch
+                    where table_id = $1::TEXT",
+
 
                 // Insert
                 DaoType::InsertNamespace =>
@@ -315,6 +372,22 @@ async fn get_prepared_statement(
                         domain
                     )
                     values($1::TEXT, $2::TEXT, $3::UUID, $4::_data_file_op, $5::TEXT, $6::BIGINT, $7::BOOL, $8::TEXT)",
+                DaoType::InsertTableTag =>
+                    "insert into table_tag(
+                        table_id,
+                        tag_name,
+                        partition_versions,
+                        domain
+                    )
+                    values($1::TEXT, $2::TEXT, $3::JSON, $4::TEXT)",
+                DaoType::InsertTableBranch =>
+                    "insert into table_branch(
+                        table_id,
+                        branch_name,
+                        partition_versions,
+                        domain
+                    )
+                    values($1::TEXT, $2::TEXT, $3::JSON, $4::TEXT)",
 
                 // Query Scalar
                 DaoType::GetLatestTimestampFromPartitionInfo =>
@@ -382,12 +455,30 @@ async fn get_prepared_statement(
                 DaoType::DeletePreviousVersionPartition =>
                     "delete from partition_info
                     where table_id = $1::TEXT and partition_desc = $2::TEXT and timestamp <= $3::BIGINT",
+                DaoType::DeletePartitionInfoByTableIdAndPartitionDescAndVersion =>
+                    "delete from partition_info
+                    where table_id = $1::TEXT and partition_desc = $2::TEXT and version = $3::INT",
+
+                DaoType::DeleteTableTagByTableIdAndTagName =>
+                    "delete from table_tag
+                    where table_id = $1::TEXT and tag_name = $2::TEXT",
+                DaoType::DeleteTableTagByTableId =>
+                    "delete from table_tag
+                    where table_id = $1::TEXT",
+
+                DaoType::DeleteTableBranchByTableIdAndBranchName =>
+                    "delete from table_branch
+                    where table_id = $1::TEXT and branch_name = $2::TEXT",
+                DaoType::DeleteTableBranchByTableId =>
+                    "delete from table_branch
+                    where table_id = $1::TEXT",
 
 
                 // not prepared
                 DaoType::UpdateTableInfoById |
                 DaoType::TransactionInsertDataCommitInfo |
                 DaoType::TransactionInsertPartitionInfo |
+                DaoType::TransactionInsertTable |
                 DaoType::ListDataCommitInfoByTableIdAndPartitionDescAndCommitList |
                 DaoType::DeleteDataCommitInfoByTableIdAndPartitionDescAndCommitIdList |
                 DaoType::ListPartitionDescByTableIdAndParList => "",
@@ -471,7 +562,12 @@ pub async fn execute_query(
                 Err(e) => return Err(LakeSoulMetaDataError::from(e)),
             }
         }
-        DaoType::ListPartitionByTableId | DaoType::ListAllPathTablePathByNamespace if params.len() == 1 => {
+        DaoType::ListPartitionByTableId
+        | DaoType::ListAllPathTablePathByNamespace
+        | DaoType::ListTableTagByTableId
+        | DaoType::ListTableBranchByTableId
+            if params.len() == 1 =>
+        {
             let result = client.query(&statement, &[&params[0]]).await;
             match result {
                 Ok(rows) => rows,
@@ -490,6 +586,8 @@ pub async fn execute_query(
         DaoType::SelectTableNameIdByTableName
         | DaoType::SelectTableInfoByTableNameAndNameSpace
         | DaoType::SelectTableInfoByIdAndTablePath
+        | DaoType::SelectTableTagByTableIdAndTagName
+        | DaoType::SelectTableBranchByTableIdAndBranchName
             if params.len() == 2 =>
         {
             let result = client.query_opt(&statement, &[&params[0], &params[1]]).await;
@@ -644,6 +742,10 @@ pub async fn execute_query(
         DaoType::ListAllPathTablePathByNamespace => ResultType::TablePathIdWithOnlyPath,
 
         DaoType::ListCommitOpsBetweenVersions => ResultType::PartitionInfoWithOnlyCommitOp,
+
+        DaoType::SelectTableTagByTableIdAndTagName | DaoType::ListTableTagByTableId => ResultType::TableTag,
+
+        DaoType::SelectTableBranchByTableIdAndBranchName | DaoType::ListTableBranchByTableId => ResultType::TableBranch,
         _ => {
             eprintln!("Invalid query_type={:?} when parsing query result type", query_type);
             return Err(LakeSoulMetaDataError::from(ErrorKind::InvalidInput));
@@ -823,6 +925,42 @@ pub async fn execute_query(
                 ..Default::default()
             }
         }
+        ResultType::TableTag => {
+            let table_tag: Vec<entity::TableTag> = rows
+                .iter()
+                .map(|row| {
+                    Ok(entity::TableTag {
+                        table_id: row.get(0),
+                        tag_name: row.get(1),
+                        partition_versions: serde_json::from_value(row.get::<_, serde_json::Value>(2))?,
+                        timestamp: row.get(3),
+                        domain: row.get(4),
+                    })
+                })
+                .collect::<Result<Vec<entity::TableTag>>>()?;
+            entity::JniWrapper {
+                table_tag,
+                ..Default::default()
+            }
+        }
+        ResultType::TableBranch => {
+            let table_branch: Vec<entity::TableBranch> = rows
+                .iter()
+                .map(|row| {
+                    Ok(entity::TableBranch {
+                        table_id: row.get(0),
+                        branch_name: row.get(1),
+                        partition_versions: serde_json::from_value(row.get::<_, serde_json::Value>(2))?,
+                        timestamp: row.get(3),
+                        domain: row.get(4),
+                    })
+                })
+                .collect::<Result<Vec<entity::TableBranch>>>()?;
+            entity::JniWrapper {
+                table_branch,
+                ..Default::default()
+            }
+        }
     };
     Ok(wrapper.encode_to_vec())
 }
@@ -949,88 +1087,40 @@ pub async fn execute_insert(
                 )
                 .await
         }
+        DaoType::InsertTableTag if wrapper.table_tag.len() == 1 => {
+            let table_tag = wrapper.table_tag.first().unwrap();
+            let partition_versions = serde_json::to_value(&table_tag.partition_versions)?;
+            client
+                .execute(
+                    &statement,
+                    &[
+                        &table_tag.table_id,
+                        &table_tag.tag_name,
+                        &partition_versions,
+                        &table_tag.domain,
+                    ],
+                )
+                .await
+        }
+        DaoType::InsertTableBranch if wrapper.table_branch.len() == 1 => {
+            let table_branch = wrapper.table_branch.first().unwrap();
+            let partition_versions = serde_json::to_value(&table_branch.partition_versions)?;
+            client
+                .execute(
+                    &statement,
+                    &[
+                        &table_branch.table_id,
+                        &table_branch.branch_name,
+                        &partition_versions,
+                        &table_branch.domain,
+                    ],
+                )
+                .await
+        }
         DaoType::TransactionInsertPartitionInfo => {
-            let mut partition_info_list = wrapper.partition_info.clone();
-            let snapshot_container = partition_info_list.pop().unwrap();
-            let result = {
-                let transaction = client.transaction().await?;
-                let transaction_insert_statement = match transaction
-                    .prepare(
-                        "insert into partition_info(
-                        table_id,
-                        partition_desc,
-                        version,
-                        commit_op,
-                        snapshot,
-                        expression,
-                        domain
-                    )
-                    values($1::TEXT, $2::TEXT, $3::INT, $4::TEXT, $5::_UUID, $6::TEXT, $7::TEXT)",
-                    )
-                    .await
-                {
-                    Ok(statement) => statement,
-                    Err(e) => return Err(LakeSoulMetaDataError::from(e)),
-                };
-
-                let update_statement = match transaction
-                    .prepare("update data_commit_info set committed = 'true' where commit_id = $1::UUID")
-                    .await
-                {
-                    Ok(statement) => statement,
-                    Err(e) => return Err(LakeSoulMetaDataError::from(e)),
-                };
-
-                for partition_info in &partition_info_list {
-                    let snapshot = partition_info
-                        .snapshot
-                        .iter()
-                        .map(|_uuid| uuid::Uuid::from_u64_pair(_uuid.high, _uuid.low))
-                        .collect::<Vec<uuid::Uuid>>();
-
-                    let result = transaction
-                        .execute(
-                            &transaction_insert_statement,
-                            &[
-                                &partition_info.table_id,
-                                &partition_info.partition_desc,
-                                &partition_info.version,
-                                &partition_info.commit_op().as_str_name(),
-                                &snapshot,
-                                &partition_info.expression,
-                                &partition_info.domain,
-                            ],
-                        )
-                        .await;
-
-                    if let Some(e) = result.err() {
-                        eprintln!("transaction insert error, err = {:?}", e);
-                        return match transaction.rollback().await {
-                            Ok(()) => Ok(0i32),
-                            Err(e) => Err(LakeSoulMetaDataError::from(e)),
-                        };
-                    };
-                }
-                for _uuid in &snapshot_container.snapshot {
-                    let uid = uuid::Uuid::from_u64_pair(_uuid.high, _uuid.low);
-                    let result = transaction.execute(&update_statement, &[&uid]).await;
-                    if let Some(e) = result.err() {
-                        eprintln!("update committed error, err = {:?}", e);
-                        return match transaction.rollback().await {
-                            Ok(()) => Ok(0i32),
-                            Err(e) => Err(LakeSoulMetaDataError::from(e)),
-                        };
-                    }
-                }
-                match transaction.commit().await {
-                    Ok(()) => Ok(partition_info_list.len() as u64),
-                    Err(e) => Err(e),
-                }
-            };
-            match result {
-                Ok(count) => Ok(count),
-                Err(e) => return Err(LakeSoulMetaDataError::from(e)),
-            }
+            return transaction_insert_partition_info(client, wrapper.partition_info, false)
+                .await
+                .map(|count| count as i32);
         }
         DaoType::TransactionInsertDataCommitInfo => {
             let data_commit_info_list = wrapper.data_commit_info;
@@ -1101,6 +1191,76 @@ pub async fn execute_insert(
                 Err(e) => return Err(LakeSoulMetaDataError::from(e)),
             }
         }
+        DaoType::TransactionInsertTable
+            if wrapper.table_info.len() == 1
+                && wrapper.table_name_id.len() == 1
+                && wrapper.table_path_id.len() == 1 =>
+        {
+            let table_info = wrapper.table_info.first().unwrap();
+            let table_name_id = wrapper.table_name_id.first().unwrap();
+            let table_path_id = wrapper.table_path_id.first().unwrap();
+            let properties: serde_json::Value = serde_json::from_str(&table_info.properties)?;
+            let transaction = client.transaction().await?;
+            let result = async {
+                transaction
+                    .execute(
+                        "insert into table_path_id(table_id, table_path, table_namespace, domain)
+                        values($1::TEXT, $2::TEXT, $3::TEXT, $4::TEXT)",
+                        &[
+                            &table_path_id.table_id,
+                            &table_path_id.table_path,
+                            &table_path_id.table_namespace,
+                            &table_path_id.domain,
+                        ],
+                    )
+                    .await?;
+                transaction
+                    .execute(
+                        "insert into table_name_id(table_id, table_name, table_namespace, domain)
+                        values($1::TEXT, $2::TEXT, $3::TEXT, $4::TEXT)",
+                        &[
+                            &table_name_id.table_id,
+                            &table_name_id.table_name,
+                            &table_name_id.table_namespace,
+                            &table_name_id.domain,
+                        ],
+                    )
+                    .await?;
+                transaction
+                    .execute(
+                        "insert into table_info(
+                            table_id,
+                            table_name,
+                            table_path,
+                            table_schema,
+                            properties,
+                            partitions,
+                            table_namespace,
+                            domain)
+                        values($1::TEXT, $2::TEXT, $3::TEXT, $4::TEXT, $5::JSON, $6::TEXT, $7::TEXT, $8::TEXT)",
+                        &[
+                            &table_info.table_id,
+                            &table_info.table_name,
+                            &table_info.table_path,
+                            &table_info.table_schema,
+                            &properties,
+                            &table_info.partitions,
+                            &table_info.table_namespace,
+                            &table_info.domain,
+                        ],
+                    )
+                    .await
+            }
+            .await;
+            match result {
+                Ok(count) => transaction.commit().await.map(|_| count),
+                Err(e) => {
+                    eprintln!("transaction insert table error, err = {:?}", e);
+                    transaction.rollback().await?;
+                    Err(e)
+                }
+            }
+        }
         _ => {
             eprintln!("InvalidInput of type={:?}: {:?}", insert_type, wrapper);
             return Err(LakeSoulMetaDataError::from(ErrorKind::InvalidInput));
@@ -1112,6 +1272,100 @@ pub async fn execute_insert(
     }
 }
 
+/// Insert the partition versions of `TransactionInsertPartitionInfo` and mark the commits in the snapshot of
+/// the last element as committed, all or none in a transaction, returning 0 if any version already exists.
+/// The subscribers of [notification] are notified of the inserted versions in the transaction if `notify`.
+pub async fn transaction_insert_partition_info(
+    client: &mut Client,
+    mut partition_info_list: Vec<entity::PartitionInfo>,
+    notify: bool,
+) -> Result<u64> {
+    let snapshot_container = partition_info_list.pop().unwrap();
+    let result = {
+        let transaction = client.transaction().await?;
+        let transaction_insert_statement = match transaction
+            .prepare(
+                "insert into partition_info(
+                table_id,
+                partition_desc,
+                version,
+                commit_op,
+                snapshot,
+                expression,
+                domain
+            )
+            values($1::TEXT, $2::TEXT, $3::INT, $4::TEXT, $5::_UUID, $6::TEXT, $7::TEXT)",
+            )
+            .await
+        {
+            Ok(statement) => statement,
+            Err(e) => return Err(LakeSoulMetaDataError::from(e)),
+        };
+
+        let update_statement = match transaction
+            .prepare("update data_commit_info set committed = 'true' where commit_id = $1::UUID")
+            .await
+        {
+            Ok(statement) => statement,
+            Err(e) => return Err(LakeSoulMetaDataError::from(e)),
+        };
+
+        for partition_info in &partition_info_list {
+            let snapshot = partition_info
+                .snapshot
+                .iter()
+                .map(|_uuid| uuid::Uuid::from_u64_pair(_uuid.high, _uuid.low))
+                .collect::<Vec<uuid::Uuid>>();
+
+            let result = transaction
+                .execute(
+                    &transaction_insert_statement,
+                    &[
+                        &partition_info.table_id,
+                        &partition_info.partition_desc,
+                        &partition_info.version,
+                        &partition_info.commit_op().as_str_name(),
+                        &snapshot,
+                        &partition_info.expression,
+                        &partition_info.domain,
+                    ],
+                )
+                .await;
+
+            if let Some(e) = result.err() {
+                eprintln!("transaction insert error, err = {:?}", e);
+                return match transaction.rollback().await {
+                    Ok(()) => Ok(0),
+                    Err(e) => Err(LakeSoulMetaDataError::from(e)),
+                };
+            };
+        }
+        for _uuid in &snapshot_container.snapshot {
+            let uid = uuid::Uuid::from_u64_pair(_uuid.high, _uuid.low);
+            let result = transaction.execute(&update_statement, &[&uid]).await;
+            if let Some(e) = result.err() {
+                eprintln!("update committed error, err = {:?}", e);
+                return match transaction.rollback().await {
+                    Ok(()) => Ok(0),
+                    Err(e) => Err(LakeSoulMetaDataError::from(e)),
+                };
+            }
+        }
+        if notify {
+            // pg delivers the notifications when and only if the transaction commits
+            if let Err(e) = notify_commits(&transaction, &partition_info_list).await {
+                transaction.rollback().await?;
+                return Err(e);
+            }
+        }
+        match transaction.commit().await {
+            Ok(()) => Ok(partition_info_list.len() as u64),
+            Err(e) => Err(e),
+        }
+    };
+    Ok(result?)
+}
+
 pub async fn execute_update(
     client: &mut Client,
     prepared: &mut PreparedStatementMap,
@@ -1139,6 +1393,8 @@ pub async fn execute_update(
         | DaoType::DeleteTableNameIdByTableId
         | DaoType::DeleteTablePathIdByTableId
         | DaoType::DeleteTablePathIdByTablePath
+        | DaoType::DeleteTableTagByTableId
+        | DaoType::DeleteTableBranchByTableId
             if params.len() == 1 =>
         {
             client.execute(&statement, &[&params[0]]).await
@@ -1147,6 +1403,8 @@ pub async fn execute_update(
         | DaoType::DeleteTableNameIdByTableNameAndNamespace
         | DaoType::DeletePartitionInfoByTableIdAndPartitionDesc
         | DaoType::DeleteDataCommitInfoByTableIdAndPartitionDesc
+        | DaoType::DeleteTableTagByTableIdAndTagName
+        | DaoType::DeleteTableBranchByTableIdAndBranchName
             if params.len() == 2 =>
         {
             client.execute(&statement, &[&params[0], &params[1]]).await
@@ -1159,6 +1417,10 @@ pub async fn execute_update(
             let ts = i64::from_str(&params[2])?;
             client.execute(&statement, &[&params[0], &params[1], &ts]).await
         }
+        DaoType::DeletePartitionInfoByTableIdAndPartitionDescAndVersion if params.len() == 3 => {
+            let version = i32::from_str(&params[2])?;
+            client.execute(&statement, &[&params[0], &params[1], &version]).await
+        }
         DaoType::DeleteOneDataCommitInfoByTableIdAndPartitionDescAndCommitId if params.len() == 3 => {
             let commit_id: uuid::Uuid = uuid::Uuid::from_str(&params[2])?;
             client.execute(&statement, &[&params[0], &params[1], &commit_id]).await
@@ -1318,7 +1580,9 @@ pub async fn clean_meta_for_test(client: &Client) -> Result<i32> {
             delete from table_info;
             delete from table_path_id;
             delete from table_name_id;
-            delete from partition_info;",
+            delete from partition_info;
+            delete from table_tag;
+            delete from table_branch;",
         )
         .await;
     match result {
@@ -1328,18 +1592,20 @@ pub async fn clean_meta_for_test(client: &Client) -> Result<i32> {
 }
 
 ///  Create a pg connection, return pg client
+/// The TLS options of `config`, e.g. `sslmode`, are applied as described in [crate::tls].
+/// The client is closed once its connection fails, see [crate::pool] for reconnecting.
 pub async fn create_connection(config: String) -> Result<Client> {
-    let (client, connection) = match tokio_postgres::connect(config.as_str(), NoTls).await {
+    let (client, connection) = match tls::connect(&config).await {
         Ok((client, connection)) => (client, connection),
         Err(e) => {
-            eprintln!("{}", e);
-            return Err(LakeSoulMetaDataError::from(ErrorKind::ConnectionRefused));
+            error!("failed to connect to pg: {}", e);
+            return Err(e);
         }
     };
 
     spawn(async move {
         if let Err(e) = connection.await {
-            eprintln!("connection error: {}", e);
+            warn!("pg connection closed by error: {}", e);
         }
     });
 
@@ -0,0 +1,984 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! A [MetaDataBackend] keeping the metadata in memory, so that a [MetaDataClient](crate::MetaDataClient) runs
+//! without Postgres, e.g. in tests and single-node tools.
+//!
+//! The tables of `script/meta_init.sql` are kept as maps by their primary keys behind a lock, so that each operation
+//! is atomic: a transactional insert checks all of its keys before inserting any entity, and returns 0 on a conflict
+//! just like the rolled back transaction of [PostgresBackend](crate::backend::PostgresBackend).
+//! The metadata is lost when the backend is dropped.
+
+use std::any::Any;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
+use std::io::{self, ErrorKind};
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tracing::error;
+
+use proto::proto::entity::{
+    self, DataCommitInfo, JniWrapper, Namespace, PartitionInfo, TableBranch, TableInfo, TableNameId, TablePathId,
+    TableTag,
+};
+
+use crate::backend::MetaDataBackend;
+use crate::error::{LakeSoulMetaDataError, Result};
+use crate::notification::{CommitNotification, CommitNotificationStream};
+use crate::{
+    get_params, separate_uuid, DaoType, DataFileOp, DAO_TYPE_INSERT_ONE_OFFSET, DAO_TYPE_QUERY_ONE_OFFSET,
+    DAO_TYPE_QUERY_SCALAR_OFFSET, DAO_TYPE_UPDATE_OFFSET, PARTITION_DESC_DELIM,
+};
+
+type PartitionKey = (String, String, i32);
+type DataCommitKey = (String, String, uuid::Uuid);
+
+/// The metadata tables by their primary keys.
+#[derive(Default)]
+struct Tables {
+    namespace: BTreeMap<String, Namespace>,
+    table_info: BTreeMap<String, TableInfo>,
+    table_name_id: BTreeMap<(String, String), TableNameId>,
+    table_path_id: BTreeMap<String, TablePathId>,
+    partition_info: BTreeMap<PartitionKey, PartitionInfo>,
+    data_commit_info: BTreeMap<DataCommitKey, DataCommitInfo>,
+    table_tag: BTreeMap<(String, String), TableTag>,
+    table_branch: BTreeMap<(String, String), TableBranch>,
+}
+
+impl Tables {
+    /// The versions of all the partitions of a table, ordered by partition_desc and version.
+    fn table_partitions<'a>(&'a self, table_id: &'a str) -> impl Iterator<Item = &'a PartitionInfo> + 'a {
+        self.partition_info
+            .range((table_id.to_string(), String::new(), i32::MIN)..)
+            .map(|(_, partition_info)| partition_info)
+            .take_while(move |partition_info| partition_info.table_id == table_id)
+    }
+
+    /// The versions of a partition, ordered by version.
+    fn partition_versions(&self, table_id: &str, partition_desc: &str) -> impl Iterator<Item = &PartitionInfo> {
+        let (table_id, partition_desc) = (table_id.to_string(), partition_desc.to_string());
+        self.partition_info
+            .range((table_id.clone(), partition_desc.clone(), i32::MIN)..=(table_id, partition_desc, i32::MAX))
+            .map(|(_, partition_info)| partition_info)
+    }
+
+    /// The latest version of each partition of a table.
+    fn latest_partitions(&self, table_id: &str) -> Vec<&PartitionInfo> {
+        let mut latest: Vec<&PartitionInfo> = vec![];
+        for partition_info in self.table_partitions(table_id) {
+            match latest.last_mut() {
+                Some(last) if last.partition_desc == partition_info.partition_desc => *last = partition_info,
+                _ => latest.push(partition_info),
+            }
+        }
+        latest
+    }
+}
+
+/// The metadata in memory, see the [module docs](self).
+pub struct MemoryBackend {
+    tables: Mutex<Tables>,
+    subscribers: Mutex<Vec<UnboundedSender<Result<CommitNotification>>>>,
+}
+
+impl Debug for MemoryBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryBackend").finish()
+    }
+}
+
+impl Default for MemoryBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryBackend {
+    /// An empty backend with the `default` namespace, as created by `script/meta_init.sql`.
+    pub fn new() -> Self {
+        let mut tables = Tables::default();
+        tables.namespace.insert(
+            "default".to_string(),
+            Namespace {
+                namespace: "default".to_string(),
+                properties: "{}".to_string(),
+                comment: "".to_string(),
+                domain: "public".to_string(),
+            },
+        );
+        Self {
+            tables: Mutex::new(tables),
+            subscribers: Mutex::new(vec![]),
+        }
+    }
+
+    /// The backend shared by the whole process, e.g. by all the clients created by
+    /// [MetaDataClient::from_env](crate::MetaDataClient::from_env).
+    pub fn shared() -> Arc<Self> {
+        static SHARED: OnceLock<Arc<MemoryBackend>> = OnceLock::new();
+        SHARED.get_or_init(|| Arc::new(Self::new())).clone()
+    }
+
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn query(&self, query_type: DaoType, params: &[String]) -> Result<JniWrapper> {
+        let tables = self.tables();
+        let wrapper = match query_type {
+            DaoType::ListNamespaces if params.len() == 1 && params[0].is_empty() => JniWrapper {
+                namespace: tables.namespace.values().cloned().collect(),
+                ..Default::default()
+            },
+            DaoType::SelectNamespaceByNamespace if params.len() == 1 => JniWrapper {
+                namespace: tables.namespace.get(&params[0]).cloned().into_iter().collect(),
+                ..Default::default()
+            },
+            DaoType::SelectTablePathIdByTablePath if params.len() == 1 => JniWrapper {
+                table_path_id: tables.table_path_id.get(&params[0]).cloned().into_iter().collect(),
+                ..Default::default()
+            },
+            DaoType::ListAllTablePath if params.len() == 1 && params[0].is_empty() => JniWrapper {
+                table_path_id: tables.table_path_id.values().cloned().collect(),
+                ..Default::default()
+            },
+            DaoType::ListAllPathTablePathByNamespace if params.len() == 1 => JniWrapper {
+                table_path_id: tables
+                    .table_path_id
+                    .values()
+                    .filter(|table_path_id| table_path_id.table_namespace == params[0])
+                    .map(|table_path_id| TablePathId {
+                        table_path: table_path_id.table_path.clone(),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+            DaoType::SelectTableNameIdByTableName if params.len() == 2 => JniWrapper {
+                table_name_id: tables
+                    .table_name_id
+                    .get(&(params[0].clone(), params[1].clone()))
+                    .cloned()
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+            DaoType::ListTableNameByNamespace if params.len() == 1 => JniWrapper {
+                table_name_id: tables
+                    .table_name_id
+                    .values()
+                    .filter(|table_name_id| table_name_id.table_namespace == params[0])
+                    .cloned()
+                    .collect(),
+                ..Default::default()
+            },
+            DaoType::SelectTableInfoByTableId
+            | DaoType::SelectTableInfoByTablePath
+            | DaoType::SelectTableInfoByTableNameAndNameSpace
+            | DaoType::SelectTableInfoByIdAndTablePath => {
+                let matches: Box<dyn Fn(&TableInfo) -> bool> = match query_type {
+                    DaoType::SelectTableInfoByTableId if params.len() == 1 => {
+                        Box::new(|table_info| table_info.table_id == params[0])
+                    }
+                    DaoType::SelectTableInfoByTablePath if params.len() == 1 => {
+                        Box::new(|table_info| table_info.table_path == params[0])
+                    }
+                    DaoType::SelectTableInfoByTableNameAndNameSpace if params.len() == 2 => Box::new(|table_info| {
+                        table_info.table_name == params[0] && table_info.table_namespace == params[1]
+                    }),
+                    DaoType::SelectTableInfoByIdAndTablePath if params.len() == 2 => {
+                        Box::new(|table_info| table_info.table_id == params[0] && table_info.table_path == params[1])
+                    }
+                    _ => return Err(invalid_input(query_type, params)),
+                };
+                JniWrapper {
+                    table_info: tables
+                        .table_info
+                        .values()
+                        .filter(|t| matches(t))
+                        .take(1)
+                        .cloned()
+                        .collect(),
+                    ..Default::default()
+                }
+            }
+            DaoType::SelectPartitionVersionByTableIdAndDescAndVersion if params.len() == 3 => {
+                let key = (params[0].clone(), params[1].clone(), i32::from_str(&params[2])?);
+                JniWrapper {
+                    partition_info: tables
+                        .partition_info
+                        .get(&key)
+                        .map(without_timestamp)
+                        .into_iter()
+                        .collect(),
+                    ..Default::default()
+                }
+            }
+            DaoType::SelectOnePartitionVersionByTableIdAndDesc if params.len() == 2 => JniWrapper {
+                partition_info: tables
+                    .partition_versions(&params[0], &params[1])
+                    .last()
+                    .map(without_timestamp)
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+            DaoType::ListPartitionByTableIdAndDesc if params.len() == 2 => JniWrapper {
+                partition_info: tables.partition_versions(&params[0], &params[1]).cloned().collect(),
+                ..Default::default()
+            },
+            DaoType::ListPartitionByTableId if params.len() == 1 => JniWrapper {
+                partition_info: tables
+                    .latest_partitions(&params[0])
+                    .into_iter()
+                    .map(without_timestamp)
+                    .collect(),
+                ..Default::default()
+            },
+            DaoType::ListPartitionDescByTableIdAndParList if params.len() == 2 => {
+                let partition_descs = params[1].split(PARTITION_DESC_DELIM).collect::<HashSet<_>>();
+                JniWrapper {
+                    partition_info: tables
+                        .latest_partitions(&params[0])
+                        .into_iter()
+                        .filter(|partition_info| partition_descs.contains(partition_info.partition_desc.as_str()))
+                        .map(without_timestamp)
+                        .collect(),
+                    ..Default::default()
+                }
+            }
+            DaoType::ListPartitionVersionByTableIdAndPartitionDescAndTimestampRange if params.len() == 4 => {
+                let timestamps = i64::from_str(&params[2])?..i64::from_str(&params[3])?;
+                JniWrapper {
+                    partition_info: tables
+                        .partition_versions(&params[0], &params[1])
+                        .filter(|partition_info| timestamps.contains(&partition_info.timestamp))
+                        .cloned()
+                        .collect(),
+                    ..Default::default()
+                }
+            }
+            DaoType::ListPartitionVersionByTableIdAndPartitionDescAndVersionRange if params.len() == 4 => {
+                let versions = i32::from_str(&params[2])?..=i32::from_str(&params[3])?;
+                JniWrapper {
+                    partition_info: tables
+                        .partition_versions(&params[0], &params[1])
+                        .filter(|partition_info| versions.contains(&partition_info.version))
+                        .cloned()
+                        .collect(),
+                    ..Default::default()
+                }
+            }
+            DaoType::ListCommitOpsBetweenVersions if params.len() == 4 => {
+                let versions = i32::from_str(&params[2])?..=i32::from_str(&params[3])?;
+                let mut commit_ops = vec![];
+                for partition_info in tables.partition_versions(&params[0], &params[1]) {
+                    if versions.contains(&partition_info.version) && !commit_ops.contains(&partition_info.commit_op) {
+                        commit_ops.push(partition_info.commit_op);
+                    }
+                }
+                JniWrapper {
+                    partition_info: commit_ops
+                        .into_iter()
+                        .map(|commit_op| PartitionInfo {
+                            commit_op,
+                            ..Default::default()
+                        })
+                        .collect(),
+                    ..Default::default()
+                }
+            }
+            DaoType::SelectOneDataCommitInfoByTableIdAndPartitionDescAndCommitId if params.len() == 3 => {
+                let key = (params[0].clone(), params[1].clone(), uuid::Uuid::from_str(&params[2])?);
+                JniWrapper {
+                    data_commit_info: tables.data_commit_info.get(&key).cloned().into_iter().collect(),
+                    ..Default::default()
+                }
+            }
+            DaoType::ListDataCommitInfoByTableIdAndPartitionDescAndCommitList if params.len() == 3 => {
+                // in the order of the commit list
+                let mut data_commit_info = vec![];
+                for commit_id in commit_id_list(query_type, params)? {
+                    let key = (params[0].clone(), params[1].clone(), commit_id);
+                    if let Some(info) = tables.data_commit_info.get(&key) {
+                        if !data_commit_info.contains(info) {
+                            data_commit_info.push(info.clone());
+                        }
+                    }
+                }
+                JniWrapper {
+                    data_commit_info,
+                    ..Default::default()
+                }
+            }
+            DaoType::SelectTableTagByTableIdAndTagName if params.len() == 2 => JniWrapper {
+                table_tag: tables
+                    .table_tag
+                    .get(&(params[0].clone(), params[1].clone()))
+                    .cloned()
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+            DaoType::ListTableTagByTableId if params.len() == 1 => JniWrapper {
+                table_tag: tables
+                    .table_tag
+                    .values()
+                    .filter(|table_tag| table_tag.table_id == params[0])
+                    .cloned()
+                    .collect(),
+                ..Default::default()
+            },
+            DaoType::SelectTableBranchByTableIdAndBranchName if params.len() == 2 => JniWrapper {
+                table_branch: tables
+                    .table_branch
+                    .get(&(params[0].clone(), params[1].clone()))
+                    .cloned()
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+            DaoType::ListTableBranchByTableId if params.len() == 1 => JniWrapper {
+                table_branch: tables
+                    .table_branch
+                    .values()
+                    .filter(|table_branch| table_branch.table_id == params[0])
+                    .cloned()
+                    .collect(),
+                ..Default::default()
+            },
+            _ => return Err(invalid_input(query_type, params)),
+        };
+        Ok(wrapper)
+    }
+
+    fn insert(&self, insert_type: DaoType, wrapper: JniWrapper) -> Result<i32> {
+        let mut tables = self.tables();
+        let timestamp = now_millis();
+        match insert_type {
+            DaoType::InsertNamespace if wrapper.namespace.len() == 1 => {
+                let namespace = Namespace {
+                    properties: normalize_json(&wrapper.namespace[0].properties)?,
+                    ..wrapper.namespace[0].clone()
+                };
+                insert_unique(&mut tables.namespace, namespace.namespace.clone(), namespace)?;
+            }
+            DaoType::InsertTableInfo if wrapper.table_info.len() == 1 => {
+                let table_info = normalize_table_info(&wrapper.table_info[0])?;
+                insert_unique(&mut tables.table_info, table_info.table_id.clone(), table_info)?;
+            }
+            DaoType::InsertTableNameId if wrapper.table_name_id.len() == 1 => {
+                let table_name_id = wrapper.table_name_id[0].clone();
+                let key = (table_name_id.table_name.clone(), table_name_id.table_namespace.clone());
+                insert_unique(&mut tables.table_name_id, key, table_name_id)?;
+            }
+            DaoType::InsertTablePathId if wrapper.table_path_id.len() == 1 => {
+                let table_path_id = wrapper.table_path_id[0].clone();
+                insert_unique(
+                    &mut tables.table_path_id,
+                    table_path_id.table_path.clone(),
+                    table_path_id,
+                )?;
+            }
+            DaoType::InsertPartitionInfo if wrapper.partition_info.len() == 1 => {
+                let (key, partition_info) = normalize_partition_info(&wrapper.partition_info[0], timestamp);
+                insert_unique(&mut tables.partition_info, key, partition_info)?;
+            }
+            DaoType::InsertDataCommitInfo if wrapper.data_commit_info.len() == 1 => {
+                let (key, data_commit_info) = normalize_data_commit_info(&wrapper.data_commit_info[0])?;
+                insert_unique(&mut tables.data_commit_info, key, data_commit_info)?;
+            }
+            DaoType::InsertTableTag if wrapper.table_tag.len() == 1 => {
+                let table_tag = TableTag {
+                    timestamp,
+                    ..wrapper.table_tag[0].clone()
+                };
+                let key = (table_tag.table_id.clone(), table_tag.tag_name.clone());
+                insert_unique(&mut tables.table_tag, key, table_tag)?;
+            }
+            DaoType::InsertTableBranch if wrapper.table_branch.len() == 1 => {
+                let table_branch = TableBranch {
+                    timestamp,
+                    ..wrapper.table_branch[0].clone()
+                };
+                let key = (table_branch.table_id.clone(), table_branch.branch_name.clone());
+                insert_unique(&mut tables.table_branch, key, table_branch)?;
+            }
+            DaoType::TransactionInsertPartitionInfo if !wrapper.partition_info.is_empty() => {
+                // the last element only carries the commits to be marked as committed
+                let (snapshot_container, partition_info_list) = wrapper.partition_info.split_last().unwrap();
+                let partition_info_list = partition_info_list
+                    .iter()
+                    .map(|partition_info| normalize_partition_info(partition_info, timestamp))
+                    .collect::<Vec<_>>();
+                if has_conflict(&tables.partition_info, &partition_info_list) {
+                    return Ok(0);
+                }
+                let count = partition_info_list.len();
+                tables.partition_info.extend(partition_info_list);
+                for data_commit_info in tables.data_commit_info.values_mut() {
+                    if data_commit_info
+                        .commit_id
+                        .as_ref()
+                        .map_or(false, |commit_id| snapshot_container.snapshot.contains(commit_id))
+                    {
+                        data_commit_info.committed = true;
+                    }
+                }
+                return Ok(count as i32);
+            }
+            DaoType::TransactionInsertDataCommitInfo => {
+                let data_commit_info_list = wrapper
+                    .data_commit_info
+                    .iter()
+                    .map(normalize_data_commit_info)
+                    .collect::<Result<Vec<_>>>()?;
+                if has_conflict(&tables.data_commit_info, &data_commit_info_list) {
+                    return Ok(0);
+                }
+                let count = data_commit_info_list.len();
+                tables.data_commit_info.extend(data_commit_info_list);
+                return Ok(count as i32);
+            }
+            DaoType::TransactionInsertTable
+                if wrapper.table_info.len() == 1
+                    && wrapper.table_name_id.len() == 1
+                    && wrapper.table_path_id.len() == 1 =>
+            {
+                let table_info = normalize_table_info(&wrapper.table_info[0])?;
+                let table_name_id = wrapper.table_name_id[0].clone();
+                let table_path_id = wrapper.table_path_id[0].clone();
+                let name_key = (table_name_id.table_name.clone(), table_name_id.table_namespace.clone());
+                check_unique(&tables.table_path_id, &table_path_id.table_path)?;
+                check_unique(&tables.table_name_id, &name_key)?;
+                check_unique(&tables.table_info, &table_info.table_id)?;
+                tables
+                    .table_path_id
+                    .insert(table_path_id.table_path.clone(), table_path_id);
+                tables.table_name_id.insert(name_key, table_name_id);
+                tables.table_info.insert(table_info.table_id.clone(), table_info);
+            }
+            _ => {
+                error!("InvalidInput of type={:?}: {:?}", insert_type, wrapper);
+                return Err(LakeSoulMetaDataError::from(ErrorKind::InvalidInput));
+            }
+        }
+        Ok(1)
+    }
+
+    fn update(&self, update_type: DaoType, params: &[String]) -> Result<i32> {
+        let mut tables = self.tables();
+        let tables = &mut *tables;
+        let count = match update_type {
+            DaoType::DeleteNamespaceByNamespace if params.len() == 1 => {
+                tables.namespace.remove(&params[0]).map_or(0, |_| 1)
+            }
+            DaoType::UpdateNamespacePropertiesByNamespace if params.len() == 2 => {
+                let properties = normalize_json(&params[1])?;
+                match tables.namespace.get_mut(&params[0]) {
+                    Some(namespace) => {
+                        namespace.properties = properties;
+                        1
+                    }
+                    None => 0,
+                }
+            }
+            DaoType::DeleteTableInfoByIdAndPath if params.len() == 2 => remove_where(&mut tables.table_info, |_, t| {
+                t.table_id == params[0] && t.table_path == params[1]
+            }),
+            DaoType::UpdateTableInfoPropertiesById if params.len() == 2 => {
+                let properties = normalize_json(&params[1])?;
+                match tables.table_info.get_mut(&params[0]) {
+                    Some(table_info) => {
+                        table_info.properties = properties;
+                        1
+                    }
+                    None => 0,
+                }
+            }
+            DaoType::UpdateTableInfoById if params.len() == 4 && params[1..].iter().any(|param| !param.is_empty()) => {
+                match tables.table_info.get_mut(&params[0]) {
+                    Some(table_info) => {
+                        for (field, value) in [
+                            (&mut table_info.table_name, &params[1]),
+                            (&mut table_info.table_path, &params[2]),
+                            (&mut table_info.table_schema, &params[3]),
+                        ] {
+                            if !value.is_empty() {
+                                *field = value.clone();
+                            }
+                        }
+                        1
+                    }
+                    None => 0,
+                }
+            }
+            DaoType::DeleteTablePathIdByTablePath if params.len() == 1 => {
+                tables.table_path_id.remove(&params[0]).map_or(0, |_| 1)
+            }
+            DaoType::DeleteTablePathIdByTableId if params.len() == 1 => {
+                remove_where(&mut tables.table_path_id, |_, t| t.table_id == params[0])
+            }
+            DaoType::DeleteTableNameIdByTableNameAndNamespace if params.len() == 2 => tables
+                .table_name_id
+                .remove(&(params[0].clone(), params[1].clone()))
+                .map_or(0, |_| 1),
+            DaoType::DeleteTableNameIdByTableId if params.len() == 1 => {
+                remove_where(&mut tables.table_name_id, |_, t| t.table_id == params[0])
+            }
+            DaoType::DeletePartitionInfoByTableIdAndPartitionDesc if params.len() == 2 => {
+                remove_where(&mut tables.partition_info, |(table_id, partition_desc, _), _| {
+                    *table_id == params[0] && *partition_desc == params[1]
+                })
+            }
+            DaoType::DeletePartitionInfoByTableId if params.len() == 1 => {
+                remove_where(&mut tables.partition_info, |(table_id, _, _), _| *table_id == params[0])
+            }
+            DaoType::DeletePreviousVersionPartition if params.len() == 3 => {
+                let timestamp = i64::from_str(&params[2])?;
+                remove_where(&mut tables.partition_info, |(table_id, partition_desc, _), p| {
+                    *table_id == params[0] && *partition_desc == params[1] && p.timestamp <= timestamp
+                })
+            }
+            DaoType::DeletePartitionInfoByTableIdAndPartitionDescAndVersion if params.len() == 3 => {
+                let key = (params[0].clone(), params[1].clone(), i32::from_str(&params[2])?);
+                tables.partition_info.remove(&key).map_or(0, |_| 1)
+            }
+            DaoType::DeleteOneDataCommitInfoByTableIdAndPartitionDescAndCommitId if params.len() == 3 => {
+                let key = (params[0].clone(), params[1].clone(), uuid::Uuid::from_str(&params[2])?);
+                tables.data_commit_info.remove(&key).map_or(0, |_| 1)
+            }
+            DaoType::DeleteDataCommitInfoByTableIdAndPartitionDescAndCommitIdList if params.len() == 3 => {
+                let mut count = 0;
+                for commit_id in commit_id_list(update_type, params)? {
+                    let key = (params[0].clone(), params[1].clone(), commit_id);
+                    count += tables.data_commit_info.remove(&key).map_or(0, |_| 1);
+                }
+                count
+            }
+            DaoType::DeleteDataCommitInfoByTableIdAndPartitionDesc if params.len() == 2 => {
+                remove_where(&mut tables.data_commit_info, |(table_id, partition_desc, _), _| {
+                    *table_id == params[0] && *partition_desc == params[1]
+                })
+            }
+            DaoType::DeleteDataCommitInfoByTableId if params.len() == 1 => {
+                remove_where(&mut tables.data_commit_info, |(table_id, _, _), _| {
+                    *table_id == params[0]
+                })
+            }
+            DaoType::DeleteTableTagByTableIdAndTagName if params.len() == 2 => tables
+                .table_tag
+                .remove(&(params[0].clone(), params[1].clone()))
+                .map_or(0, |_| 1),
+            DaoType::DeleteTableTagByTableId if params.len() == 1 => {
+                remove_where(&mut tables.table_tag, |(table_id, _), _| *table_id == params[0])
+            }
+            DaoType::DeleteTableBranchByTableIdAndBranchName if params.len() == 2 => tables
+                .table_branch
+                .remove(&(params[0].clone(), params[1].clone()))
+                .map_or(0, |_| 1),
+            DaoType::DeleteTableBranchByTableId if params.len() == 1 => {
+                remove_where(&mut tables.table_branch, |(table_id, _), _| *table_id == params[0])
+            }
+            _ => return Err(invalid_input(update_type, params)),
+        };
+        Ok(count as i32)
+    }
+
+    fn query_scalar(&self, query_type: DaoType, params: &[String]) -> Result<Option<String>> {
+        let tables = self.tables();
+        let scalar = match query_type {
+            DaoType::GetLatestTimestampFromPartitionInfoWithoutPartitionDesc if params.len() == 1 => tables
+                .table_partitions(&params[0])
+                .map(|partition_info| partition_info.timestamp)
+                .max(),
+            DaoType::GetLatestTimestampFromPartitionInfo if params.len() == 2 => tables
+                .partition_versions(&params[0], &params[1])
+                .map(|partition_info| partition_info.timestamp)
+                .max(),
+            DaoType::GetLatestVersionUpToTimeFromPartitionInfo if params.len() == 3 => {
+                let timestamp = i64::from_str(&params[2])?;
+                tables
+                    .partition_versions(&params[0], &params[1])
+                    .filter(|partition_info| partition_info.timestamp < timestamp)
+                    .map(|partition_info| partition_info.version as i64)
+                    .max()
+            }
+            DaoType::GetLatestVersionTimestampUpToTimeFromPartitionInfo if params.len() == 3 => {
+                let timestamp = i64::from_str(&params[2])?;
+                tables
+                    .partition_versions(&params[0], &params[1])
+                    .filter(|partition_info| partition_info.timestamp < timestamp)
+                    .map(|partition_info| partition_info.timestamp)
+                    .max()
+            }
+            _ => return Err(invalid_input(query_type, params)),
+        };
+        Ok(scalar.map(|scalar| scalar.to_string()))
+    }
+}
+
+#[async_trait]
+impl MetaDataBackend for MemoryBackend {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// The operations are serialized by the lock of the tables.
+    fn pool_size(&self) -> usize {
+        1
+    }
+
+    async fn execute_query(&self, query_type: i32, joined_string: String) -> Result<JniWrapper> {
+        let query_type = dao_type(query_type, DAO_TYPE_QUERY_ONE_OFFSET..DAO_TYPE_INSERT_ONE_OFFSET)?;
+        self.query(query_type, &get_params(joined_string))
+    }
+
+    async fn execute_insert(&self, insert_type: i32, wrapper: JniWrapper) -> Result<i32> {
+        let insert_type = dao_type(insert_type, DAO_TYPE_INSERT_ONE_OFFSET..DAO_TYPE_QUERY_SCALAR_OFFSET)?;
+        self.insert(insert_type, wrapper)
+    }
+
+    async fn execute_update(&self, update_type: i32, joined_string: String) -> Result<i32> {
+        let update_type = dao_type(update_type, DAO_TYPE_UPDATE_OFFSET..i32::MAX)?;
+        self.update(update_type, &get_params(joined_string))
+    }
+
+    async fn execute_query_scalar(&self, query_type: i32, joined_string: String) -> Result<Option<String>> {
+        let query_type = dao_type(query_type, DAO_TYPE_QUERY_SCALAR_OFFSET..DAO_TYPE_UPDATE_OFFSET)?;
+        self.query_scalar(query_type, &get_params(joined_string))
+    }
+
+    async fn clean_meta_for_test(&self) -> Result<i32> {
+        *self.tables() = Tables::default();
+        Ok(0)
+    }
+
+    async fn transaction_insert_partition_info_with_notification(
+        &self,
+        partition_info_list: Vec<PartitionInfo>,
+    ) -> Result<i32> {
+        // the last element only carries the commits to be marked as committed
+        let committed = partition_info_list[..partition_info_list.len().saturating_sub(1)].to_vec();
+        let count = self
+            .execute_insert(
+                DaoType::TransactionInsertPartitionInfo as i32,
+                JniWrapper {
+                    partition_info: partition_info_list,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        if count > 0 {
+            let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+            for partition_info in &committed {
+                // the stream of a closed channel has been dropped
+                subscribers.retain(|subscriber| subscriber.send(Ok(CommitNotification::from(partition_info))).is_ok());
+            }
+        }
+        Ok(count)
+    }
+
+    async fn subscribe_commit_notifications(&self) -> Result<CommitNotificationStream> {
+        let (sender, receiver) = unbounded_channel();
+        self.subscribers.lock().unwrap_or_else(|e| e.into_inner()).push(sender);
+        Ok(CommitNotificationStream::from_receiver(receiver))
+    }
+}
+
+/// The [DaoType] of a type index in `range`, like the ranges checked by [crate::execute_query] and the others.
+fn dao_type(value: i32, range: Range<i32>) -> Result<DaoType> {
+    if !range.contains(&value) {
+        error!("Invalid dao_type_index: {:?}", value);
+        return Err(LakeSoulMetaDataError::from(ErrorKind::InvalidInput));
+    }
+    DaoType::try_from(value).map_err(|e| LakeSoulMetaDataError::Other(Box::new(e)))
+}
+
+fn invalid_input(dao_type: DaoType, params: &[String]) -> LakeSoulMetaDataError {
+    error!("Invalid params of type={:?}, params={:?}", dao_type, params);
+    LakeSoulMetaDataError::from(ErrorKind::InvalidInput)
+}
+
+/// The commit ids concatenated in hex by the last param, see [separate_uuid].
+fn commit_id_list(dao_type: DaoType, params: &[String]) -> Result<Vec<uuid::Uuid>> {
+    if params[2].len() % 32 != 0 {
+        return Err(invalid_input(dao_type, params));
+    }
+    separate_uuid(&params[2])?
+        .iter()
+        .map(|commit_id| Ok(uuid::Uuid::from_str(commit_id)?))
+        .collect()
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
+
+/// The JSON as stored and printed by pg, failing like pg for an invalid JSON.
+fn normalize_json(json: &str) -> Result<String> {
+    Ok(serde_json::from_str::<serde_json::Value>(json)?.to_string())
+}
+
+fn without_timestamp(partition_info: &PartitionInfo) -> PartitionInfo {
+    PartitionInfo {
+        timestamp: 0,
+        ..partition_info.clone()
+    }
+}
+
+fn normalize_table_info(table_info: &TableInfo) -> Result<TableInfo> {
+    Ok(TableInfo {
+        properties: normalize_json(&table_info.properties)?,
+        ..table_info.clone()
+    })
+}
+
+/// A partition version committed at `timestamp`, which defaults to the time of insertion in pg.
+fn normalize_partition_info(partition_info: &PartitionInfo, timestamp: i64) -> (PartitionKey, PartitionInfo) {
+    let key = (
+        partition_info.table_id.clone(),
+        partition_info.partition_desc.clone(),
+        partition_info.version,
+    );
+    let partition_info = PartitionInfo {
+        commit_op: partition_info.commit_op() as i32,
+        timestamp,
+        ..partition_info.clone()
+    };
+    (key, partition_info)
+}
+
+fn normalize_data_commit_info(data_commit_info: &DataCommitInfo) -> Result<(DataCommitKey, DataCommitInfo)> {
+    let commit_id = data_commit_info
+        .commit_id
+        .as_ref()
+        .ok_or(LakeSoulMetaDataError::Internal("commit_id missing".into()))?;
+    let key = (
+        data_commit_info.table_id.clone(),
+        data_commit_info.partition_desc.clone(),
+        uuid::Uuid::from_u64_pair(commit_id.high, commit_id.low),
+    );
+    let data_commit_info = DataCommitInfo {
+        file_ops: data_commit_info
+            .file_ops
+            .iter()
+            .map(|file_op| DataFileOp::from_proto_data_file_op(file_op)?.as_proto_data_file_op())
+            .collect::<Result<Vec<entity::DataFileOp>>>()?,
+        commit_op: data_commit_info.commit_op() as i32,
+        ..data_commit_info.clone()
+    };
+    Ok((key, data_commit_info))
+}
+
+/// Fail like a unique violation of pg if `key` exists.
+fn check_unique<K: Ord + Debug, V>(table: &BTreeMap<K, V>, key: &K) -> Result<()> {
+    if table.contains_key(key) {
+        return Err(LakeSoulMetaDataError::from(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!("duplicate key {:?}", key),
+        )));
+    }
+    Ok(())
+}
+
+fn insert_unique<K: Ord + Debug, V>(table: &mut BTreeMap<K, V>, key: K, value: V) -> Result<()> {
+    check_unique(table, &key)?;
+    table.insert(key, value);
+    Ok(())
+}
+
+/// Whether any of the `entries` conflicts with `table` or with another entry.
+fn has_conflict<K: Ord + Hash, V>(table: &BTreeMap<K, V>, entries: &[(K, V)]) -> bool {
+    let mut keys = HashSet::new();
+    entries
+        .iter()
+        .any(|(key, _)| table.contains_key(key) || !keys.insert(key))
+}
+
+/// Remove the entries matching `predicate`, returning the number of the removed entries.
+fn remove_where<K: Ord, V>(table: &mut BTreeMap<K, V>, mut predicate: impl FnMut(&K, &V) -> bool) -> usize {
+    let len = table.len();
+    table.retain(|key, value| !predicate(key, value));
+    len - table.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use proto::proto::entity::{
+        CommitOp, DataCommitInfo, DataFileOp, FileOp, JniWrapper, PartitionInfo, TableInfo, Uuid,
+    };
+
+    use super::MemoryBackend;
+    use crate::error::LakeSoulMetaDataError;
+    use crate::{branch_table_id, DaoType, MetaDataClient};
+
+    fn table_info() -> TableInfo {
+        TableInfo {
+            table_id: "table_1".to_string(),
+            table_namespace: "default".to_string(),
+            table_name: "test_memory_backend".to_string(),
+            table_path: "file:///tmp/test_memory_backend".to_string(),
+            table_schema: "{}".to_string(),
+            properties: "{ \"hashBucketNum\": \"2\" }".to_string(),
+            partitions: ";".to_string(),
+            domain: "public".to_string(),
+        }
+    }
+
+    fn data_commit_info(low: u64, path: &str) -> DataCommitInfo {
+        data_commit_info_of_table("table_1", low, path)
+    }
+
+    fn data_commit_info_of_table(table_id: &str, low: u64, path: &str) -> DataCommitInfo {
+        DataCommitInfo {
+            table_id: table_id.to_string(),
+            partition_desc: "-5".to_string(),
+            commit_id: Some(Uuid { high: 1, low }),
+            file_ops: vec![DataFileOp {
+                path: path.to_string(),
+                file_op: FileOp::Add as i32,
+                size: 1,
+                file_exist_cols: "".to_string(),
+            }],
+            commit_op: CommitOp::AppendCommit as i32,
+            timestamp: 0,
+            committed: false,
+            domain: "public".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_table_and_commits() {
+        let client = MetaDataClient::from_backend(Arc::new(MemoryBackend::new()));
+        assert_eq!(client.get_all_namespace().await.unwrap().len(), 1);
+
+        client.create_table(table_info()).await.unwrap();
+        assert!(client.create_table(table_info()).await.is_err());
+        let table_info = client
+            .get_table_info_by_table_name("test_memory_backend", "default")
+            .await
+            .unwrap();
+        assert_eq!(table_info.properties, "{\"hashBucketNum\":\"2\"}");
+
+        client
+            .commit_data_commit_info(data_commit_info(1, "file:///tmp/a.parquet"))
+            .await
+            .unwrap();
+        client
+            .commit_data_commit_info(data_commit_info(2, "file:///tmp/b.parquet"))
+            .await
+            .unwrap();
+        let partition_info_list = client.get_all_partition_info("table_1").await.unwrap();
+        assert_eq!(partition_info_list.len(), 1);
+        assert_eq!(partition_info_list[0].version, 1);
+        assert_eq!(
+            client
+                .get_data_files_of_single_partition(&partition_info_list[0])
+                .await
+                .unwrap(),
+            vec!["file:///tmp/a.parquet", "file:///tmp/b.parquet"]
+        );
+
+        client.delete_table_by_table_info_cascade(&table_info).await.unwrap();
+        assert!(client.get_all_partition_info("table_1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_branch_commits() {
+        let client = MetaDataClient::from_backend(Arc::new(MemoryBackend::new()));
+        client.create_table(table_info()).await.unwrap();
+        client
+            .commit_data_commit_info(data_commit_info(1, "file:///tmp/a.parquet"))
+            .await
+            .unwrap();
+        let partition_info_list = client.get_all_partition_info("table_1").await.unwrap();
+        let table_branch = client
+            .create_branch("table_1", "dev", &partition_info_list)
+            .await
+            .unwrap();
+        assert_eq!(table_branch.partition_versions.get("-5"), Some(&0));
+        assert!(matches!(
+            client.create_branch("table_1", "dev", &partition_info_list).await,
+            Err(LakeSoulMetaDataError::AlreadyExists(_))
+        ));
+
+        // the commits to the table id of the branch do not change the table
+        let branch_id = branch_table_id("table_1", "dev");
+        assert_eq!(
+            client.get_table_info_by_table_id(&branch_id).await.unwrap().table_id,
+            branch_id
+        );
+        client
+            .commit_data_commit_info(data_commit_info_of_table(&branch_id, 2, "file:///tmp/b.parquet"))
+            .await
+            .unwrap();
+        client
+            .commit_data_commit_info(data_commit_info(3, "file:///tmp/c.parquet"))
+            .await
+            .unwrap();
+        let branch_partition = &client.get_all_partition_info(&branch_id).await.unwrap()[0];
+        assert_eq!(branch_partition.version, 1);
+        assert_eq!(
+            client
+                .get_data_files_of_single_partition(branch_partition)
+                .await
+                .unwrap(),
+            vec!["file:///tmp/a.parquet", "file:///tmp/b.parquet"]
+        );
+        let table_partition = &client.get_all_partition_info("table_1").await.unwrap()[0];
+        assert_eq!(
+            client
+                .get_data_files_of_single_partition(table_partition)
+                .await
+                .unwrap(),
+            vec!["file:///tmp/a.parquet", "file:///tmp/c.parquet"]
+        );
+
+        assert_eq!(client.delete_branch("table_1", "dev").await.unwrap(), 1);
+        assert!(client.get_all_partition_info(&branch_id).await.unwrap().is_empty());
+        assert!(client.get_all_branches("table_1").await.unwrap().is_empty());
+        assert!(client.get_table_info_by_table_id(&branch_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_transaction_insert_conflict() {
+        let backend = MemoryBackend::new();
+        let partition_info = |version: i32| PartitionInfo {
+            table_id: "table_1".to_string(),
+            partition_desc: "-5".to_string(),
+            version,
+            commit_op: CommitOp::AppendCommit as i32,
+            ..Default::default()
+        };
+        let insert = |mut partition_info_list: Vec<PartitionInfo>| {
+            // the last element carries the commits to be marked as committed
+            partition_info_list.push(PartitionInfo::default());
+            backend.insert(
+                DaoType::TransactionInsertPartitionInfo,
+                JniWrapper {
+                    partition_info: partition_info_list,
+                    ..Default::default()
+                },
+            )
+        };
+        assert_eq!(insert(vec![partition_info(0)]).unwrap(), 1);
+        // a conflicting version rolls back the other versions of the transaction
+        assert_eq!(insert(vec![partition_info(1), partition_info(0)]).unwrap(), 0);
+        assert_eq!(insert(vec![partition_info(1), partition_info(1)]).unwrap(), 0);
+        assert_eq!(backend.tables().partition_info.len(), 1);
+        assert_eq!(insert(vec![partition_info(1)]).unwrap(), 1);
+    }
+}
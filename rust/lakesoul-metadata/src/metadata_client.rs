@@ -3,108 +3,174 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::fmt::{Debug, Formatter};
-use std::ops::DerefMut;
 use std::sync::Arc;
-use std::{collections::HashMap, env, fs, vec};
+use std::{
+    collections::{HashMap, HashSet},
+    vec,
+};
 
-use prost::Message;
-use tokio::sync::Mutex;
-use tokio_postgres::Client;
-use tracing::debug;
-use url::Url;
+use tracing::{debug, warn};
 
 use proto::proto::entity::{
-    self, CommitOp, DataCommitInfo, JniWrapper, MetaInfo, Namespace, PartitionInfo, TableInfo, TableNameId, TablePathId,
+    self, CommitOp, DataCommitInfo, FileOp, JniWrapper, MetaInfo, Namespace, PartitionInfo, TableBranch, TableInfo,
+    TableNameId, TablePathId, TableTag,
 };
 
+use crate::backend::{MetaDataBackend, PostgresBackend};
+use crate::config::{MetaDataBackendKind, MetaDataConfig};
 use crate::error::{LakeSoulMetaDataError, Result};
-use crate::{
-    clean_meta_for_test, create_connection, execute_insert, execute_query, execute_update, DaoType,
-    PreparedStatementMap, PARAM_DELIM, PARTITION_DESC_DELIM,
-};
+use crate::memory_backend::MemoryBackend;
+use crate::notification::CommitNotificationStream;
+use crate::pool::DEFAULT_POOL_SIZE;
+use crate::retry::{retry_backoff, DEFAULT_MAX_RETRY};
+use crate::transfusion::config::MAX_COMMIT_ATTEMPTS;
+use crate::{DaoType, PARAM_DELIM, PARTITION_DESC_DELIM};
 
 pub struct MetaDataClient {
-    client: Arc<Mutex<Client>>,
-    prepared: Arc<Mutex<PreparedStatementMap>>,
+    backend: Arc<dyn MetaDataBackend>,
     max_retry: usize,
+    commit_notification: bool,
 }
 
 impl Debug for MetaDataClient {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("MetaDataClient")
-            .field("client", &"{pg_client}")
+            .field("backend", &self.backend)
             .field("max_retry", &self.max_retry)
+            .field("commit_notification", &self.commit_notification)
             .finish()
     }
 }
 
 pub type MetaDataClientRef = Arc<MetaDataClient>;
 
+/// The delimiter between the table id and the branch name of the table id of a branch, see [branch_table_id].
+pub const BRANCH_TABLE_ID_DELIM: char = '@';
+
+/// The table id `{table_id}@{branch_name}` keeping the versions and the data commits of branch `branch_name`,
+/// so that the branch is read and committed to like a table by this id.
+pub fn branch_table_id(table_id: &str, branch_name: &str) -> String {
+    format!("{}{}{}", table_id, BRANCH_TABLE_ID_DELIM, branch_name)
+}
+
+/// Split the table id of a branch into the table id of its table and the branch name, see [branch_table_id].
+pub fn split_branch_table_id(table_id: &str) -> (&str, Option<&str>) {
+    match table_id.split_once(BRANCH_TABLE_ID_DELIM) {
+        Some((table_id, branch_name)) => (table_id, Some(branch_name)),
+        None => (table_id, None),
+    }
+}
+
+/// A version of a partition in the commit history of a table, see [MetaDataClient::list_commit_history].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitHistory {
+    pub partition_desc: String,
+    pub version: i32,
+    pub commit_op: CommitOp,
+    /// Timestamp in milliseconds of the version
+    pub timestamp: i64,
+    /// Domain of the committer, which is 'public' unless the rbac feature is enabled
+    pub domain: String,
+    /// Number of the data commits added by the version
+    pub num_commits: usize,
+    /// Number of the files added by the version
+    pub num_files: usize,
+    /// Total size in bytes of the files added by the version, as recorded in their data commits
+    pub total_size: i64,
+}
+
 impl MetaDataClient {
+    /// Create a client by the config file of the `lakesoul_home` env and the env, see [crate::config].
     pub async fn from_env() -> Result<Self> {
-        match env::var("lakesoul_home") {
-            Ok(config_path) => {
-                let config = fs::read_to_string(&config_path)
-                    .unwrap_or_else(|_| panic!("Fails at reading config file {}", &config_path));
-                let config_map = config
-                    .split('\n')
-                    .filter_map(|property| property.find('=').map(|idx| property.split_at(idx + 1)))
-                    .collect::<HashMap<_, _>>();
-                let url = Url::parse(
-                    &config_map
-                        .get("lakesoul.pg.url=")
-                        .unwrap_or(&"jdbc:postgresql://127.0.0.1:5432/lakesoul_test?stringtype=unspecified")[5..],
-                )?;
-                Self::from_config(format!(
-                    "host={} port={} dbname={} user={} password={}",
-                    url.host_str()
-                        .ok_or(LakeSoulMetaDataError::Internal("url host missing".to_string()))?,
-                    url.port()
-                        .ok_or(LakeSoulMetaDataError::Internal("url port missing".to_string()))?,
-                    url.path_segments()
-                        .ok_or(LakeSoulMetaDataError::Internal("url path missing".to_string()))?
-                        .next()
-                        .ok_or(LakeSoulMetaDataError::Internal("url path missing".to_string()))?,
-                    config_map.get("lakesoul.pg.username=").unwrap_or(&"lakesoul_test"),
-                    config_map.get("lakesoul.pg.password=").unwrap_or(&"lakesoul_test")
-                ))
-                .await
-            }
-            Err(_) => {
-                Self::from_config(
-                    "host=127.0.0.1 port=5432 dbname=lakesoul_test user=lakesoul_test password=lakesoul_test"
-                        .to_string(),
-                )
-                .await
-            }
+        Self::from_metadata_config(&MetaDataConfig::from_env()?).await
+    }
+
+    pub async fn from_metadata_config(config: &MetaDataConfig) -> Result<Self> {
+        match config.backend {
+            MetaDataBackendKind::Memory => Ok(Self::from_backend(MemoryBackend::shared())),
+            MetaDataBackendKind::Postgres => Self::from_config(config.pg_config()).await,
         }
     }
 
     pub async fn from_config(config: String) -> Result<Self> {
-        Self::from_config_and_max_retry(config, 3).await
+        Self::from_config_and_max_retry(config, DEFAULT_MAX_RETRY).await
     }
 
     pub async fn from_config_and_max_retry(config: String, max_retry: usize) -> Result<Self> {
-        let client = Arc::new(Mutex::new(create_connection(config).await?));
-        let prepared = Arc::new(Mutex::new(PreparedStatementMap::new()));
+        let backend = PostgresBackend::try_new(config, DEFAULT_POOL_SIZE).await?;
         Ok(Self {
-            client,
-            prepared,
             max_retry,
+            ..Self::from_backend(Arc::new(backend))
+        })
+    }
+
+    /// Create a client of the metadata in `backend`, e.g. a [MemoryBackend], see [crate::backend].
+    pub fn from_backend(backend: Arc<dyn MetaDataBackend>) -> Self {
+        Self {
+            backend,
+            max_retry: DEFAULT_MAX_RETRY,
+            commit_notification: false,
+        }
+    }
+
+    pub fn backend(&self) -> &Arc<dyn MetaDataBackend> {
+        &self.backend
+    }
+
+    /// Run the queries of this client concurrently on at most `pool_size` connections, see [crate::pool].
+    /// Only a [PostgresBackend] has a connection pool.
+    pub fn with_pool_size(self, pool_size: usize) -> Result<Self> {
+        let backend = match self.backend.as_any().downcast_ref::<PostgresBackend>() {
+            Some(backend) => backend.with_pool_size(pool_size)?,
+            None => {
+                return Err(LakeSoulMetaDataError::Internal(format!(
+                    "{:?} has no connection pool",
+                    self.backend
+                )))
+            }
+        };
+        Ok(Self {
+            backend: Arc::new(backend),
+            ..self
         })
     }
 
+    pub fn pool_size(&self) -> usize {
+        self.backend.pool_size()
+    }
+
+    /// Send a [CommitNotification](crate::CommitNotification) for each partition version committed by this client,
+    /// see [crate::notification].
+    pub fn with_commit_notification(self, commit_notification: bool) -> Self {
+        Self {
+            commit_notification,
+            ..self
+        }
+    }
+
+    /// Subscribe to the partition versions committed by the clients with commit notification,
+    /// on a new connection of the same config for a [PostgresBackend].
+    pub async fn subscribe_commit_notifications(&self) -> Result<CommitNotificationStream> {
+        self.backend.subscribe_commit_notifications().await
+    }
+
     pub async fn create_namespace(&self, namespace: Namespace) -> Result<()> {
         self.insert_namespace(&namespace).await?;
         Ok(())
     }
 
+    /// Insert the `TablePathId`, `TableNameId` and `TableInfo` of a new table in one transaction.
     pub async fn create_table(&self, table_info: TableInfo) -> Result<()> {
-        self.insert_table_path_id(&table_path_id_from_table_info(&table_info))
-            .await?;
-        self.insert_table_name_id(&table_name_id_from_table_info(&table_info))
-            .await?;
-        self.insert_table_info(&table_info).await?;
+        self.execute_insert(
+            DaoType::TransactionInsertTable as i32,
+            JniWrapper {
+                table_path_id: vec![table_path_id_from_table_info(&table_info)],
+                table_name_id: vec![table_name_id_from_table_info(&table_info)],
+                table_info: vec![table_info],
+                ..Default::default()
+            },
+        )
+        .await?;
         Ok(())
     }
 
@@ -124,6 +190,11 @@ impl MetaDataClient {
         self.delete_table_path_id_by_table_id(&table_info.table_id).await?;
         self.delete_partition_info_by_table_id(&table_info.table_id).await?;
         self.delete_data_commit_info_by_table_id(&table_info.table_id).await?;
+        self.delete_table_tag_by_table_id(&table_info.table_id).await?;
+        for table_branch in self.get_all_branches(&table_info.table_id).await? {
+            self.delete_branch(&table_info.table_id, &table_branch.branch_name)
+                .await?;
+        }
         self.delete_table_info_by_id_and_path(&table_info.table_id, &table_info.table_path)
             .await?;
         Ok(())
@@ -154,23 +225,74 @@ impl MetaDataClient {
         .await
     }
 
+    /// Delete the versions of a partition committed not later than `timestamp` in milliseconds.
+    pub async fn delete_previous_version_partition(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        timestamp: i64,
+    ) -> Result<i32> {
+        self.execute_update(
+            DaoType::DeletePreviousVersionPartition as i32,
+            [table_id, partition_desc, timestamp.to_string().as_str()].join(PARAM_DELIM),
+        )
+        .await
+    }
+
+    pub async fn delete_partition_version(&self, table_id: &str, partition_desc: &str, version: i32) -> Result<i32> {
+        self.execute_update(
+            DaoType::DeletePartitionInfoByTableIdAndPartitionDescAndVersion as i32,
+            [table_id, partition_desc, version.to_string().as_str()].join(PARAM_DELIM),
+        )
+        .await
+    }
+
+    pub async fn delete_table_tag_by_table_id(&self, table_id: &str) -> Result<i32> {
+        self.execute_update(DaoType::DeleteTableTagByTableId as i32, [table_id].join(PARAM_DELIM))
+            .await
+    }
+
+    pub async fn delete_single_data_commit_info(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        commit_id: &str,
+    ) -> Result<i32> {
+        self.execute_update(
+            DaoType::DeleteOneDataCommitInfoByTableIdAndPartitionDescAndCommitId as i32,
+            [table_id, partition_desc, commit_id].join(PARAM_DELIM),
+        )
+        .await
+    }
+
     pub async fn delete_table_info_by_id_and_path(&self, id: &str, path: &str) -> Result<i32> {
         self.execute_update(DaoType::DeleteTableInfoByIdAndPath as i32, [id, path].join(PARAM_DELIM))
             .await
     }
 
+    /// Replace the `table_schema` of a table, leaving its name and path unchanged.
+    pub async fn update_table_schema(&self, table_id: &str, table_schema: &str) -> Result<i32> {
+        self.execute_update(
+            DaoType::UpdateTableInfoById as i32,
+            [table_id, "", "", table_schema].join(PARAM_DELIM),
+        )
+        .await
+    }
+
+    pub async fn update_table_properties(&self, table_id: &str, properties: &str) -> Result<i32> {
+        self.execute_update(
+            DaoType::UpdateTableInfoPropertiesById as i32,
+            [table_id, properties].join(PARAM_DELIM),
+        )
+        .await
+    }
+
     async fn execute_insert(&self, insert_type: i32, wrapper: JniWrapper) -> Result<i32> {
-        for times in 0..self.max_retry as i64 {
-            match execute_insert(
-                self.client.lock().await.deref_mut(),
-                self.prepared.lock().await.deref_mut(),
-                insert_type,
-                wrapper.clone(),
-            )
-            .await
-            {
+        for attempt in 0..self.max_retry {
+            let result = self.backend.execute_insert(insert_type, wrapper.clone()).await;
+            match result {
                 Ok(count) => return Ok(count),
-                Err(_) if times < self.max_retry as i64 - 1 => continue,
+                Err(e) if self.should_retry(attempt, &e).await => continue,
                 Err(e) => return Err(e),
             };
         }
@@ -178,17 +300,11 @@ impl MetaDataClient {
     }
 
     async fn execute_update(&self, update_type: i32, joined_string: String) -> Result<i32> {
-        for times in 0..self.max_retry as i64 {
-            match execute_update(
-                self.client.lock().await.deref_mut(),
-                self.prepared.lock().await.deref_mut(),
-                update_type,
-                joined_string.clone(),
-            )
-            .await
-            {
+        for attempt in 0..self.max_retry {
+            let result = self.backend.execute_update(update_type, joined_string.clone()).await;
+            match result {
                 Ok(count) => return Ok(count),
-                Err(_) if times < self.max_retry as i64 - 1 => continue,
+                Err(e) if self.should_retry(attempt, &e).await => continue,
                 Err(e) => return Err(e),
             };
         }
@@ -196,61 +312,55 @@ impl MetaDataClient {
     }
 
     async fn execute_query(&self, query_type: i32, joined_string: String) -> Result<JniWrapper> {
-        for times in 0..self.max_retry as i64 {
-            match execute_query(
-                self.client.lock().await.deref_mut(),
-                self.prepared.lock().await.deref_mut(),
-                query_type,
-                joined_string.clone(),
-            )
-            .await
-            {
-                Ok(encoded) => return Ok(JniWrapper::decode(prost::bytes::Bytes::from(encoded))?),
-                Err(_) if times < self.max_retry as i64 - 1 => continue,
+        for attempt in 0..self.max_retry {
+            let result = self.backend.execute_query(query_type, joined_string.clone()).await;
+            match result {
+                Ok(wrapper) => return Ok(wrapper),
+                Err(e) if self.should_retry(attempt, &e).await => continue,
                 Err(e) => return Err(e),
             };
         }
         Err(LakeSoulMetaDataError::Internal("unreachable".to_string()))
     }
 
-    async fn insert_namespace(&self, namespace: &Namespace) -> Result<i32> {
-        self.execute_insert(
-            DaoType::InsertNamespace as i32,
-            JniWrapper {
-                namespace: vec![namespace.clone()],
-                ..Default::default()
-            },
-        )
-        .await
-    }
-
-    async fn insert_table_info(&self, table_info: &TableInfo) -> Result<i32> {
-        self.execute_insert(
-            DaoType::InsertTableInfo as i32,
-            JniWrapper {
-                table_info: vec![table_info.clone()],
-                ..Default::default()
-            },
-        )
-        .await
+    async fn execute_query_scalar(&self, query_type: i32, joined_string: String) -> Result<Option<String>> {
+        for attempt in 0..self.max_retry {
+            let result = self
+                .backend
+                .execute_query_scalar(query_type, joined_string.clone())
+                .await;
+            match result {
+                Ok(scalar) => return Ok(scalar),
+                Err(e) if self.should_retry(attempt, &e).await => continue,
+                Err(e) => return Err(e),
+            };
+        }
+        Err(LakeSoulMetaDataError::Internal("unreachable".to_string()))
     }
 
-    async fn insert_table_name_id(&self, table_name_id: &TableNameId) -> Result<i32> {
-        self.execute_insert(
-            DaoType::InsertTableNameId as i32,
-            JniWrapper {
-                table_name_id: vec![table_name_id.clone()],
-                ..Default::default()
-            },
-        )
-        .await
+    /// Whether to retry an operation failed by `error` at its `attempt`-th attempt, counted from 0,
+    /// which is the case of a transient error before the last attempt.
+    /// The retry is delayed by [retry_backoff], and a broken connection has been dropped from the pool meanwhile.
+    async fn should_retry(&self, attempt: usize, error: &LakeSoulMetaDataError) -> bool {
+        if attempt + 1 >= self.max_retry || !error.is_transient() {
+            return false;
+        }
+        let backoff = retry_backoff(attempt);
+        warn!(
+            "retry metadata operation after {:?}, attempt {} failed by {}",
+            backoff,
+            attempt + 1,
+            error
+        );
+        tokio::time::sleep(backoff).await;
+        true
     }
 
-    async fn insert_table_path_id(&self, table_path_id: &TablePathId) -> Result<i32> {
+    async fn insert_namespace(&self, namespace: &Namespace) -> Result<i32> {
         self.execute_insert(
-            DaoType::InsertTablePathId as i32,
+            DaoType::InsertNamespace as i32,
             JniWrapper {
-                table_path_id: vec![table_path_id.clone()],
+                namespace: vec![namespace.clone()],
                 ..Default::default()
             },
         )
@@ -269,18 +379,33 @@ impl MetaDataClient {
     }
 
     async fn transaction_insert_partition_info(&self, partition_info_list: Vec<PartitionInfo>) -> Result<i32> {
-        self.execute_insert(
-            DaoType::TransactionInsertPartitionInfo as i32,
-            JniWrapper {
-                partition_info: partition_info_list,
-                ..Default::default()
-            },
-        )
-        .await
+        if !self.commit_notification {
+            return self
+                .execute_insert(
+                    DaoType::TransactionInsertPartitionInfo as i32,
+                    JniWrapper {
+                        partition_info: partition_info_list,
+                        ..Default::default()
+                    },
+                )
+                .await;
+        }
+        for attempt in 0..self.max_retry {
+            let result = self
+                .backend
+                .transaction_insert_partition_info_with_notification(partition_info_list.clone())
+                .await;
+            match result {
+                Ok(count) => return Ok(count),
+                Err(e) if self.should_retry(attempt, &e).await => continue,
+                Err(e) => return Err(e),
+            };
+        }
+        Err(LakeSoulMetaDataError::Internal("unreachable".to_string()))
     }
 
     pub async fn meta_cleanup(&self) -> Result<i32> {
-        clean_meta_for_test(self.client.lock().await.deref_mut()).await?;
+        self.backend.clean_meta_for_test().await?;
         self.insert_namespace(&Namespace {
             namespace: "default".to_string(),
             properties: "{}".to_string(),
@@ -290,81 +415,236 @@ impl MetaDataClient {
         .await
     }
 
+    /// Commit the partition versions of `meta_info` to its table, or to a branch if the table id of its table info is
+    /// the one of the branch, see [branch_table_id].
     pub async fn commit_data(&self, meta_info: MetaInfo, commit_op: CommitOp) -> Result<()> {
         let table_info = meta_info
             .table_info
             .ok_or(LakeSoulMetaDataError::Internal("table info missing".to_string()))?;
-
-        if !table_info.table_name.is_empty() {
-            // todo: updateTableShortName
-        }
-        // todo: updateTableProperties
+        let domain = self.get_table_domain(&table_info.table_id).await?;
 
         // conflict handling
-        let _raw_map = meta_info
+        let raw_map = meta_info
             .list_partition
             .iter()
             .map(|partition_info| (partition_info.partition_desc.clone(), partition_info.clone()))
             .collect::<HashMap<String, PartitionInfo>>();
 
-        let partition_desc_list = meta_info
+        let read_partition_map = meta_info
+            .read_partition_info
+            .iter()
+            .map(|partition_info| (partition_info.partition_desc.clone(), partition_info.clone()))
+            .collect::<HashMap<String, PartitionInfo>>();
+
+        let mut partition_desc_list = meta_info
             .list_partition
             .iter()
             .map(|partition_info| partition_info.partition_desc.clone())
             .collect::<Vec<String>>();
 
-        let _snapshot_list = meta_info
+        let mut snapshot_list = meta_info
             .list_partition
             .iter()
             .flat_map(|partition_info| partition_info.snapshot.clone())
             .collect::<Vec<entity::Uuid>>();
 
-        // conflict handling
-        let cur_map = self
-            .get_cur_partition_map(&table_info.table_id, &partition_desc_list)
-            .await?;
+        // partition infos submitted by the previous failed attempt
+        let mut new_map = HashMap::<String, PartitionInfo>::new();
 
-        match commit_op {
-            CommitOp::AppendCommit | CommitOp::MergeCommit => {
-                let mut new_partition_list = meta_info
-                    .list_partition
-                    .iter()
-                    .map(|partition_info| {
-                        let partition_desc = &partition_info.partition_desc;
-                        match cur_map.get(partition_desc) {
-                            Some(cur_partition_info) => {
-                                let mut cur_partition_info = cur_partition_info.clone();
-                                cur_partition_info.domain = self.get_table_domain(&table_info.table_id)?;
-                                cur_partition_info
-                                    .snapshot
-                                    .extend_from_slice(&partition_info.snapshot[..]);
-                                cur_partition_info.version += 1;
-                                cur_partition_info.commit_op = commit_op as i32;
-                                cur_partition_info.expression = partition_info.expression.clone();
-                                Ok(cur_partition_info)
+        for retry_times in 0..=MAX_COMMIT_ATTEMPTS {
+            let cur_map = self
+                .get_cur_partition_map(&table_info.table_id, &partition_desc_list)
+                .await?;
+
+            let mut new_partition_list = Vec::with_capacity(partition_desc_list.len() + 1);
+            match commit_op {
+                CommitOp::AppendCommit | CommitOp::MergeCommit => {
+                    for partition_desc in &partition_desc_list {
+                        let raw_partition_info = &raw_map[partition_desc];
+                        let mut cur_partition_info = self.get_or_create_cur_partition_info(
+                            &cur_map,
+                            partition_desc,
+                            &table_info.table_id,
+                            &domain,
+                        );
+                        if let Some(last_partition_info) = new_map.get(partition_desc) {
+                            if cur_partition_info.version + 1 == last_partition_info.version {
+                                // this partition was not touched by others since the last attempt
+                                new_partition_list.push(last_partition_info.clone());
+                                continue;
+                            }
+                            // every version committed by others since the last attempt must be rebased on
+                            let concurrent_commit_ops = self
+                                .get_commit_ops_between_versions(
+                                    &table_info.table_id,
+                                    partition_desc,
+                                    last_partition_info.version,
+                                    cur_partition_info.version,
+                                )
+                                .await?;
+                            if let Some(cur_commit_op) = concurrent_commit_ops
+                                .iter()
+                                .find(|cur_commit_op| !can_rebase(commit_op, **cur_commit_op))
+                            {
+                                return Err(LakeSoulMetaDataError::CommitConflict(format!(
+                                    "{:?} conflicts with concurrent {:?} on table {}, partition {}",
+                                    commit_op, cur_commit_op, table_info.table_id, partition_desc
+                                )));
                             }
-                            None => Ok(PartitionInfo {
-                                table_id: table_info.table_id.clone(),
-                                partition_desc: partition_desc.clone(),
-                                version: 0,
-                                snapshot: Vec::from(&partition_info.snapshot[..]),
-                                domain: self.get_table_domain(&table_info.table_id)?,
-                                commit_op: commit_op as i32,
-                                expression: partition_info.expression.clone(),
-                                ..Default::default()
-                            }),
                         }
-                    })
-                    .collect::<Result<Vec<PartitionInfo>>>()?;
-                new_partition_list.push(PartitionInfo { ..Default::default() });
-                let val = self.transaction_insert_partition_info(new_partition_list).await?;
-                let vec = self.get_all_partition_info(table_info.table_id.as_str()).await?;
-                debug!("val = {val} ,get partition list after finished: {:?}", vec);
-                Ok(())
+                        cur_partition_info
+                            .snapshot
+                            .extend_from_slice(&raw_partition_info.snapshot[..]);
+                        cur_partition_info.version += 1;
+                        cur_partition_info.commit_op = commit_op as i32;
+                        cur_partition_info.expression = raw_partition_info.expression.clone();
+                        new_partition_list.push(cur_partition_info);
+                    }
+                }
+                CommitOp::CompactionCommit | CommitOp::UpdateCommit | CommitOp::DeleteCommit => {
+                    let mut skipped = Vec::new();
+                    for partition_desc in &partition_desc_list {
+                        match self
+                            .rebase_rewrite_partition(
+                                &table_info,
+                                commit_op,
+                                &raw_map[partition_desc],
+                                self.get_or_create_cur_partition_info(
+                                    &cur_map,
+                                    partition_desc,
+                                    &table_info.table_id,
+                                    &domain,
+                                ),
+                                read_partition_map.get(partition_desc),
+                            )
+                            .await?
+                        {
+                            Some(partition_info) => new_partition_list.push(partition_info),
+                            None => skipped.push(partition_desc.clone()),
+                        }
+                    }
+                    for partition_desc in &skipped {
+                        debug!("skip {:?} of partition {}", commit_op, partition_desc);
+                        partition_desc_list.retain(|desc| desc != partition_desc);
+                        snapshot_list.retain(|commit_id| !raw_map[partition_desc].snapshot.contains(commit_id));
+                    }
+                }
             }
-            _ => {
-                todo!()
+
+            if new_partition_list.is_empty() {
+                return Ok(());
+            }
+            new_map = new_partition_list
+                .iter()
+                .map(|partition_info| (partition_info.partition_desc.clone(), partition_info.clone()))
+                .collect();
+            // the last element carries the commits to be marked as committed
+            new_partition_list.push(PartitionInfo {
+                snapshot: snapshot_list.clone(),
+                ..Default::default()
+            });
+            let val = self.transaction_insert_partition_info(new_partition_list).await?;
+            if val > 0 {
+                debug!("{:?} finished after {} retries", commit_op, retry_times);
+                return Ok(());
+            }
+            debug!(
+                "{:?} of table {} lost the race of partition version, retry {}",
+                commit_op, table_info.table_id, retry_times
+            );
+        }
+        Err(LakeSoulMetaDataError::CommitConflict(format!(
+            "{:?} of table {} failed after {} attempts",
+            commit_op, table_info.table_id, MAX_COMMIT_ATTEMPTS
+        )))
+    }
+
+    /// Build the next version of a partition rewritten by CompactionCommit, UpdateCommit or DeleteCommit,
+    /// rebasing on the commits made since `read_partition` was read.
+    /// Returns `None` when the partition should be skipped.
+    ///
+    /// The rewrite is rebased only if every version committed since the read still has all the commits read,
+    /// i.e. no concurrent DeleteCommit, UpdateCommit or CompactionCommit has rewritten the files read.
+    /// The commits appended since then are kept on top of the rewritten snapshot.
+    async fn rebase_rewrite_partition(
+        &self,
+        table_info: &TableInfo,
+        commit_op: CommitOp,
+        raw_partition_info: &PartitionInfo,
+        mut cur_partition_info: PartitionInfo,
+        read_partition: Option<&PartitionInfo>,
+    ) -> Result<Option<PartitionInfo>> {
+        let partition_desc = &raw_partition_info.partition_desc;
+        let cur_version = cur_partition_info.version;
+        if commit_op == CommitOp::DeleteCommit && read_partition.is_none() {
+            return Ok(None);
+        }
+        // a partition which did not exist when read has been read at version -1
+        let read_partition_version = read_partition.map_or(-1, |p| p.version);
+
+        if read_partition_version == cur_version {
+            cur_partition_info.snapshot = raw_partition_info.snapshot.clone();
+        } else {
+            let middle_partitions = self
+                .get_incremental_partitions(
+                    &table_info.table_id,
+                    partition_desc,
+                    read_partition_version + 1,
+                    cur_version,
+                )
+                .await?;
+            let rewritten = read_partition.map_or(vec![], |read_partition| {
+                middle_partitions
+                    .iter()
+                    .chain(std::iter::once(&cur_partition_info))
+                    .filter(|partition_info| {
+                        read_partition
+                            .snapshot
+                            .iter()
+                            .any(|commit_id| !partition_info.snapshot.contains(commit_id))
+                    })
+                    .map(|partition_info| (partition_info.version, partition_info.commit_op()))
+                    .collect::<Vec<_>>()
+            });
+            if !rewritten.is_empty() {
+                if commit_op == CommitOp::CompactionCommit {
+                    // the partition has been rewritten since we read it, drop this compaction
+                    return Ok(None);
+                }
+                return Err(LakeSoulMetaDataError::CommitConflict(format!(
+                    "{:?} conflicts with the versions {:?} rewriting the files read at version {} on table {}, \
+                    partition {}",
+                    commit_op, rewritten, read_partition_version, table_info.table_id, partition_desc
+                )));
             }
+            update_submit_partition_snapshot(raw_partition_info, &mut cur_partition_info, read_partition);
+        }
+
+        cur_partition_info.version = cur_version + 1;
+        cur_partition_info.commit_op = commit_op as i32;
+        cur_partition_info.expression = raw_partition_info.expression.clone();
+        Ok(Some(cur_partition_info))
+    }
+
+    fn get_or_create_cur_partition_info(
+        &self,
+        cur_map: &HashMap<String, PartitionInfo>,
+        partition_desc: &str,
+        table_id: &str,
+        domain: &str,
+    ) -> PartitionInfo {
+        match cur_map.get(partition_desc) {
+            Some(cur_partition_info) => PartitionInfo {
+                domain: domain.to_string(),
+                ..cur_partition_info.clone()
+            },
+            None => PartitionInfo {
+                table_id: table_id.to_string(),
+                partition_desc: partition_desc.to_string(),
+                version: -1,
+                domain: domain.to_string(),
+                ..Default::default()
+            },
         }
     }
 
@@ -382,50 +662,573 @@ impl MetaDataClient {
     }
 
     pub async fn commit_data_commit_info(&self, data_commit_info: DataCommitInfo) -> Result<()> {
-        let table_id = &data_commit_info.table_id;
-        let partition_desc = &data_commit_info.partition_desc;
-        let commit_op = data_commit_info.commit_op;
-        let commit_id = &data_commit_info
-            .commit_id
-            .clone()
-            .ok_or(LakeSoulMetaDataError::Internal("commit_id missing".to_string()))?;
-        let commit_id_str = uuid::Uuid::from_u64_pair(commit_id.high, commit_id.low).to_string();
+        self.commit_data_commit_info_with_read_partition_info(data_commit_info, vec![])
+            .await
+    }
+
+    /// Commit a [DataCommitInfo] together with the partition versions it has read from.
+    /// `read_partition_info` is required by CompactionCommit, UpdateCommit and DeleteCommit.
+    pub async fn commit_data_commit_info_with_read_partition_info(
+        &self,
+        data_commit_info: DataCommitInfo,
+        read_partition_info: Vec<PartitionInfo>,
+    ) -> Result<()> {
+        let commit_op = CommitOp::try_from(data_commit_info.commit_op)
+            .map_err(|_| LakeSoulMetaDataError::Internal("unknown commit_op".to_string()))?;
+        self.commit_data_commit_info_list(vec![data_commit_info], read_partition_info, commit_op)
+            .await
+    }
+
+    /// Commit [DataCommitInfo]s of one table, which may span several partitions,
+    /// as a single partition_info transaction.
+    pub async fn commit_data_commit_info_list(
+        &self,
+        data_commit_info_list: Vec<DataCommitInfo>,
+        read_partition_info: Vec<PartitionInfo>,
+        commit_op: CommitOp,
+    ) -> Result<()> {
+        let Some(table_id) = data_commit_info_list.first().map(|info| info.table_id.clone()) else {
+            return Ok(());
+        };
+        let table_info = self.get_table_info_by_table_id(&table_id).await?;
+        let domain = table_info.domain.clone();
+        let mut list_partition = Vec::<PartitionInfo>::new();
+        for data_commit_info in data_commit_info_list {
+            let partition_desc = &data_commit_info.partition_desc;
+            let commit_id = data_commit_info
+                .commit_id
+                .clone()
+                .ok_or(LakeSoulMetaDataError::Internal("commit_id missing".to_string()))?;
+            let commit_id_str = uuid::Uuid::from_u64_pair(commit_id.high, commit_id.low).to_string();
+            match self
+                .get_single_data_commit_info(&table_id, partition_desc, &commit_id_str)
+                .await?
+            {
+                Some(data_commit_info) if data_commit_info.committed => {
+                    continue;
+                }
+                None => {
+                    self.insert_data_commit_info(&data_commit_info).await?;
+                }
+                _ => {}
+            };
+            match list_partition
+                .iter_mut()
+                .find(|partition_info| &partition_info.partition_desc == partition_desc)
+            {
+                Some(partition_info) => partition_info.snapshot.push(commit_id),
+                None => list_partition.push(PartitionInfo {
+                    table_id: table_id.clone(),
+                    partition_desc: partition_desc.clone(),
+                    commit_op: commit_op as i32,
+                    domain: domain.clone(),
+                    snapshot: vec![commit_id],
+                    ..Default::default()
+                }),
+            }
+        }
+        if list_partition.is_empty() {
+            return Ok(());
+        }
+        self.commit_data(
+            MetaInfo {
+                table_info: Some(table_info),
+                list_partition,
+                read_partition_info,
+            },
+            commit_op,
+        )
+        .await
+    }
+
+    /// Roll back a partition to its version `to_version` by committing a new version with the snapshot of
+    /// `to_version`, so that the rollback is itself a version which can be read, audited and rolled back.
+    /// The new version is an UpdateCommit, i.e. incremental reads see the rows it removes and restores.
+    ///
+    /// Returns the new version, or `None` if `to_version` is already the latest version.
+    /// Fails with NotFound if `to_version` does not exist or has been expired.
+    pub async fn rollback_partition(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        to_version: i32,
+    ) -> Result<Option<PartitionInfo>> {
+        let partition_info = self
+            .get_partition_info_by_version(table_id, partition_desc, to_version)
+            .await?
+            .ok_or_else(|| {
+                LakeSoulMetaDataError::NotFound(format!(
+                    "version {} of table {}, partition {}",
+                    to_version, table_id, partition_desc
+                ))
+            })?;
+        self.commit_partition_snapshot(table_id, partition_desc, to_version, partition_info.snapshot)
+            .await
+    }
+
+    /// Roll back each partition of a table to its version as of `version`, see [Self::rollback_partition].
+    /// The partitions whose latest version is not greater than `version` are unchanged.
+    pub async fn rollback_to_version(&self, table_id: &str, version: i32) -> Result<Vec<PartitionInfo>> {
+        let mut rolled_back = Vec::new();
+        for partition_info in self.get_all_partition_info(table_id).await? {
+            if partition_info.version > version {
+                rolled_back.extend(
+                    self.rollback_partition(table_id, &partition_info.partition_desc, version)
+                        .await?,
+                );
+            }
+        }
+        Ok(rolled_back)
+    }
+
+    /// Roll back each partition of a table to its latest version committed not later than `timestamp`
+    /// in milliseconds, see [Self::rollback_partition].
+    /// The partitions created after `timestamp` are rolled back to an empty snapshot.
+    pub async fn rollback_to_timestamp(&self, table_id: &str, timestamp: i64) -> Result<Vec<PartitionInfo>> {
+        let mut rolled_back = Vec::new();
+        for partition_info in self.get_all_partition_info(table_id).await? {
+            let partition_desc = &partition_info.partition_desc;
+            // the version committed at `timestamp` is included
+            let new_partition_info = match self
+                .get_latest_version_up_to_time(table_id, partition_desc, timestamp + 1)
+                .await?
+            {
+                Some(version) if version == partition_info.version => None,
+                Some(version) => self.rollback_partition(table_id, partition_desc, version).await?,
+                None if partition_info.snapshot.is_empty() => None,
+                None => {
+                    // without the first version, the versions before `timestamp` may have been expired
+                    if self
+                        .get_partition_info_by_version(table_id, partition_desc, 0)
+                        .await?
+                        .is_none()
+                    {
+                        return Err(LakeSoulMetaDataError::NotFound(format!(
+                            "version as of timestamp {} of table {}, partition {}",
+                            timestamp, table_id, partition_desc
+                        )));
+                    }
+                    self.commit_partition_snapshot(table_id, partition_desc, -1, vec![])
+                        .await?
+                }
+            };
+            rolled_back.extend(new_partition_info);
+        }
+        Ok(rolled_back)
+    }
+
+    /// Roll back each partition of a table to its version pinned by tag `tag_name`, see [Self::rollback_partition].
+    /// The partitions created after tagging are rolled back to an empty snapshot.
+    pub async fn rollback_to_tag(&self, table_id: &str, tag_name: &str) -> Result<Vec<PartitionInfo>> {
+        let table_tag = self
+            .get_tag(table_id, tag_name)
+            .await?
+            .ok_or_else(|| LakeSoulMetaDataError::NotFound(format!("tag {} of table {}", tag_name, table_id)))?;
+        let mut rolled_back = Vec::new();
+        for partition_info in self.get_all_partition_info(table_id).await? {
+            let partition_desc = &partition_info.partition_desc;
+            let new_partition_info = match table_tag.partition_versions.get(partition_desc) {
+                Some(&version) => self.rollback_partition(table_id, partition_desc, version).await?,
+                None if partition_info.snapshot.is_empty() => None,
+                None => {
+                    self.commit_partition_snapshot(table_id, partition_desc, -1, vec![])
+                        .await?
+                }
+            };
+            rolled_back.extend(new_partition_info);
+        }
+        Ok(rolled_back)
+    }
+
+    /// Create tag `tag_name` of a table pinning the versions of `partition_info_list`,
+    /// e.g. the latest versions from [Self::get_all_partition_info].
+    /// The pinned versions are kept by snapshot expiration until the tag is deleted.
+    /// Fails with [LakeSoulMetaDataError::AlreadyExists] if the table has a tag of the same name.
+    pub async fn create_tag(
+        &self,
+        table_id: &str,
+        tag_name: &str,
+        partition_info_list: &[PartitionInfo],
+    ) -> Result<TableTag> {
+        let table_tag = TableTag {
+            table_id: table_id.to_string(),
+            tag_name: tag_name.to_string(),
+            partition_versions: partition_info_list
+                .iter()
+                .map(|partition_info| (partition_info.partition_desc.clone(), partition_info.version))
+                .collect(),
+            domain: self.get_table_domain(table_id).await?,
+            ..Default::default()
+        };
+        // the primary key (table_id, tag_name) rejects the duplicate tags, even if created concurrently
         match self
-            .get_single_data_commit_info(table_id, partition_desc, &commit_id_str)
+            .execute_insert(
+                DaoType::InsertTableTag as i32,
+                JniWrapper {
+                    table_tag: vec![table_tag.clone()],
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            Ok(_) => Ok(table_tag),
+            Err(e) if e.is_unique_violation() => Err(LakeSoulMetaDataError::AlreadyExists(format!(
+                "tag {} of table {} already exists",
+                tag_name, table_id
+            ))),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn delete_tag(&self, table_id: &str, tag_name: &str) -> Result<i32> {
+        self.execute_update(
+            DaoType::DeleteTableTagByTableIdAndTagName as i32,
+            [table_id, tag_name].join(PARAM_DELIM),
+        )
+        .await
+    }
+
+    pub async fn get_tag(&self, table_id: &str, tag_name: &str) -> Result<Option<TableTag>> {
+        self.execute_query(
+            DaoType::SelectTableTagByTableIdAndTagName as i32,
+            [table_id, tag_name].join(PARAM_DELIM),
+        )
+        .await
+        .map(|wrapper| wrapper.table_tag.into_iter().next())
+    }
+
+    /// Get all the tags of a table, ordered by name.
+    pub async fn get_all_tags(&self, table_id: &str) -> Result<Vec<TableTag>> {
+        let mut tags = self
+            .execute_query(DaoType::ListTableTagByTableId as i32, table_id.to_string())
             .await?
+            .table_tag;
+        tags.sort_by(|a, b| a.tag_name.cmp(&b.tag_name));
+        Ok(tags)
+    }
+
+    /// Create branch `branch_name` of a table forked from the versions of `partition_info_list`,
+    /// e.g. the latest versions from [Self::get_all_partition_info].
+    /// The branch starts with copies of the versions and their data commits under the table id of the branch,
+    /// see [branch_table_id], while the files are shared with the table.
+    /// The versions the branch is forked from are kept by snapshot expiration until the branch is deleted.
+    /// Fails with [LakeSoulMetaDataError::AlreadyExists] if the table has a branch of the same name.
+    pub async fn create_branch(
+        &self,
+        table_id: &str,
+        branch_name: &str,
+        partition_info_list: &[PartitionInfo],
+    ) -> Result<TableBranch> {
+        // a branch of a branch is not supported, whose table id would be ambiguous
+        if branch_name.is_empty()
+            || branch_name.contains(BRANCH_TABLE_ID_DELIM)
+            || table_id.contains(BRANCH_TABLE_ID_DELIM)
         {
-            Some(data_commit_info) if data_commit_info.committed => {
-                return Ok(());
+            return Err(LakeSoulMetaDataError::from(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid branch name '{}' of table {}", branch_name, table_id),
+            )));
+        }
+        let table_branch = TableBranch {
+            table_id: table_id.to_string(),
+            branch_name: branch_name.to_string(),
+            partition_versions: partition_info_list
+                .iter()
+                .map(|partition_info| (partition_info.partition_desc.clone(), partition_info.version))
+                .collect(),
+            domain: self.get_table_domain(table_id).await?,
+            ..Default::default()
+        };
+        // the primary key (table_id, branch_name) rejects the duplicate branches, even if created concurrently
+        match self
+            .execute_insert(
+                DaoType::InsertTableBranch as i32,
+                JniWrapper {
+                    table_branch: vec![table_branch.clone()],
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            Ok(_) => {}
+            Err(e) if e.is_unique_violation() => {
+                return Err(LakeSoulMetaDataError::AlreadyExists(format!(
+                    "branch {} of table {} already exists",
+                    branch_name, table_id
+                )))
             }
-            None => {
-                self.insert_data_commit_info(&data_commit_info).await?;
+            Err(e) => return Err(e),
+        }
+        // the branch is only usable once forked, so a partially forked branch is deleted
+        if let Err(e) = self
+            .fork_branch(&branch_table_id(table_id, branch_name), partition_info_list)
+            .await
+        {
+            if let Err(delete_error) = self.delete_branch(table_id, branch_name).await {
+                warn!(
+                    "failed to delete branch {} of table {} after a failed fork: {}",
+                    branch_name, table_id, delete_error
+                );
+            }
+            return Err(e);
+        }
+        Ok(table_branch)
+    }
+
+    /// Copy the versions of `partition_info_list` and their data commits to the table id `branch_id` of a branch.
+    async fn fork_branch(&self, branch_id: &str, partition_info_list: &[PartitionInfo]) -> Result<()> {
+        let mut data_commit_info_list = Vec::new();
+        let mut branch_partition_info_list = Vec::with_capacity(partition_info_list.len() + 1);
+        for partition_info in partition_info_list {
+            if !partition_info.snapshot.is_empty() {
+                for data_commit_info in self.get_data_commit_info_of_single_partition(partition_info).await? {
+                    data_commit_info_list.push(DataCommitInfo {
+                        table_id: branch_id.to_string(),
+                        ..data_commit_info
+                    });
+                }
             }
-            _ => {}
+            branch_partition_info_list.push(PartitionInfo {
+                table_id: branch_id.to_string(),
+                ..partition_info.clone()
+            });
+        }
+        if branch_partition_info_list.is_empty() {
+            return Ok(());
+        }
+        if !data_commit_info_list.is_empty() {
+            let count = data_commit_info_list.len() as i32;
+            let inserted = self
+                .execute_insert(
+                    DaoType::TransactionInsertDataCommitInfo as i32,
+                    JniWrapper {
+                        data_commit_info: data_commit_info_list,
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            if inserted != count {
+                return Err(LakeSoulMetaDataError::CommitConflict(format!(
+                    "data commits of branch {} already exist",
+                    branch_id
+                )));
+            }
+        }
+        // the copied data commits are already committed, so the last element carries nothing
+        branch_partition_info_list.push(PartitionInfo::default());
+        if self
+            .transaction_insert_partition_info(branch_partition_info_list)
+            .await?
+            == 0
+        {
+            return Err(LakeSoulMetaDataError::CommitConflict(format!(
+                "versions of branch {} already exist",
+                branch_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Delete branch `branch_name` with its versions and data commits, so that the versions it is forked from can be
+    /// expired. The files written to the branch are not deleted.
+    /// Returns the number of deleted branches, i.e. 0 if the table has no such branch.
+    pub async fn delete_branch(&self, table_id: &str, branch_name: &str) -> Result<i32> {
+        let branch_id = branch_table_id(table_id, branch_name);
+        self.delete_partition_info_by_table_id(&branch_id).await?;
+        self.delete_data_commit_info_by_table_id(&branch_id).await?;
+        self.execute_update(
+            DaoType::DeleteTableBranchByTableIdAndBranchName as i32,
+            [table_id, branch_name].join(PARAM_DELIM),
+        )
+        .await
+    }
+
+    pub async fn get_branch(&self, table_id: &str, branch_name: &str) -> Result<Option<TableBranch>> {
+        self.execute_query(
+            DaoType::SelectTableBranchByTableIdAndBranchName as i32,
+            [table_id, branch_name].join(PARAM_DELIM),
+        )
+        .await
+        .map(|wrapper| wrapper.table_branch.into_iter().next())
+    }
+
+    /// Get all the branches of a table, ordered by name.
+    pub async fn get_all_branches(&self, table_id: &str) -> Result<Vec<TableBranch>> {
+        let mut branches = self
+            .execute_query(DaoType::ListTableBranchByTableId as i32, table_id.to_string())
+            .await?
+            .table_branch;
+        branches.sort_by(|a, b| a.branch_name.cmp(&b.branch_name));
+        Ok(branches)
+    }
+
+    /// Get the versions of the partitions pinned by tag `tag_name`.
+    pub async fn get_all_partition_info_of_tag(&self, table_id: &str, tag_name: &str) -> Result<Vec<PartitionInfo>> {
+        let table_tag = self
+            .get_tag(table_id, tag_name)
+            .await?
+            .ok_or_else(|| LakeSoulMetaDataError::NotFound(format!("tag {} of table {}", tag_name, table_id)))?;
+        let mut partition_info_list = Vec::with_capacity(table_tag.partition_versions.len());
+        for (partition_desc, version) in &table_tag.partition_versions {
+            partition_info_list.push(
+                self.get_partition_info_by_version(table_id, partition_desc, *version)
+                    .await?
+                    .ok_or_else(|| {
+                        LakeSoulMetaDataError::NotFound(format!(
+                            "version {} of table {}, partition {} pinned by tag {}",
+                            version, table_id, partition_desc, tag_name
+                        ))
+                    })?,
+            );
+        }
+        Ok(partition_info_list)
+    }
+
+    /// List the commit history of a table, i.e. each version of its partitions with the data commits added by it,
+    /// ordered from the latest to the earliest.
+    ///
+    /// The history can be filtered by `partition_desc` and by the timestamps in milliseconds of the versions,
+    /// with `start_timestamp` inclusive and `end_timestamp` exclusive.
+    /// The data commits of the versions removed by snapshot expiration are no longer counted.
+    pub async fn list_commit_history(
+        &self,
+        table_id: &str,
+        partition_desc: Option<&str>,
+        start_timestamp: Option<i64>,
+        end_timestamp: Option<i64>,
+    ) -> Result<Vec<CommitHistory>> {
+        let partition_desc_list = match partition_desc {
+            Some(partition_desc) => vec![partition_desc.to_string()],
+            None => self
+                .get_all_partition_info(table_id)
+                .await?
+                .into_iter()
+                .map(|partition_info| partition_info.partition_desc)
+                .collect(),
         };
-        let table_info = Some(self.get_table_info_by_table_id(table_id).await?);
-        let domain = self.get_table_domain(table_id)?;
-        self.commit_data(
-            MetaInfo {
-                table_info,
-                list_partition: vec![PartitionInfo {
-                    table_id: table_id.clone(),
+        let in_range = |timestamp: i64| {
+            start_timestamp.map_or(true, |start| timestamp >= start)
+                && end_timestamp.map_or(true, |end| timestamp < end)
+        };
+
+        let mut history = Vec::new();
+        for partition_desc in &partition_desc_list {
+            let versions = self.get_all_versions_of_partition(table_id, partition_desc).await?;
+            // the data commits added by each version, compared with its previous version
+            let mut previous_snapshot = HashSet::new();
+            let mut added_commits = Vec::with_capacity(versions.len());
+            for partition_info in &versions {
+                let snapshot = partition_info
+                    .snapshot
+                    .iter()
+                    .map(|commit_id| (commit_id.high, commit_id.low))
+                    .collect::<HashSet<_>>();
+                added_commits.push(
+                    partition_info
+                        .snapshot
+                        .iter()
+                        .filter(|commit_id| !previous_snapshot.contains(&(commit_id.high, commit_id.low)))
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                );
+                previous_snapshot = snapshot;
+            }
+
+            let versions = versions
+                .into_iter()
+                .zip(added_commits)
+                .filter(|(partition_info, _)| in_range(partition_info.timestamp))
+                .collect::<Vec<_>>();
+            let all_added_commits = versions
+                .iter()
+                .flat_map(|(_, added_commits)| added_commits.iter().cloned())
+                .collect::<Vec<_>>();
+            let data_commit_info_map = if all_added_commits.is_empty() {
+                HashMap::new()
+            } else {
+                self.get_data_commit_info_of_single_partition(&PartitionInfo {
+                    table_id: table_id.to_string(),
                     partition_desc: partition_desc.clone(),
-                    commit_op,
-                    domain,
-                    snapshot: vec![commit_id.clone()],
+                    snapshot: all_added_commits,
                     ..Default::default()
-                }],
-                ..Default::default()
-            },
-            CommitOp::try_from(commit_op)
-                .map_err(|_| LakeSoulMetaDataError::Internal("unknown commit_op".to_string()))?,
-        )
-        .await
+                })
+                .await?
+                .into_iter()
+                .filter_map(|data_commit_info| {
+                    let commit_id = data_commit_info.commit_id.as_ref()?;
+                    Some(((commit_id.high, commit_id.low), data_commit_info))
+                })
+                .collect::<HashMap<_, _>>()
+            };
+
+            for (partition_info, added_commits) in versions {
+                let added_files = added_commits
+                    .iter()
+                    .filter_map(|commit_id| data_commit_info_map.get(&(commit_id.high, commit_id.low)))
+                    .flat_map(|data_commit_info| data_commit_info.file_ops.iter())
+                    .filter(|file_op| file_op.file_op == FileOp::Add as i32)
+                    .collect::<Vec<_>>();
+                history.push(CommitHistory {
+                    commit_op: partition_info.commit_op(),
+                    partition_desc: partition_info.partition_desc,
+                    version: partition_info.version,
+                    timestamp: partition_info.timestamp,
+                    domain: partition_info.domain,
+                    num_commits: added_commits.len(),
+                    num_files: added_files.len(),
+                    total_size: added_files.iter().map(|file_op| file_op.size).sum(),
+                });
+            }
+        }
+        history.sort_by(|a, b| {
+            b.timestamp
+                .cmp(&a.timestamp)
+                .then_with(|| a.partition_desc.cmp(&b.partition_desc))
+                .then_with(|| b.version.cmp(&a.version))
+        });
+        Ok(history)
     }
 
-    pub fn get_table_domain(&self, _table_id: &str) -> Result<String> {
-        // todo: get property table_domain
-        Ok("public".to_string())
+    /// Commit a new version of a partition with `snapshot`, which is taken from `from_version`.
+    /// A rollback does not rebase on concurrent commits, which would be silently discarded by it.
+    async fn commit_partition_snapshot(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        from_version: i32,
+        snapshot: Vec<entity::Uuid>,
+    ) -> Result<Option<PartitionInfo>> {
+        let cur_map = self
+            .get_cur_partition_map(table_id, &[partition_desc.to_string()])
+            .await?;
+        let domain = self.get_table_domain(table_id).await?;
+        let mut partition_info = self.get_or_create_cur_partition_info(&cur_map, partition_desc, table_id, &domain);
+        if partition_info.version == from_version {
+            return Ok(None);
+        }
+        partition_info.version += 1;
+        partition_info.commit_op = CommitOp::UpdateCommit as i32;
+        partition_info.snapshot = snapshot;
+        // the commits of the snapshot have been committed, so the last element carries nothing
+        let val = self
+            .transaction_insert_partition_info(vec![partition_info.clone(), PartitionInfo::default()])
+            .await?;
+        if val == 0 {
+            return Err(LakeSoulMetaDataError::CommitConflict(format!(
+                "rollback of table {}, partition {} conflicts with a concurrent commit",
+                table_id, partition_desc
+            )));
+        }
+        debug!(
+            "roll back table {}, partition {} to version {} as version {}",
+            table_id, partition_desc, from_version, partition_info.version
+        );
+        Ok(Some(partition_info))
+    }
+
+    /// The domain of a table, in which its partition versions, data commits and tags are created.
+    pub async fn get_table_domain(&self, table_id: &str) -> Result<String> {
+        Ok(self.get_table_info_by_table_id(table_id).await?.domain)
     }
 
     pub async fn get_all_table_name_id_by_namespace(&self, namespace: &str) -> Result<Vec<TableNameId>> {
@@ -495,12 +1298,26 @@ impl MetaDataClient {
         }
     }
 
+    /// Get the table info of `table_id`, or the table info of the table of a branch by the table id of the branch,
+    /// which keeps the table id of the branch, see [branch_table_id].
     pub async fn get_table_info_by_table_id(&self, table_id: &str) -> Result<TableInfo> {
+        let (main_table_id, branch_name) = split_branch_table_id(table_id);
+        if let Some(branch_name) = branch_name {
+            self.get_branch(main_table_id, branch_name).await?.ok_or_else(|| {
+                LakeSoulMetaDataError::NotFound(format!("branch {} of table {}", branch_name, main_table_id))
+            })?;
+        }
         match self
-            .execute_query(DaoType::SelectTableInfoByTableId as i32, table_id.to_string())
+            .execute_query(DaoType::SelectTableInfoByTableId as i32, main_table_id.to_string())
             .await
         {
-            Ok(wrapper) => Ok(wrapper.table_info[0].clone()),
+            Ok(wrapper) if wrapper.table_info.is_empty() => {
+                Err(LakeSoulMetaDataError::NotFound(format!("table {}", main_table_id)))
+            }
+            Ok(wrapper) => Ok(TableInfo {
+                table_id: table_id.to_string(),
+                ..wrapper.table_info[0].clone()
+            }),
             Err(err) => Err(err),
         }
     }
@@ -528,21 +1345,25 @@ impl MetaDataClient {
 
     pub async fn get_data_files_of_single_partition(&self, partition_info: &PartitionInfo) -> Result<Vec<String>> {
         let data_commit_info_list = self.get_data_commit_info_of_single_partition(partition_info).await?;
-        // let data_commit_info_list = Vec::<DataCommitInfo>::new();
-        let data_file_list = data_commit_info_list
+        // a file removed by a later commit (e.g. UpdateCommit or DeleteCommit) is no longer visible
+        let mut deleted = HashSet::new();
+        let mut data_file_list = Vec::new();
+        for file_op in data_commit_info_list
             .iter()
-            .flat_map(|data_commit_info| {
-                data_commit_info
-                    .file_ops
-                    .iter()
-                    .map(|file_op| file_op.path.clone())
-                    .collect::<Vec<String>>()
-            })
-            .collect::<Vec<String>>();
+            .flat_map(|data_commit_info| data_commit_info.file_ops.iter())
+            .rev()
+        {
+            if file_op.file_op == FileOp::Del as i32 {
+                deleted.insert(file_op.path.clone());
+            } else if !deleted.contains(&file_op.path) {
+                data_file_list.push(file_op.path.clone());
+            }
+        }
+        data_file_list.reverse();
         Ok(data_file_list)
     }
 
-    async fn get_data_commit_info_of_single_partition(
+    pub async fn get_data_commit_info_of_single_partition(
         &self,
         partition_info: &PartitionInfo,
     ) -> Result<Vec<DataCommitInfo>> {
@@ -582,6 +1403,135 @@ impl MetaDataClient {
         }
     }
 
+    /// Get the snapshot of the partitions of a table as of `version`,
+    /// i.e. the latest version of each partition which is not greater than `version`.
+    /// Fails with [LakeSoulMetaDataError::VersionExpired] if that version of any partition has been expired.
+    pub async fn get_all_partition_info_as_of_version(
+        &self,
+        table_id: &str,
+        version: i32,
+    ) -> Result<Vec<PartitionInfo>> {
+        let mut partition_info_list = Vec::new();
+        for partition_info in self.get_all_partition_info(table_id).await? {
+            if partition_info.version <= version {
+                partition_info_list.push(partition_info);
+                continue;
+            }
+            // the versions of a partition start from 0 without gaps until some of them are expired
+            match self
+                .get_partition_info_by_version(table_id, &partition_info.partition_desc, version)
+                .await?
+            {
+                Some(partition_info) => partition_info_list.push(partition_info),
+                None if version < 0 => {}
+                None => {
+                    return Err(LakeSoulMetaDataError::VersionExpired(format!(
+                        "version {} of partition {} of table {} has been expired",
+                        version, partition_info.partition_desc, table_id
+                    )))
+                }
+            }
+        }
+        Ok(partition_info_list)
+    }
+
+    /// Get the snapshot of the partitions of a table as of `timestamp` in milliseconds,
+    /// i.e. the latest version of each partition committed not later than `timestamp`.
+    /// The partitions created after `timestamp` are not included.
+    /// Fails with [LakeSoulMetaDataError::VersionExpired] if the version of any partition as of `timestamp`
+    /// has been expired.
+    pub async fn get_all_partition_info_as_of_timestamp(
+        &self,
+        table_id: &str,
+        timestamp: i64,
+    ) -> Result<Vec<PartitionInfo>> {
+        let mut partition_info_list = Vec::new();
+        for partition_info in self.get_all_partition_info(table_id).await? {
+            // the version committed at `timestamp` is included
+            if self
+                .get_latest_version_up_to_time(table_id, &partition_info.partition_desc, timestamp + 1)
+                .await?
+                == Some(partition_info.version)
+            {
+                partition_info_list.push(partition_info);
+                continue;
+            }
+            // the latest retained version up to `timestamp` is the one as of `timestamp`
+            // only if the version following it has not been expired
+            let versions = self
+                .get_all_versions_of_partition(table_id, &partition_info.partition_desc)
+                .await?;
+            match versions.iter().rposition(|version| version.timestamp <= timestamp) {
+                Some(idx)
+                    if versions
+                        .get(idx + 1)
+                        .map_or(true, |next| next.version == versions[idx].version + 1) =>
+                {
+                    partition_info_list.push(versions[idx].clone())
+                }
+                // the partition is created after `timestamp`
+                None if versions.first().map_or(true, |version| version.version == 0) => {}
+                _ => {
+                    return Err(LakeSoulMetaDataError::VersionExpired(format!(
+                        "the version as of timestamp {} of partition {} of table {} has been expired",
+                        timestamp, partition_info.partition_desc, table_id
+                    )))
+                }
+            }
+        }
+        Ok(partition_info_list)
+    }
+
+    /// Get all the versions of a partition, ordered by version.
+    pub async fn get_all_versions_of_partition(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+    ) -> Result<Vec<PartitionInfo>> {
+        let mut versions = self
+            .execute_query(
+                DaoType::ListPartitionByTableIdAndDesc as i32,
+                [table_id, partition_desc].join(PARAM_DELIM),
+            )
+            .await?
+            .partition_info;
+        versions.sort_by_key(|partition_info| partition_info.version);
+        Ok(versions)
+    }
+
+    pub async fn get_partition_info_by_version(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        version: i32,
+    ) -> Result<Option<PartitionInfo>> {
+        self.execute_query(
+            DaoType::SelectPartitionVersionByTableIdAndDescAndVersion as i32,
+            [table_id, partition_desc, version.to_string().as_str()].join(PARAM_DELIM),
+        )
+        .await
+        .map(|wrapper| wrapper.partition_info.into_iter().next())
+    }
+
+    /// Get the latest version of a partition committed before `timestamp` in milliseconds.
+    pub async fn get_latest_version_up_to_time(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        timestamp: i64,
+    ) -> Result<Option<i32>> {
+        match self
+            .execute_query_scalar(
+                DaoType::GetLatestVersionUpToTimeFromPartitionInfo as i32,
+                [table_id, partition_desc, timestamp.to_string().as_str()].join(PARAM_DELIM),
+            )
+            .await?
+        {
+            Some(version) => Ok(Some(version.parse::<i32>()?)),
+            None => Ok(None),
+        }
+    }
+
     pub async fn get_single_data_commit_info(
         &self,
         table_id: &str,
@@ -604,6 +1554,58 @@ impl MetaDataClient {
         }
     }
 
+    pub async fn get_commit_ops_between_versions(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        start_version: i32,
+        end_version: i32,
+    ) -> Result<HashSet<CommitOp>> {
+        self.execute_query(
+            DaoType::ListCommitOpsBetweenVersions as i32,
+            [
+                table_id,
+                partition_desc,
+                start_version.to_string().as_str(),
+                end_version.to_string().as_str(),
+            ]
+            .join(PARAM_DELIM),
+        )
+        .await?
+        .partition_info
+        .iter()
+        .map(|partition_info| {
+            CommitOp::try_from(partition_info.commit_op)
+                .map_err(|_| LakeSoulMetaDataError::Internal("unknown commit_op".to_string()))
+        })
+        .collect()
+    }
+
+    pub async fn get_incremental_partitions(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        start_version: i32,
+        end_version: i32,
+    ) -> Result<Vec<PartitionInfo>> {
+        match self
+            .execute_query(
+                DaoType::ListPartitionVersionByTableIdAndPartitionDescAndVersionRange as i32,
+                [
+                    table_id,
+                    partition_desc,
+                    start_version.to_string().as_str(),
+                    end_version.to_string().as_str(),
+                ]
+                .join(PARAM_DELIM),
+            )
+            .await
+        {
+            Ok(wrapper) => Ok(wrapper.partition_info),
+            Err(e) => Err(e),
+        }
+    }
+
     pub async fn get_partition_info_by_table_id_and_partition_list(
         &self,
         table_id: &str,
@@ -622,6 +1624,39 @@ impl MetaDataClient {
     }
 }
 
+/// Whether a commit of `commit_op` can be rebased on top of a concurrent commit of `cur_commit_op`.
+fn can_rebase(commit_op: CommitOp, cur_commit_op: CommitOp) -> bool {
+    match commit_op {
+        CommitOp::AppendCommit => matches!(
+            cur_commit_op,
+            CommitOp::CompactionCommit | CommitOp::AppendCommit | CommitOp::UpdateCommit
+        ),
+        CommitOp::MergeCommit => matches!(
+            cur_commit_op,
+            CommitOp::CompactionCommit | CommitOp::UpdateCommit | CommitOp::MergeCommit
+        ),
+        _ => false,
+    }
+}
+
+/// Keep the commits appended by others after `read_partition` was read,
+/// on top of the snapshot submitted by `raw_partition_info`.
+fn update_submit_partition_snapshot(
+    raw_partition_info: &PartitionInfo,
+    cur_partition_info: &mut PartitionInfo,
+    read_partition: Option<&PartitionInfo>,
+) {
+    let mut snapshot = raw_partition_info.snapshot.clone();
+    snapshot.extend(
+        cur_partition_info
+            .snapshot
+            .iter()
+            .filter(|commit_id| read_partition.map_or(true, |p| !p.snapshot.contains(commit_id)))
+            .cloned(),
+    );
+    cur_partition_info.snapshot = snapshot;
+}
+
 pub fn table_path_id_from_table_info(table_info: &TableInfo) -> TablePathId {
     TablePathId {
         table_path: table_info.table_path.clone(),
@@ -639,3 +1674,181 @@ pub fn table_name_id_from_table_info(table_info: &TableInfo) -> TableNameId {
         domain: table_info.domain.clone(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use proto::proto::entity::{CommitOp, DataCommitInfo, MetaInfo, PartitionInfo, TableInfo, Uuid};
+
+    use super::{can_rebase, update_submit_partition_snapshot};
+    use crate::error::LakeSoulMetaDataError;
+    use crate::{MemoryBackend, MetaDataClient};
+
+    fn uuid(low: u64) -> Uuid {
+        Uuid { high: 0, low }
+    }
+
+    fn table_info() -> TableInfo {
+        TableInfo {
+            table_id: "table_1".to_string(),
+            table_namespace: "default".to_string(),
+            table_name: "test_rebase".to_string(),
+            table_path: "file:///tmp/test_rebase".to_string(),
+            table_schema: "{}".to_string(),
+            properties: "{}".to_string(),
+            partitions: ";".to_string(),
+            domain: "public".to_string(),
+        }
+    }
+
+    async fn append(client: &MetaDataClient, low: u64) {
+        client
+            .commit_data_commit_info(DataCommitInfo {
+                table_id: "table_1".to_string(),
+                partition_desc: "-5".to_string(),
+                commit_id: Some(uuid(low)),
+                commit_op: CommitOp::AppendCommit as i32,
+                domain: "public".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+    }
+
+    /// Commit a rewrite of the partition read as `read_partition` to the commits `snapshot`.
+    async fn rewrite(
+        client: &MetaDataClient,
+        commit_op: CommitOp,
+        snapshot: Vec<Uuid>,
+        read_partition: &PartitionInfo,
+    ) -> crate::error::Result<()> {
+        client
+            .commit_data(
+                MetaInfo {
+                    table_info: Some(table_info()),
+                    list_partition: vec![PartitionInfo {
+                        table_id: "table_1".to_string(),
+                        partition_desc: "-5".to_string(),
+                        commit_op: commit_op as i32,
+                        snapshot,
+                        ..Default::default()
+                    }],
+                    read_partition_info: vec![read_partition.clone()],
+                },
+                commit_op,
+            )
+            .await
+    }
+
+    async fn latest_partition(client: &MetaDataClient) -> PartitionInfo {
+        client.get_all_partition_info("table_1").await.unwrap().remove(0)
+    }
+
+    #[tokio::test]
+    async fn test_rebase_rewrite() {
+        let client = MetaDataClient::from_backend(Arc::new(MemoryBackend::new()));
+        client.create_table(table_info()).await.unwrap();
+        append(&client, 1).await;
+        let read = latest_partition(&client).await;
+
+        // a delete of all the rows read keeps the rows appended since the read
+        append(&client, 2).await;
+        rewrite(&client, CommitOp::DeleteCommit, vec![], &read).await.unwrap();
+        let latest = latest_partition(&client).await;
+        assert_eq!((latest.version, latest.snapshot), (2, vec![uuid(2)]));
+
+        // an update is not rebased on a concurrent update of the files read, even followed by an append
+        let read = latest_partition(&client).await;
+        rewrite(&client, CommitOp::UpdateCommit, vec![uuid(3)], &read)
+            .await
+            .unwrap();
+        append(&client, 4).await;
+        assert!(matches!(
+            rewrite(&client, CommitOp::DeleteCommit, vec![uuid(5)], &read).await,
+            Err(LakeSoulMetaDataError::CommitConflict(_))
+        ));
+        // while a compaction is dropped
+        rewrite(&client, CommitOp::CompactionCommit, vec![uuid(6)], &read)
+            .await
+            .unwrap();
+        let latest = latest_partition(&client).await;
+        assert_eq!((latest.version, latest.snapshot), (4, vec![uuid(3), uuid(4)]));
+
+        // an update only rebased on appends keeps them
+        let read = latest_partition(&client).await;
+        append(&client, 7).await;
+        rewrite(&client, CommitOp::UpdateCommit, vec![uuid(8)], &read)
+            .await
+            .unwrap();
+        assert_eq!(latest_partition(&client).await.snapshot, vec![uuid(8), uuid(7)]);
+    }
+
+    #[tokio::test]
+    async fn test_create_duplicate_tag() {
+        let client = MetaDataClient::from_backend(Arc::new(MemoryBackend::new()));
+        client.create_table(table_info()).await.unwrap();
+        append(&client, 1).await;
+        let partitions = client.get_all_partition_info("table_1").await.unwrap();
+        client.create_tag("table_1", "v1", &partitions).await.unwrap();
+        assert!(matches!(
+            client.create_tag("table_1", "v1", &partitions).await,
+            Err(LakeSoulMetaDataError::AlreadyExists(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_time_travel_to_expired_version() {
+        let client = MetaDataClient::from_backend(Arc::new(MemoryBackend::new()));
+        client.create_table(table_info()).await.unwrap();
+        append(&client, 1).await;
+        // a partition created after the timestamp is not included
+        assert!(client
+            .get_all_partition_info_as_of_timestamp("table_1", 0)
+            .await
+            .unwrap()
+            .is_empty());
+        append(&client, 2).await;
+        append(&client, 3).await;
+        client.delete_partition_version("table_1", "-5", 0).await.unwrap();
+
+        let partitions = client.get_all_partition_info_as_of_version("table_1", 1).await.unwrap();
+        assert_eq!(partitions[0].snapshot, vec![uuid(1), uuid(2)]);
+        assert!(matches!(
+            client.get_all_partition_info_as_of_version("table_1", 0).await,
+            Err(LakeSoulMetaDataError::VersionExpired(_))
+        ));
+        assert!(matches!(
+            client.get_all_partition_info_as_of_timestamp("table_1", 0).await,
+            Err(LakeSoulMetaDataError::VersionExpired(_))
+        ));
+    }
+
+    #[test]
+    fn test_can_rebase() {
+        assert!(can_rebase(CommitOp::AppendCommit, CommitOp::AppendCommit));
+        assert!(can_rebase(CommitOp::AppendCommit, CommitOp::CompactionCommit));
+        assert!(!can_rebase(CommitOp::AppendCommit, CommitOp::MergeCommit));
+        assert!(can_rebase(CommitOp::MergeCommit, CommitOp::MergeCommit));
+        assert!(!can_rebase(CommitOp::MergeCommit, CommitOp::AppendCommit));
+        assert!(!can_rebase(CommitOp::MergeCommit, CommitOp::DeleteCommit));
+    }
+
+    #[test]
+    fn test_update_submit_partition_snapshot() {
+        let raw = PartitionInfo {
+            snapshot: vec![uuid(10)],
+            ..Default::default()
+        };
+        let read = PartitionInfo {
+            snapshot: vec![uuid(1), uuid(2)],
+            ..Default::default()
+        };
+        let mut cur = PartitionInfo {
+            snapshot: vec![uuid(1), uuid(2), uuid(3)],
+            ..Default::default()
+        };
+        update_submit_partition_snapshot(&raw, &mut cur, Some(&read));
+        assert_eq!(cur.snapshot, vec![uuid(10), uuid(3)]);
+    }
+}
@@ -0,0 +1,218 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Commit notifications over Postgres LISTEN/NOTIFY.
+//!
+//! A [MetaDataClient](crate::MetaDataClient) created with `with_commit_notification(true)` sends a notification on
+//! [COMMIT_NOTIFICATION_CHANNEL] for each partition version it commits, which can be received by
+//! [subscribe_commit_notifications].
+//! The notifications are sent in the transaction inserting the versions, so they are delivered if and only if
+//! the versions are committed.
+//! Notifications are not persisted, so a subscriber should still read `partition_info` after (re)subscribing,
+//! or after an error in the stream.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use tokio::{select, spawn};
+use tokio_postgres::types::Type;
+use tokio_postgres::{AsyncMessage, Client, Transaction};
+use tracing::{debug, warn};
+
+use proto::proto::entity::{CommitOp, PartitionInfo};
+
+use crate::error::{LakeSoulMetaDataError, Result};
+use crate::retry::{retry_backoff, DEFAULT_MAX_RETRY};
+use crate::tls;
+
+/// The channel of commit notifications.
+pub const COMMIT_NOTIFICATION_CHANNEL: &str = "lakesoul_commit";
+
+/// A new partition version committed to a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitNotification {
+    pub table_id: String,
+    pub partition_desc: String,
+    pub version: i32,
+    pub commit_op: CommitOp,
+}
+
+/// The JSON payload of a notification, with the commit op by name.
+#[derive(Serialize, Deserialize)]
+struct CommitNotificationPayload {
+    table_id: String,
+    partition_desc: String,
+    version: i32,
+    commit_op: String,
+}
+
+impl CommitNotification {
+    pub fn to_payload(&self) -> Result<String> {
+        Ok(serde_json::to_string(&CommitNotificationPayload {
+            table_id: self.table_id.clone(),
+            partition_desc: self.partition_desc.clone(),
+            version: self.version,
+            commit_op: self.commit_op.as_str_name().to_string(),
+        })?)
+    }
+
+    pub fn from_payload(payload: &str) -> Result<Self> {
+        let payload = serde_json::from_str::<CommitNotificationPayload>(payload)?;
+        Ok(Self {
+            commit_op: CommitOp::from_str_name(&payload.commit_op).ok_or(LakeSoulMetaDataError::Internal(format!(
+                "unknown commit_op {} of commit notification",
+                payload.commit_op
+            )))?,
+            table_id: payload.table_id,
+            partition_desc: payload.partition_desc,
+            version: payload.version,
+        })
+    }
+}
+
+impl From<&PartitionInfo> for CommitNotification {
+    fn from(partition_info: &PartitionInfo) -> Self {
+        Self {
+            table_id: partition_info.table_id.clone(),
+            partition_desc: partition_info.partition_desc.clone(),
+            version: partition_info.version,
+            commit_op: partition_info.commit_op(),
+        }
+    }
+}
+
+/// Notify the subscribers of [COMMIT_NOTIFICATION_CHANNEL] of the partition versions inserted by `transaction`,
+/// which pg delivers if and only if the transaction commits.
+pub(crate) async fn notify_commits(transaction: &Transaction<'_>, partition_info_list: &[PartitionInfo]) -> Result<()> {
+    let payloads = partition_info_list
+        .iter()
+        .map(|partition_info| CommitNotification::from(partition_info).to_payload())
+        .collect::<Result<Vec<_>>>()?;
+    let statement = transaction
+        .prepare_typed(
+            "select pg_notify($1, payload) from unnest($2) as payload",
+            &[Type::TEXT, Type::TEXT_ARRAY],
+        )
+        .await?;
+    transaction
+        .execute(&statement, &[&COMMIT_NOTIFICATION_CHANNEL, &payloads])
+        .await?;
+    Ok(())
+}
+
+/// An unbounded stream of [CommitNotification], which listens until dropped.
+///
+/// An error in the stream means that the notifications in the meantime may have been lost, e.g. when the connection
+/// listening on pg was dropped. The stream goes on if the connection is reopened, and ends otherwise.
+pub struct CommitNotificationStream {
+    receiver: UnboundedReceiver<Result<CommitNotification>>,
+}
+
+impl CommitNotificationStream {
+    /// A stream of the notifications sent to `receiver` by a backend without pg LISTEN/NOTIFY.
+    pub(crate) fn from_receiver(receiver: UnboundedReceiver<Result<CommitNotification>>) -> Self {
+        Self { receiver }
+    }
+}
+
+impl Stream for CommitNotificationStream {
+    type Item = Result<CommitNotification>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+type NotificationSender = UnboundedSender<Result<CommitNotification>>;
+
+/// Open a connection by the pg `config` listening on [COMMIT_NOTIFICATION_CHANNEL].
+/// When the connection is dropped, the error is sent to the stream and the connection is reopened,
+/// retried with the backoff of [crate::retry] on transient errors; the stream ends if it cannot be reopened.
+pub async fn subscribe_commit_notifications(config: &str) -> Result<CommitNotificationStream> {
+    let (sender, receiver) = unbounded_channel();
+    let (client, forward) = listen(config, sender.clone()).await?;
+    spawn(relisten(config.to_string(), sender, client, forward));
+    Ok(CommitNotificationStream::from_receiver(receiver))
+}
+
+/// Open a connection by `config` listening on [COMMIT_NOTIFICATION_CHANNEL], whose notifications are forwarded to
+/// `sender` by the returned task until the client or the stream is dropped, or the connection fails with the
+/// returned error.
+async fn listen(
+    config: &str,
+    sender: NotificationSender,
+) -> Result<(Client, JoinHandle<Option<LakeSoulMetaDataError>>)> {
+    let (client, mut connection) = tls::connect(config).await?;
+
+    let forward = spawn(async move {
+        loop {
+            let notification = match std::future::poll_fn(|cx| connection.poll_message(cx)).await {
+                Some(Ok(AsyncMessage::Notification(notification))) => notification,
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Some(LakeSoulMetaDataError::from(e)),
+                // the client is dropped
+                None => return None,
+            };
+            if notification.channel() != COMMIT_NOTIFICATION_CHANNEL {
+                continue;
+            }
+            debug!("commit notification {}", notification.payload());
+            if sender
+                .send(CommitNotification::from_payload(notification.payload()))
+                .is_err()
+            {
+                return None;
+            }
+        }
+    });
+
+    client
+        .batch_execute(&format!("LISTEN {}", COMMIT_NOTIFICATION_CHANNEL))
+        .await?;
+    Ok((client, forward))
+}
+
+/// Keep the `client` of [listen] open until the stream is dropped, and reopen it when its connection fails.
+async fn relisten(
+    config: String,
+    sender: NotificationSender,
+    mut client: Client,
+    mut forward: JoinHandle<Option<LakeSoulMetaDataError>>,
+) {
+    loop {
+        let error = select! {
+            // closes the connection by dropping the client
+            _ = sender.closed() => return,
+            result = &mut forward => match result {
+                Ok(Some(e)) => e,
+                Ok(None) => return,
+                Err(e) => LakeSoulMetaDataError::Internal(format!("commit notification task failed: {}", e)),
+            },
+        };
+        drop(client);
+        let reopen = error.is_transient();
+        warn!("commit notification connection failed: {}", error);
+        // the notifications until the connection is reopened are lost
+        if sender.send(Err(error)).is_err() || !reopen {
+            return;
+        }
+        let mut attempt = 0;
+        (client, forward) = loop {
+            sleep(retry_backoff(attempt)).await;
+            match listen(&config, sender.clone()).await {
+                Ok(listening) => break listening,
+                Err(e) if e.is_transient() && attempt + 1 < DEFAULT_MAX_RETRY => attempt += 1,
+                Err(e) => {
+                    let _ = sender.send(Err(e));
+                    return;
+                }
+            }
+        };
+    }
+}
@@ -0,0 +1,165 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! A pool of pg connections for the concurrent queries of a [MetaDataClient](crate::MetaDataClient).
+//!
+//! Each connection keeps its own [PreparedStatementMap], since a prepared statement belongs to the connection which
+//! prepared it. A connection is health-checked before it is handed out, and a broken connection is dropped from the
+//! pool, so that the next checkout transparently establishes a new one.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio_postgres::Client;
+use tracing::debug;
+
+use crate::error::{LakeSoulMetaDataError, Result};
+use crate::{create_connection, PreparedStatementMap};
+
+/// Default maximum number of connections of a [ConnectionPool].
+pub const DEFAULT_POOL_SIZE: usize = 8;
+
+/// A connection idle for longer than this is checked by a query before it is handed out again.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A pg connection of a [ConnectionPool] with its prepared statements.
+pub struct PooledClient {
+    pub client: Client,
+    pub prepared: PreparedStatementMap,
+    last_used: Instant,
+}
+
+impl PooledClient {
+    async fn connect(config: &str) -> Result<Self> {
+        Ok(Self {
+            client: create_connection(config.to_string()).await?,
+            prepared: PreparedStatementMap::new(),
+            last_used: Instant::now(),
+        })
+    }
+
+    /// Whether the connection can still be used, querying the connection if it has been idle for a while.
+    async fn is_healthy(&self) -> bool {
+        if self.client.is_closed() {
+            return false;
+        }
+        self.last_used.elapsed() < IDLE_CHECK_INTERVAL || self.client.simple_query("SELECT 1").await.is_ok()
+    }
+}
+
+/// A pool of at most `pool_size` pg connections of the same config, which are established on demand.
+pub struct ConnectionPool {
+    config: String,
+    pool_size: usize,
+    idle: Mutex<Vec<PooledClient>>,
+    permits: Semaphore,
+}
+
+impl ConnectionPool {
+    /// Create a pool of `config`, establishing its first connection to validate the config.
+    pub async fn try_new(config: String, pool_size: usize) -> Result<Self> {
+        if pool_size == 0 {
+            return Err(LakeSoulMetaDataError::Internal(
+                "pool size must be positive".to_string(),
+            ));
+        }
+        let first = PooledClient::connect(&config).await?;
+        Ok(Self {
+            config,
+            pool_size,
+            idle: Mutex::new(vec![first]),
+            permits: Semaphore::new(pool_size),
+        })
+    }
+
+    /// A pool of `pool_size` connections of the same config, taking over the idle connections of this pool.
+    pub fn with_pool_size(&self, pool_size: usize) -> Result<Self> {
+        if pool_size == 0 {
+            return Err(LakeSoulMetaDataError::Internal(
+                "pool size must be positive".to_string(),
+            ));
+        }
+        let mut idle = std::mem::take(&mut *self.idle.lock().unwrap_or_else(|e| e.into_inner()));
+        idle.truncate(pool_size);
+        Ok(Self {
+            config: self.config.clone(),
+            pool_size,
+            idle: Mutex::new(idle),
+            permits: Semaphore::new(pool_size),
+        })
+    }
+
+    pub fn config(&self) -> &str {
+        &self.config
+    }
+
+    pub fn pool_size(&self) -> usize {
+        self.pool_size
+    }
+
+    /// Check out a connection, waiting while all the connections of the pool are in use.
+    /// The connection is returned to the pool when the [PooledConnection] is dropped.
+    pub async fn get(&self) -> Result<PooledConnection<'_>> {
+        let permit = self
+            .permits
+            .acquire()
+            .await
+            .map_err(|e| LakeSoulMetaDataError::Internal(e.to_string()))?;
+        loop {
+            let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+            match idle {
+                Some(client) if client.is_healthy().await => {
+                    return Ok(PooledConnection {
+                        pool: self,
+                        client: Some(client),
+                        _permit: permit,
+                    })
+                }
+                Some(_) => debug!("drop a broken connection of the pool"),
+                None => {
+                    return Ok(PooledConnection {
+                        pool: self,
+                        client: Some(PooledClient::connect(&self.config).await?),
+                        _permit: permit,
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// A connection checked out of a [ConnectionPool].
+pub struct PooledConnection<'a> {
+    pool: &'a ConnectionPool,
+    client: Option<PooledClient>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = PooledClient;
+
+    fn deref(&self) -> &Self::Target {
+        self.client.as_ref().expect("connection is returned to the pool")
+    }
+}
+
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.client.as_mut().expect("connection is returned to the pool")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(mut client) = self.client.take() {
+            // a closed connection is not returned, so that a new one is established instead
+            if !client.client.is_closed() {
+                client.last_used = Instant::now();
+                self.pool.idle.lock().unwrap_or_else(|e| e.into_inner()).push(client);
+            }
+        }
+    }
+}
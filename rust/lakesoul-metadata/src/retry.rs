@@ -0,0 +1,53 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Retry of the metadata operations failed by transient errors, see
+//! [LakeSoulMetaDataError::is_transient](crate::error::LakeSoulMetaDataError::is_transient).
+//!
+//! The retries are delayed by an exponential backoff with jitter, so that the clients failed by the same outage or
+//! conflict do not retry in lockstep.
+
+use std::time::Duration;
+
+/// Default maximum number of attempts of a metadata operation.
+pub const DEFAULT_MAX_RETRY: usize = 3;
+
+/// Delay before the first retry, which is doubled by each following retry.
+pub const RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+
+/// Maximum delay before a retry.
+pub const RETRY_MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// The delay before retrying an operation which has failed `attempt + 1` times,
+/// i.e. a random duration between the half and the whole of the exponential backoff.
+pub fn retry_backoff(attempt: usize) -> Duration {
+    let backoff = RETRY_BASE_DELAY
+        .saturating_mul(1u32 << attempt.min(16))
+        .min(RETRY_MAX_DELAY);
+    let half = backoff / 2;
+    half + half.mul_f64(rand::random::<f64>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{retry_backoff, RETRY_BASE_DELAY, RETRY_MAX_DELAY};
+
+    #[test]
+    fn test_retry_backoff() {
+        for attempt in 0..64 {
+            let backoff = retry_backoff(attempt);
+            let max = RETRY_BASE_DELAY
+                .saturating_mul(1u32 << attempt.min(16))
+                .min(RETRY_MAX_DELAY);
+            assert!(
+                backoff >= max / 2 && backoff <= max,
+                "{:?} of attempt {}",
+                backoff,
+                attempt
+            );
+        }
+        assert!(retry_backoff(0) <= RETRY_BASE_DELAY);
+        assert!(retry_backoff(100) >= RETRY_MAX_DELAY / 2);
+    }
+}
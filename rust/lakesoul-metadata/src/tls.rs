@@ -0,0 +1,463 @@
+// SPDX-FileCopyrightText: 2024 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! TLS of the pg connections of the metadata.
+//!
+//! The TLS options follow libpq and are given in the connection string, e.g.
+//! `host=db port=5432 dbname=lakesoul user=lakesoul sslmode=verify-full sslrootcert=/etc/lakesoul/ca.pem`,
+//! or by the `lakesoul.pg.*` properties of the `lakesoul_home` config file, see [crate::config].
+//! - `sslmode`: `disable` (the default), `prefer`, `require`, `verify-ca` or `verify-full`.
+//!   `prefer` and `require` verify the server certificate only if `sslrootcert` is given,
+//!   `verify-ca` verifies it against the CA certificates without checking the host name,
+//!   and `verify-full` also checks that the certificate is issued for the host name.
+//! - `sslrootcert`: the PEM file of the CA certificates, the webpki roots if absent.
+//! - `sslcert` and `sslkey`: the PEM files of the client certificate and of its private key.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::iter::Peekable;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::{CharIndices, FromStr};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, CertificateError, ClientConfig, OwnedTrustAnchor, PrivateKey, RootCertStore, ServerName};
+use tokio_postgres::tls::MakeTlsConnect;
+use tokio_postgres::{Client, Connection, Socket};
+use tokio_postgres_rustls::MakeRustlsConnect;
+use url::Url;
+
+use crate::config::MetaDataConfig;
+use crate::error::{LakeSoulMetaDataError, Result};
+
+pub const SSL_MODE: &str = "sslmode";
+pub const SSL_ROOT_CERT: &str = "sslrootcert";
+pub const SSL_CERT: &str = "sslcert";
+pub const SSL_KEY: &str = "sslkey";
+
+/// The TLS options of a connection string with their `lakesoul.pg.*` properties.
+pub(crate) const TLS_OPTIONS: [(&str, &str); 4] = [
+    (SSL_MODE, "lakesoul.pg.sslmode"),
+    (SSL_ROOT_CERT, "lakesoul.pg.sslrootcert"),
+    (SSL_CERT, "lakesoul.pg.sslcert"),
+    (SSL_KEY, "lakesoul.pg.sslkey"),
+];
+
+/// The stream of a pg connection opened by [connect].
+pub type TlsStream = <MakeRustlsConnect as MakeTlsConnect<Socket>>::Stream;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SslMode {
+    #[default]
+    Disable,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl SslMode {
+    /// The `sslmode` understood by tokio_postgres, which verifies nothing by itself.
+    fn pg_ssl_mode(&self) -> &'static str {
+        match self {
+            Self::Disable => "disable",
+            Self::Prefer => "prefer",
+            Self::Require | Self::VerifyCa | Self::VerifyFull => "require",
+        }
+    }
+}
+
+impl FromStr for SslMode {
+    type Err = LakeSoulMetaDataError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "disable" => Ok(Self::Disable),
+            "prefer" => Ok(Self::Prefer),
+            "require" => Ok(Self::Require),
+            "verify-ca" => Ok(Self::VerifyCa),
+            "verify-full" => Ok(Self::VerifyFull),
+            _ => Err(LakeSoulMetaDataError::Tls(format!("invalid sslmode {}", s))),
+        }
+    }
+}
+
+/// The TLS options of a connection string, see the [module docs](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub ssl_mode: SslMode,
+    pub root_cert: Option<PathBuf>,
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    /// Split the TLS options out of the connection string `config`,
+    /// returning the connection string left for tokio_postgres and the TLS options.
+    /// `config` is either of `key=value` pairs or a `postgresql://` url, whose TLS options are query parameters.
+    pub fn split_config(config: &str) -> Result<(String, Self)> {
+        let mut tls_config = Self::default();
+        if is_url(config) {
+            let mut url = Url::parse(config)?;
+            let mut query = vec![];
+            for (key, value) in url.query_pairs() {
+                if !tls_config.set(&key, &value)? {
+                    query.push((key.into_owned(), value.into_owned()));
+                }
+            }
+            url.set_query(None);
+            if !query.is_empty() {
+                url.query_pairs_mut().extend_pairs(query);
+            }
+            url.query_pairs_mut()
+                .append_pair(SSL_MODE, tls_config.ssl_mode.pg_ssl_mode());
+            return Ok((url.to_string(), tls_config));
+        }
+
+        let mut pg_config = String::with_capacity(config.len());
+        let mut last = 0;
+        for (key, value, range) in parse_options(config) {
+            if tls_config.set(&key, &value)? {
+                pg_config.push_str(&config[last..range.start]);
+                last = range.end;
+            }
+        }
+        pg_config.push_str(&config[last..]);
+        let pg_config = format!(
+            "{} {}={}",
+            pg_config.trim(),
+            SSL_MODE,
+            tls_config.ssl_mode.pg_ssl_mode()
+        );
+        Ok((pg_config, tls_config))
+    }
+
+    /// Set the TLS option `key`, returning false if `key` is not a TLS option.
+    fn set(&mut self, key: &str, value: &str) -> Result<bool> {
+        match key {
+            SSL_MODE => self.ssl_mode = value.parse()?,
+            SSL_ROOT_CERT => self.root_cert = Some(PathBuf::from(value)),
+            SSL_CERT => self.client_cert = Some(PathBuf::from(value)),
+            SSL_KEY => self.client_key = Some(PathBuf::from(value)),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// The connector of tokio_postgres of the TLS options, which loads the certificates and the key.
+    pub fn connector(&self) -> Result<MakeRustlsConnect> {
+        Ok(MakeRustlsConnect::new(self.client_config()?))
+    }
+
+    fn client_config(&self) -> Result<ClientConfig> {
+        let verifier: Arc<dyn ServerCertVerifier> = match self.ssl_mode {
+            SslMode::Disable => Arc::new(NoVerification),
+            SslMode::VerifyFull => Arc::new(WebPkiVerifier::new(self.root_cert_store()?, None)),
+            SslMode::VerifyCa => Arc::new(NoHostnameVerification(WebPkiVerifier::new(
+                self.root_cert_store()?,
+                None,
+            ))),
+            SslMode::Prefer | SslMode::Require if self.root_cert.is_some() => Arc::new(NoHostnameVerification(
+                WebPkiVerifier::new(self.root_cert_store()?, None),
+            )),
+            SslMode::Prefer | SslMode::Require => Arc::new(NoVerification),
+        };
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(verifier);
+        if self.ssl_mode == SslMode::Disable {
+            return Ok(builder.with_no_client_auth());
+        }
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => builder
+                .with_client_auth_cert(load_certs(cert)?, load_private_key(key)?)
+                .map_err(|e| LakeSoulMetaDataError::Tls(e.to_string())),
+            (None, None) => Ok(builder.with_no_client_auth()),
+            _ => Err(LakeSoulMetaDataError::Tls(format!(
+                "{} and {} must be given together",
+                SSL_CERT, SSL_KEY
+            ))),
+        }
+    }
+
+    fn root_cert_store(&self) -> Result<RootCertStore> {
+        let mut roots = RootCertStore::empty();
+        match &self.root_cert {
+            Some(path) => {
+                let (added, _) = roots.add_parsable_certificates(&load_pem_certs(path)?);
+                if added == 0 {
+                    return Err(LakeSoulMetaDataError::Tls(format!(
+                        "no valid CA certificate in {}",
+                        path.display()
+                    )));
+                }
+            }
+            None => roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+                OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)
+            })),
+        }
+        Ok(roots)
+    }
+}
+
+/// Open a pg connection by the connection string `config` with its TLS options, see the [module docs](self).
+pub async fn connect(config: &str) -> Result<(Client, Connection<Socket, TlsStream>)> {
+    let (pg_config, tls_config) = TlsConfig::split_config(config)?;
+    Ok(tokio_postgres::connect(&pg_config, tls_config.connector()?).await?)
+}
+
+/// The TLS options of the `lakesoul_home` config file `properties`, i.e. its `lakesoul.pg.sslmode`,
+/// `lakesoul.pg.sslrootcert`, `lakesoul.pg.sslcert` and `lakesoul.pg.sslkey` properties,
+/// or else the query parameters of its `lakesoul.pg.url`.
+pub fn tls_options_from_properties(properties: &str) -> Result<Vec<(String, String)>> {
+    Ok(MetaDataConfig::from_properties(properties)?.tls_options)
+}
+
+/// The TLS options of the config file of the `lakesoul_home` env and of the env, see [MetaDataConfig::from_env].
+pub fn tls_options_from_env() -> Result<Vec<(String, String)>> {
+    Ok(MetaDataConfig::from_env()?.tls_options)
+}
+
+/// Add the TLS `options` which are not given by the connection string `config`,
+/// e.g. the options of [tls_options_from_env].
+pub fn with_tls_options(mut config: String, options: &[(String, String)]) -> Result<String> {
+    if is_url(&config) {
+        let mut url = Url::parse(&config)?;
+        let given = url.query_pairs().map(|(key, _)| key.into_owned()).collect::<Vec<_>>();
+        for (key, value) in options.iter().filter(|(key, _)| !given.contains(key)) {
+            url.query_pairs_mut().append_pair(key, value);
+        }
+        return Ok(url.to_string());
+    }
+    let given = parse_options(&config)
+        .into_iter()
+        .map(|(key, _, _)| key)
+        .collect::<Vec<_>>();
+    for (key, value) in options.iter().filter(|(key, _)| !given.contains(key)) {
+        config.push_str(&format!(
+            " {}='{}'",
+            key,
+            value.replace('\\', "\\\\").replace('\'', "\\'")
+        ));
+    }
+    Ok(config)
+}
+
+fn is_url(config: &str) -> bool {
+    config.starts_with("postgres://") || config.starts_with("postgresql://")
+}
+
+/// Parse the `key=value` pairs of a connection string with their byte ranges.
+/// Values may be quoted by `'` and escape by `\`, as parsed by tokio_postgres.
+/// The parsing stops at a malformed pair, which is left for tokio_postgres to report.
+fn parse_options(config: &str) -> Vec<(String, String, Range<usize>)> {
+    fn skip_whitespace(chars: &mut Peekable<CharIndices>) {
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    let mut options = vec![];
+    let mut chars = config.char_indices().peekable();
+    loop {
+        skip_whitespace(&mut chars);
+        let start = match chars.peek() {
+            Some((start, _)) => *start,
+            None => break,
+        };
+        let mut key = String::new();
+        while let Some((_, c)) = chars.next_if(|(_, c)| *c != '=' && !c.is_whitespace()) {
+            key.push(c);
+        }
+        skip_whitespace(&mut chars);
+        if key.is_empty() || chars.next_if(|(_, c)| *c == '=').is_none() {
+            break;
+        }
+        skip_whitespace(&mut chars);
+
+        let mut value = String::new();
+        let quoted = chars.next_if(|(_, c)| *c == '\'').is_some();
+        let mut end = config.len();
+        let mut closed = !quoted;
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                '\'' if quoted => {
+                    end = idx + 1;
+                    closed = true;
+                    break;
+                }
+                c if c.is_whitespace() && !quoted => {
+                    end = idx;
+                    break;
+                }
+                c => value.push(c),
+            }
+        }
+        if !closed {
+            break;
+        }
+        options.push((key, value, start..end));
+    }
+    options
+}
+
+fn load_pem_certs(path: &Path) -> Result<Vec<Vec<u8>>> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(path)?))?;
+    if certs.is_empty() {
+        return Err(LakeSoulMetaDataError::Tls(format!(
+            "no certificate in {}",
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+
+fn load_certs(path: &Path) -> Result<Vec<Certificate>> {
+    Ok(load_pem_certs(path)?.into_iter().map(Certificate).collect())
+}
+
+fn load_private_key(path: &Path) -> Result<PrivateKey> {
+    let mut reader = BufReader::new(File::open(path)?);
+    loop {
+        match rustls_pemfile::read_one(&mut reader)? {
+            Some(rustls_pemfile::Item::RSAKey(key))
+            | Some(rustls_pemfile::Item::PKCS8Key(key))
+            | Some(rustls_pemfile::Item::ECKey(key)) => return Ok(PrivateKey(key)),
+            Some(_) => continue,
+            None => {
+                return Err(LakeSoulMetaDataError::Tls(format!(
+                    "no private key in {}",
+                    path.display()
+                )))
+            }
+        }
+    }
+}
+
+/// Accepts any server certificate, for `sslmode` `prefer` and `require` without `sslrootcert`, as libpq does.
+struct NoVerification;
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+/// Verifies the server certificate against the CA certificates without checking the host name, for `verify-ca`.
+struct NoHostnameVerification(WebPkiVerifier);
+
+impl ServerCertVerifier for NoHostnameVerification {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        match self
+            .0
+            .verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now)
+        {
+            Err(rustls::Error::InvalidCertificate(CertificateError::NotValidForName)) => {
+                Ok(ServerCertVerified::assertion())
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{tls_options_from_properties, with_tls_options, SslMode, TlsConfig};
+
+    #[test]
+    fn test_split_config() {
+        let (pg_config, tls_config) = TlsConfig::split_config(
+            "host=127.0.0.1 port=5432 sslmode=verify-full sslrootcert='/etc/lake soul/ca.pem' user=lakesoul_test",
+        )
+        .unwrap();
+        assert_eq!(
+            pg_config,
+            "host=127.0.0.1 port=5432   user=lakesoul_test sslmode=require"
+        );
+        assert_eq!(tls_config.ssl_mode, SslMode::VerifyFull);
+        assert_eq!(tls_config.root_cert, Some(PathBuf::from("/etc/lake soul/ca.pem")));
+        assert_eq!(tls_config.client_cert, None);
+
+        let (pg_config, tls_config) = TlsConfig::split_config("host=127.0.0.1 password='a b\\'c'").unwrap();
+        assert_eq!(pg_config, "host=127.0.0.1 password='a b\\'c' sslmode=disable");
+        assert_eq!(tls_config, TlsConfig::default());
+
+        let (pg_config, tls_config) = TlsConfig::split_config(
+            "postgresql://lakesoul_test@127.0.0.1:5432/lakesoul_test?sslmode=verify-ca&sslcert=c.pem&sslkey=k.pem",
+        )
+        .unwrap();
+        assert_eq!(
+            pg_config,
+            "postgresql://lakesoul_test@127.0.0.1:5432/lakesoul_test?sslmode=require"
+        );
+        assert_eq!(tls_config.ssl_mode, SslMode::VerifyCa);
+        assert_eq!(tls_config.client_cert, Some(PathBuf::from("c.pem")));
+        assert_eq!(tls_config.client_key, Some(PathBuf::from("k.pem")));
+
+        assert!(TlsConfig::split_config("host=127.0.0.1 sslmode=allow").is_err());
+    }
+
+    #[test]
+    fn test_connector() {
+        for ssl_mode in ["disable", "prefer", "require", "verify-ca", "verify-full"] {
+            let (_, tls_config) = TlsConfig::split_config(&format!("host=127.0.0.1 sslmode={}", ssl_mode)).unwrap();
+            assert!(tls_config.connector().is_ok());
+        }
+        let (_, tls_config) = TlsConfig::split_config("host=127.0.0.1 sslmode=require sslcert=c.pem").unwrap();
+        assert!(tls_config.connector().is_err());
+        let (_, tls_config) =
+            TlsConfig::split_config("host=127.0.0.1 sslmode=verify-full sslrootcert=/nonexistent/ca.pem").unwrap();
+        assert!(tls_config.connector().is_err());
+    }
+
+    #[test]
+    fn test_tls_options() {
+        let options = tls_options_from_properties(
+            "lakesoul.pg.driver=com.lakesoul.shaded.org.postgresql.Driver\n\
+            lakesoul.pg.url=jdbc:postgresql://127.0.0.1:5432/lakesoul_test?stringtype=unspecified&sslmode=require\n\
+            lakesoul.pg.sslrootcert=/etc/lakesoul/ca.pem\n",
+        )
+        .unwrap();
+        assert_eq!(
+            options,
+            vec![
+                ("sslmode".to_string(), "require".to_string()),
+                ("sslrootcert".to_string(), "/etc/lakesoul/ca.pem".to_string()),
+            ]
+        );
+
+        let config = with_tls_options("host=127.0.0.1 sslmode=verify-full".to_string(), &options).unwrap();
+        assert_eq!(
+            config,
+            "host=127.0.0.1 sslmode=verify-full sslrootcert='/etc/lakesoul/ca.pem'"
+        );
+        let config = with_tls_options("postgresql://127.0.0.1:5432/lakesoul_test".to_string(), &options).unwrap();
+        assert_eq!(
+            config,
+            "postgresql://127.0.0.1:5432/lakesoul_test?sslmode=require&sslrootcert=%2Fetc%2Flakesoul%2Fca.pem"
+        );
+    }
+}
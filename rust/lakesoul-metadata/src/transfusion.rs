@@ -24,7 +24,7 @@ use crate::transfusion::config::{
 };
 use crate::{error::Result, execute_query, DaoType, PreparedStatementMap, PARAM_DELIM};
 
-mod config {
+pub(crate) mod config {
     #![allow(unused)]
 
     /// copy from DBConfig
@@ -320,7 +320,7 @@ impl DataFileInfo {
         })
     }
 
-    fn parse_bucket_id(filename: &str) -> Option<isize> {
+    pub fn parse_bucket_id(filename: &str) -> Option<isize> {
         let re = Regex::new(DataFileInfo::BUCKET_FILE_NAME_REGEX).unwrap();
         let Some(caps) = re.captures(filename) else {
             return Some(-1);
@@ -9,3 +9,5 @@ delete from table_info;
 delete from table_path_id;
 delete from table_name_id;
 delete from partition_info;
+delete from table_tag;
+delete from table_branch;
@@ -86,6 +86,26 @@ create table if not exists partition_info
     primary key (table_id, partition_desc, version)
 );
 
+create table if not exists table_tag
+(
+    table_id           text,
+    tag_name           text,
+    partition_versions json,
+    timestamp          bigint DEFAULT (date_part('epoch'::text, now()) * (1000)::double precision),
+    domain             text default 'public',
+    primary key (table_id, tag_name)
+);
+
+create table if not exists table_branch
+(
+    table_id           text,
+    branch_name        text,
+    partition_versions json,
+    timestamp          bigint DEFAULT (date_part('epoch'::text, now()) * (1000)::double precision),
+    domain             text default 'public',
+    primary key (table_id, branch_name)
+);
+
 CREATE OR REPLACE FUNCTION partition_insert() RETURNS TRIGGER AS
 $$
 DECLARE
@@ -93,7 +113,8 @@ DECLARE
     rs_table_path      text;
     rs_table_namespace text;
 BEGIN
-    if NEW.commit_op <> 'CompactionCommit' then
+    -- the versions of a branch, whose table_id is `{table_id}@{branch_name}`, are not compacted
+    if NEW.commit_op <> 'CompactionCommit' and position('@' in NEW.table_id) = 0 then
         select version
         INTO rs_version
         from partition_info
@@ -124,6 +124,42 @@ $$
     END
 $$;
 
+ALTER TABLE table_tag
+    ENABLE ROW LEVEL SECURITY;
+CREATE INDEX CONCURRENTLY IF NOT EXISTS table_tag_domain_index ON table_tag (domain);
+DO
+$$
+    BEGIN
+        IF NOT EXISTS (SELECT 1 FROM pg_policies WHERE tablename = 'table_tag') THEN
+            CREATE POLICY domain_only_policy ON table_tag
+                USING (
+                        domain = 'public'
+                    OR domain = current_user
+                    OR domain IN (SELECT rolname
+                                  FROM pg_roles
+                                  WHERE pg_has_role(current_user, oid, 'member')));
+        END IF;
+    END
+$$;
+
+ALTER TABLE table_branch
+    ENABLE ROW LEVEL SECURITY;
+CREATE INDEX CONCURRENTLY IF NOT EXISTS table_branch_domain_index ON table_branch (domain);
+DO
+$$
+    BEGIN
+        IF NOT EXISTS (SELECT 1 FROM pg_policies WHERE tablename = 'table_branch') THEN
+            CREATE POLICY domain_only_policy ON table_branch
+                USING (
+                        domain = 'public'
+                    OR domain = current_user
+                    OR domain IN (SELECT rolname
+                                  FROM pg_roles
+                                  WHERE pg_has_role(current_user, oid, 'member')));
+        END IF;
+    END
+$$;
+
 
 
 -- create table if not exists casbin_rule